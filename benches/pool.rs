@@ -0,0 +1,27 @@
+use criterion::{Criterion, black_box, criterion_group, criterion_main};
+use rotation_logger::{Message, MessagePool};
+
+/// Message building on sender side and its release on worker side,
+/// with and without recycling through pool.
+fn message_allocation(c: &mut Criterion) {
+    let modules = vec!["service".to_string(), "handler".into()];
+    let text = "request processed in 12 ms";
+
+    c.bench_function("message new", |b| {
+        b.iter(|| {
+            let message = Message::new(black_box(&modules), black_box(text));
+            drop(black_box(message));
+        })
+    });
+
+    let pool = MessagePool::new(64);
+    c.bench_function("message pooled", |b| {
+        b.iter(|| {
+            let message = pool.message(black_box(&modules), black_box(text));
+            pool.recycle(black_box(message));
+        })
+    });
+}
+
+criterion_group!(benches, message_allocation);
+criterion_main!(benches);
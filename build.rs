@@ -0,0 +1,13 @@
+fn main() {
+    // Shim for os_log macro, only for Apple targets with `oslog` feature.
+    #[cfg(feature = "oslog")]
+    {
+        let target_vendor = std::env::var("CARGO_CFG_TARGET_VENDOR").unwrap_or_default();
+        if target_vendor == "apple" {
+            println!("cargo:rerun-if-changed=src/rotation_logger/sink/oslog.c");
+            cc::Build::new()
+                .file("src/rotation_logger/sink/oslog.c")
+                .compile("rotation_logger_oslog");
+        }
+    }
+}
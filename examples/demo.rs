@@ -25,31 +25,27 @@ fn main() {
     let logger = Logger::new(settings);
     let joiner = logger.run_async();
 
-    let logger_logger_01 = logger.clone();
-    let logger_logger_02 = logger.clone();
+    let logger_thread_01 = logger.child(["THREAD1"]);
+    let logger_thread_02 = logger.child(["THREAD2"]);
     let _ = thread::spawn(move || {
-        logger_logger_01.log(&vec!["THREAD1".into(), "MAIN".into()], "Starting...");
+        logger_thread_01.child(["MAIN"]).log("Starting...");
 
+        let worker = logger_thread_01.child(["WORKER"]);
         let mut counter = 0;
         loop {
-            logger_logger_01.log(
-                &vec!["THREAD1".into(), "WORKER".into()],
-                format!("Processing Job: {counter}").as_str(),
-            );
+            worker.log(format!("Processing Job: {counter}").as_str());
             counter += 1;
             sleep(Duration::from_secs(1));
         }
     });
 
     let _ = thread::spawn(move || {
-        logger_logger_02.log(&vec!["THREAD2".into(), "MAIN".into()], "Starting...");
+        logger_thread_02.child(["MAIN"]).log("Starting...");
 
+        let worker = logger_thread_02.child(["WORKER"]);
         let mut counter = 0;
         loop {
-            logger_logger_02.log(
-                &vec!["THREAD2".into(), "WORKER".into()],
-                format!("Processing Job: {counter}").as_str(),
-            );
+            worker.log(format!("Processing Job: {counter}").as_str());
             counter += 2;
             sleep(Duration::from_millis(400));
         }
@@ -83,10 +79,7 @@ fn main() {
         }
     });
 
-    match joiner {
-        Some(j) => {
-            let _ = j.join();
-        }
-        None => {}
-    };
+    if let Some(j) = joiner {
+        let _ = j.join();
+    }
 }
@@ -0,0 +1,184 @@
+//! # Viewer of rotation sets.
+//!
+//! Takes path of active log file, e.g. `logs/app.log`, and reads every generation
+//! from the oldest to active one. JSON records are pretty-printed, text lines are shown as they are.
+//! Compressed generations are read with `gzip` or `zstd` feature.
+//!
+//! ```text
+//! rotlog cat <file>                  whole set
+//! rotlog tail [-n <lines>] [-f] <file>   last entries, `-f` follows active file
+//! rotlog grep <pattern> <file>       entries containing pattern
+//! ```
+//!
+use std::{
+    collections::VecDeque,
+    env,
+    path::{Path, PathBuf},
+    process::ExitCode,
+    thread,
+    time::Duration,
+};
+
+use rotation_logger::{FileSettings, FileSize, LogEntry, LogReader, MessageFormatter};
+
+const USAGE: &str = "usage:
+  rotlog cat <file>
+  rotlog tail [-n <lines>] [-f] <file>
+  rotlog grep <pattern> <file>";
+
+/// How often followed file is checked for new entries.
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(500);
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let result = match args.first().map(String::as_str) {
+        Some("cat") => cat(&args[1..]),
+        Some("tail") => tail(&args[1..]),
+        Some("grep") => grep(&args[1..]),
+        _ => Err(USAGE.into()),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn cat(args: &[String]) -> Result<(), String> {
+    let [file] = args else {
+        return Err(USAGE.into());
+    };
+    let formatter = MessageFormatter::pretty();
+    for entry in set_entries(Path::new(file))? {
+        print_entry(&formatter, &entry);
+    }
+    Ok(())
+}
+
+fn tail(args: &[String]) -> Result<(), String> {
+    let mut lines = 10;
+    let mut follow = false;
+    let mut file = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-n" => {
+                lines = args
+                    .next()
+                    .and_then(|lines| lines.parse().ok())
+                    .ok_or(USAGE)?
+            }
+            "-f" => follow = true,
+            path if file.is_none() => file = Some(PathBuf::from(path)),
+            _ => return Err(USAGE.into()),
+        }
+    }
+    let file = file.ok_or(USAGE)?;
+    let formatter = MessageFormatter::pretty();
+
+    let mut last = VecDeque::with_capacity(lines);
+    for entry in set_entries(&file)? {
+        if last.len() == lines {
+            last.pop_front();
+        }
+        if lines > 0 {
+            last.push_back(entry);
+        }
+    }
+    for entry in &last {
+        print_entry(&formatter, entry);
+    }
+    if !follow {
+        return Ok(());
+    }
+
+    // Entries of active file already shown, file starts over after rotation.
+    let mut seen = file_entries(&file).map_or(0, |entries| entries.len());
+    loop {
+        thread::sleep(FOLLOW_INTERVAL);
+        let Ok(entries) = file_entries(&file) else {
+            continue;
+        };
+        if entries.len() < seen {
+            seen = 0;
+        }
+        for entry in &entries[seen..] {
+            print_entry(&formatter, entry);
+        }
+        seen = entries.len();
+    }
+}
+
+fn grep(args: &[String]) -> Result<(), String> {
+    let [pattern, file] = args else {
+        return Err(USAGE.into());
+    };
+    let formatter = MessageFormatter::pretty();
+    for entry in set_entries(Path::new(file))? {
+        let matches = match &entry {
+            LogEntry::Line(line) => line.contains(pattern.as_str()),
+            LogEntry::Record(record) => record.line().contains(pattern.as_str()),
+        };
+        if matches {
+            print_entry(&formatter, &entry);
+        }
+    }
+    Ok(())
+}
+
+fn print_entry(formatter: &MessageFormatter, entry: &LogEntry) {
+    match entry {
+        LogEntry::Line(line) => println!("{line}"),
+        LogEntry::Record(record) => {
+            println!(
+                "{}",
+                formatter.format_at_system_time(record.message(), record.time())
+            )
+        }
+    }
+}
+
+/// Settings of set with active file `file`, e.g. `app` and `log` of `logs/app.log`.
+fn settings(file: &Path) -> Result<FileSettings, String> {
+    let name = file
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or_else(|| format!("{} is not a file", file.display()))?;
+    let (filename, extension) = name
+        .rsplit_once('.')
+        .ok_or_else(|| format!("{name} has no extension"))?;
+    let dir = match file.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    Ok(FileSettings::new(
+        dir,
+        1,
+        FileSize::default(),
+        filename.into(),
+        extension.into(),
+    ))
+}
+
+/// Entries of every generation, the oldest first.
+fn set_entries(file: &Path) -> Result<Vec<LogEntry>, String> {
+    let settings = settings(file)?;
+    let files = LogReader::set_files(&settings)
+        .map_err(|err| format!("{} can't be read: {err}", settings.path().display()))?;
+    let mut entries = vec![];
+    for path in files {
+        match file_entries(&path) {
+            Ok(file_entries) => entries.extend(file_entries),
+            Err(err) => eprintln!("{} skipped: {err}", path.display()),
+        }
+    }
+    Ok(entries)
+}
+
+fn file_entries(path: &Path) -> Result<Vec<LogEntry>, String> {
+    LogReader::open(path)
+        .and_then(|reader| reader.collect())
+        .map_err(|err| err.to_string())
+}
@@ -4,7 +4,7 @@
 //!
 //! Example:
 //!
-//! ```
+//! ```no_run
 //! use std::{
 //!     thread::{self, sleep},
 //!     time::Duration,
@@ -35,13 +35,13 @@
 //!     let logger_logger_01 = logger.clone();
 //!     let logger_logger_02 = logger.clone();
 //!     let _ = thread::spawn(move || {
-//!         logger_logger_01.log(&vec!["THREAD1".into(), "MAIN".into()], "Starting...");
+//!         logger_logger_01.log_display(["THREAD1", "MAIN"], &"Starting...");
 //!
 //!         let mut counter = 0;
 //!         loop {
-//!             logger_logger_01.log(
-//!                 &vec!["THREAD1".into(), "WORKER".into()],
-//!                 format!("Processing Job: {counter}").as_str(),
+//!             logger_logger_01.log_display(
+//!                 ["THREAD1", "WORKER"],
+//!                 &format!("Processing Job: {counter}"),
 //!             );
 //!             counter += 1;
 //!             sleep(Duration::from_secs(1));
@@ -49,13 +49,13 @@
 //!     });
 //!
 //!     let _ = thread::spawn(move || {
-//!         logger_logger_02.log(&vec!["THREAD2".into(), "MAIN".into()], "Starting...");
+//!         logger_logger_02.log_display(["THREAD2", "MAIN"], &"Starting...");
 //!
 //!         let mut counter = 0;
 //!         loop {
-//!             logger_logger_02.log(
-//!                 &vec!["THREAD2".into(), "WORKER".into()],
-//!                 format!("Processing Job: {counter}").as_str(),
+//!             logger_logger_02.log_display(
+//!                 ["THREAD2", "WORKER"],
+//!                 &format!("Processing Job: {counter}"),
 //!             );
 //!             counter += 2;
 //!             sleep(Duration::from_millis(400));
@@ -100,15 +100,197 @@
 //!
 //! ```
 //!
+//! Without default `std` feature only message construction and formatting are available,
+//! with `CoreLogger` writing lines to user `Transport`, e.g. serial port on embedded target.
+//!
+//! Default `file`, `timestamps` and `formatter` features can be turned off as well, for console-only
+//! builds without rotation, `chrono` and format string parsing.
 //!
+//!
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
 
+#[cfg(feature = "std")]
+pub use crate::rotation_logger::AggregatorAddress;
+#[cfg(feature = "std")]
+pub use crate::rotation_logger::AggregatorLimits;
+#[cfg(feature = "formatter")]
+pub use crate::rotation_logger::Align;
+#[cfg(feature = "cloudwatch")]
+pub use crate::rotation_logger::AwsCredentials;
+#[cfg(feature = "cloudwatch")]
+pub use crate::rotation_logger::AwsCredentialsSource;
+#[cfg(feature = "azure")]
+pub use crate::rotation_logger::AzureLogAnalyticsSink;
+#[cfg(feature = "std")]
+pub use crate::rotation_logger::BacklogWarning;
+#[cfg(feature = "std")]
+pub use crate::rotation_logger::Banner;
+#[cfg(feature = "chat")]
+pub use crate::rotation_logger::ChatNotifierSink;
+#[cfg(feature = "chat")]
+pub use crate::rotation_logger::ChatService;
+#[cfg(feature = "checksum")]
+pub use crate::rotation_logger::ChecksumStage;
+#[cfg(feature = "std")]
+pub use crate::rotation_logger::CliLogging;
+#[cfg(feature = "cloudwatch")]
+pub use crate::rotation_logger::CloudWatchSink;
+pub use crate::rotation_logger::Color;
+pub use crate::rotation_logger::ColorChoice;
+pub use crate::rotation_logger::ColorTheme;
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+pub use crate::rotation_logger::CompressStage;
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+pub use crate::rotation_logger::Compression;
+#[cfg(feature = "std")]
+pub use crate::rotation_logger::ConsoleWriter;
+pub use crate::rotation_logger::CoreLogger;
+#[cfg(feature = "std")]
+pub use crate::rotation_logger::DISABLE_ENV;
+#[cfg(feature = "file")]
+pub use crate::rotation_logger::DeleteStage;
+pub use crate::rotation_logger::EmptyModules;
+#[cfg(feature = "file")]
+pub use crate::rotation_logger::Encoding;
+pub use crate::rotation_logger::FieldValue;
+#[cfg(feature = "std")]
+pub use crate::rotation_logger::FileHeader;
+#[cfg(feature = "file")]
 pub use crate::rotation_logger::FileSettings;
+#[cfg(feature = "file")]
+pub use crate::rotation_logger::FileSettingsBuilder;
+#[cfg(feature = "file")]
 pub use crate::rotation_logger::FileSize;
+#[cfg(feature = "file")]
+pub use crate::rotation_logger::FileWriter;
+#[cfg(feature = "std")]
+pub use crate::rotation_logger::FlushFuture;
+#[cfg(feature = "std")]
+pub use crate::rotation_logger::FlushGuard;
+#[cfg(feature = "std")]
+pub use crate::rotation_logger::FlushPolicy;
+pub use crate::rotation_logger::Format;
+#[cfg(feature = "gcp")]
+pub use crate::rotation_logger::GcpCredentials;
+#[cfg(feature = "gcp")]
+pub use crate::rotation_logger::GcpLoggingSink;
+#[cfg(feature = "formatter")]
+pub use crate::rotation_logger::Grid;
+#[cfg(feature = "formatter")]
+pub use crate::rotation_logger::Humanize;
+#[cfg(feature = "std")]
+pub use crate::rotation_logger::JsonLayout;
+#[cfg(feature = "std")]
+#[allow(deprecated)]
 pub use crate::rotation_logger::LOG_SENDER;
+pub use crate::rotation_logger::Level;
+#[cfg(all(feature = "std", feature = "timestamps"))]
+pub use crate::rotation_logger::LevelWindow;
+#[cfg(feature = "file")]
+pub use crate::rotation_logger::LineEnding;
+#[cfg(feature = "file")]
+pub use crate::rotation_logger::LockPolicy;
+#[cfg(feature = "yaml")]
+pub use crate::rotation_logger::Log4rsConfig;
+#[cfg(feature = "std")]
+pub use crate::rotation_logger::LogEntry;
+#[cfg(feature = "log")]
+pub use crate::rotation_logger::LogFacade;
+#[cfg(all(feature = "file", not(target_arch = "wasm32")))]
+pub use crate::rotation_logger::LogImport;
+#[cfg(feature = "std")]
+pub use crate::rotation_logger::LogReader;
+pub use crate::rotation_logger::LogText;
+#[cfg(all(feature = "android", target_os = "android"))]
+pub use crate::rotation_logger::LogcatSink;
+#[cfg(feature = "std")]
 pub use crate::rotation_logger::Logger;
+#[cfg(feature = "std")]
+pub use crate::rotation_logger::LoggerStats;
 pub use crate::rotation_logger::Message;
 pub use crate::rotation_logger::MessageFormatter;
+#[cfg(feature = "std")]
+pub use crate::rotation_logger::MessagePool;
+#[cfg(feature = "std")]
+pub use crate::rotation_logger::ModuleLogger;
+#[cfg(feature = "mmap")]
+pub use crate::rotation_logger::MsyncPolicy;
+#[cfg(all(feature = "oslog", target_vendor = "apple"))]
+pub use crate::rotation_logger::OsLogSink;
+#[cfg(feature = "std")]
 pub use crate::rotation_logger::OutputChannel;
+#[cfg(feature = "std")]
+pub use crate::rotation_logger::OverflowPolicy;
+#[cfg(feature = "std")]
+pub use crate::rotation_logger::RUST_LOG_ENV;
+#[cfg(feature = "std")]
+pub use crate::rotation_logger::Record;
+#[cfg(feature = "std")]
+pub use crate::rotation_logger::RecordFormat;
+#[cfg(feature = "http")]
+pub use crate::rotation_logger::RetryPolicy;
+#[cfg(feature = "file")]
+pub use crate::rotation_logger::RotationPipeline;
+#[cfg(feature = "file")]
+pub use crate::rotation_logger::RotationStage;
+pub use crate::rotation_logger::SCHEMA_VERSION;
+pub use crate::rotation_logger::STATIC_MAX_LEVEL;
+#[cfg(feature = "std")]
+pub use crate::rotation_logger::ScopedLogger;
+#[cfg(all(feature = "file", not(target_arch = "wasm32")))]
+pub use crate::rotation_logger::Search;
+#[cfg(all(feature = "file", not(target_arch = "wasm32")))]
+pub use crate::rotation_logger::SearchMatch;
+#[cfg(all(feature = "file", not(target_arch = "wasm32")))]
+pub use crate::rotation_logger::SearchOptions;
+#[cfg(all(feature = "file", not(target_arch = "wasm32")))]
+pub use crate::rotation_logger::SearchPattern;
+#[cfg(feature = "sentry")]
+pub use crate::rotation_logger::SentrySink;
+#[cfg(feature = "std")]
 pub use crate::rotation_logger::Settings;
+#[cfg(feature = "std")]
+pub use crate::rotation_logger::SettingsBuilder;
+#[cfg(feature = "std")]
+pub use crate::rotation_logger::SharedSink;
+#[cfg(feature = "shm")]
+pub use crate::rotation_logger::ShmConsumer;
+#[cfg(feature = "std")]
+pub use crate::rotation_logger::Sink;
+#[cfg(feature = "slog")]
+pub use crate::rotation_logger::SlogDrain;
+#[cfg(feature = "smtp")]
+pub use crate::rotation_logger::SmtpAlertSink;
+#[cfg(feature = "ssh")]
+pub use crate::rotation_logger::SshAuth;
+#[cfg(feature = "ssh")]
+pub use crate::rotation_logger::SshTransfer;
+#[cfg(feature = "ssh")]
+pub use crate::rotation_logger::SshUploadStage;
+#[cfg(feature = "formatter")]
+pub use crate::rotation_logger::Template;
+#[cfg(feature = "regex")]
+pub use crate::rotation_logger::TextFilter;
+pub use crate::rotation_logger::TimestampFormat;
+pub use crate::rotation_logger::TimestampPrecision;
+#[cfg(feature = "tracing")]
+pub use crate::rotation_logger::TracingLayer;
+pub use crate::rotation_logger::Transport;
+#[cfg(feature = "std")]
+pub use crate::rotation_logger::ValidationReport;
+#[cfg(feature = "webhook")]
+pub use crate::rotation_logger::WebhookSink;
+#[cfg(feature = "std")]
+pub use crate::rotation_logger::WorkerThread;
+#[cfg(feature = "std")]
+pub use crate::rotation_logger::flush_on_exit;
+#[cfg(feature = "std")]
+pub use crate::rotation_logger::init;
+#[cfg(feature = "std")]
+pub use crate::rotation_logger::try_init;
+#[cfg(all(feature = "file", not(target_arch = "wasm32")))]
+pub use crate::rotation_logger::verify_log_set;
 
 mod rotation_logger;
@@ -1,14 +1,216 @@
+#[cfg(feature = "std")]
+mod aggregator;
+#[cfg(feature = "std")]
+mod bridge;
+#[cfg(feature = "std")]
+mod cli;
+#[cfg(feature = "yaml")]
+mod config;
+mod core;
+#[cfg(feature = "std")]
+mod filter;
+#[cfg(feature = "std")]
+mod init;
+#[cfg(feature = "std")]
 mod logger;
+#[cfg(feature = "std")]
 mod macros;
+#[cfg(feature = "file")]
+mod pipeline;
+#[cfg(feature = "std")]
+mod reader;
+#[cfg(feature = "std")]
 mod settings;
-#[cfg(test)]
+#[cfg(feature = "std")]
+mod sink;
+// Tests use files, worker thread and format strings, which are not available on `wasm32`.
+#[cfg(all(
+    test,
+    feature = "file",
+    feature = "timestamps",
+    feature = "formatter",
+    not(target_arch = "wasm32")
+))]
 mod tests;
 
+#[cfg(feature = "formatter")]
+pub use self::core::Align;
+pub use self::core::Color;
+pub use self::core::ColorChoice;
+pub use self::core::ColorTheme;
+pub use self::core::CoreLogger;
+pub use self::core::EmptyModules;
+pub use self::core::FieldValue;
+pub use self::core::Format;
+#[cfg(feature = "formatter")]
+pub use self::core::Grid;
+#[cfg(feature = "formatter")]
+pub use self::core::Humanize;
+pub use self::core::Level;
+pub use self::core::LogText;
+pub use self::core::Message;
+pub use self::core::MessageFormatter;
+pub use self::core::SCHEMA_VERSION;
+pub use self::core::STATIC_MAX_LEVEL;
+#[cfg(feature = "formatter")]
+pub use self::core::Template;
+pub use self::core::TimestampFormat;
+pub use self::core::TimestampPrecision;
+pub use self::core::Transport;
+#[cfg(feature = "std")]
+pub use aggregator::AggregatorAddress;
+#[cfg(feature = "std")]
+pub use aggregator::AggregatorLimits;
+#[cfg(feature = "log")]
+pub use bridge::LogFacade;
+#[cfg(feature = "slog")]
+pub use bridge::SlogDrain;
+#[cfg(feature = "tracing")]
+pub use bridge::TracingLayer;
+#[cfg(feature = "std")]
+pub use cli::CliLogging;
+#[cfg(feature = "yaml")]
+pub use config::Log4rsConfig;
+#[cfg(all(feature = "std", feature = "timestamps"))]
+pub use filter::LevelWindow;
+#[cfg(feature = "regex")]
+pub use filter::TextFilter;
+#[cfg(feature = "std")]
+pub use init::RUST_LOG_ENV;
+#[cfg(feature = "std")]
+pub use init::init;
+#[cfg(feature = "std")]
+pub use init::try_init;
+#[cfg(feature = "std")]
+pub use logger::BacklogWarning;
+#[cfg(feature = "std")]
+pub use logger::Banner;
+#[cfg(feature = "std")]
+pub use logger::DISABLE_ENV;
+#[cfg(feature = "std")]
+pub use logger::FlushFuture;
+#[cfg(feature = "std")]
+pub use logger::FlushGuard;
+#[cfg(feature = "std")]
+#[allow(deprecated)]
 pub use logger::LOG_SENDER;
+#[cfg(feature = "std")]
 pub use logger::Logger;
-pub use logger::Message;
+#[cfg(feature = "std")]
+pub use logger::LoggerStats;
+#[cfg(feature = "std")]
+pub use logger::MessagePool;
+#[cfg(feature = "std")]
+pub use logger::ModuleLogger;
+#[cfg(feature = "std")]
+pub use logger::OverflowPolicy;
+#[cfg(feature = "std")]
+pub use logger::ScopedLogger;
+#[cfg(feature = "std")]
+pub use logger::WorkerThread;
+#[cfg(feature = "std")]
+pub use logger::flush_on_exit;
+#[cfg(feature = "checksum")]
+pub use pipeline::ChecksumStage;
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+pub use pipeline::CompressStage;
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+pub use pipeline::Compression;
+#[cfg(feature = "file")]
+pub use pipeline::DeleteStage;
+#[cfg(feature = "file")]
+pub use pipeline::RotationPipeline;
+#[cfg(feature = "file")]
+pub use pipeline::RotationStage;
+#[cfg(feature = "ssh")]
+pub use pipeline::SshAuth;
+#[cfg(feature = "ssh")]
+pub use pipeline::SshTransfer;
+#[cfg(feature = "ssh")]
+pub use pipeline::SshUploadStage;
+#[cfg(feature = "std")]
+pub use reader::FileHeader;
+#[cfg(feature = "std")]
+pub use reader::LogEntry;
+#[cfg(feature = "std")]
+pub use reader::LogReader;
+#[cfg(feature = "std")]
+pub use reader::RecordFormat;
+#[cfg(all(feature = "file", not(target_arch = "wasm32")))]
+pub use reader::Search;
+#[cfg(all(feature = "file", not(target_arch = "wasm32")))]
+pub use reader::SearchMatch;
+#[cfg(all(feature = "file", not(target_arch = "wasm32")))]
+pub use reader::SearchOptions;
+#[cfg(all(feature = "file", not(target_arch = "wasm32")))]
+pub use reader::SearchPattern;
+#[cfg(all(feature = "file", not(target_arch = "wasm32")))]
+pub use reader::verify_log_set;
+#[cfg(feature = "file")]
+pub use settings::Encoding;
+#[cfg(feature = "file")]
 pub use settings::FileSettings;
+#[cfg(feature = "file")]
+pub use settings::FileSettingsBuilder;
+#[cfg(feature = "file")]
 pub use settings::FileSize;
-pub use settings::MessageFormatter;
+#[cfg(feature = "file")]
+pub use settings::FileWriter;
+#[cfg(feature = "std")]
+pub use settings::FlushPolicy;
+#[cfg(feature = "file")]
+pub use settings::LineEnding;
+#[cfg(feature = "file")]
+pub use settings::LockPolicy;
+#[cfg(feature = "mmap")]
+pub use settings::MsyncPolicy;
+#[cfg(feature = "std")]
 pub use settings::OutputChannel;
+#[cfg(feature = "std")]
 pub use settings::Settings;
+#[cfg(feature = "std")]
+pub use settings::SettingsBuilder;
+#[cfg(feature = "std")]
+pub use settings::ValidationReport;
+#[cfg(feature = "cloudwatch")]
+pub use sink::AwsCredentials;
+#[cfg(feature = "cloudwatch")]
+pub use sink::AwsCredentialsSource;
+#[cfg(feature = "azure")]
+pub use sink::AzureLogAnalyticsSink;
+#[cfg(feature = "chat")]
+pub use sink::ChatNotifierSink;
+#[cfg(feature = "chat")]
+pub use sink::ChatService;
+#[cfg(feature = "cloudwatch")]
+pub use sink::CloudWatchSink;
+#[cfg(feature = "std")]
+pub use sink::ConsoleWriter;
+#[cfg(feature = "gcp")]
+pub use sink::GcpCredentials;
+#[cfg(feature = "gcp")]
+pub use sink::GcpLoggingSink;
+#[cfg(feature = "std")]
+pub use sink::JsonLayout;
+#[cfg(all(feature = "file", not(target_arch = "wasm32")))]
+pub use sink::LogImport;
+#[cfg(all(feature = "android", target_os = "android"))]
+pub use sink::LogcatSink;
+#[cfg(all(feature = "oslog", target_vendor = "apple"))]
+pub use sink::OsLogSink;
+#[cfg(feature = "std")]
+pub use sink::Record;
+#[cfg(feature = "http")]
+pub use sink::RetryPolicy;
+#[cfg(feature = "sentry")]
+pub use sink::SentrySink;
+#[cfg(feature = "std")]
+pub use sink::SharedSink;
+#[cfg(feature = "shm")]
+pub use sink::ShmConsumer;
+#[cfg(feature = "std")]
+pub use sink::Sink;
+#[cfg(feature = "smtp")]
+pub use sink::SmtpAlertSink;
+#[cfg(feature = "webhook")]
+pub use sink::WebhookSink;
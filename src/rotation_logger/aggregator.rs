@@ -0,0 +1,331 @@
+use std::{
+    io::{self, BufReader, BufWriter, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+#[cfg(unix)]
+use std::{
+    os::unix::net::{UnixListener, UnixStream},
+    path::PathBuf,
+};
+
+use crate::rotation_logger::{
+    logger::{Level, Message, queue::Queue},
+    sink::{Record, Sink},
+};
+
+/// Field added to every message received by aggregator.
+const SOURCE_FIELD: &str = "source";
+/// Targets starting with it are kept for logger itself, messages of clients with them
+/// are refused.
+const RESERVED_TARGET: char = '\0';
+/// Sink reconnects after this long without messages,
+/// before aggregator drops connection by `AggregatorLimits::with_read_timeout`.
+const IDLE_RECONNECT: Duration = Duration::from_secs(60);
+
+/// Address where aggregator `Logger` accepts messages of other processes.
+#[derive(Debug, Clone)]
+pub enum AggregatorAddress {
+    Tcp(SocketAddr),
+    #[cfg(unix)]
+    Unix(PathBuf),
+}
+
+/// Limits protecting aggregator from clients, attach with `Settings::with_aggregator_limits`.
+///
+/// Every connection is served by own thread, so their number is limited,
+/// and connection silent longer than read timeout is dropped.
+/// `AggregatorSink` of other process reconnects after a minute without messages,
+/// so read timeout under a minute may lose records of quiet clients.
+///
+/// # Example:
+///
+/// ```
+/// # use std::time::Duration;
+/// # use rotation_logger::AggregatorLimits;
+/// let limits = AggregatorLimits::new()
+///     .with_max_connections(16)
+///     .with_read_timeout(Duration::from_secs(120))
+///     .with_max_frame_size(64 * 1024);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct AggregatorLimits {
+    max_connections: usize,
+    read_timeout: Option<Duration>,
+    max_frame_size: usize,
+}
+
+impl AggregatorLimits {
+    /// 64 connections, dropped after 5 minutes of silence, messages up to 1 MiB.
+    pub fn new() -> Self {
+        Self {
+            max_connections: 64,
+            read_timeout: Some(Duration::from_secs(300)),
+            max_frame_size: 1024 * 1024,
+        }
+    }
+
+    /// Connections served at once, more are closed right after accept.
+    pub fn with_max_connections(mut self, max_connections: usize) -> Self {
+        self.max_connections = max_connections.max(1);
+        self
+    }
+
+    /// Longest wait for next message of connection.
+    pub fn with_read_timeout(mut self, read_timeout: Duration) -> Self {
+        self.read_timeout = Some(read_timeout);
+        self
+    }
+
+    /// Keep silent connections until client closes them.
+    pub fn without_read_timeout(mut self) -> Self {
+        self.read_timeout = None;
+        self
+    }
+
+    /// Largest encoded message in bytes, connection sending bigger one is dropped.
+    pub fn with_max_frame_size(mut self, max_frame_size: usize) -> Self {
+        self.max_frame_size = max_frame_size;
+        self
+    }
+
+    pub fn max_connections(&self) -> usize {
+        self.max_connections
+    }
+    pub fn read_timeout(&self) -> Option<Duration> {
+        self.read_timeout
+    }
+    pub fn max_frame_size(&self) -> usize {
+        self.max_frame_size
+    }
+}
+
+impl Default for AggregatorLimits {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Accept connections on `address` and pass received messages to logger worker.
+///
+/// Every connection starts with frame holding name of source process,
+/// which is added to its messages as `source` field.
+/// Dropped connections and refused ones are reported by warnings written along with messages.
+pub(crate) fn listen(
+    address: &AggregatorAddress,
+    limits: &AggregatorLimits,
+    queue: Arc<Queue>,
+) -> io::Result<JoinHandle<()>> {
+    let limits = limits.clone();
+    let connections = Connections::default();
+    match address {
+        AggregatorAddress::Tcp(address) => {
+            let listener = TcpListener::bind(address)?;
+            Ok(thread::spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    let _ = stream.set_read_timeout(limits.read_timeout);
+                    connections.accept(stream, &limits, &queue);
+                }
+            }))
+        }
+        #[cfg(unix)]
+        AggregatorAddress::Unix(path) => {
+            // Socket file left by previous run blocks bind.
+            let _ = std::fs::remove_file(path);
+            let listener = UnixListener::bind(path)?;
+            Ok(thread::spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    let _ = stream.set_read_timeout(limits.read_timeout);
+                    connections.accept(stream, &limits, &queue);
+                }
+            }))
+        }
+    }
+}
+
+/// Connections served at the moment, shared by their threads.
+#[derive(Default, Clone)]
+struct Connections {
+    active: Arc<AtomicUsize>,
+    /// Refused connection was reported, next one is reported after some connection closes.
+    refusing: Arc<AtomicBool>,
+}
+
+impl Connections {
+    /// Serve `stream` on own thread, or close it when there are too many connections.
+    fn accept(
+        &self,
+        stream: impl Read + Send + 'static,
+        limits: &AggregatorLimits,
+        queue: &Arc<Queue>,
+    ) {
+        // Only accepting thread adds connections, so count can't grow past check.
+        if self.active.load(Ordering::SeqCst) >= limits.max_connections {
+            if !self.refusing.swap(true, Ordering::SeqCst) {
+                let text = format!(
+                    "logger aggregator refuses connections, limit of {} reached",
+                    limits.max_connections
+                );
+                report(queue, None, &text);
+            }
+            return;
+        }
+
+        self.active.fetch_add(1, Ordering::SeqCst);
+        let connections = self.clone();
+        let max_frame_size = limits.max_frame_size;
+        let queue = queue.clone();
+        thread::spawn(move || {
+            serve(stream, max_frame_size, &queue);
+            connections.active.fetch_sub(1, Ordering::SeqCst);
+            connections.refusing.store(false, Ordering::SeqCst);
+        });
+    }
+}
+
+fn serve(stream: impl Read, max_frame_size: usize, queue: &Queue) {
+    let mut reader = BufReader::new(stream);
+    let source = match read_frame(&mut reader, max_frame_size) {
+        Ok(source) => String::from_utf8_lossy(&source).into_owned(),
+        Err(err) => return drop_connection(queue, None, err),
+    };
+
+    loop {
+        let frame = match read_frame(&mut reader, max_frame_size) {
+            Ok(frame) => frame,
+            Err(err) => return drop_connection(queue, Some(&source), err),
+        };
+        let Some(message) = Message::from_bytes(&frame) else {
+            let text = "logger aggregator dropped connection, malformed message";
+            return report(queue, Some(&source), text);
+        };
+        if let Some(target) = message.target()
+            && target.starts_with(RESERVED_TARGET)
+        {
+            let text = format!("logger aggregator refused message with reserved target {target:?}");
+            report(queue, Some(&source), &text);
+            continue;
+        }
+        // Message dropped by full queue is not a reason to drop connection.
+        if !queue.send(message.with_field(SOURCE_FIELD, &source)) && queue.is_closed() {
+            return;
+        }
+    }
+}
+
+/// Report connection dropped by `err`, client closing it is not reported.
+fn drop_connection(queue: &Queue, source: Option<&str>, err: io::Error) {
+    if err.kind() != io::ErrorKind::UnexpectedEof {
+        let text = format!("logger aggregator dropped connection. Error: {err}");
+        report(queue, source, &text);
+    }
+}
+
+/// Warning of aggregator itself, written with received messages.
+fn report(queue: &Queue, source: Option<&str>, text: &str) {
+    let message = Message::new(&["rotation_logger".into()], text).with_level(Level::Warn);
+    let _ = queue.send(match source {
+        Some(source) => message.with_field(SOURCE_FIELD, source),
+        None => message,
+    });
+}
+
+fn read_frame(reader: &mut impl Read, max_frame_size: usize) -> io::Result<Vec<u8>> {
+    let mut len = [0; 4];
+    reader.read_exact(&mut len)?;
+    let len = u32::from_le_bytes(len) as usize;
+    if len > max_frame_size {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("frame of {len} bytes is bigger than {max_frame_size}"),
+        ));
+    }
+
+    let mut frame = vec![0; len];
+    reader.read_exact(&mut frame)?;
+    Ok(frame)
+}
+
+fn write_frame(writer: &mut impl Write, frame: &[u8]) -> io::Result<()> {
+    writer.write_all(&(frame.len() as u32).to_le_bytes())?;
+    writer.write_all(frame)
+}
+
+/// Sends messages to aggregator `Logger` of other process.
+pub(crate) struct AggregatorSink {
+    address: AggregatorAddress,
+    source: String,
+    stream: Option<BufWriter<Box<dyn Write + Send>>>,
+    /// Time of last write to `stream`.
+    written: Instant,
+}
+
+impl AggregatorSink {
+    pub(crate) fn new(address: AggregatorAddress) -> Self {
+        let name = std::env::current_exe()
+            .ok()
+            .and_then(|path| {
+                path.file_stem()
+                    .map(|name| name.to_string_lossy().into_owned())
+            })
+            .unwrap_or_else(|| "unknown".into());
+
+        Self {
+            address,
+            source: format!("{name}[{}]", std::process::id()),
+            stream: None,
+            written: Instant::now(),
+        }
+    }
+
+    fn connect(&self) -> io::Result<BufWriter<Box<dyn Write + Send>>> {
+        let stream: Box<dyn Write + Send> = match &self.address {
+            AggregatorAddress::Tcp(address) => Box::new(TcpStream::connect(address)?),
+            #[cfg(unix)]
+            AggregatorAddress::Unix(path) => Box::new(UnixStream::connect(path)?),
+        };
+        let mut stream = BufWriter::new(stream);
+        write_frame(&mut stream, self.source.as_bytes())?;
+        Ok(stream)
+    }
+
+    fn send(&mut self, records: &[Record]) -> io::Result<()> {
+        // Aggregator may have dropped idle connection, and write to it may still succeed.
+        if self.written.elapsed() > IDLE_RECONNECT {
+            self.stream = None;
+        }
+        if self.stream.is_none() {
+            self.stream = Some(self.connect()?);
+        }
+        let Some(stream) = &mut self.stream else {
+            return Ok(());
+        };
+
+        let result = records
+            .iter()
+            .try_for_each(|record| write_frame(stream, &record.message().to_bytes()))
+            .and_then(|_| stream.flush());
+        match result {
+            Ok(()) => self.written = Instant::now(),
+            Err(_) => self.stream = None,
+        }
+        result
+    }
+}
+
+impl Sink for AggregatorSink {
+    fn write_batch(&mut self, records: &[Record]) -> io::Result<()> {
+        // Stale connection is found out only on write, retry with new one.
+        let had_stream = self.stream.is_some();
+        match self.send(records) {
+            Err(_) if had_stream => self.send(records),
+            result => result,
+        }
+    }
+}
@@ -0,0 +1,18 @@
+//! # Adapters for other logging frontends.
+//!
+//! Records of other logging crates are converted to `Message`s and passed
+//! to running `Logger` worker, same as `log!` macros do.
+//!
+#[cfg(feature = "log")]
+mod log;
+#[cfg(feature = "slog")]
+mod slog;
+#[cfg(feature = "tracing")]
+mod tracing;
+
+#[cfg(feature = "log")]
+pub use log::LogFacade;
+#[cfg(feature = "slog")]
+pub use slog::SlogDrain;
+#[cfg(feature = "tracing")]
+pub use tracing::TracingLayer;
@@ -0,0 +1,139 @@
+use std::time::Duration;
+
+use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+
+use crate::rotation_logger::{
+    Logger,
+    logger::{Level, Message, flush},
+    settings::LevelFilter as Levels,
+};
+
+/// How long `log::logger().flush()` waits for worker.
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// `log` crate logger writing records through `Logger` worker, installed by `Logger::init_log_facade`.
+///
+/// Target of record, module path by default, becomes modules split by `::`.
+/// Target set by `log::info!(target: "wire", ..)` is kept as target of message too.
+#[derive(Debug, Clone)]
+pub struct LogFacade {
+    /// Levels of `Settings`, with module levels, `None` for disabled `Logger`.
+    levels: Option<Levels>,
+    send: fn(Message),
+}
+
+impl LogFacade {
+    pub fn new(logger: &Logger) -> Self {
+        Self {
+            levels: match logger {
+                Logger::Enabled(settings) => Some(settings.levels().clone()),
+                Logger::Disabled => None,
+            },
+            send: Logger::send,
+        }
+    }
+
+    /// Pass messages to `send` instead of worker.
+    #[cfg(all(
+        test,
+        feature = "file",
+        feature = "timestamps",
+        feature = "formatter",
+        not(target_arch = "wasm32")
+    ))]
+    pub(crate) fn with_send(mut self, send: fn(Message)) -> Self {
+        self.send = send;
+        self
+    }
+
+    fn level(level: log::Level) -> Level {
+        match level {
+            log::Level::Error => Level::Error,
+            log::Level::Warn => Level::Warn,
+            log::Level::Info => Level::Info,
+            log::Level::Debug => Level::Debug,
+            log::Level::Trace => Level::Trace,
+        }
+    }
+
+    fn allows(&self, level: Level, modules: &[&str]) -> bool {
+        level.is_statically_enabled()
+            && self
+                .levels
+                .as_ref()
+                .is_some_and(|levels| levels.is_enabled_for(level, modules))
+    }
+
+    /// Modules of `db::pool` like target.
+    fn modules(target: &str) -> Vec<&str> {
+        target
+            .split("::")
+            .filter(|module| !module.is_empty())
+            .collect()
+    }
+
+    /// Most verbose level of `log` macros let through to `enabled`,
+    /// the most verbose one of any module.
+    pub(crate) fn max_level(&self) -> LevelFilter {
+        match self.levels.as_ref().map(Levels::max_level) {
+            None => LevelFilter::Off,
+            Some(Level::Error) => LevelFilter::Error,
+            Some(Level::Warn) => LevelFilter::Warn,
+            Some(Level::Info) => LevelFilter::Info,
+            Some(Level::Debug) => LevelFilter::Debug,
+            Some(Level::Trace) => LevelFilter::Trace,
+        }
+    }
+}
+
+impl Log for LogFacade {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        self.allows(
+            Self::level(metadata.level()),
+            &Self::modules(metadata.target()),
+        )
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        let level = Self::level(record.level());
+        let modules = Self::modules(record.target());
+        if !self.allows(level, &modules) {
+            return;
+        }
+
+        let modules: Vec<String> = modules.into_iter().map(String::from).collect();
+        let mut message = Message::new(&modules, &record.args().to_string()).with_level(level);
+        if record.module_path() != Some(record.target()) {
+            message = message.with_target(record.target());
+        }
+        (self.send)(message);
+    }
+
+    fn flush(&self) {
+        let _ = flush::request(FLUSH_TIMEOUT);
+    }
+}
+
+impl Logger {
+    /// Install global logger of `log` crate, so `log::info!` and others, also in dependencies,
+    /// go to worker of this logger. Error when other logger is installed already.
+    ///
+    /// # Example:
+    ///
+    /// ```no_run
+    /// # use rotation_logger::{Logger, Settings};
+    /// let logger = Logger::new(Settings::default());
+    /// logger.run_async();
+    /// logger.init_log_facade().unwrap();
+    ///
+    /// log::info!("started"); // modules: name of this crate
+    /// log::warn!(target: "db::pool", "exhausted"); // modules: db, pool
+    /// ```
+    pub fn init_log_facade(&self) -> Result<(), SetLoggerError> {
+        let facade = LogFacade::new(self);
+        let max_level = facade.max_level();
+        log::set_boxed_logger(Box::new(facade))?;
+        log::set_max_level(max_level);
+        Ok(())
+    }
+}
@@ -0,0 +1,120 @@
+use std::fmt;
+
+use slog::{Drain, KV, Key, OwnedKVList, Serializer};
+
+use crate::rotation_logger::{
+    FieldValue, Logger,
+    logger::{Level, Message},
+};
+
+/// `slog` drain writing records through `Logger` worker.
+/// Module path of record becomes modules, key-values of record and loggers become fields.
+///
+/// # Example:
+///
+/// ```no_run
+/// # use rotation_logger::{Logger, Settings, SlogDrain};
+/// let logger = Logger::new(Settings::default());
+/// logger.run_async();
+///
+/// let root = slog::Logger::root(SlogDrain::new(&logger), slog::o!("app" => "demo"));
+/// slog::info!(root, "started"; "port" => 8080);
+/// ```
+#[derive(Debug, Clone)]
+pub struct SlogDrain {
+    /// Least important level passed, `None` for disabled `Logger`.
+    min_level: Option<Level>,
+}
+
+impl SlogDrain {
+    pub fn new(logger: &Logger) -> Self {
+        Self {
+            min_level: match logger {
+                Logger::Enabled(settings) => Some(settings.min_level()),
+                Logger::Disabled => None,
+            },
+        }
+    }
+
+    fn level(level: slog::Level) -> Level {
+        match level {
+            slog::Level::Critical | slog::Level::Error => Level::Error,
+            slog::Level::Warning => Level::Warn,
+            slog::Level::Info => Level::Info,
+            slog::Level::Debug => Level::Debug,
+            slog::Level::Trace => Level::Trace,
+        }
+    }
+
+    fn allows(&self, level: Level) -> bool {
+        level.is_statically_enabled() && self.min_level.is_some_and(|min_level| level <= min_level)
+    }
+}
+
+impl Drain for SlogDrain {
+    type Ok = ();
+    type Err = slog::Never;
+
+    fn log(&self, record: &slog::Record<'_>, values: &OwnedKVList) -> Result<(), slog::Never> {
+        let level = Self::level(record.level());
+        if !self.allows(level) {
+            return Ok(());
+        }
+
+        let modules: Vec<String> = record.module().split("::").map(String::from).collect();
+        let mut fields = Fields(vec![]);
+        let _ = record.kv().serialize(record, &mut fields);
+        let _ = values.serialize(record, &mut fields);
+
+        let message = fields.0.into_iter().fold(
+            Message::new(&modules, &record.msg().to_string()).with_level(level),
+            |message, (key, value)| message.with_field(&key, value),
+        );
+        Logger::send(message);
+        Ok(())
+    }
+
+    fn is_enabled(&self, level: slog::Level) -> bool {
+        self.allows(Self::level(level))
+    }
+}
+
+/// Collects key-values, numbers and booleans keep their type, the rest becomes strings.
+struct Fields(Vec<(String, FieldValue)>);
+
+impl Fields {
+    fn push(&mut self, key: Key, value: impl Into<FieldValue>) -> slog::Result {
+        self.0.push((key.to_string(), value.into()));
+        Ok(())
+    }
+}
+
+impl Serializer for Fields {
+    fn emit_arguments(&mut self, key: Key, value: &fmt::Arguments<'_>) -> slog::Result {
+        self.push(key, value.to_string())
+    }
+
+    fn emit_bool(&mut self, key: Key, value: bool) -> slog::Result {
+        self.push(key, value)
+    }
+
+    fn emit_i64(&mut self, key: Key, value: i64) -> slog::Result {
+        self.push(key, value)
+    }
+
+    fn emit_u64(&mut self, key: Key, value: u64) -> slog::Result {
+        self.push(key, value)
+    }
+
+    fn emit_i32(&mut self, key: Key, value: i32) -> slog::Result {
+        self.push(key, value)
+    }
+
+    fn emit_u32(&mut self, key: Key, value: u32) -> slog::Result {
+        self.push(key, value)
+    }
+
+    fn emit_f64(&mut self, key: Key, value: f64) -> slog::Result {
+        self.push(key, value)
+    }
+}
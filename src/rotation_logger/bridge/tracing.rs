@@ -0,0 +1,183 @@
+use std::fmt;
+
+use tracing_core::{
+    Event, Metadata, Subscriber,
+    field::{Field, Visit},
+};
+use tracing_subscriber::{layer::Context, registry::LookupSpan};
+
+use crate::rotation_logger::{
+    FieldValue, Logger,
+    logger::{Level, Message},
+};
+
+/// `tracing_subscriber` layer writing events through `Logger` worker.
+///
+/// Modules are names of active spans, from the outermost one, e.g. `HTTP::handler::db_query`.
+/// Events outside of spans use their target split by `::`.
+/// Fields of event other than `message` become fields of message, or part of its text
+/// with `with_fields_in_text`, for outputs showing text only.
+///
+/// # Example:
+///
+/// ```no_run
+/// # use rotation_logger::{Logger, Settings, TracingLayer};
+/// use tracing_subscriber::layer::SubscriberExt;
+///
+/// let logger = Logger::new(Settings::default());
+/// logger.run_async();
+///
+/// let subscriber = tracing_subscriber::registry().with(TracingLayer::new(&logger));
+/// tracing_core::dispatcher::set_global_default(subscriber.into()).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct TracingLayer {
+    /// Least important level passed, `None` for disabled `Logger`.
+    min_level: Option<Level>,
+    /// Fields appended to text as `key=value`, instead of message fields.
+    fields_in_text: bool,
+    send: fn(Message),
+}
+
+impl TracingLayer {
+    pub fn new(logger: &Logger) -> Self {
+        Self {
+            min_level: match logger {
+                Logger::Enabled(settings) => Some(settings.min_level()),
+                Logger::Disabled => None,
+            },
+            fields_in_text: false,
+            send: Logger::send,
+        }
+    }
+
+    /// Write fields of event into message text, e.g. `slow query rows=3`.
+    pub fn with_fields_in_text(mut self, fields_in_text: bool) -> Self {
+        self.fields_in_text = fields_in_text;
+        self
+    }
+
+    pub fn fields_in_text(&self) -> bool {
+        self.fields_in_text
+    }
+
+    /// Pass messages to `send` instead of worker.
+    #[cfg(all(
+        test,
+        feature = "file",
+        feature = "timestamps",
+        feature = "formatter",
+        not(target_arch = "wasm32")
+    ))]
+    pub(crate) fn with_send(mut self, send: fn(Message)) -> Self {
+        self.send = send;
+        self
+    }
+
+    fn level(level: &tracing_core::Level) -> Level {
+        match *level {
+            tracing_core::Level::ERROR => Level::Error,
+            tracing_core::Level::WARN => Level::Warn,
+            tracing_core::Level::INFO => Level::Info,
+            tracing_core::Level::DEBUG => Level::Debug,
+            _ => Level::Trace,
+        }
+    }
+
+    fn allows(&self, level: Level) -> bool {
+        level.is_statically_enabled() && self.min_level.is_some_and(|min_level| level <= min_level)
+    }
+}
+
+impl<S> tracing_subscriber::Layer<S> for TracingLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn enabled(&self, metadata: &Metadata<'_>, _ctx: Context<'_, S>) -> bool {
+        // Spans are needed for modules of events inside them.
+        metadata.is_span() || self.allows(Self::level(metadata.level()))
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let level = Self::level(event.metadata().level());
+        if !self.allows(level) {
+            return;
+        }
+
+        let modules: Vec<String> = match ctx.event_scope(event) {
+            Some(scope) => scope
+                .from_root()
+                .map(|span| span.name().to_string())
+                .collect(),
+            None => event
+                .metadata()
+                .target()
+                .split("::")
+                .map(String::from)
+                .collect(),
+        };
+
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+
+        if self.fields_in_text {
+            let mut text = fields.message;
+            for (key, value) in fields.fields {
+                if !text.is_empty() {
+                    text.push(' ');
+                }
+                text.push_str(&format!("{key}={value}"));
+            }
+            (self.send)(Message::new(&modules, &text).with_level(level));
+            return;
+        }
+
+        let message = fields.fields.into_iter().fold(
+            Message::new(&modules, &fields.message).with_level(level),
+            |message, (key, value)| message.with_field(key, value),
+        );
+        (self.send)(message);
+    }
+}
+
+/// `message` field of event and the rest of fields.
+#[derive(Default)]
+struct Fields {
+    message: String,
+    fields: Vec<(&'static str, FieldValue)>,
+}
+
+impl Visit for Fields {
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.fields.push((field.name(), value.into()));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.fields.push((field.name(), value.into()));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.fields.push((field.name(), value.into()));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.fields.push((field.name(), value.into()));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = value.into();
+        } else {
+            self.fields.push((field.name(), value.into()));
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{value:?}");
+        } else {
+            self.fields
+                .push((field.name(), format!("{value:?}").into()));
+        }
+    }
+}
@@ -0,0 +1,152 @@
+//! # Logging setup from command line flags.
+//!
+//! Maps `-q`/`-v` counts and `--log-file PATH` of CLI applications into `Settings`:
+//! - `-qq` and more: logger disabled
+//! - `-q`: `Level::Warn`
+//! - none: `Level::Info`
+//! - `-v`: `Level::Debug`
+//! - `-vv` and more: `Level::Trace`
+//!
+//! Without `--log-file` lines go to console.
+//!
+//! # Example:
+//!
+//! ```no_run
+//! # use rotation_logger::{CliLogging, Logger};
+//! // Parse `std::env::args` directly...
+//! let logger = Logger::new(CliLogging::from_env().settings());
+//!
+//! // ...or take counts already parsed by argument parser, e.g. clap `ArgAction::Count`.
+//! let settings = CliLogging::new(2, 0).settings();
+//! ```
+//!
+#[cfg(feature = "file")]
+use std::path::PathBuf;
+
+#[cfg(feature = "file")]
+use crate::rotation_logger::{FileSettings, FileSize};
+use crate::rotation_logger::{MessageFormatter, OutputChannel, Settings, logger::Level};
+
+/// Verbosity and output requested on command line.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CliLogging {
+    verbose: u8,
+    quiet: u8,
+    #[cfg(feature = "file")]
+    log_file: Option<PathBuf>,
+}
+
+impl CliLogging {
+    pub fn new(verbose: u8, quiet: u8) -> Self {
+        Self {
+            verbose,
+            quiet,
+            #[cfg(feature = "file")]
+            log_file: None,
+        }
+    }
+
+    /// Write rotated files at `path` instead of console,
+    /// its file name and extension become name of log files.
+    #[cfg(feature = "file")]
+    pub fn with_log_file(mut self, path: PathBuf) -> Self {
+        self.log_file = Some(path);
+        self
+    }
+
+    /// Collect `-q`, `-v` (also combined as `-vv`), `--quiet`, `--verbose`
+    /// and `--log-file PATH` (or `--log-file=PATH`), other arguments are ignored.
+    // `--log-file` takes next argument from iterator.
+    #[cfg_attr(not(feature = "file"), allow(clippy::while_let_on_iterator))]
+    pub fn from_args<I, S>(args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut result = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let arg = arg.as_ref();
+            match arg {
+                "--verbose" => result.verbose = result.verbose.saturating_add(1),
+                "--quiet" => result.quiet = result.quiet.saturating_add(1),
+                #[cfg(feature = "file")]
+                "--log-file" => result.log_file = args.next().map(|path| path.as_ref().into()),
+                #[cfg(feature = "file")]
+                _ if arg.starts_with("--log-file=") => {
+                    result.log_file = Some(arg["--log-file=".len()..].into())
+                }
+                _ if arg.len() > 1
+                    && arg.starts_with('-')
+                    && !arg.starts_with("--")
+                    && arg[1..].chars().all(|flag| flag == 'v' || flag == 'q') =>
+                {
+                    for flag in arg[1..].chars() {
+                        match flag {
+                            'v' => result.verbose = result.verbose.saturating_add(1),
+                            _ => result.quiet = result.quiet.saturating_add(1),
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        result
+    }
+
+    /// Flags of current process, without program name.
+    pub fn from_env() -> Self {
+        Self::from_args(std::env::args().skip(1))
+    }
+
+    /// Least important level to log, `None` when logging is turned off.
+    pub fn level(&self) -> Option<Level> {
+        match self.verbose as i16 - self.quiet as i16 {
+            ..=-2 => None,
+            -1 => Some(Level::Warn),
+            0 => Some(Level::Info),
+            1 => Some(Level::Debug),
+            2.. => Some(Level::Trace),
+        }
+    }
+
+    pub fn settings(&self) -> Settings {
+        let output = self.output();
+        // Short-lived tools may exit before buffer is filled, so every line is written at once.
+        match self.level() {
+            Some(level) => {
+                Settings::new(true, 1, output, MessageFormatter::default()).with_min_level(level)
+            }
+            None => Settings::new(false, 1, output, MessageFormatter::default()),
+        }
+    }
+
+    fn output(&self) -> OutputChannel {
+        #[cfg(feature = "file")]
+        if let Some(path) = &self.log_file {
+            let name = |value: Option<&std::ffi::OsStr>, default: &str| {
+                value
+                    .map(|value| value.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| default.into())
+            };
+            let defaults = FileSettings::default();
+            return OutputChannel::File(FileSettings::new(
+                path.parent()
+                    .filter(|dir| !dir.as_os_str().is_empty())
+                    .map(|dir| dir.to_path_buf())
+                    .unwrap_or_else(|| ".".into()),
+                defaults.capacity(),
+                FileSize::default(),
+                name(path.file_stem(), defaults.filename()),
+                name(path.extension(), defaults.file_extension()),
+            ));
+        }
+        OutputChannel::Console
+    }
+}
+
+impl From<CliLogging> for Settings {
+    fn from(value: CliLogging) -> Self {
+        value.settings()
+    }
+}
@@ -0,0 +1,7 @@
+//! # Settings from configuration files of other loggers.
+//!
+#[cfg(feature = "yaml")]
+mod log4rs;
+
+#[cfg(feature = "yaml")]
+pub use log4rs::Log4rsConfig;
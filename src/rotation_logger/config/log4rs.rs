@@ -0,0 +1,308 @@
+use std::{collections::HashMap, fs, io, path::Path};
+
+use serde::Deserialize;
+
+use crate::rotation_logger::{
+    FileSettings, FileSize, MessageFormatter, OutputChannel, Settings, Template, logger::Level,
+};
+
+/// `log4rs` YAML config, translated to `Settings` to ease migration.
+///
+/// First appender of `root` becomes output, supported kinds are `console`, `file` and
+/// `rolling_file` with `size` trigger and `fixed_window` roller. Pattern is translated to
+/// format string: `{d}`/`{d(<chrono format>)}` to timestamp, `{m}` to message,
+/// `{M}`/`{t}` to modules, `{X}` to fields, other specifiers are dropped.
+/// Loggers other than `root` and `refresh_rate` are ignored.
+///
+/// # Example:
+///
+/// ```
+/// # use rotation_logger::Log4rsConfig;
+/// let settings = Log4rsConfig::from_yaml(
+///     r#"
+/// appenders:
+///   file:
+///     kind: rolling_file
+///     path: logs/app.log
+///     encoder:
+///       pattern: "{d(%H:%M:%S)} {M} - {m}{n}"
+///     policy:
+///       trigger:
+///         kind: size
+///         limit: 10 mb
+///       roller:
+///         kind: fixed_window
+///         count: 5
+/// root:
+///   level: info
+///   appenders:
+///     - file
+/// "#,
+/// )
+/// .unwrap()
+/// .settings()
+/// .unwrap();
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct Log4rsConfig {
+    #[serde(default)]
+    appenders: HashMap<String, Appender>,
+    root: Root,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Root {
+    #[serde(default)]
+    level: Option<String>,
+    #[serde(default)]
+    appenders: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Appender {
+    kind: String,
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default)]
+    encoder: Option<Encoder>,
+    #[serde(default)]
+    policy: Option<Policy>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Encoder {
+    #[serde(default)]
+    pattern: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Policy {
+    #[serde(default)]
+    trigger: Option<Trigger>,
+    #[serde(default)]
+    roller: Option<Roller>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Trigger {
+    kind: String,
+    #[serde(default)]
+    limit: Option<serde_yaml::Value>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Roller {
+    kind: String,
+    #[serde(default)]
+    count: Option<usize>,
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+impl Log4rsConfig {
+    pub fn from_yaml(yaml: &str) -> io::Result<Self> {
+        serde_yaml::from_str(yaml).map_err(|err| invalid(err.to_string()))
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Self::from_yaml(&fs::read_to_string(path)?)
+    }
+
+    /// Translate config, fails on unknown appender kinds and malformed values.
+    pub fn settings(&self) -> io::Result<Settings> {
+        let appender = match self.root.appenders.first() {
+            Some(name) => Some(
+                self.appenders
+                    .get(name)
+                    .ok_or_else(|| invalid(format!("appender `{name}` is not declared")))?,
+            ),
+            None => None,
+        };
+
+        let (output, formatter) = match appender {
+            Some(appender) => (appender.output()?, appender.formatter()),
+            None => (OutputChannel::Console, MessageFormatter::default()),
+        };
+
+        let level = self.root.level.as_deref().unwrap_or("debug");
+        let settings = Settings::new(true, Settings::default().buffer_size(), output, formatter);
+        Ok(match level.to_lowercase().as_str() {
+            "off" => Settings::new(
+                false,
+                settings.buffer_size(),
+                settings.output().clone(),
+                MessageFormatter::default(),
+            ),
+            "error" => settings.with_min_level(Level::Error),
+            "warn" => settings.with_min_level(Level::Warn),
+            "info" => settings.with_min_level(Level::Info),
+            "debug" => settings.with_min_level(Level::Debug),
+            "trace" => settings.with_min_level(Level::Trace),
+            _ => return Err(invalid(format!("unknown level `{level}`"))),
+        })
+    }
+}
+
+impl TryFrom<Log4rsConfig> for Settings {
+    type Error = io::Error;
+
+    fn try_from(value: Log4rsConfig) -> io::Result<Self> {
+        value.settings()
+    }
+}
+
+impl Appender {
+    fn output(&self) -> io::Result<OutputChannel> {
+        match self.kind.as_str() {
+            "console" => Ok(OutputChannel::Console),
+            "file" | "rolling_file" => {
+                let path = self
+                    .path
+                    .as_deref()
+                    .ok_or_else(|| invalid(format!("`{}` appender without path", self.kind)))?;
+                let path = Path::new(path);
+                let defaults = FileSettings::default();
+                let mut capacity = defaults.capacity();
+                let mut file_size = FileSize::default();
+
+                if let Some(policy) = &self.policy {
+                    if let Some(trigger) = &policy.trigger {
+                        if trigger.kind != "size" {
+                            return Err(invalid(format!("unsupported trigger `{}`", trigger.kind)));
+                        }
+                        if let Some(limit) = &trigger.limit {
+                            file_size = FileSize::from_bytes(parse_size(limit)?);
+                        }
+                    }
+                    if let Some(roller) = &policy.roller {
+                        match roller.kind.as_str() {
+                            "fixed_window" => capacity = roller.count.unwrap_or(capacity),
+                            // Rotated files are kept anyway, removing is up to pipeline.
+                            "delete" => {}
+                            kind => return Err(invalid(format!("unsupported roller `{kind}`"))),
+                        }
+                    }
+                }
+
+                let name = |value: Option<&std::ffi::OsStr>, default: &str| {
+                    value
+                        .map(|value| value.to_string_lossy().into_owned())
+                        .unwrap_or_else(|| default.into())
+                };
+                Ok(OutputChannel::File(FileSettings::new(
+                    path.parent()
+                        .filter(|dir| !dir.as_os_str().is_empty())
+                        .map(|dir| dir.to_path_buf())
+                        .unwrap_or_else(|| ".".into()),
+                    capacity,
+                    file_size,
+                    name(path.file_stem(), defaults.filename()),
+                    name(path.extension(), defaults.file_extension()),
+                )))
+            }
+            kind => Err(invalid(format!("unsupported appender kind `{kind}`"))),
+        }
+    }
+
+    fn formatter(&self) -> MessageFormatter {
+        match self
+            .encoder
+            .as_ref()
+            .and_then(|encoder| encoder.pattern.as_ref())
+        {
+            Some(pattern) => {
+                let mut timestamp = "%Y-%m-%dT%H:%M:%S%.f%:z".to_string();
+                let template = translate_pattern(pattern, &mut timestamp);
+                MessageFormatter::from_template("::", template, &timestamp)
+            }
+            None => MessageFormatter::default(),
+        }
+    }
+}
+
+/// `10 mb`, `512kb` or plain number of bytes.
+fn parse_size(limit: &serde_yaml::Value) -> io::Result<usize> {
+    if let Some(bytes) = limit.as_u64() {
+        return Ok(bytes as usize);
+    }
+    let limit = limit.as_str().unwrap_or_default().trim().to_lowercase();
+    let split = limit
+        .find(|char: char| !char.is_ascii_digit())
+        .unwrap_or(limit.len());
+    let (number, unit) = limit.split_at(split);
+    let number: usize = number
+        .parse()
+        .map_err(|_| invalid(format!("wrong size limit `{limit}`")))?;
+    let multiplier = match unit.trim() {
+        "" | "b" => 1,
+        "kb" => 1024,
+        "mb" => 1024 * 1024,
+        "gb" => 1024 * 1024 * 1024,
+        unit => return Err(invalid(format!("unknown size unit `{unit}`"))),
+    };
+    Ok(number * multiplier)
+}
+
+/// Pattern to template, date format found in pattern is stored to `timestamp`.
+fn translate_pattern(pattern: &str, timestamp: &mut String) -> Template {
+    let mut template = Template::new();
+    let mut text = String::new();
+    let mut chars = pattern.chars();
+
+    while let Some(char) = chars.next() {
+        match char {
+            '{' => {
+                if !text.is_empty() {
+                    template = template.text(&std::mem::take(&mut text));
+                }
+                let mut spec = String::new();
+                let mut depth = 1;
+                for char in chars.by_ref() {
+                    match char {
+                        '{' => depth += 1,
+                        '}' => depth -= 1,
+                        _ => {}
+                    }
+                    if depth == 0 {
+                        break;
+                    }
+                    spec.push(char);
+                }
+                let (name, argument) = match spec.split_once('(') {
+                    Some((name, argument)) => (name, argument.strip_suffix(')')),
+                    None => (spec.as_str(), None),
+                };
+                // Unpadded and not cut, as in log4rs without format modifiers.
+                template = match name {
+                    "d" | "date" => {
+                        if let Some(argument) = argument {
+                            *timestamp = argument.into();
+                        }
+                        template.timestamp().length(0).width(0)
+                    }
+                    "m" | "message" => template.message().length(0).width(0),
+                    "M" | "module" | "t" | "target" => template.modules().length(0).width(0),
+                    "X" | "mdc" => template.fields().length(0).width(0),
+                    // Highlight wraps other specifiers.
+                    "h" | "highlight" => {
+                        template.then(translate_pattern(argument.unwrap_or_default(), timestamp))
+                    }
+                    _ => template,
+                };
+            }
+            // Escaped braces and parentheses.
+            '\\' => {
+                if let Some(char) = chars.next() {
+                    text.push(char);
+                }
+            }
+            _ => text.push(char),
+        }
+    }
+    if !text.is_empty() {
+        template = template.text(&text);
+    }
+    template
+}
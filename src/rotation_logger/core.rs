@@ -0,0 +1,62 @@
+//! # Core of logger, available without `std`.
+//!
+//! Message construction and formatting need only `alloc`, so they can be used on embedded targets.
+//! There `CoreLogger` writes formatted lines right away to `Transport`, e.g. serial port.
+//!
+//! # Example:
+//!
+//! ```
+//! # use rotation_logger::{CoreLogger, Message, MessageFormatter, Transport};
+//! struct Serial;
+//!
+//! impl Transport for Serial {
+//!     fn write_line(&mut self, line: &str) {
+//!         // Send line and `\r\n` to UART.
+//!     }
+//! }
+//!
+//! let mut logger = CoreLogger::new(MessageFormatter::default(), Serial);
+//! let timestamp = chrono::DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+//! logger.log_at(&Message::new(&[], "booted"), &timestamp);
+//! // Or without time, where there is no clock.
+//! logger.log(&Message::new(&[], "ready"));
+//! ```
+//!
+mod color;
+mod field;
+mod format;
+mod formatter;
+mod level;
+mod message;
+mod transport;
+
+pub use color::Color;
+pub use color::ColorChoice;
+pub use color::ColorTheme;
+pub use field::FieldValue;
+pub use format::Format;
+#[cfg(feature = "std")]
+pub(crate) use format::SharedFormat;
+#[cfg(feature = "formatter")]
+pub use formatter::Align;
+#[cfg(all(feature = "std", feature = "formatter", not(target_arch = "wasm32")))]
+pub(crate) use formatter::Columns;
+#[cfg(feature = "std")]
+pub(crate) use formatter::Deltas;
+pub use formatter::EmptyModules;
+#[cfg(feature = "formatter")]
+pub use formatter::Grid;
+#[cfg(feature = "formatter")]
+pub use formatter::Humanize;
+pub use formatter::MessageFormatter;
+#[cfg(feature = "formatter")]
+pub use formatter::Template;
+pub use formatter::TimestampFormat;
+pub use formatter::TimestampPrecision;
+pub use level::Level;
+pub use level::STATIC_MAX_LEVEL;
+pub use message::LogText;
+pub use message::Message;
+pub use message::SCHEMA_VERSION;
+pub use transport::CoreLogger;
+pub use transport::Transport;
@@ -0,0 +1,182 @@
+//! # Colors of console output.
+//!
+//! `ColorTheme` paints parts of formatted line with ANSI escape sequences:
+//! message text by its level, modules by hash of their names, so each module keeps
+//! its color between runs, and dimmed timestamp. Padding is applied before painting,
+//! so columns stay aligned.
+//!
+//! Whether theme is used is decided by `ColorChoice`, see `Settings::with_color_choice`.
+//!
+//! # Example:
+//!
+//! ```
+//! # use rotation_logger::{Color, ColorTheme, Level};
+//! let theme = ColorTheme::default()
+//!     .with_level(Level::Info, None)
+//!     .with_level(Level::Debug, Some(Color::Ansi256(244)))
+//!     .with_module_hashing(false);
+//! ```
+//!
+use alloc::{format, string::String};
+
+use crate::rotation_logger::core::Level;
+
+/// Terminal color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    BrightBlack,
+    BrightRed,
+    BrightGreen,
+    BrightYellow,
+    BrightBlue,
+    BrightMagenta,
+    BrightCyan,
+    BrightWhite,
+    /// Color of 256-color palette.
+    Ansi256(u8),
+    Rgb(u8, u8, u8),
+}
+
+impl Color {
+    /// SGR parameters of foreground color.
+    fn sgr(&self) -> String {
+        let code = match self {
+            Color::Black => 30,
+            Color::Red => 31,
+            Color::Green => 32,
+            Color::Yellow => 33,
+            Color::Blue => 34,
+            Color::Magenta => 35,
+            Color::Cyan => 36,
+            Color::White => 37,
+            Color::BrightBlack => 90,
+            Color::BrightRed => 91,
+            Color::BrightGreen => 92,
+            Color::BrightYellow => 93,
+            Color::BrightBlue => 94,
+            Color::BrightMagenta => 95,
+            Color::BrightCyan => 96,
+            Color::BrightWhite => 97,
+            Color::Ansi256(index) => return format!("38;5;{index}"),
+            Color::Rgb(red, green, blue) => return format!("38;2;{red};{green};{blue}"),
+        };
+        format!("{code}")
+    }
+}
+
+/// When console output is painted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Always, with default theme if none is set.
+    Always,
+    /// With color theme set, when stdout is terminal and `NO_COLOR` is not set.
+    #[default]
+    Auto,
+    Never,
+}
+
+/// Colors used for modules when hashing is on.
+const MODULE_PALETTE: [Color; 6] = [
+    Color::Cyan,
+    Color::Magenta,
+    Color::Blue,
+    Color::Green,
+    Color::Yellow,
+    Color::BrightCyan,
+];
+
+/// Console colors of log line parts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColorTheme {
+    /// Colors of message text, from `Level::Error` to `Level::Trace`.
+    levels: [Option<Color>; 5],
+    /// Color modules by hash of their names.
+    module_hashing: bool,
+    /// Draw timestamp dimmed.
+    timestamp_dimming: bool,
+}
+
+impl Default for ColorTheme {
+    fn default() -> Self {
+        Self {
+            levels: [
+                Some(Color::Red),
+                Some(Color::Yellow),
+                None,
+                Some(Color::Blue),
+                Some(Color::BrightBlack),
+            ],
+            module_hashing: true,
+            timestamp_dimming: true,
+        }
+    }
+}
+
+impl ColorTheme {
+    /// Theme without any colors, to be filled with `with_*`.
+    pub fn plain() -> Self {
+        Self {
+            levels: [None; 5],
+            module_hashing: false,
+            timestamp_dimming: false,
+        }
+    }
+
+    /// Color of message text for `level`, `None` for terminal default.
+    pub fn with_level(mut self, level: Level, color: Option<Color>) -> Self {
+        self.levels[level as usize] = color;
+        self
+    }
+
+    pub fn with_module_hashing(mut self, module_hashing: bool) -> Self {
+        self.module_hashing = module_hashing;
+        self
+    }
+
+    pub fn with_timestamp_dimming(mut self, timestamp_dimming: bool) -> Self {
+        self.timestamp_dimming = timestamp_dimming;
+        self
+    }
+
+    pub fn level(&self, level: Level) -> Option<Color> {
+        self.levels[level as usize]
+    }
+
+    pub(crate) fn paint(&self, part: Part, value: &str, level: Level) -> String {
+        let sgr = match part {
+            Part::Timestamp if self.timestamp_dimming => "2".into(),
+            Part::Modules if self.module_hashing && !value.trim().is_empty() => {
+                // FNV-1a, stable between runs and builds.
+                let hash = value.trim().bytes().fold(0x811c9dc5_u32, |hash, byte| {
+                    (hash ^ byte as u32).wrapping_mul(0x01000193)
+                });
+                MODULE_PALETTE[hash as usize % MODULE_PALETTE.len()].sgr()
+            }
+            Part::Message => match self.level(level) {
+                Some(color) => color.sgr(),
+                None => return value.into(),
+            },
+            _ => return value.into(),
+        };
+        if value.is_empty() {
+            return value.into();
+        }
+        format!("\x1b[{sgr}m{value}\x1b[0m")
+    }
+}
+
+/// Painted part of log line.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Part {
+    Timestamp,
+    Modules,
+    Message,
+}
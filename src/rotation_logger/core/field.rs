@@ -0,0 +1,203 @@
+use alloc::string::{String, ToString};
+use core::fmt::{self, Display};
+
+/// Value of structured field of `Message`, kept typed so JSON output has numbers and booleans.
+/// Text masks and line sinks show it same as `Display` of original value.
+///
+/// # Example:
+///
+/// ```
+/// # use rotation_logger::{FieldValue, Message};
+/// let message = Message::new(&[], "request done")
+///     .with_field("status", 200)
+///     .with_field("cached", false)
+///     .with_field("path", "/index.html");
+/// assert_eq!(message.field("status"), Some(&FieldValue::I64(200)));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    I64(i64),
+    F64(f64),
+    Bool(bool),
+    Str(String),
+    /// Any serializable value, see `Message::with_serde_field`.
+    #[cfg(feature = "serde")]
+    Json(serde_json::Value),
+}
+
+impl FieldValue {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            FieldValue::Str(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            FieldValue::I64(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            FieldValue::F64(value) => Some(*value),
+            FieldValue::I64(value) => Some(*value as f64),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            FieldValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Variant of value in binary and spill forms of message.
+    #[cfg(feature = "std")]
+    pub(crate) fn tag(&self) -> u8 {
+        match self {
+            FieldValue::Str(_) => 0,
+            FieldValue::I64(_) => 1,
+            FieldValue::F64(_) => 2,
+            FieldValue::Bool(_) => 3,
+            #[cfg(feature = "serde")]
+            FieldValue::Json(_) => 4,
+        }
+    }
+
+    /// Value of `tag` variant from its `Display` text, text as is when it does not parse.
+    #[cfg(feature = "std")]
+    pub(crate) fn from_tagged(tag: u8, text: String) -> Self {
+        let value = match tag {
+            1 => text.parse().ok().map(FieldValue::I64),
+            2 => text.parse().ok().map(FieldValue::F64),
+            3 => text.parse().ok().map(FieldValue::Bool),
+            #[cfg(feature = "serde")]
+            4 => serde_json::from_str(&text).ok().map(FieldValue::Json),
+            _ => None,
+        };
+        value.unwrap_or(FieldValue::Str(text))
+    }
+
+    /// Value for sinks building JSON with `serde_json`, non-finite floats are `null`.
+    #[cfg(any(feature = "webhook", feature = "sentry"))]
+    pub(crate) fn to_json_value(&self) -> serde_json::Value {
+        match self {
+            FieldValue::I64(value) => (*value).into(),
+            FieldValue::F64(value) => (*value).into(),
+            FieldValue::Bool(value) => (*value).into(),
+            FieldValue::Str(value) => value.as_str().into(),
+            #[cfg(feature = "serde")]
+            FieldValue::Json(value) => value.clone(),
+        }
+    }
+}
+
+impl Display for FieldValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldValue::I64(value) => value.fmt(f),
+            FieldValue::F64(value) => value.fmt(f),
+            FieldValue::Bool(value) => value.fmt(f),
+            FieldValue::Str(value) => value.fmt(f),
+            #[cfg(feature = "serde")]
+            FieldValue::Json(value) => value.fmt(f),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for FieldValue {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            FieldValue::I64(value) => serializer.serialize_i64(*value),
+            FieldValue::F64(value) => serializer.serialize_f64(*value),
+            FieldValue::Bool(value) => serializer.serialize_bool(*value),
+            FieldValue::Str(value) => serializer.serialize_str(value),
+            FieldValue::Json(value) => value.serialize(serializer),
+        }
+    }
+}
+
+macro_rules! from_integer {
+    ($($integer:ty),*) => {
+        $(
+            impl From<$integer> for FieldValue {
+                fn from(value: $integer) -> Self {
+                    FieldValue::I64(value.into())
+                }
+            }
+        )*
+    };
+}
+
+/// Integers that may not fit `i64` are kept as text then.
+macro_rules! from_wide_integer {
+    ($($integer:ty),*) => {
+        $(
+            impl From<$integer> for FieldValue {
+                fn from(value: $integer) -> Self {
+                    match i64::try_from(value) {
+                        Ok(value) => FieldValue::I64(value),
+                        Err(_) => FieldValue::Str(value.to_string()),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+from_integer!(i8, i16, i32, i64, u8, u16, u32);
+from_wide_integer!(u64, usize, isize, u128, i128);
+
+impl From<f32> for FieldValue {
+    fn from(value: f32) -> Self {
+        FieldValue::F64(value.into())
+    }
+}
+
+impl From<f64> for FieldValue {
+    fn from(value: f64) -> Self {
+        FieldValue::F64(value)
+    }
+}
+
+impl From<bool> for FieldValue {
+    fn from(value: bool) -> Self {
+        FieldValue::Bool(value)
+    }
+}
+
+impl From<&str> for FieldValue {
+    fn from(value: &str) -> Self {
+        FieldValue::Str(value.into())
+    }
+}
+
+impl From<&String> for FieldValue {
+    fn from(value: &String) -> Self {
+        FieldValue::Str(value.clone())
+    }
+}
+
+impl From<String> for FieldValue {
+    fn from(value: String) -> Self {
+        FieldValue::Str(value)
+    }
+}
+
+impl From<char> for FieldValue {
+    fn from(value: char) -> Self {
+        FieldValue::Str(value.to_string())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<serde_json::Value> for FieldValue {
+    fn from(value: serde_json::Value) -> Self {
+        FieldValue::Json(value)
+    }
+}
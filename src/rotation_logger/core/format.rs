@@ -0,0 +1,77 @@
+use core::fmt;
+#[cfg(feature = "std")]
+use std::sync::Arc;
+
+#[cfg(not(feature = "std"))]
+use crate::rotation_logger::core::formatter::Stamp;
+use crate::rotation_logger::core::{Message, MessageFormatter};
+
+/// Rendering of message into line, for layouts masks of `MessageFormatter` can not describe.
+/// Set with `Settings::with_custom_format`, line must not end with line break.
+///
+/// # Example:
+///
+/// ```
+/// # use core::fmt;
+/// # use rotation_logger::{Format, Message, Settings};
+/// struct Logfmt;
+///
+/// impl Format for Logfmt {
+///     fn format(&self, message: &Message, out: &mut dyn fmt::Write) -> fmt::Result {
+///         write!(out, "level={} msg={:?}", message.level(), message.text())?;
+///         for (key, value) in message.fields() {
+///             write!(out, " {key}={value}")?;
+///         }
+///         Ok(())
+///     }
+/// }
+///
+/// let settings = Settings::default().with_custom_format(Box::new(Logfmt));
+/// ```
+pub trait Format: Send + Sync {
+    fn format(&self, message: &Message, out: &mut dyn fmt::Write) -> fmt::Result;
+}
+
+/// Lines with current time, or without time when there is no clock.
+impl Format for MessageFormatter {
+    fn format(&self, message: &Message, out: &mut dyn fmt::Write) -> fmt::Result {
+        #[cfg(feature = "std")]
+        return out.write_str(&MessageFormatter::format(self, message));
+        #[cfg(not(feature = "std"))]
+        out.write_str(&self.render(message, &Stamp::default(), None))
+    }
+}
+
+/// User format stored in `Settings`, shared between `Logger` clones.
+#[cfg(feature = "std")]
+#[derive(Clone)]
+pub(crate) struct SharedFormat(Arc<dyn Format>);
+
+#[cfg(feature = "std")]
+impl SharedFormat {
+    pub(crate) fn new(format: Box<dyn Format>) -> Self {
+        Self(format.into())
+    }
+
+    /// Line of `message`, what was written before error when format failed.
+    pub(crate) fn line(&self, message: &Message) -> String {
+        let mut line = String::new();
+        if let Err(err) = self.0.format(message, &mut line) {
+            println!("Logger cant format message. Error: {err}");
+        }
+        line
+    }
+
+    pub(crate) fn try_line(&self, message: &Message) -> Result<String, fmt::Error> {
+        let mut line = String::new();
+        self.0.format(message, &mut line)?;
+        Ok(line)
+    }
+}
+
+#[cfg(feature = "std")]
+impl fmt::Debug for SharedFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Format")
+    }
+}
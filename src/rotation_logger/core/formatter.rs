@@ -0,0 +1,648 @@
+//! # Formatter of log messages.
+//!
+//! Format string with masks is parsed with `formatter` feature (default). Without it only
+//! fixed `timestamp splitter modules splitter message` layout of `MessageFormatter::simple` is available.
+//!
+//! `Logs Formatter` support five `Mask Types`(mask_type) you can operate with:
+//! - timestamp: represent timestamp of logged data. Time will be taken when logged message received by logger, so it not 100% accurate when event occurred.
+//! - timestamp_ms: same time as milliseconds since Unix epoch, whatever timestamp format is.
+//! - delta: time since previous record of same modules, e.g. `+0.012s`, empty for the first one.
+//! - delta_all: time since previous record of any modules.
+//! - splitter: represent splitter symbol which will separate every `Mask`.
+//!   Every splitter can have own symbol with `with_splitters`.
+//! - modules: list of modules that was source of log data, joined with splitter
+//!   or with `with_modules_separator`
+//! - target: target of `log!(target: "wire", ..)`, empty for messages without it
+//! - message: log message it self
+//! - fields: `key=value` pairs attached to message, e.g. `source` of messages received by aggregator
+//!   Durations and sizes are shown readable, e.g. `elapsed=340ms`, with `with_humanized_field`.
+//!
+//! Each `Mask Type` except `splitter` accept format syntax after `:` char:
+//! `{<mask_type:<mask_length>_<mask_width>_<mask_align>>}`
+//! - mask_length: length of string. On positive value limit string length from begin, on negative value from end, `0` for no limit.
+//! - mask_width: width of column for this Mask Type, `0` for value as is, without padding.
+//! - mask_align: vertical align for text on this column. Possible values: left, center, right.
+//!
+//! `?` after mask type or any of its parts, e.g. `{fields:?}` or `{target:0:0:left:?}`,
+//! leaves out empty value together with splitter before it, so optional data does not leave
+//! dangling splitters.
+//!
+//! Same format can be built in code with `Template`.
+//!
+//! # Example:
+//!
+//! ```
+//! # use rotation_logger::MessageFormatter;
+//! MessageFormatter::new(
+//!     "::",
+//!     "{timestamp:-6:30:right}{splitter}{modules:_:_:left}{splitter}{message}",
+//!     "%Y-%m-%d %H:%M:%S.%f",
+//! );
+//!
+//! ```
+//!
+use alloc::{string::String, vec::Vec};
+#[cfg(feature = "timestamps")]
+use core::fmt::Display;
+use core::time::Duration;
+
+#[cfg(feature = "timestamps")]
+use chrono::{DateTime, TimeZone};
+
+#[cfg(feature = "formatter")]
+use crate::rotation_logger::core::Level;
+use crate::rotation_logger::core::{ColorTheme, Message, color::Part};
+
+#[cfg(feature = "formatter")]
+mod grid;
+#[cfg(feature = "formatter")]
+mod humanize;
+#[cfg(feature = "formatter")]
+mod mask;
+#[cfg(feature = "formatter")]
+mod template;
+mod timestamp;
+
+#[cfg(feature = "formatter")]
+pub use grid::Grid;
+#[cfg(feature = "formatter")]
+pub use humanize::Humanize;
+#[cfg(feature = "formatter")]
+pub use mask::Align;
+#[cfg(feature = "formatter")]
+pub(crate) use mask::Columns;
+#[cfg(feature = "formatter")]
+use mask::{FormatMask, MaskType};
+#[cfg(feature = "formatter")]
+pub use template::Template;
+pub use timestamp::{TimestampFormat, TimestampPrecision};
+
+/// Formatted for Log Message.
+#[derive(Debug, Clone)]
+pub struct MessageFormatter {
+    /// Timestamp format.
+    /// Unused by `CoreLogger` without both `std` and `timestamps`, it has no time to format.
+    #[cfg_attr(not(any(feature = "std", feature = "timestamps")), allow(dead_code))]
+    timestamp: TimestampFormat,
+    /// Digits of fraction of second, as in format when not set.
+    #[cfg_attr(not(any(feature = "std", feature = "timestamps")), allow(dead_code))]
+    precision: Option<TimestampPrecision>,
+    /// List of parsed Mask with set format values.
+    /// Empty for fixed layout.
+    #[cfg(feature = "formatter")]
+    _masks: Vec<FormatMask>,
+    /// Masks of levels with own template, instead of `_masks`.
+    #[cfg(feature = "formatter")]
+    level_masks: Vec<(Level, Vec<FormatMask>)>,
+    /// Fields shown in readable form by `fields` mask.
+    #[cfg(feature = "formatter")]
+    humanized: Vec<(String, Humanize)>,
+    /// SPlitter symbols
+    splitter: String,
+    /// Splitters by position, `splitter` for positions past them.
+    splitters: Vec<String>,
+    /// Joins modules, `splitter` when not set.
+    modules_separator: Option<String>,
+    /// What `modules` mask shows for message without modules.
+    empty_modules: EmptyModules,
+}
+
+/// Rendering of `modules` mask when message has no modules, see `MessageFormatter::with_empty_modules`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum EmptyModules {
+    /// Empty value, padded to width of mask.
+    #[default]
+    Keep,
+    /// Leave out mask together with splitter after it, or before it when modules are the last value.
+    /// Fixed layout of `MessageFormatter::simple` always does so.
+    Collapse,
+    /// Show text instead, e.g. `-`.
+    Placeholder(String),
+}
+
+impl Default for MessageFormatter {
+    fn default() -> Self {
+        #[cfg(feature = "formatter")]
+        return Self::new(
+            "::",
+            "{timestamp} {splitter} {modules} {splitter} {message}",
+            "%Y-%m-%d %H:%M:%S.%f",
+        );
+        #[cfg(not(feature = "formatter"))]
+        Self::simple("::", "%Y-%m-%d %H:%M:%S.%f")
+    }
+}
+
+impl MessageFormatter {
+    #[cfg(feature = "formatter")]
+    pub fn new(splitter: &str, format: &str, timestamp: &str) -> Self {
+        Self::from_template(splitter, Template::parse(format), timestamp)
+    }
+
+    /// Same as `new`, with every problem of format and timestamp strings instead of panic.
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// # use rotation_logger::MessageFormatter;
+    /// let problems = MessageFormatter::try_new("::", "{timestmp} {message:x}", "%Y").unwrap_err();
+    /// assert_eq!(problems.len(), 2);
+    /// ```
+    #[cfg(feature = "formatter")]
+    pub fn try_new(splitter: &str, format: &str, timestamp: &str) -> Result<Self, Vec<String>> {
+        #[cfg_attr(not(feature = "timestamps"), allow(unused_mut))]
+        let mut problems = mask::problems(format);
+        #[cfg(feature = "timestamps")]
+        if chrono::format::StrftimeItems::new(timestamp)
+            .any(|item| item == chrono::format::Item::Error)
+        {
+            problems.push(alloc::format!("timestamp format is not valid: {timestamp}"));
+        }
+        match problems.is_empty() {
+            true => Ok(Self::new(splitter, format, timestamp)),
+            false => Err(problems),
+        }
+    }
+
+    /// Formatter with template built in code, see `Template`.
+    /// Empty template gives fixed layout of `simple`.
+    #[cfg(feature = "formatter")]
+    pub fn from_template(splitter: &str, template: Template, timestamp: &str) -> Self {
+        Self {
+            timestamp: timestamp.into(),
+            precision: None,
+            splitter: splitter.into(),
+            splitters: Vec::new(),
+            modules_separator: None,
+            empty_modules: EmptyModules::Keep,
+            _masks: template.into_masks(),
+            level_masks: Vec::new(),
+            humanized: Vec::new(),
+        }
+    }
+
+    /// Two lines per message for reading in terminal: time and modules,
+    /// then indented message with its fields.
+    #[cfg(feature = "formatter")]
+    pub fn pretty() -> Self {
+        let template = Template::new()
+            .timestamp()
+            .width(0)
+            .text(" ")
+            .modules()
+            .length(0)
+            .width(0)
+            .text("\n    ")
+            .message()
+            .length(0)
+            .width(0)
+            .text("  ")
+            .fields()
+            .length(0)
+            .width(0);
+        Self::from_template("::", template, "%H:%M:%S%.3f")
+    }
+
+    /// Fixed `timestamp splitter modules splitter message` layout, without padding.
+    /// Empty parts are skipped.
+    pub fn simple(splitter: &str, timestamp: &str) -> Self {
+        Self {
+            timestamp: timestamp.into(),
+            precision: None,
+            splitter: splitter.into(),
+            splitters: Vec::new(),
+            modules_separator: None,
+            empty_modules: EmptyModules::Keep,
+            #[cfg(feature = "formatter")]
+            _masks: Vec::new(),
+            #[cfg(feature = "formatter")]
+            level_masks: Vec::new(),
+            #[cfg(feature = "formatter")]
+            humanized: Vec::new(),
+        }
+    }
+
+    /// Same formatter with `timestamp` mask in `format`, instead of chrono format given on creation.
+    pub fn with_timestamp_format(mut self, format: TimestampFormat) -> Self {
+        self.timestamp = format;
+        self
+    }
+
+    /// Same formatter with fraction of second in `timestamp` mask cut to `precision`,
+    /// whatever chrono format or preset is set.
+    pub fn with_timestamp_precision(mut self, precision: TimestampPrecision) -> Self {
+        self.precision = Some(precision);
+        self
+    }
+
+    /// Same formatter with own symbol for every splitter, from the first one.
+    /// Splitters past `splitters` keep splitter given on creation. E.g. `::` and `|` give
+    /// `12:00 :: db | started`. In fixed layout of `simple` they go between shown parts.
+    pub fn with_splitters(mut self, splitters: &[&str]) -> Self {
+        self.splitters = splitters
+            .iter()
+            .map(|splitter| (*splitter).into())
+            .collect();
+        self
+    }
+
+    /// Same formatter joining modules with `separator` instead of splitter.
+    pub fn with_modules_separator(mut self, separator: &str) -> Self {
+        self.modules_separator = Some(separator.into());
+        self
+    }
+
+    /// Same formatter showing messages without modules as `empty_modules` tells.
+    pub fn with_empty_modules(mut self, empty_modules: EmptyModules) -> Self {
+        self.empty_modules = empty_modules;
+        self
+    }
+
+    pub fn timestamp_format(&self) -> &TimestampFormat {
+        &self.timestamp
+    }
+
+    pub fn timestamp_precision(&self) -> Option<TimestampPrecision> {
+        self.precision
+    }
+
+    pub fn empty_modules(&self) -> &EmptyModules {
+        &self.empty_modules
+    }
+
+    pub fn splitter(&self) -> &str {
+        &self.splitter
+    }
+
+    pub fn splitters(&self) -> &[String] {
+        &self.splitters
+    }
+
+    pub fn modules_separator(&self) -> &str {
+        self.modules_separator.as_deref().unwrap_or(&self.splitter)
+    }
+
+    /// Splitter at `position`, from 0.
+    fn splitter_at(&self, position: usize) -> &str {
+        self.splitters
+            .get(position)
+            .map_or(self.splitter.as_str(), String::as_str)
+    }
+
+    /// Modules of `message` joined, or placeholder for empty ones.
+    fn modules_text(&self, message: &Message) -> String {
+        match (message.modules().is_empty(), &self.empty_modules) {
+            (true, EmptyModules::Placeholder(placeholder)) => placeholder.clone(),
+            _ => message.modules().join(self.modules_separator()),
+        }
+    }
+
+    /// Same formatter with masks of `grid` columns.
+    #[cfg(feature = "formatter")]
+    #[cfg_attr(
+        not(all(feature = "std", not(target_arch = "wasm32"))),
+        allow(dead_code)
+    )]
+    pub(crate) fn grid(&self, grid: &Grid) -> Self {
+        Self {
+            _masks: grid.masks(),
+            level_masks: Vec::new(),
+            ..self.clone()
+        }
+    }
+
+    /// Same formatter rendering messages of `level` with own `template`, e.g. errors with
+    /// their fields and info compact. Other levels keep format given on creation.
+    /// Template is picked by level of every message before it is rendered.
+    #[cfg(feature = "formatter")]
+    pub fn with_level_template(mut self, level: Level, template: impl Into<Template>) -> Self {
+        let masks = template.into().into_masks();
+        self.level_masks.retain(|(other, _)| *other != level);
+        self.level_masks.push((level, masks));
+        self
+    }
+
+    /// Masks of `level`.
+    #[cfg(feature = "formatter")]
+    fn masks(&self, level: Level) -> &[FormatMask] {
+        self.level_masks
+            .iter()
+            .find(|(other, _)| *other == level)
+            .map_or(&self._masks, |(_, masks)| masks)
+    }
+
+    /// Show field `key` in readable form of `humanize` in `fields` mask, e.g. `elapsed=340ms`.
+    /// Values which are not numbers are shown as is.
+    #[cfg(feature = "formatter")]
+    pub fn with_humanized_field(mut self, key: &str, humanize: Humanize) -> Self {
+        self.humanized.push((key.into(), humanize));
+        self
+    }
+
+    /// Format string of masks, with every mask parameter spelled out.
+    /// Fixed layout of `simple` is shown without padding.
+    pub fn format_string(&self) -> String {
+        #[cfg(feature = "formatter")]
+        if !self._masks.is_empty() {
+            return self._masks.iter().map(FormatMask::to_format).collect();
+        }
+        "{timestamp:0:0} {splitter} {modules:0:0} {splitter} {message:0:0}".into()
+    }
+
+    /// Process input message with rules.
+    #[cfg(feature = "std")]
+    pub fn format(&self, message: &Message) -> String {
+        self.format_at_system_time(message, std::time::SystemTime::now())
+    }
+
+    /// Process input message with rules, using given time for `timestamp` mask.
+    #[cfg(feature = "std")]
+    pub fn format_at_system_time(&self, message: &Message, time: std::time::SystemTime) -> String {
+        self.render(message, &self.timestamp_at(time), None)
+    }
+
+    /// Same as `format_at_system_time`, with parts painted by `theme`.
+    #[cfg(feature = "std")]
+    pub fn format_colored(
+        &self,
+        message: &Message,
+        time: std::time::SystemTime,
+        theme: &ColorTheme,
+    ) -> String {
+        self.render(message, &self.timestamp_at(time), Some(theme))
+    }
+
+    /// Format with time since previous records for `delta` masks, known only to worker.
+    #[cfg(feature = "std")]
+    pub(crate) fn format_with_deltas(
+        &self,
+        message: &Message,
+        time: std::time::SystemTime,
+        deltas: Deltas,
+        theme: Option<&ColorTheme>,
+    ) -> String {
+        let mut stamp = self.timestamp_at(time);
+        stamp.deltas = deltas;
+        self.render(message, &stamp, theme)
+    }
+
+    /// Same as `format_with_deltas`, with widths of masks given by `columns`.
+    #[cfg(all(feature = "std", feature = "formatter", not(target_arch = "wasm32")))]
+    pub(crate) fn format_in_columns(
+        &self,
+        message: &Message,
+        time: std::time::SystemTime,
+        deltas: Deltas,
+        theme: Option<&ColorTheme>,
+        columns: &mut Columns,
+    ) -> String {
+        let mut stamp = self.timestamp_at(time);
+        stamp.deltas = deltas;
+        let masks = self.masks(message.level());
+        if masks.is_empty() {
+            return self.render(message, &stamp, theme);
+        }
+        self.render_masks(masks, message, &stamp, theme, Some(columns))
+    }
+
+    /// Does format have `delta` masks, which need time of previous records.
+    #[cfg(feature = "std")]
+    pub(crate) fn uses_deltas(&self) -> bool {
+        #[cfg(feature = "formatter")]
+        return self
+            ._masks
+            .iter()
+            .chain(self.level_masks.iter().flat_map(|(_, masks)| masks))
+            .any(|mask| matches!(mask.mask_type, MaskType::Delta | MaskType::DeltaAll));
+        #[cfg(not(feature = "formatter"))]
+        false
+    }
+
+    #[cfg(feature = "std")]
+    fn timestamp_at(&self, time: std::time::SystemTime) -> Stamp {
+        let millis = match time.duration_since(std::time::UNIX_EPOCH) {
+            Ok(since_epoch) => since_epoch.as_millis() as i64,
+            Err(before_epoch) => -(before_epoch.duration().as_millis() as i64),
+        };
+        Stamp {
+            formatted: self.timestamp.format_system_time(time, self.precision),
+            millis: Some(millis),
+            deltas: Deltas::default(),
+        }
+    }
+
+    /// Process input message with rules, using given time for `timestamp` mask.
+    #[cfg(feature = "timestamps")]
+    pub fn format_at<Tz: TimeZone>(&self, message: &Message, timestamp: &DateTime<Tz>) -> String
+    where
+        Tz::Offset: Display,
+    {
+        let stamp = Stamp {
+            formatted: self.timestamp.format_datetime(timestamp, self.precision),
+            millis: Some(timestamp.timestamp_millis()),
+            deltas: Deltas::default(),
+        };
+        self.render(message, &stamp, None)
+    }
+
+    /// Build line with already formatted timestamp, painted when `theme` is set.
+    pub(crate) fn render(
+        &self,
+        message: &Message,
+        stamp: &Stamp,
+        theme: Option<&ColorTheme>,
+    ) -> String {
+        #[cfg(feature = "formatter")]
+        {
+            let masks = self.masks(message.level());
+            if !masks.is_empty() {
+                return self.render_masks(masks, message, stamp, theme, None);
+            }
+        }
+
+        let modules = self.modules_text(message);
+        let mut result = String::new();
+        [
+            (stamp.formatted.as_str(), Part::Timestamp),
+            (modules.as_str(), Part::Modules),
+            (message.text(), Part::Message),
+        ]
+        .into_iter()
+        .filter(|(part, _)| !part.is_empty())
+        .enumerate()
+        .for_each(|(index, (value, part))| {
+            if index > 0 {
+                result.push_str(&alloc::format!(" {} ", self.splitter_at(index - 1)));
+            }
+            result.push_str(&paint(theme, part, value.into(), message));
+        });
+        result
+    }
+
+    #[cfg(feature = "formatter")]
+    fn render_masks(
+        &self,
+        masks: &[FormatMask],
+        message: &Message,
+        stamp: &Stamp,
+        theme: Option<&ColorTheme>,
+        mut columns: Option<&mut Columns>,
+    ) -> String {
+        let mut result = String::new();
+        let collapsed = match (message.modules().is_empty(), &self.empty_modules) {
+            (true, EmptyModules::Collapse) => collapsed_masks(masks),
+            _ => Vec::new(),
+        };
+
+        let mut splitters = 0;
+        // Length of line before every mask, to take back masks left out.
+        let mut starts = Vec::with_capacity(masks.len());
+
+        for (index, mask) in masks.iter().enumerate() {
+            starts.push(result.len());
+            // Position of splitter is counted in template, with collapsed ones.
+            let position = splitters;
+            if matches!(mask.mask_type, MaskType::Splitter) {
+                splitters += 1;
+            }
+            if collapsed.get(index) == Some(&true) {
+                continue;
+            }
+            let (value, part) = match &mask.mask_type {
+                MaskType::Raw(value) => {
+                    result.push_str(value);
+                    continue;
+                }
+                MaskType::Splitter => {
+                    result.push_str(self.splitter_at(position));
+                    continue;
+                }
+                MaskType::Timestamp => (stamp.formatted.clone(), Some(Part::Timestamp)),
+                MaskType::TimestampMs => {
+                    let millis = stamp.millis.map(|millis| alloc::format!("{millis}"));
+                    (millis.unwrap_or_default(), Some(Part::Timestamp))
+                }
+                MaskType::Message => (message.text().clone(), Some(Part::Message)),
+                MaskType::Delta | MaskType::DeltaAll => {
+                    let delta = match mask.mask_type {
+                        MaskType::Delta => stamp.deltas.module,
+                        _ => stamp.deltas.all,
+                    };
+                    let delta = delta.map(|delta| {
+                        alloc::format!("+{}.{:03}s", delta.as_secs(), delta.subsec_millis())
+                    });
+                    (delta.unwrap_or_default(), Some(Part::Timestamp))
+                }
+                MaskType::Modules => (self.modules_text(message), Some(Part::Modules)),
+                MaskType::Target => (
+                    message.target().unwrap_or_default().into(),
+                    Some(Part::Modules),
+                ),
+                MaskType::Fields => {
+                    let fields = message
+                        .fields()
+                        .iter()
+                        .map(|(key, value)| {
+                            let value = self
+                                .humanized
+                                .iter()
+                                .find(|(humanized, _)| humanized == key)
+                                .and_then(|(_, humanize)| humanize.format(value))
+                                .unwrap_or_else(|| alloc::format!("{value}"));
+                            alloc::format!("{key}={value}")
+                        })
+                        .collect::<Vec<String>>()
+                        .join(" ");
+                    (fields, None)
+                }
+            };
+            if mask.optional && value.is_empty() {
+                if let Some(start) = splitter_before(masks, index) {
+                    result.truncate(starts[start]);
+                }
+                continue;
+            }
+            let value = match columns.as_deref_mut() {
+                Some(columns) => mask.fit_column(&value, index, columns),
+                None => mask.fit(&value),
+            };
+            match part {
+                Some(part) => result.push_str(&paint(theme, part, value, message)),
+                None => result.push_str(&value),
+            }
+        }
+        result
+    }
+}
+
+/// Masks left out of line without modules with `EmptyModules::Collapse`: `modules` mask,
+/// the nearest splitter after it, or before it, and text between them and around splitter.
+#[cfg(feature = "formatter")]
+fn collapsed_masks(masks: &[FormatMask]) -> Vec<bool> {
+    let mut collapsed = alloc::vec![false; masks.len()];
+    let is_raw = |index: usize| matches!(masks[index].mask_type, MaskType::Raw(_));
+    for index in 0..masks.len() {
+        if !matches!(masks[index].mask_type, MaskType::Modules) {
+            continue;
+        }
+        let after = (index + 1..masks.len()).find(|&next| !is_raw(next));
+        let range = match after {
+            Some(next) if matches!(masks[next].mask_type, MaskType::Splitter) => {
+                // Text after splitter stays only when nothing follows it.
+                match next + 2 < masks.len() && is_raw(next + 1) {
+                    true => index..=next + 1,
+                    false => index..=next,
+                }
+            }
+            Some(_) => index..=index,
+            None => match splitter_before(masks, index) {
+                Some(start) => start..=masks.len() - 1,
+                None => index..=index,
+            },
+        };
+        range.for_each(|index| collapsed[index] = true);
+    }
+    collapsed
+}
+
+/// First of masks left out together with mask `index`: splitter before it, with only text
+/// between them, and text before splitter. `None` without such splitter.
+#[cfg(feature = "formatter")]
+fn splitter_before(masks: &[FormatMask], index: usize) -> Option<usize> {
+    let is_raw = |index: usize| matches!(masks[index].mask_type, MaskType::Raw(_));
+    let previous = (0..index).rev().find(|&previous| !is_raw(previous))?;
+    if !matches!(masks[previous].mask_type, MaskType::Splitter) {
+        return None;
+    }
+    match previous > 0 && is_raw(previous - 1) {
+        true => Some(previous - 1),
+        false => Some(previous),
+    }
+}
+
+/// Time of message for masks, empty when there is no clock.
+#[derive(Debug, Default)]
+pub(crate) struct Stamp {
+    /// Time in format of `timestamp` mask.
+    formatted: String,
+    /// Milliseconds since Unix epoch.
+    #[cfg_attr(not(feature = "formatter"), allow(dead_code))]
+    millis: Option<i64>,
+    #[cfg_attr(not(feature = "formatter"), allow(dead_code))]
+    deltas: Deltas,
+}
+
+/// Time since previous record of same modules and of any modules, `None` for the first one.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Deltas {
+    pub(crate) module: Option<Duration>,
+    pub(crate) all: Option<Duration>,
+}
+
+/// Paint already fitted value of line part.
+fn paint(theme: Option<&ColorTheme>, part: Part, value: String, message: &Message) -> String {
+    match theme {
+        Some(theme) => theme.paint(part, &value, message.level()),
+        None => value,
+    }
+}
@@ -0,0 +1,128 @@
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use crate::rotation_logger::core::formatter::{
+    Template,
+    mask::{FormatMask, MaskType},
+};
+
+/// Console output as table: values of template in aligned columns,
+/// with separator between them and header row of column names, e.g.
+///
+/// ```text
+///  TIMESTAMP  | MODULES |       MESSAGE
+/// ------------+---------+---------------------
+///  12:00:01.2 |   DB    |  connection restored
+/// ```
+///
+/// Text and splitters of template are dropped, columns keep width and align of their masks.
+/// Set with `Settings::with_console_grid`, other outputs keep format of `MessageFormatter`.
+///
+/// # Example:
+///
+/// ```
+/// # use rotation_logger::{Grid, Settings, Template};
+/// let grid = Grid::new(Template::parse("{timestamp:0:12}{modules:0:10}{message:0:40:left}"))
+///     .with_separator(" │ ");
+/// let settings = Settings::default().with_console_grid(grid);
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    not(all(feature = "std", not(target_arch = "wasm32"))),
+    allow(dead_code)
+)]
+pub struct Grid {
+    masks: Vec<FormatMask>,
+    separator: String,
+    header: bool,
+}
+
+#[cfg_attr(
+    not(all(feature = "std", not(target_arch = "wasm32"))),
+    allow(dead_code)
+)]
+impl Grid {
+    /// Grid of value masks of `template`, separated by ` | `, with header.
+    pub fn new(template: Template) -> Self {
+        Self {
+            masks: template
+                .into_masks()
+                .into_iter()
+                .filter(|mask| mask.mask_type.is_value())
+                .collect(),
+            separator: " | ".into(),
+            header: true,
+        }
+    }
+
+    /// Text between columns, empty for none.
+    pub fn with_separator(mut self, separator: &str) -> Self {
+        self.separator = separator.into();
+        self
+    }
+
+    /// Print header row before the first line.
+    pub fn with_header(mut self, header: bool) -> Self {
+        self.header = header;
+        self
+    }
+
+    pub fn separator(&self) -> &str {
+        &self.separator
+    }
+    pub fn header(&self) -> bool {
+        self.header
+    }
+
+    /// Masks of columns with separators between them.
+    pub(super) fn masks(&self) -> Vec<FormatMask> {
+        let mut masks = Vec::with_capacity(self.masks.len() * 2);
+        for (index, mask) in self.masks.iter().enumerate() {
+            if index > 0 && !self.separator.is_empty() {
+                masks.push(FormatMask::new(MaskType::Raw(self.separator.clone())));
+            }
+            masks.push(mask.clone());
+        }
+        masks
+    }
+
+    /// Column names and rule under them, `None` without header.
+    pub(crate) fn header_lines(&self) -> Option<[String; 2]> {
+        if !self.header {
+            return None;
+        }
+        let names = self
+            .masks
+            .iter()
+            .map(|mask| {
+                let name = mask.mask_type.name().to_uppercase();
+                // Names fill the whole column, cut to its width when longer.
+                FormatMask {
+                    length: 0,
+                    ..mask.clone()
+                }
+                .fit(&name)
+            })
+            .collect::<Vec<String>>();
+        let (line, cross) = match self.separator.is_ascii() {
+            true => ('-', '+'),
+            false => ('─', '┼'),
+        };
+        let rule_separator = self
+            .separator
+            .chars()
+            .map(|char| match char {
+                ' ' => line,
+                _ => cross,
+            })
+            .collect::<String>();
+        let rule = names
+            .iter()
+            .map(|name| line.to_string().repeat(name.chars().count()))
+            .collect::<Vec<String>>()
+            .join(&rule_separator);
+        Some([names.join(&self.separator), rule])
+    }
+}
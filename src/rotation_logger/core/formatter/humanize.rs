@@ -0,0 +1,91 @@
+use alloc::{format, string::String};
+use core::time::Duration;
+
+use crate::rotation_logger::core::FieldValue;
+
+/// Readable form of numeric field, set per field key with `MessageFormatter::with_humanized_field`.
+/// Shown by `fields` mask, e.g. `elapsed=1.2s` instead of `elapsed=1200000000`.
+///
+/// # Example:
+///
+/// ```
+/// # use std::time::Duration;
+/// # use rotation_logger::Humanize;
+/// assert_eq!(Humanize::duration(Duration::from_millis(340)), "340ms");
+/// assert_eq!(Humanize::bytes(1_468_006), "1.4 MiB");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Humanize {
+    /// Duration in nanoseconds.
+    Nanos,
+    /// Duration in microseconds.
+    Micros,
+    /// Duration in milliseconds.
+    Millis,
+    /// Duration in seconds.
+    Seconds,
+    /// Size in bytes, with binary units.
+    Bytes,
+}
+
+impl Humanize {
+    /// Value in readable form, `None` when it is not a number.
+    pub fn format(&self, value: &FieldValue) -> Option<String> {
+        let number = match value {
+            FieldValue::Str(text) => text.parse().ok()?,
+            value => value.as_f64()?,
+        };
+        if !number.is_finite() || number < 0.0 {
+            return None;
+        }
+        let nanos = match self {
+            Humanize::Nanos => number,
+            Humanize::Micros => number * 1e3,
+            Humanize::Millis => number * 1e6,
+            Humanize::Seconds => number * 1e9,
+            Humanize::Bytes => return Some(Self::bytes(number as u64)),
+        };
+        Some(Self::duration(Duration::from_nanos(nanos as u64)))
+    }
+
+    /// Duration with the largest unit below it, e.g. `1.2s`, `340ms`, `15µs`, `80ns`.
+    pub fn duration(duration: Duration) -> String {
+        let nanos = duration.as_nanos() as f64;
+        let (value, unit) = if nanos >= 1e9 {
+            (nanos / 1e9, "s")
+        } else if nanos >= 1e6 {
+            (nanos / 1e6, "ms")
+        } else if nanos >= 1e3 {
+            (nanos / 1e3, "µs")
+        } else {
+            return format!("{nanos}ns");
+        };
+        format!("{}{unit}", short(value))
+    }
+
+    /// Size in bytes, KiB, MiB, GiB or TiB, e.g. `512 B`, `1.4 MiB`.
+    pub fn bytes(bytes: u64) -> String {
+        const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+        if bytes < 1024 {
+            return format!("{bytes} B");
+        }
+        let mut value = bytes as f64 / 1024.0;
+        let mut unit = UNITS[0];
+        for next in &UNITS[1..] {
+            if value < 1024.0 {
+                break;
+            }
+            value /= 1024.0;
+            unit = next;
+        }
+        format!("{} {unit}", short(value))
+    }
+}
+
+/// One decimal below `10`, whole number above, so values stay about same width.
+fn short(value: f64) -> String {
+    match value < 9.95 {
+        true => format!("{value:.1}"),
+        false => format!("{value:.0}"),
+    }
+}
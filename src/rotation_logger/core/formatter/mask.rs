@@ -0,0 +1,341 @@
+//! Parsing of format string into `Mask`s, available with `formatter` feature.
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::cmp::min;
+
+const DEFAULT_LENGTH: i32 = 30;
+const DEFAULT_WIDTH: usize = 30;
+
+/// Split format string into masks.
+pub(super) fn parse(format: &str) -> Vec<FormatMask> {
+    let mut result = vec![];
+    let format = format.to_string();
+    let mut format = format.as_str();
+    if !format.contains("{") || !format.contains("}") {
+        panic!("Format String wrong syntax: {format}")
+    }
+    while !format.is_empty() {
+        let opening_delimiter = format.find("{");
+        if opening_delimiter.is_none() {
+            result.push(FormatMask::from(format));
+            return result;
+        }
+        let opening_delimiter = opening_delimiter.unwrap();
+        if format[0..opening_delimiter].to_string() != "" {
+            result.push(FormatMask::from(&format[0..opening_delimiter]));
+        }
+
+        let close_delimiter = format.find("}");
+        if close_delimiter.is_none() {
+            result.push(FormatMask::from(format));
+            return result;
+        }
+        let close_delimiter = close_delimiter.unwrap();
+        let scoped_value = &format[opening_delimiter + 1..close_delimiter];
+        result.push(FormatMask::from(scoped_value));
+        format = &format[close_delimiter + 1..format.len()];
+    }
+    result
+}
+
+/// Every problem of format string `parse` would panic on or silently default, empty when there is none.
+pub(super) fn problems(format: &str) -> Vec<String> {
+    let mut problems = vec![];
+    if !format.contains("{") || !format.contains("}") {
+        problems.push(format!("format has no masks: {format}"));
+        return problems;
+    }
+    let mut rest = format;
+    while let Some(open) = rest.find("{") {
+        let Some(close) = rest[open..].find("}").map(|close| open + close) else {
+            problems.push(format!("mask is not closed: {}", &rest[open..]));
+            break;
+        };
+        let value = &rest[open + 1..close];
+        if value.contains("{") {
+            problems.push(format!("mask is not closed: {}", &rest[open..close + 1]));
+        } else {
+            mask_problems(value, &mut problems);
+        }
+        rest = &rest[close + 1..];
+    }
+    problems
+}
+
+fn mask_problems(value: &str, problems: &mut Vec<String>) {
+    let parts: Vec<&str> = value.split(":").filter(|part| *part != "?").collect();
+    if parts.len() > 4 {
+        problems.push(format!("mask {{{value}}} has more than 4 parts"));
+        return;
+    }
+    if let MaskType::Raw(name) = MaskType::from(parts[0]) {
+        problems.push(format!("unknown mask {{{name}}}"));
+    }
+    if let Some(length) = parts.get(1)
+        && length.parse::<i32>().is_err()
+    {
+        problems.push(format!("mask {{{value}}}: length {length} is not a number"));
+    }
+    if let Some(width) = parts.get(2)
+        && width.parse::<usize>().is_err()
+    {
+        problems.push(format!("mask {{{value}}}: width {width} is not a number"));
+    }
+    if let Some(align) = parts.get(3)
+        && !["left", "center", "right"].contains(&align.to_lowercase().as_str())
+    {
+        problems.push(format!("mask {{{value}}}: unknown align {align}"));
+    }
+}
+
+/// Format Mask with rules.
+#[derive(Debug, Clone)]
+pub(super) struct FormatMask {
+    pub(super) mask_type: MaskType,
+    pub(super) length: i32,
+    pub(super) width: usize,
+    pub(super) align: Align,
+    /// Left out with splitter before it when value is empty, `?` in format string.
+    pub(super) optional: bool,
+}
+
+impl FormatMask {
+    /// Mask with same defaults as in format string.
+    pub(super) fn new(mask_type: MaskType) -> Self {
+        Self {
+            mask_type,
+            length: DEFAULT_LENGTH,
+            width: DEFAULT_WIDTH,
+            align: Align::Center,
+            optional: false,
+        }
+    }
+
+    /// Mask as in format string, text as is.
+    pub(super) fn to_format(&self) -> String {
+        let name = match &self.mask_type {
+            MaskType::Raw(text) => return text.clone(),
+            MaskType::Splitter => return "{splitter}".into(),
+            mask_type => mask_type.name(),
+        };
+        let align = match self.align {
+            Align::Left => "left",
+            Align::Center => "center",
+            Align::Right => "right",
+        };
+        let optional = match self.optional {
+            true => ":?",
+            false => "",
+        };
+        format!(
+            "{{{name}:{}:{}:{align}{optional}}}",
+            self.length, self.width
+        )
+    }
+
+    /// Apply length, width and align of mask to value.
+    pub(super) fn fit(&self, value: &str) -> String {
+        let value = self.format_by_length(value);
+        self.format_by_width_align(&value, self.width)
+    }
+
+    /// Same as `fit`, with width of column given by `columns`.
+    pub(super) fn fit_column(&self, value: &str, index: usize, columns: &mut Columns) -> String {
+        let value = self.format_by_length(value);
+        if self.width == 0 {
+            return value;
+        }
+        let width = columns.width(index, value.len());
+        self.format_by_width_align(&value, width)
+    }
+
+    fn format_by_length(&self, value: &str) -> String {
+        if self.length > 0 {
+            value[0..min(self.length as usize, value.len())].to_string()
+        } else {
+            value[0..value.len() - min(self.length.unsigned_abs() as usize, value.len())]
+                .to_string()
+        }
+    }
+
+    fn format_by_width_align(&self, value: &str, width: usize) -> String {
+        if width == 0 {
+            return value.to_string();
+        }
+        if value.len() >= width {
+            return value[0..width].to_string();
+        };
+
+        let free_space = width - value.len();
+        let (left_space, right_space) = match self.align {
+            Align::Left => ("".to_string(), " ".repeat(free_space)),
+            Align::Center => {
+                let half = free_space / 2;
+                (" ".repeat(half), " ".repeat(free_space - half))
+            }
+            Align::Right => (" ".repeat(free_space), "".to_string()),
+        };
+        format!("{left_space}{value}{right_space}")
+    }
+}
+
+impl From<&str> for FormatMask {
+    fn from(value: &str) -> Self {
+        // `?` may follow any part, e.g. `{fields:?}` or `{fields:0:0:left:?}`.
+        let splitted_data: Vec<&str> = value.split(":").filter(|part| *part != "?").collect();
+        let optional = value.split(":").skip(1).any(|part| part == "?");
+        if splitted_data.len() > 4 {
+            panic!("Wrong Mask format: {value}")
+        }
+
+        let default_width = DEFAULT_WIDTH;
+        let default_length = DEFAULT_LENGTH;
+        let mask_type = splitted_data[0];
+        let length = splitted_data
+            .get(1)
+            .unwrap_or(&default_length.to_string().as_str())
+            .parse::<i32>()
+            .unwrap_or(default_length);
+        let width = splitted_data
+            .get(2)
+            .unwrap_or(&default_width.to_string().as_str())
+            .parse::<usize>()
+            .unwrap_or(default_width);
+        let align = *splitted_data.get(3).unwrap_or(&"center");
+
+        Self {
+            mask_type: MaskType::from(mask_type),
+            length,
+            width,
+            align: Align::from(align),
+            optional,
+        }
+    }
+}
+
+/// Widths of columns growing to the longest value seen so far, up to `max_width`.
+/// Console output only, which needs `std` and is not available on `wasm32`.
+#[derive(Debug, Clone)]
+#[cfg_attr(
+    not(all(feature = "std", not(target_arch = "wasm32"))),
+    allow(dead_code)
+)]
+pub(crate) struct Columns {
+    max_width: usize,
+    widths: Vec<usize>,
+}
+
+#[cfg_attr(
+    not(all(feature = "std", not(target_arch = "wasm32"))),
+    allow(dead_code)
+)]
+impl Columns {
+    pub(crate) fn new(max_width: usize) -> Self {
+        Self {
+            max_width,
+            widths: vec![],
+        }
+    }
+
+    /// Width of column `index` for value of `len`, remembering it.
+    fn width(&mut self, index: usize, len: usize) -> usize {
+        if self.widths.len() <= index {
+            self.widths.resize(index + 1, 0);
+        }
+        let width = self.widths[index].max(len).min(self.max_width);
+        self.widths[index] = width;
+        width
+    }
+}
+
+/// Type of Format Masks
+#[derive(Debug, Clone)]
+pub(super) enum MaskType {
+    Raw(String),
+    Timestamp,
+    /// Milliseconds since Unix epoch.
+    TimestampMs,
+    /// Time since previous record of same modules.
+    Delta,
+    /// Time since previous record.
+    DeltaAll,
+    Message,
+    Splitter,
+    Modules,
+    /// Target of `log!(target: ..)`, empty without it.
+    Target,
+    Fields,
+}
+
+impl MaskType {
+    /// Name in format string.
+    pub(super) fn name(&self) -> &str {
+        match self {
+            MaskType::Raw(text) => text,
+            MaskType::Splitter => "splitter",
+            MaskType::Timestamp => "timestamp",
+            MaskType::TimestampMs => "timestamp_ms",
+            MaskType::Delta => "delta",
+            MaskType::DeltaAll => "delta_all",
+            MaskType::Message => "message",
+            MaskType::Modules => "modules",
+            MaskType::Target => "target",
+            MaskType::Fields => "fields",
+        }
+    }
+
+    /// Value of message, not text between values.
+    pub(super) fn is_value(&self) -> bool {
+        !matches!(self, MaskType::Raw(_) | MaskType::Splitter)
+    }
+}
+
+impl From<&str> for MaskType {
+    fn from(value: &str) -> Self {
+        if value.to_lowercase() == "timestamp" {
+            Self::Timestamp
+        } else if value.to_lowercase() == "timestamp_ms" {
+            Self::TimestampMs
+        } else if value.to_lowercase() == "delta" {
+            Self::Delta
+        } else if value.to_lowercase() == "delta_all" {
+            Self::DeltaAll
+        } else if value.to_lowercase() == "splitter" {
+            Self::Splitter
+        } else if value.to_lowercase() == "modules" {
+            Self::Modules
+        } else if value.to_lowercase() == "target" {
+            Self::Target
+        } else if value.to_lowercase() == "message" {
+            Self::Message
+        } else if value.to_lowercase() == "fields" {
+            Self::Fields
+        } else {
+            Self::Raw(value.to_string())
+        }
+    }
+}
+
+/// Text horizontal align in mask column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+impl From<&str> for Align {
+    fn from(value: &str) -> Self {
+        if value.to_lowercase() == "left" {
+            Self::Left
+        } else if value.to_lowercase() == "right" {
+            Self::Right
+        } else {
+            Self::Center
+        }
+    }
+}
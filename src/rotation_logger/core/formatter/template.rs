@@ -0,0 +1,161 @@
+use alloc::{string::String, vec::Vec};
+
+use super::mask::{self, Align, FormatMask, MaskType};
+
+/// Format of log line built in code, same as format string but checked by compiler.
+///
+/// Every mask is added with the defaults of format string (length and width 30, center align),
+/// `length`, `width` and `align` change the last added mask.
+///
+/// # Example:
+///
+/// ```
+/// # use rotation_logger::{Align, MessageFormatter, Template};
+/// let template = Template::new()
+///     .timestamp()
+///     .splitter()
+///     .modules()
+///     .width(20)
+///     .align(Align::Left)
+///     .splitter()
+///     .message();
+///
+/// MessageFormatter::from_template("::", template, "%Y-%m-%d %H:%M:%S.%f");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Template {
+    masks: Vec<FormatMask>,
+}
+
+impl Template {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Template from format string, e.g. `{timestamp}{splitter}{message:_:_:left}`.
+    pub fn parse(format: &str) -> Self {
+        Self {
+            masks: mask::parse(format),
+        }
+    }
+
+    /// Same as `parse`, with every problem of format string instead of panic,
+    /// e.g. unknown mask or length which is not a number.
+    pub fn try_parse(format: &str) -> Result<Self, Vec<String>> {
+        let problems = mask::problems(format);
+        match problems.is_empty() {
+            true => Ok(Self::parse(format)),
+            false => Err(problems),
+        }
+    }
+
+    pub fn timestamp(self) -> Self {
+        self.mask(MaskType::Timestamp)
+    }
+
+    /// Milliseconds since Unix epoch.
+    pub fn timestamp_ms(self) -> Self {
+        self.mask(MaskType::TimestampMs)
+    }
+
+    /// Time since previous record of same modules.
+    pub fn delta(self) -> Self {
+        self.mask(MaskType::Delta)
+    }
+
+    /// Time since previous record of any modules.
+    pub fn delta_all(self) -> Self {
+        self.mask(MaskType::DeltaAll)
+    }
+
+    pub fn splitter(self) -> Self {
+        self.mask(MaskType::Splitter)
+    }
+
+    pub fn modules(self) -> Self {
+        self.mask(MaskType::Modules)
+    }
+
+    /// Target of `log!(target: ..)`.
+    pub fn target(self) -> Self {
+        self.mask(MaskType::Target)
+    }
+
+    pub fn message(self) -> Self {
+        self.mask(MaskType::Message)
+    }
+
+    pub fn fields(self) -> Self {
+        self.mask(MaskType::Fields)
+    }
+
+    /// Text written as is.
+    pub fn text(self, text: &str) -> Self {
+        self.mask(MaskType::Raw(text.into()))
+    }
+
+    /// Append masks of other template.
+    pub fn then(mut self, other: Template) -> Self {
+        self.masks.extend(other.masks);
+        self
+    }
+
+    /// Limit length of last mask value: from begin on positive value, from end on negative,
+    /// `0` for no limit.
+    pub fn length(mut self, length: i32) -> Self {
+        if let Some(mask) = self.masks.last_mut() {
+            mask.length = length;
+        }
+        self
+    }
+
+    /// Column width of last mask, `0` for value as is.
+    pub fn width(mut self, width: usize) -> Self {
+        if let Some(mask) = self.masks.last_mut() {
+            mask.width = width;
+        }
+        self
+    }
+
+    /// Align of last mask value in its column.
+    pub fn align(mut self, align: Align) -> Self {
+        if let Some(mask) = self.masks.last_mut() {
+            mask.align = align;
+        }
+        self
+    }
+
+    /// Leave out last mask together with splitter before it when its value is empty,
+    /// same as `?` in format string, e.g. `{fields:?}`.
+    pub fn optional(mut self) -> Self {
+        if let Some(mask) = self.masks.last_mut() {
+            mask.optional = true;
+        }
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.masks.is_empty()
+    }
+
+    pub(super) fn into_masks(self) -> Vec<FormatMask> {
+        self.masks
+    }
+
+    fn mask(mut self, mask_type: MaskType) -> Self {
+        self.masks.push(FormatMask::new(mask_type));
+        self
+    }
+}
+
+impl From<&str> for Template {
+    fn from(format: &str) -> Self {
+        Self::parse(format)
+    }
+}
+
+impl From<String> for Template {
+    fn from(format: String) -> Self {
+        Self::parse(&format)
+    }
+}
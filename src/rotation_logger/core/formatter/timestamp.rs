@@ -0,0 +1,163 @@
+use alloc::string::String;
+#[cfg(any(feature = "std", feature = "timestamps"))]
+use alloc::string::ToString;
+#[cfg(feature = "timestamps")]
+use core::fmt::Display;
+
+#[cfg(feature = "timestamps")]
+use chrono::{DateTime, SecondsFormat, TimeZone};
+
+/// Format of `timestamp` mask, presets for machine-readable formats or chrono format string.
+/// Without `timestamps` feature RFC 3339 and non empty custom formats give seconds since
+/// Unix epoch with micros.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TimestampFormat {
+    /// `2024-01-10T12:00:00+01:00`
+    Rfc3339,
+    /// `2024-01-10T12:00:00.123+01:00`
+    Rfc3339Millis,
+    /// Seconds since Unix epoch, `1704884400`.
+    EpochSeconds,
+    /// Milliseconds since Unix epoch, `1704884400123`.
+    EpochMillis,
+    /// Chrono format, e.g. `%Y-%m-%d %H:%M:%S%.3f`, empty for no timestamp.
+    Custom(String),
+}
+
+impl From<&str> for TimestampFormat {
+    fn from(format: &str) -> Self {
+        Self::Custom(format.into())
+    }
+}
+
+/// Digits of fraction of second in `timestamp` mask, see `MessageFormatter::with_timestamp_precision`.
+/// Replaces fraction of custom format, e.g. `%f` is 9 digits, with `Millis` it is 3.
+/// `Seconds` drops fraction together with dot before it. Epoch presets are kept as is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimestampPrecision {
+    Seconds,
+    Millis,
+    Micros,
+    Nanos,
+}
+
+impl TimestampPrecision {
+    #[cfg(any(feature = "std", feature = "timestamps"))]
+    fn digits(self) -> u32 {
+        match self {
+            Self::Seconds => 0,
+            Self::Millis => 3,
+            Self::Micros => 6,
+            Self::Nanos => 9,
+        }
+    }
+
+    #[cfg(feature = "timestamps")]
+    fn seconds_format(self) -> SecondsFormat {
+        match self {
+            Self::Seconds => SecondsFormat::Secs,
+            Self::Millis => SecondsFormat::Millis,
+            Self::Micros => SecondsFormat::Micros,
+            Self::Nanos => SecondsFormat::Nanos,
+        }
+    }
+
+    /// Chrono `format` with every fraction specifier, `%f`, `%.f`, `%3f`, `%.6f` and so on,
+    /// of own digits.
+    #[cfg(feature = "timestamps")]
+    fn apply(self, format: &str) -> String {
+        let mut result = String::with_capacity(format.len());
+        let mut rest = format;
+        while let Some(start) = rest.find('%') {
+            result.push_str(&rest[..start]);
+            let spec = &rest[start + 1..];
+            let dot = spec.starts_with('.');
+            let after_dot = &spec[dot as usize..];
+            let digits = after_dot.chars().take_while(char::is_ascii_digit).count();
+            if !after_dot[digits..].starts_with('f') {
+                // Other specifiers, `%%` among them, are kept as is.
+                let len = spec.chars().next().map_or(0, char::len_utf8);
+                result.push('%');
+                result.push_str(&spec[..len]);
+                rest = &spec[len..];
+                continue;
+            }
+            rest = &after_dot[digits + 1..];
+            match (self.digits(), dot) {
+                (0, true) => {}
+                // `%S.%f` becomes `%S`.
+                (0, false) => {
+                    if result.ends_with('.') {
+                        result.pop();
+                    }
+                }
+                (digits, true) => result.push_str(&alloc::format!("%.{digits}f")),
+                (digits, false) => result.push_str(&alloc::format!("%{digits}f")),
+            }
+        }
+        result.push_str(rest);
+        result
+    }
+}
+
+impl TimestampFormat {
+    #[cfg(feature = "std")]
+    pub(crate) fn format_system_time(
+        &self,
+        time: std::time::SystemTime,
+        precision: Option<TimestampPrecision>,
+    ) -> String {
+        #[cfg(feature = "timestamps")]
+        return self.format_datetime(&DateTime::<chrono::Local>::from(time), precision);
+
+        #[cfg(not(feature = "timestamps"))]
+        {
+            let since_epoch = time
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default();
+            match self {
+                Self::Custom(format) if format.is_empty() => String::new(),
+                Self::EpochSeconds => since_epoch.as_secs().to_string(),
+                Self::EpochMillis => since_epoch.as_millis().to_string(),
+                _ => {
+                    let digits = precision.map_or(6, TimestampPrecision::digits);
+                    match digits {
+                        0 => since_epoch.as_secs().to_string(),
+                        digits => alloc::format!(
+                            "{}.{:0width$}",
+                            since_epoch.as_secs(),
+                            since_epoch.subsec_nanos() / 10u32.pow(9 - digits),
+                            width = digits as usize
+                        ),
+                    }
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "timestamps")]
+    pub(crate) fn format_datetime<Tz: TimeZone>(
+        &self,
+        time: &DateTime<Tz>,
+        precision: Option<TimestampPrecision>,
+    ) -> String
+    where
+        Tz::Offset: Display,
+    {
+        let rfc3339 = |seconds: SecondsFormat| {
+            let seconds = precision.map_or(seconds, TimestampPrecision::seconds_format);
+            time.to_rfc3339_opts(seconds, false)
+        };
+        match self {
+            Self::Rfc3339 => rfc3339(SecondsFormat::Secs),
+            Self::Rfc3339Millis => rfc3339(SecondsFormat::Millis),
+            Self::EpochSeconds => time.timestamp().to_string(),
+            Self::EpochMillis => time.timestamp_millis().to_string(),
+            Self::Custom(format) if format.is_empty() => String::new(),
+            Self::Custom(format) => match precision {
+                Some(precision) => time.format(&precision.apply(format)).to_string(),
+                None => time.format(format).to_string(),
+            },
+        }
+    }
+}
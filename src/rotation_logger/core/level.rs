@@ -0,0 +1,90 @@
+use core::fmt::Display;
+
+/// Severity of log message, from the most important to the most verbose.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+    Trace,
+}
+
+/// Most verbose level compiled in, set by `max_level_*` and `release_max_level_*` features.
+/// `None` when logging is compiled out completely.
+pub const STATIC_MAX_LEVEL: Option<Level> = static_max_level();
+
+const fn static_max_level() -> Option<Level> {
+    // Release features take precedence in builds without debug assertions,
+    // the most restrictive feature wins when several are enabled.
+    if !cfg!(debug_assertions) {
+        if cfg!(feature = "release_max_level_off") {
+            return None;
+        } else if cfg!(feature = "release_max_level_error") {
+            return Some(Level::Error);
+        } else if cfg!(feature = "release_max_level_warn") {
+            return Some(Level::Warn);
+        } else if cfg!(feature = "release_max_level_info") {
+            return Some(Level::Info);
+        } else if cfg!(feature = "release_max_level_debug") {
+            return Some(Level::Debug);
+        } else if cfg!(feature = "release_max_level_trace") {
+            return Some(Level::Trace);
+        }
+    }
+
+    if cfg!(feature = "max_level_off") {
+        None
+    } else if cfg!(feature = "max_level_error") {
+        Some(Level::Error)
+    } else if cfg!(feature = "max_level_warn") {
+        Some(Level::Warn)
+    } else if cfg!(feature = "max_level_info") {
+        Some(Level::Info)
+    } else if cfg!(feature = "max_level_debug") {
+        Some(Level::Debug)
+    } else {
+        Some(Level::Trace)
+    }
+}
+
+impl Level {
+    /// Whether messages of this level are compiled in, see `STATIC_MAX_LEVEL`.
+    /// Being `const`, check lets compiler drop disabled macro calls with their arguments.
+    pub const fn is_statically_enabled(self) -> bool {
+        match STATIC_MAX_LEVEL {
+            Some(max) => self as u8 <= max as u8,
+            None => false,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        }
+    }
+
+    /// Level of name, e.g. `warn` or `WARN`, `None` for unknown name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        [
+            Level::Error,
+            Level::Warn,
+            Level::Info,
+            Level::Debug,
+            Level::Trace,
+        ]
+        .into_iter()
+        .find(|level| level.as_str().eq_ignore_ascii_case(name))
+    }
+}
+
+impl Display for Level {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
@@ -0,0 +1,265 @@
+#[cfg(feature = "std")]
+use alloc::string::ToString;
+use alloc::{borrow::Cow, string::String, vec, vec::Vec};
+
+use smallvec::SmallVec;
+
+use crate::rotation_logger::core::{FieldValue, Level};
+
+/// Modules kept inline, without heap allocation of the list, most call sites use less.
+const INLINE_MODULES: usize = 3;
+
+/// Version of file header, JSON and binary records written by this crate.
+pub const SCHEMA_VERSION: u32 = 3;
+
+/// First byte of binary message of schema `1` and later, above any level of schema `0`.
+#[cfg(feature = "std")]
+const BINARY_SCHEMA_MARK: u8 = 0x80;
+
+/// Message that must be shared across logger senders.
+#[derive(Debug, Clone)]
+pub struct Message {
+    level: Level,
+    modules: SmallVec<[String; INLINE_MODULES]>,
+    text: String,
+    /// Structured key-value data attached to message.
+    fields: Vec<(String, FieldValue)>,
+    /// Target of `log!(target: ..)`, e.g. `wire`, apart from modules.
+    target: Option<String>,
+}
+
+impl Message {
+    pub fn new(modules: &[String], text: &str) -> Self {
+        Self {
+            level: Default::default(),
+            modules: modules.iter().cloned().collect(),
+            text: text.into(),
+            fields: vec![],
+            target: None,
+        }
+    }
+
+    pub fn with_field(mut self, key: &str, value: impl Into<FieldValue>) -> Self {
+        self.fields.push((key.into(), value.into()));
+        self
+    }
+
+    /// Field of any serializable value, written as nested JSON by JSON outputs.
+    /// Error of serialization is kept as text value.
+    #[cfg(feature = "serde")]
+    pub fn with_serde_field(self, key: &str, value: &impl serde::Serialize) -> Self {
+        let value = match serde_json::to_value(value) {
+            Ok(value) => FieldValue::Json(value),
+            Err(err) => FieldValue::Str(err.to_string()),
+        };
+        self.with_field(key, value)
+    }
+
+    pub fn with_level(mut self, level: Level) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Target of message, same as `target` of `log` crate, shown by `{target}` mask.
+    pub fn with_target(mut self, target: &str) -> Self {
+        self.target = Some(target.into());
+        self
+    }
+
+    /// Put `modules` before modules of message.
+    #[cfg(feature = "std")]
+    pub(crate) fn with_module_prefix(mut self, modules: &[String]) -> Self {
+        if !modules.is_empty() {
+            self.modules.insert_many(0, modules.iter().cloned());
+        }
+        self
+    }
+
+    /// Make message same as `Message::new(modules, text)`, keeping allocated memory.
+    #[cfg(feature = "std")]
+    pub(crate) fn reset(&mut self, modules: &[String], text: &str) {
+        self.level = Default::default();
+        self.modules.truncate(modules.len());
+        for (index, module) in modules.iter().enumerate() {
+            match self.modules.get_mut(index) {
+                Some(current) => current.clone_from(module),
+                None => self.modules.push(module.clone()),
+            }
+        }
+        self.text.clear();
+        self.text.push_str(text);
+        self.fields.clear();
+        self.target = None;
+    }
+
+    pub fn level(&self) -> Level {
+        self.level
+    }
+
+    pub fn modules(&self) -> &[String] {
+        &self.modules
+    }
+
+    pub fn text(&self) -> &String {
+        &self.text
+    }
+
+    pub fn fields(&self) -> &Vec<(String, FieldValue)> {
+        &self.fields
+    }
+
+    /// Value of the first field with `key`.
+    pub fn field(&self, key: &str) -> Option<&FieldValue> {
+        self.fields
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value)
+    }
+
+    pub fn target(&self) -> Option<&str> {
+        self.target.as_deref()
+    }
+
+    /// Binary form for passing message to other process, starting with schema version.
+    #[cfg(feature = "std")]
+    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+        fn put(data: &mut Vec<u8>, value: &str) {
+            data.extend_from_slice(&(value.len() as u32).to_le_bytes());
+            data.extend_from_slice(value.as_bytes());
+        }
+
+        let mut data = vec![BINARY_SCHEMA_MARK | SCHEMA_VERSION as u8, self.level as u8];
+        data.extend_from_slice(&(self.modules.len() as u32).to_le_bytes());
+        for module in &self.modules {
+            put(&mut data, module);
+        }
+        put(&mut data, &self.text);
+        data.extend_from_slice(&(self.fields.len() as u32).to_le_bytes());
+        for (key, value) in &self.fields {
+            put(&mut data, key);
+            data.push(value.tag());
+            put(&mut data, &value.to_string());
+        }
+        match &self.target {
+            Some(target) => {
+                data.push(1);
+                put(&mut data, target);
+            }
+            None => data.push(0),
+        }
+        data
+    }
+
+    /// Message from `to_bytes` data of any schema, `None` if data is malformed or schema unknown.
+    #[cfg(feature = "std")]
+    pub(crate) fn from_bytes(data: &[u8]) -> Option<Self> {
+        match data.first()? {
+            // Schema `0` had no version, it starts with level.
+            mark if mark & BINARY_SCHEMA_MARK == 0 => Self::from_bytes_of(data, 0),
+            // Schema `1` only added version, `2` appended target, `3` added type of field values.
+            mark @ 0x81..=0x83 => Self::from_bytes_of(&data[1..], mark & !BINARY_SCHEMA_MARK),
+            _ => None,
+        }
+    }
+
+    /// Message of schema `schema` from data after version.
+    #[cfg(feature = "std")]
+    fn from_bytes_of(data: &[u8], schema: u8) -> Option<Self> {
+        struct Reader<'a>(&'a [u8]);
+
+        impl Reader<'_> {
+            fn u32(&mut self) -> Option<usize> {
+                let (value, rest) = self.0.split_first_chunk::<4>()?;
+                self.0 = rest;
+                Some(u32::from_le_bytes(*value) as usize)
+            }
+
+            fn u8(&mut self) -> Option<u8> {
+                let (value, rest) = self.0.split_first()?;
+                self.0 = rest;
+                Some(*value)
+            }
+
+            fn string(&mut self) -> Option<String> {
+                let len = self.u32()?;
+                if len > self.0.len() {
+                    return None;
+                }
+                let (value, rest) = self.0.split_at(len);
+                self.0 = rest;
+                Some(String::from_utf8_lossy(value).into_owned())
+            }
+        }
+
+        let (level, rest) = data.split_first()?;
+        let level = match level {
+            0 => Level::Error,
+            1 => Level::Warn,
+            2 => Level::Info,
+            3 => Level::Debug,
+            4 => Level::Trace,
+            _ => return None,
+        };
+        let mut reader = Reader(rest);
+
+        let modules = (0..reader.u32()?)
+            .map(|_| reader.string())
+            .collect::<Option<Vec<String>>>()?;
+        let mut message = Message::new(&modules, &reader.string()?).with_level(level);
+        for _ in 0..reader.u32()? {
+            let key = reader.string()?;
+            let tag = if schema >= 3 { reader.u8()? } else { 0 };
+            message = message.with_field(&key, FieldValue::from_tagged(tag, reader.string()?));
+        }
+        if schema >= 2 && reader.u8()? == 1 {
+            message = message.with_target(&reader.string()?);
+        }
+        Some(message)
+    }
+}
+
+/// Message of `Info` level without modules.
+impl From<&str> for Message {
+    fn from(text: &str) -> Self {
+        Self::new(&[], text)
+    }
+}
+
+impl From<String> for Message {
+    fn from(text: String) -> Self {
+        Self::new(&[], &text)
+    }
+}
+
+/// Text of message for macros: string, or closure building it only when message is written.
+pub trait LogText<'a> {
+    fn into_text(self) -> Cow<'a, str>;
+}
+
+impl<'a> LogText<'a> for &'a str {
+    fn into_text(self) -> Cow<'a, str> {
+        Cow::Borrowed(self)
+    }
+}
+
+impl<'a> LogText<'a> for &'a String {
+    fn into_text(self) -> Cow<'a, str> {
+        Cow::Borrowed(self)
+    }
+}
+
+impl LogText<'_> for String {
+    fn into_text(self) -> Cow<'static, str> {
+        Cow::Owned(self)
+    }
+}
+
+impl<F, T> LogText<'_> for F
+where
+    F: FnOnce() -> T,
+    T: Into<String>,
+{
+    fn into_text(self) -> Cow<'static, str> {
+        Cow::Owned(self().into())
+    }
+}
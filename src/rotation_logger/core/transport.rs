@@ -0,0 +1,48 @@
+#[cfg(feature = "timestamps")]
+use core::fmt::Display;
+
+#[cfg(feature = "timestamps")]
+use chrono::{DateTime, TimeZone};
+
+use crate::rotation_logger::core::{Message, MessageFormatter, formatter::Stamp};
+
+/// Destination of formatted lines, where there is no `std` worker.
+pub trait Transport {
+    fn write_line(&mut self, line: &str);
+}
+
+/// Logger without worker thread and channel: every message is formatted and written
+/// to transport on call. Time is passed by caller, since there may be no system clock.
+pub struct CoreLogger<T: Transport> {
+    formatter: MessageFormatter,
+    transport: T,
+}
+
+impl<T: Transport> CoreLogger<T> {
+    pub fn new(formatter: MessageFormatter, transport: T) -> Self {
+        Self {
+            formatter,
+            transport,
+        }
+    }
+
+    /// Write message with empty `timestamp` mask.
+    pub fn log(&mut self, message: &Message) {
+        let line = self.formatter.render(message, &Stamp::default(), None);
+        self.transport.write_line(&line);
+    }
+
+    /// Write message stamped with given time.
+    #[cfg(feature = "timestamps")]
+    pub fn log_at<Tz: TimeZone>(&mut self, message: &Message, timestamp: &DateTime<Tz>)
+    where
+        Tz::Offset: Display,
+    {
+        let line = self.formatter.format_at(message, timestamp);
+        self.transport.write_line(&line);
+    }
+
+    pub fn transport(&mut self) -> &mut T {
+        &mut self.transport
+    }
+}
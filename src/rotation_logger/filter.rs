@@ -0,0 +1,117 @@
+//! # Rules dropping messages before they are formatted.
+//!
+#[cfg(feature = "timestamps")]
+use chrono::NaiveTime;
+#[cfg(feature = "regex")]
+use regex::Regex;
+
+#[cfg(feature = "timestamps")]
+use crate::rotation_logger::Level;
+
+/// Keep or drop messages with text matching precompiled regex,
+/// e.g. to suppress noisy warning of third-party code.
+///
+/// Rules are checked in order they were added to `Settings`, the first matching one decides.
+/// Messages matching no rule are kept.
+///
+/// # Example:
+///
+/// ```
+/// # use rotation_logger::{Settings, TextFilter};
+/// let settings = Settings::default()
+///     .with_text_filter(TextFilter::keep("critical").unwrap())
+///     .with_text_filter(TextFilter::drop(r"^connection \d+ reset$").unwrap());
+/// ```
+#[cfg(feature = "regex")]
+#[derive(Debug, Clone)]
+pub struct TextFilter {
+    pattern: Regex,
+    keep: bool,
+}
+
+#[cfg(feature = "regex")]
+impl TextFilter {
+    /// Drop messages matching `pattern`.
+    pub fn drop(pattern: &str) -> Result<Self, regex::Error> {
+        Ok(Self {
+            pattern: Regex::new(pattern)?,
+            keep: false,
+        })
+    }
+
+    /// Keep messages matching `pattern`, even if later rules drop them.
+    pub fn keep(pattern: &str) -> Result<Self, regex::Error> {
+        Ok(Self {
+            pattern: Regex::new(pattern)?,
+            keep: true,
+        })
+    }
+
+    pub fn pattern(&self) -> &str {
+        self.pattern.as_str()
+    }
+
+    pub fn is_keep(&self) -> bool {
+        self.keep
+    }
+
+    /// Decision of the first rule of `filters` matching `text`, `true` if none matches.
+    pub(crate) fn keeps(filters: &[TextFilter], text: &str) -> bool {
+        filters
+            .iter()
+            .find(|filter| filter.pattern.is_match(text))
+            .is_none_or(|filter| filter.keep)
+    }
+}
+
+/// Level of every message during daily window of local time, instead of `Settings` levels,
+/// e.g. `Level::Info` at night to drop debug messages, or `Level::Trace` during maintenance.
+/// Window ending before its start goes over midnight. Checked by worker, when message arrives.
+///
+/// # Example:
+///
+/// ```
+/// # use chrono::NaiveTime;
+/// # use rotation_logger::{Level, LevelWindow, Settings};
+/// let night = LevelWindow::new(
+///     NaiveTime::from_hms_opt(0, 0, 0).unwrap(),
+///     NaiveTime::from_hms_opt(6, 0, 0).unwrap(),
+///     Level::Info,
+/// );
+/// let settings = Settings::default().with_level_window(night);
+/// ```
+#[cfg(feature = "timestamps")]
+#[derive(Debug, Clone)]
+pub struct LevelWindow {
+    start: NaiveTime,
+    end: NaiveTime,
+    level: Level,
+}
+
+#[cfg(feature = "timestamps")]
+impl LevelWindow {
+    pub fn new(start: NaiveTime, end: NaiveTime, level: Level) -> Self {
+        Self { start, end, level }
+    }
+
+    pub fn start(&self) -> NaiveTime {
+        self.start
+    }
+
+    pub fn end(&self) -> NaiveTime {
+        self.end
+    }
+
+    pub fn level(&self) -> Level {
+        self.level
+    }
+
+    /// Is `time` of day in window, start included, end excluded.
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            self.start <= time || time < self.end
+        }
+    }
+}
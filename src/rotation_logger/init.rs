@@ -0,0 +1,93 @@
+//! # Zero-config setup.
+//!
+//! `init` builds `Settings` from environment, starts worker and installs global sender
+//! used by macros, e.g. for small tools and tests:
+//!
+//! ```no_run
+//! use rotation_logger::log;
+//!
+//! rotation_logger::init();
+//! log!("Started");
+//! ```
+//!
+//! `RUST_LOG` sets level threshold (`error`, `warn`, `info`, `debug`, `trace` or `off`),
+//! `info` when not set. Module directives like `my_crate=debug` are skipped.
+//! Output is console in debug builds and rotated files in `./logs` in release ones.
+//!
+use std::{
+    io,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+#[cfg(feature = "file")]
+use crate::rotation_logger::FileSettings;
+use crate::rotation_logger::{
+    Logger, MessageFormatter, OutputChannel, Settings,
+    logger::{Level, queue},
+};
+
+/// Environment variable with level threshold, as of `env_logger`.
+pub const RUST_LOG_ENV: &str = "RUST_LOG";
+
+static INITIALIZED: AtomicBool = AtomicBool::new(false);
+
+/// Start global logger, panics if logger is already running.
+pub fn init() {
+    try_init().expect("rotation_logger::init failed")
+}
+
+/// Start global logger, fails if `init` was already called or other `Logger` is running.
+pub fn try_init() -> io::Result<()> {
+    if queue::running().is_some() || INITIALIZED.swap(true, Ordering::AcqRel) {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            "logger is already running",
+        ));
+    }
+
+    // Worker runs for the rest of process life, its handle is not needed.
+    let _ = Logger::new(settings_from_env()).run_async();
+    Ok(())
+}
+
+fn settings_from_env() -> Settings {
+    #[cfg(feature = "file")]
+    let output = OutputChannel::Auto(FileSettings::default());
+    #[cfg(not(feature = "file"))]
+    let output = OutputChannel::Console;
+
+    let settings = Settings::new(
+        true,
+        Settings::default().buffer_size(),
+        output,
+        MessageFormatter::default(),
+    );
+    match parse_level(&std::env::var(RUST_LOG_ENV).unwrap_or_default()) {
+        Some(Some(level)) => settings.with_min_level(level),
+        Some(None) => Settings::new(
+            false,
+            settings.buffer_size(),
+            settings.output().clone(),
+            MessageFormatter::default(),
+        ),
+        None => settings.with_min_level(Level::Info),
+    }
+}
+
+/// Global level of `RUST_LOG` value: `Some(None)` for `off`, `None` when not set.
+pub(crate) fn parse_level(value: &str) -> Option<Option<Level>> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|directive| !directive.contains('='))
+        .filter_map(|directive| match directive.to_lowercase().as_str() {
+            "off" => Some(None),
+            "error" => Some(Some(Level::Error)),
+            "warn" => Some(Some(Level::Warn)),
+            "info" => Some(Some(Level::Info)),
+            "debug" => Some(Some(Level::Debug)),
+            "trace" => Some(Some(Level::Trace)),
+            _ => None,
+        })
+        .next_back()
+}
@@ -1,26 +1,75 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::{Once, mpsc::channel};
 use std::{
-    ptr,
+    fmt::Display,
+    io, ptr,
     sync::{
-        atomic::{AtomicPtr, Ordering},
-        mpsc::{Sender, channel},
+        Arc, OnceLock,
+        atomic::{AtomicBool, AtomicPtr, Ordering},
+        mpsc::Sender,
     },
-    thread::{self, JoinHandle},
+    thread::JoinHandle,
+    time::Duration,
 };
 
-use crate::rotation_logger::Settings;
+use smallvec::SmallVec;
 
+use crate::rotation_logger::{
+    Settings, aggregator,
+    settings::{LevelFilter, module_path},
+};
+
+pub(crate) mod backlog;
+mod banner;
+pub(crate) mod dead_letter;
 mod enabled;
-mod message;
+pub(crate) mod flush;
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) mod lazy;
+mod module;
+mod overflow;
+mod pool;
+pub(crate) mod queue;
+mod scope;
+pub(crate) mod spill;
+pub(crate) mod stats;
+mod worker;
 
+pub use backlog::BacklogWarning;
+pub use banner::Banner;
 pub use enabled::EnabledLogger;
+pub use flush::{FlushFuture, FlushGuard, flush_on_exit};
+pub use module::ModuleLogger;
+pub use overflow::OverflowPolicy;
+pub use pool::MessagePool;
+use queue::Queue;
+pub use scope::ScopedLogger;
+pub(crate) use scope::{scoped_modules, with_scoped_modules};
+pub use stats::LoggerStats;
+pub use worker::WorkerThread;
 
-pub use message::Message;
+pub use crate::rotation_logger::core::{Level, Message};
 
+/// Sender of messages to running logger, for code sending to it directly.
+/// Messages are passed to running logger by thread started with the first one,
+/// null until then and always on `wasm32`.
+#[deprecated(note = "log with `Logger` or macros, they filter messages before sending")]
 pub static LOG_SENDER: AtomicPtr<Sender<Message>> = AtomicPtr::new(ptr::null_mut());
 
+/// Set to `1` or `true` to make every `Logger` disabled without code changes.
+/// Checked once, on first `Logger` creation.
+pub const DISABLE_ENV: &str = "ROTATION_LOGGER_DISABLE";
+
+static DISABLED_BY_ENV: OnceLock<bool> = OnceLock::new();
+/// Live kill switch, messages are dropped while it is on.
+static KILL_SWITCH: AtomicBool = AtomicBool::new(false);
+/// How long `Logger::log_sync` and `Logger::shutdown` wait for worker.
+const SYNC_TIMEOUT: Duration = Duration::from_secs(30);
+
 /// Logger builder based on settings.
 /// Initialize logger from this data structure.
 #[derive(Clone)]
+#[allow(clippy::large_enum_variant)]
 pub enum Logger {
     Enabled(Settings),
     Disabled,
@@ -34,7 +83,11 @@ impl Logger {
             Self::disabled()
         }
     }
+    /// Enabled logger, unless `ROTATION_LOGGER_DISABLE` is set.
     pub fn enabled(settings: Settings) -> Self {
+        if Self::disabled_by_env() {
+            return Self::disabled();
+        }
         Self::Enabled(settings)
     }
 
@@ -42,37 +95,333 @@ impl Logger {
         Self::Disabled
     }
 
-    pub fn log(&self, modules: &Vec<String>, text: &str) {
+    /// Log message or anything turning into one, e.g. text or domain event with `From` impl.
+    /// Same as `log_message`.
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// # use rotation_logger::{Logger, Message, Settings};
+    /// let logger = Logger::new(Settings::default());
+    /// logger.log("started");
+    /// logger.log(Message::new(&["DB".into()], "connected").with_field("pool", 8u64));
+    /// ```
+    pub fn log<M: Into<Message>>(&self, message: M) {
+        self.log_message(message.into())
+    }
+
+    /// Log `value` of `Info` level, e.g. error or number, turned to text only when it is written.
+    pub fn log_display(
+        &self,
+        modules: impl IntoIterator<Item = impl AsRef<str>>,
+        value: &impl Display,
+    ) {
+        let modules = stack_modules(modules);
+        if self.is_enabled(Level::Info, &modules) {
+            self.log_with_level(Level::Info, &modules, &value.to_string())
+        }
+    }
+
+    /// Log `text` of `level`, modules are borrowed, e.g. `&["DB", "POOL"]`,
+    /// and copied only when message is written.
+    pub fn log_with_level(
+        &self,
+        level: Level,
+        modules: impl IntoIterator<Item = impl AsRef<str>>,
+        text: &str,
+    ) {
+        if let Logger::Enabled(settings) = self {
+            let modules = stack_modules(modules);
+            if !Self::is_enabled_by(settings.levels(), level, &modules) {
+                return;
+            }
+            let modules = scoped_modules(&modules);
+            let message = match settings.message_pool() {
+                Some(pool) => pool.message(&modules, text),
+                None => Message::new(&modules, text),
+            };
+            Self::send(message.with_level(level));
+        }
+    }
+
+    /// Log and wait until worker wrote and flushed message, e.g. audit events,
+    /// which must be on disk before going on. Files are synced to disk by flush.
+    ///
+    /// Error when no worker runs, message is dropped by full queue, worker did not answer
+    /// in 30 seconds or failed to write or flush, also when only some of sinks failed.
+    /// Messages dropped by levels of running logger or kill switch are not waited for.
+    ///
+    /// With `OverflowPolicy::DropOldest` message waiting in full queue may still make room
+    /// for later ones, `Ok` does not tell it was written then.
+    ///
+    /// # Example:
+    ///
+    /// ```no_run
+    /// # use rotation_logger::{Logger, Settings};
+    /// let logger = Logger::new(Settings::default());
+    /// logger.run_async();
+    /// logger
+    ///     .log_sync(["AUDIT"], "user 42 deleted")
+    ///     .expect("audit record is not written");
+    /// ```
+    pub fn log_sync(
+        &self,
+        modules: impl IntoIterator<Item = impl AsRef<str>>,
+        text: &str,
+    ) -> io::Result<()> {
+        if let Logger::Disabled = self {
+            return Ok(());
+        }
+        let Some(queue) = queue::running() else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "logger is not running",
+            ));
+        };
+        let modules = stack_modules(modules);
+        if !Self::is_enabled_by(queue.levels(), Level::Info, &modules) || Self::kill_switch() {
+            return Ok(());
+        }
+        if !queue.send(Message::new(&scoped_modules(&modules), text)) {
+            return Err(io::Error::other(
+                "logger dropped message, queue is full or closed",
+            ));
+        }
+        // Request is taken after message is queued, so worker serves it after handling message.
+        flush::request_on_queue(&queue, SYNC_TIMEOUT)
+    }
+
+    /// Would message of `level` and `modules` be written, e.g. to skip building expensive text.
+    /// Modules are any strings, e.g. `["DB"]` or `&vec`, `[""; 0]` for none.
+    pub fn is_enabled(
+        &self,
+        level: Level,
+        modules: impl IntoIterator<Item = impl AsRef<str>>,
+    ) -> bool {
+        match self {
+            Logger::Enabled(settings) => {
+                Self::is_enabled_by(settings.levels(), level, &stack_modules(modules))
+            }
+            Logger::Disabled => false,
+        }
+    }
+
+    fn is_enabled_by(levels: &LevelFilter, level: Level, modules: &[impl AsRef<str>]) -> bool {
+        level.is_statically_enabled()
+            && !Self::kill_switch()
+            && with_scoped_modules(modules, |modules| levels.is_enabled_for(level, modules))
+    }
+
+    /// Message with modules of open scopes before `modules`, see `ScopedLogger`. Used by macros.
+    #[doc(hidden)]
+    pub fn scoped_message(modules: &[&str], text: &str) -> Message {
+        Message::new(&scoped_modules(modules), text)
+    }
+
+    /// Same as `is_enabled` for logger started with `run_async`, `false` if none runs. Used by macros.
+    #[doc(hidden)]
+    pub fn is_running_enabled(level: Level, modules: &[&str]) -> bool {
+        level.is_statically_enabled()
+            && queue::running()
+                .is_some_and(|queue| Self::is_enabled_by(queue.levels(), level, modules))
+    }
+
+    /// Guard adding `module` to messages logged on this thread while it lives, see `ScopedLogger`.
+    pub fn scoped(&self, module: &str) -> ScopedLogger<'_> {
+        ScopedLogger::new(self, module)
+    }
+
+    /// Logger of module in hierarchy, e.g. `app::db`, see `ModuleLogger`.
+    pub fn get(&self, name: &str) -> ModuleLogger {
+        ModuleLogger::new(self.clone(), module_path(name))
+    }
+
+    /// Handle prepending `modules` to every message, e.g. `logger.child(["THREAD1", "WORKER"])`.
+    pub fn child<I>(&self, modules: I) -> ModuleLogger
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        ModuleLogger::new(self.clone(), modules.into_iter().map(Into::into).collect())
+    }
+
+    fn disabled_by_env() -> bool {
+        *DISABLED_BY_ENV.get_or_init(|| {
+            std::env::var(DISABLE_ENV)
+                .map(|value| value == "1" || value.eq_ignore_ascii_case("true"))
+                .unwrap_or(false)
+        })
+    }
+
+    /// Turn logging off and on again while running, e.g. from signal handler or admin command.
+    /// While on, messages of every `Logger` and macros are dropped.
+    pub fn set_kill_switch(on: bool) {
+        KILL_SWITCH.store(on, Ordering::Relaxed);
+    }
+
+    pub fn kill_switch() -> bool {
+        KILL_SWITCH.load(Ordering::Relaxed)
+    }
+
+    /// Log prepared message, e.g. with fields.
+    pub fn log_message(&self, message: Message) {
+        let message = scope::scoped_message(message);
         match &self {
-            Logger::Enabled(_) => {
-                let prt = LOG_SENDER.load(Ordering::Acquire);
-
-                if !prt.is_null() {
-                    unsafe {
-                        let sender = &*prt;
-                        let message = Message::new(modules, text);
-                        let _ = sender.send(message);
-                    }
-                }
+            Logger::Enabled(settings)
+                if message.level().is_statically_enabled()
+                    && settings.is_enabled_for(message.level(), message.modules()) =>
+            {
+                Self::send(message)
             }
-            Logger::Disabled => return,
+            Logger::Enabled(_) => {}
+            Logger::Disabled => {}
+        }
+    }
+
+    /// Pass message to running logger worker, if any. Used by macros.
+    #[doc(hidden)]
+    pub fn send(message: Message) {
+        if Self::kill_switch() {
+            return;
+        }
+        if let Some(queue) = queue::running() {
+            queue.send(message);
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        enabled::poll_local();
+    }
+
+    /// Write and flush everything logged before the call, e.g. before responding to request.
+    /// Future is ready at once without running worker, any executor can wait for it.
+    ///
+    /// # Example:
+    ///
+    /// ```no_run
+    /// # use rotation_logger::{Logger, Settings};
+    /// # async fn respond() {
+    /// Logger::new(Settings::default()).log_display(["HTTP"], &"order placed");
+    /// Logger::flush().await;
+    /// # }
+    /// ```
+    pub fn flush() -> FlushFuture {
+        flush::flush()
+    }
+
+    /// Same as `flush`, with `callback` called once it is done, at once without running worker.
+    ///
+    /// Callback runs on worker thread, which writes nothing meanwhile, so it should be short,
+    /// e.g. send to channel or wake up task. When worker stops before serving request,
+    /// also by panic, callback is called as it stops.
+    pub fn flush_with(callback: impl FnOnce() + Send + 'static) {
+        flush::flush_with(callback)
+    }
+
+    /// Stop running worker after it writes and flushes everything logged before the call,
+    /// e.g. at the end of `main`, so buffered records are not lost on exit.
+    /// Messages logged later are dropped. Error when worker did not stop in 30 seconds
+    /// or failed to write or flush.
+    ///
+    /// # Example:
+    ///
+    /// ```no_run
+    /// # use rotation_logger::{Logger, Settings};
+    /// let logger = Logger::new(Settings::default());
+    /// logger.run_async();
+    /// logger.log_display(["MAIN"], &"done");
+    /// logger.shutdown().unwrap();
+    /// ```
+    pub fn shutdown(self) -> io::Result<()> {
+        if let Logger::Disabled = self {
+            return Ok(());
         }
+        flush::shutdown(SYNC_TIMEOUT)
+    }
+
+    /// Queue of logger started with `run_async`, to notice worker falling behind.
+    pub fn stats() -> LoggerStats {
+        queue::running().map_or_else(LoggerStats::default, |queue| queue.stats())
     }
 
+    /// Start logger worker thread.
+    /// On `wasm32` there is no worker, messages are written right when logged and `None` returned.
+    ///
+    /// With `Settings::with_lazy_start` thread is started by first message and `None` returned.
     pub fn run_async(&self) -> Option<JoinHandle<()>> {
         match self {
             Logger::Enabled(settings) => {
-                let (tx, rx) = channel::<Message>();
-
-                let boxed = Box::new(tx.clone());
-                let ptr = Box::into_raw(boxed);
+                let queue = Queue::new()
+                    .with_bound(settings.queue_capacity())
+                    .with_spill(settings.spill())
+                    .with_levels(settings.levels().clone());
+                let queue = Arc::new(queue);
+                let logger = EnabledLogger::new(settings.clone(), queue.clone());
 
-                LOG_SENDER.store(ptr, Ordering::Relaxed);
-                let logger = EnabledLogger::new(settings.clone(), rx);
+                // No threads in browser, messages are processed on log call.
+                #[cfg(target_arch = "wasm32")]
+                let handle = {
+                    enabled::set_local(logger);
+                    None
+                };
+                // Deferred before queue is running, so its first message starts worker.
+                #[cfg(not(target_arch = "wasm32"))]
+                let handle = {
+                    let worker = settings.worker_thread().clone();
+                    let spawn = move || {
+                        forward_log_sender();
+                        match worker.spawn(move || logger.run()) {
+                            Ok(handle) => Some(handle),
+                            Err(err) => {
+                                println!("Logger cant start worker thread. Error: {err}");
+                                None
+                            }
+                        }
+                    };
+                    if settings.lazy_start() {
+                        queue.lazy().defer(move || drop(spawn()));
+                        None
+                    } else {
+                        spawn()
+                    }
+                };
 
-                Some(thread::spawn(move || logger.run()))
+                if let Some(address) = settings.aggregator()
+                    && let Err(err) =
+                        aggregator::listen(address, settings.aggregator_limits(), queue.clone())
+                {
+                    println!("Logger cant listen for aggregated messages. Error: {err}");
+                }
+                // Previous queue is closed, its worker not started yet is dropped.
+                queue::set_running(queue);
+                handle
             }
             Logger::Disabled => None,
         }
     }
 }
+
+/// Point `LOG_SENDER` to channel passing messages to running logger, once for whole process.
+#[cfg(not(target_arch = "wasm32"))]
+#[allow(deprecated)]
+fn forward_log_sender() {
+    static FORWARDING: Once = Once::new();
+    FORWARDING.call_once(|| {
+        let (sender, receiver) = channel::<Message>();
+        let forwarder = std::thread::Builder::new()
+            .name("rotation_logger-sender".into())
+            .spawn(move || {
+                for message in receiver {
+                    Logger::send(message);
+                }
+            });
+        // Sender is never freed, other threads may still hold it.
+        if forwarder.is_ok() {
+            LOG_SENDER.store(Box::into_raw(Box::new(sender)), Ordering::Release);
+        }
+    });
+}
+
+/// Modules argument on stack, checked by levels before anything is allocated.
+fn stack_modules<S: AsRef<str>>(modules: impl IntoIterator<Item = S>) -> SmallVec<[S; 4]> {
+    modules.into_iter().collect()
+}
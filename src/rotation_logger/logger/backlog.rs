@@ -0,0 +1,128 @@
+use std::time::{Duration, SystemTime};
+
+use crate::rotation_logger::logger::{Level, Message};
+
+/// Warning record written by worker when its queue grows past share of capacity,
+/// e.g. `logger backlog at 80% (8000/10000)`, early signal of slow disk or sink.
+/// Written to every output regardless of levels and filters, attach with `Settings::with_backlog_warning`.
+///
+/// Capacity is the one of `Settings::with_queue_capacity`, so warnings come before messages
+/// are blocked or dropped. Queue without capacity needs one of `with_capacity`.
+///
+/// Warns once per threshold crossed upwards, and not more often than `interval`.
+/// Queue is looked at whenever worker takes message and while it waits for more.
+///
+/// # Example:
+///
+/// ```
+/// # use std::time::Duration;
+/// # use rotation_logger::{BacklogWarning, OverflowPolicy, Settings};
+/// let warning = BacklogWarning::new()
+///     .with_thresholds(&[50, 80, 95])
+///     .with_interval(Duration::from_secs(10));
+/// let settings = Settings::default()
+///     .with_queue_capacity(10_000, OverflowPolicy::Block)
+///     .with_backlog_warning(warning);
+/// ```
+#[derive(Debug, Clone)]
+pub struct BacklogWarning {
+    /// Used only with queue without capacity.
+    capacity: Option<u64>,
+    /// Percents of `capacity`, ascending.
+    thresholds: Vec<u8>,
+    interval: Duration,
+}
+
+impl BacklogWarning {
+    /// Warning at 80% of queue capacity, at most once a minute.
+    pub fn new() -> Self {
+        Self {
+            capacity: None,
+            thresholds: vec![80],
+            interval: Duration::from_secs(60),
+        }
+    }
+
+    /// Number of waiting messages thresholds are percents of, when queue has no capacity.
+    /// Capacity of `Settings::with_queue_capacity` is used instead of it.
+    pub fn with_capacity(mut self, capacity: u64) -> Self {
+        self.capacity = Some(capacity.max(1));
+        self
+    }
+
+    /// Percents of capacity to warn at.
+    pub fn with_thresholds(mut self, thresholds: &[u8]) -> Self {
+        self.thresholds = thresholds.to_vec();
+        self.thresholds.sort_unstable();
+        self.thresholds.dedup();
+        self
+    }
+
+    /// Least time between two warnings.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    pub fn capacity(&self) -> Option<u64> {
+        self.capacity
+    }
+    pub fn thresholds(&self) -> &[u8] {
+        &self.thresholds
+    }
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Warning for queue of `len` out of `capacity` messages at `time`, if it is due.
+    pub(crate) fn check(
+        &self,
+        state: &mut BacklogState,
+        len: u64,
+        capacity: u64,
+        time: SystemTime,
+    ) -> Option<Message> {
+        let capacity = capacity.max(1);
+        let percent = len.saturating_mul(100) / capacity;
+        let crossed = self
+            .thresholds
+            .iter()
+            .filter(|threshold| u64::from(**threshold) <= percent)
+            .count();
+        if crossed <= state.crossed {
+            // Queue shrinks, crossing threshold again warns again.
+            state.crossed = crossed;
+            return None;
+        }
+        let due = state.last.is_none_or(|last| {
+            time.duration_since(last)
+                .is_ok_and(|elapsed| elapsed >= self.interval)
+        });
+        if !due {
+            return None;
+        }
+        state.crossed = crossed;
+        state.last = Some(time);
+        let threshold = self.thresholds[crossed - 1];
+        let text = format!("logger backlog at {threshold}% ({len}/{capacity})");
+        Some(
+            Message::new(&["rotation_logger".into()], &text)
+                .with_level(Level::Warn)
+                .with_field("queue_len", len)
+                .with_field("capacity", capacity),
+        )
+    }
+}
+
+impl Default for BacklogWarning {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Thresholds crossed and time of last warning, kept by worker.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct BacklogState {
+    crossed: usize,
+    last: Option<SystemTime>,
+}
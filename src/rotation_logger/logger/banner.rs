@@ -0,0 +1,93 @@
+use crate::rotation_logger::logger::{Level, Message};
+
+/// Record written by worker on start, telling which build produced log set.
+/// Written to every output regardless of levels and filters, attach with `Settings::with_banner`.
+///
+/// # Example:
+///
+/// ```
+/// # use rotation_logger::{Banner, Settings};
+/// let banner = Banner::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+///     .with_git_sha("4f2a9c1")
+///     .with_args(true)
+///     .with_env("RUST_LOG");
+/// let settings = Settings::default().with_banner(banner);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Banner {
+    name: String,
+    version: String,
+    git_sha: Option<String>,
+    /// Add command-line arguments of process.
+    args: bool,
+    /// Names of environment variables to add, if set.
+    env: Vec<String>,
+}
+
+impl Banner {
+    /// Banner of app `name` of `version`, e.g. `CARGO_PKG_NAME` and `CARGO_PKG_VERSION` of it.
+    pub fn new(name: &str, version: &str) -> Self {
+        Self {
+            name: name.into(),
+            version: version.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Commit app was built from.
+    pub fn with_git_sha(mut self, git_sha: &str) -> Self {
+        self.git_sha = Some(git_sha.into());
+        self
+    }
+
+    /// Add command-line arguments, joined with spaces.
+    pub fn with_args(mut self, args: bool) -> Self {
+        self.args = args;
+        self
+    }
+
+    /// Add environment variable `name` as `env.<name>` field, when it is set.
+    pub fn with_env(mut self, name: &str) -> Self {
+        self.env.push(name.into());
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+    pub fn git_sha(&self) -> Option<&str> {
+        self.git_sha.as_deref()
+    }
+    pub fn args(&self) -> bool {
+        self.args
+    }
+    pub fn env(&self) -> &[String] {
+        &self.env
+    }
+
+    /// `started <name> <version>` message with fields `app`, `version`, `git_sha`, `args`
+    /// and `env.<name>`.
+    pub(crate) fn message(&self) -> Message {
+        let text = format!("started {} {}", self.name, self.version);
+        let mut message = Message::new(&["rotation_logger".into()], &text)
+            .with_level(Level::Info)
+            .with_field("app", &self.name)
+            .with_field("version", &self.version);
+        if let Some(git_sha) = &self.git_sha {
+            message = message.with_field("git_sha", git_sha);
+        }
+        if self.args {
+            let args = std::env::args().collect::<Vec<String>>().join(" ");
+            message = message.with_field("args", args);
+        }
+        for name in &self.env {
+            if let Ok(value) = std::env::var(name) {
+                message = message.with_field(&format!("env.{name}"), value);
+            }
+        }
+        message
+    }
+}
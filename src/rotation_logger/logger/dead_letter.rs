@@ -0,0 +1,181 @@
+//! # Records sink kept failing to write.
+//!
+//! With `Settings::with_dead_letter` records of failed batch are held and written again
+//! with the next batch. After `max_attempts` failures in a row they are appended to
+//! dead-letter file as formatted lines, one per record, and not tried anymore.
+//! Held records are buried sooner once there are `MAX_HELD` of them. When file can't
+//! take them either, the oldest records past `MAX_HELD` are dropped and counted.
+//! File is never rotated, it is shared by all channels of worker.
+//!
+//! When worker starts, lines of existing dead-letter file are written to main output
+//! in batches before any new record, then file is removed. Lines main output failed
+//! to write wait in `*.replaying` file for next start.
+//!
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, BufWriter, ErrorKind, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::SystemTime,
+};
+
+use crate::rotation_logger::{
+    logger::{Message, queue::Queue},
+    sink::{Record, Sink},
+};
+
+/// Lines of dead-letter file written to sink at once.
+pub(crate) const REPLAY_BATCH: usize = 1_000;
+
+/// Write lines of dead-letter file in `path` to `sink` and remove it, in batches of
+/// `REPLAY_BATCH` lines. Number of lines written.
+///
+/// File is renamed to `*.replaying` first, so records buried meanwhile go to new file.
+/// When sink fails, lines not written yet are kept there and written first on next start,
+/// as is file of replay interrupted by crash.
+pub(crate) fn replay(path: &Path, sink: &mut dyn Sink) -> io::Result<usize> {
+    let mut replaying = path.as_os_str().to_owned();
+    replaying.push(".replaying");
+    let replaying = PathBuf::from(replaying);
+    let mut written = replay_file(&replaying, sink)?;
+    match fs::rename(path, &replaying) {
+        Ok(()) => written += replay_file(&replaying, sink)?,
+        Err(err) if err.kind() == ErrorKind::NotFound => {}
+        Err(err) => return Err(err),
+    }
+    Ok(written)
+}
+
+/// Write lines of file in `path` to `sink` and remove it, keep lines not written on error.
+fn replay_file(path: &Path, sink: &mut dyn Sink) -> io::Result<usize> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(0),
+        Err(err) => return Err(err),
+    };
+    let mut reader = BufReader::new(file);
+    let mut line = String::new();
+    let mut batch = Vec::with_capacity(REPLAY_BATCH);
+    let time = SystemTime::now();
+    let mut written = 0;
+    // Position of line read last and of the first line not written yet.
+    let (mut position, mut offset) = (0, 0);
+    loop {
+        line.clear();
+        let len = reader.read_line(&mut line)?;
+        position += len as u64;
+        if len > 0 {
+            let line = line.trim_end_matches(['\r', '\n']);
+            batch.push(Record::new(Message::new(&[], line), line.into(), time));
+        }
+        if batch.len() >= REPLAY_BATCH || (len == 0 && !batch.is_empty()) {
+            if let Err(err) = sink.write_batch(&batch).and_then(|()| sink.flush()) {
+                keep_from(path, reader.get_mut(), offset)?;
+                return Err(err);
+            }
+            written += batch.len();
+            offset = position;
+            batch.clear();
+        }
+        if len == 0 {
+            break;
+        }
+    }
+    fs::remove_file(path)?;
+    Ok(written)
+}
+
+/// Cut lines before `offset` from `file` in `path`, through copy of the rest.
+fn keep_from(path: &Path, file: &mut File, offset: u64) -> io::Result<()> {
+    let rest = path.with_extension("replaying.rest");
+    file.seek(SeekFrom::Start(offset))?;
+    io::copy(file, &mut File::create(&rest)?)?;
+    fs::rename(rest, path)
+}
+
+/// Most records held for single channel.
+pub(crate) const MAX_HELD: usize = 10_000;
+
+/// Records held for single channel until they are written or buried in dead-letter file.
+pub(crate) struct DeadLetter {
+    path: PathBuf,
+    max_attempts: usize,
+    held: Vec<Record>,
+    /// Failed writes of `held` in a row.
+    attempts: usize,
+    /// Queue of worker, counting buried records.
+    queue: Arc<Queue>,
+}
+
+impl DeadLetter {
+    pub(crate) fn new(path: PathBuf, max_attempts: usize, queue: Arc<Queue>) -> Self {
+        Self {
+            path,
+            max_attempts: max_attempts.max(1),
+            held: vec![],
+            attempts: 0,
+            queue,
+        }
+    }
+
+    /// Write held records followed by `records`, bury them when sink failed too many times.
+    /// Error of sink also tells when records could not be buried.
+    pub(crate) fn write(&mut self, sink: &mut dyn Sink, records: &[Record]) -> io::Result<()> {
+        let result = if self.held.is_empty() {
+            sink.write_batch(records)
+        } else {
+            self.held.extend_from_slice(records);
+            sink.write_batch(&self.held)
+        };
+        match result {
+            Ok(()) => {
+                self.held.clear();
+                self.attempts = 0;
+                Ok(())
+            }
+            Err(err) => {
+                if self.held.is_empty() {
+                    self.held.extend_from_slice(records);
+                }
+                self.attempts += 1;
+                if (self.attempts >= self.max_attempts || self.held.len() >= MAX_HELD)
+                    && let Err(bury_err) = self.bury()
+                {
+                    self.trim();
+                    return Err(io::Error::new(
+                        err.kind(),
+                        format!("{err}, dead letters not written: {bury_err}"),
+                    ));
+                }
+                Err(err)
+            }
+        }
+    }
+
+    /// Drop the oldest held records past `MAX_HELD`.
+    fn trim(&mut self) {
+        let excess = self.held.len().saturating_sub(MAX_HELD);
+        self.held.drain(..excess);
+        self.queue.counters().dropped(excess);
+    }
+
+    /// Append held records to dead-letter file.
+    fn bury(&mut self) -> io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(&self.path)?;
+        let mut file = BufWriter::new(file);
+        for record in &self.held {
+            writeln!(file, "{}", record.line())?;
+        }
+        file.flush()?;
+        self.queue.counters().dead_lettered(self.held.len());
+        self.held.clear();
+        self.attempts = 0;
+        Ok(())
+    }
+}
@@ -1,248 +1,717 @@
 use std::{
-    fs::{self, DirEntry, File, OpenOptions},
-    io::{BufWriter, Write},
-    sync::mpsc::Receiver,
+    cell::Cell,
+    collections::HashMap,
+    io,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
 };
 
+#[cfg(all(feature = "file", not(target_arch = "wasm32")))]
+use crate::rotation_logger::FileSettings;
+#[cfg(all(feature = "file", feature = "formatter", not(target_arch = "wasm32")))]
+use crate::rotation_logger::MessageFormatter;
+#[cfg(feature = "regex")]
+use crate::rotation_logger::TextFilter;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::rotation_logger::logger::queue::Control;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::rotation_logger::sink::ConsoleSink;
+#[cfg(all(feature = "file", not(target_arch = "wasm32")))]
+use crate::rotation_logger::sink::FileSink;
+#[cfg(feature = "shm")]
+use crate::rotation_logger::sink::ShmSink;
+#[cfg(target_arch = "wasm32")]
+use crate::rotation_logger::sink::WebConsoleSink;
+#[cfg(unix)]
+use crate::rotation_logger::sink::{FifoSink, UnixSocketSink};
 use crate::{
-    FileSettings, OutputChannel,
-    rotation_logger::{Settings, logger::Message},
+    OutputChannel,
+    rotation_logger::{
+        FlushPolicy, MessagePool, Settings,
+        aggregator::AggregatorSink,
+        core::Deltas,
+        logger::{
+            Message,
+            backlog::BacklogState,
+            dead_letter::{self, DeadLetter},
+            queue::{Item, Queue},
+            spill::{self, SpillQueue},
+        },
+        sink::{Record, Sink},
+    },
 };
 
 /// Enabled Logger worker.
 pub struct EnabledLogger {
     settings: Settings,
-    receiver: Receiver<Message>,
-    buffer_size: usize,
+    /// Messages of senders, also counters of `Logger::stats`.
+    queue: Arc<Queue>,
+    backlog: Cell<BacklogState>,
 }
 
 impl EnabledLogger {
-    pub fn new(settings: Settings, receiver: Receiver<Message>) -> Self {
+    pub(crate) fn new(settings: Settings, queue: Arc<Queue>) -> Self {
         Self {
-            buffer_size: settings.buffer_size().clone(),
             settings,
-            receiver,
+            queue,
+            backlog: Cell::default(),
         }
     }
 
     /// Synced runner.
-    pub fn run(&self) {
-        match self.settings.output() {
-            OutputChannel::File(file_settings) => self.write_to_file(file_settings),
-            OutputChannel::Console => self.write_to_console(),
-            OutputChannel::Auto(file_settings) => {
-                if cfg!(debug_assertions) {
-                    self.write_to_console()
-                } else {
-                    self.write_to_file(file_settings)
-                }
-            }
-        }
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn run(self) {
+        // Dropped last, so shutdown waits until sinks and settings of worker are dropped too.
+        let _stopped = Stopped(self.queue.clone());
+        let logger = self;
+        logger.work();
     }
 
-    fn write_to_console(&self) {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn work(&self) {
+        use std::sync::mpsc::RecvTimeoutError;
+
+        crate::rotation_logger::logger::queue::enter_worker();
+        let mut channels = self.channels();
+        let mut buffer = Buffer::new(&self.settings);
+        self.start(&mut channels, &mut buffer);
+
+        // Wake up often enough to serve the shortest flush interval and look at backlog.
+        let backlog = self
+            .settings
+            .backlog_warning()
+            .map(|warning| warning.interval());
+        let interval = channels
+            .iter()
+            .filter_map(|channel| channel.flush_interval)
+            .chain(backlog)
+            .min();
+
         loop {
-            match &self.receiver.recv() {
-                Ok(message) => println!("{}", self.settings.format_message(message)),
+            match self.queue.recv(interval) {
+                Ok(Item::Message(message)) => self.handle(message, &mut channels, &mut buffer),
+                Ok(Item::Control(Control::Stop)) => break,
+                Ok(Item::Control(Control::Wake)) => {}
+                Err(RecvTimeoutError::Timeout) => {
+                    self.warn_backlog(SystemTime::now(), &mut channels, &mut buffer);
+                    buffer.poll(&mut channels);
+                }
                 Err(err) => {
                     println!("Logger Channel closed. Error: {err}");
-                    return;
+                    break;
                 }
             }
+            if let Some(request) = self.queue.requests().pending() {
+                // Messages sent before request are in queue already, they go first.
+                let stopped = self.drain(&mut channels, &mut buffer);
+                let result = buffer.flush(&mut channels);
+                self.queue.requests().served(request, &result);
+                // Shutdown may be requested after the number was read.
+                if stopped {
+                    break;
+                }
+            }
+        }
+        // Records held in buffer are written all the same, requests taken so far served.
+        let request = self.queue.requests().pending();
+        self.drain(&mut channels, &mut buffer);
+        let result = buffer.flush(&mut channels);
+        if let Some(request) = request {
+            self.queue.requests().served(request, &result);
         }
     }
 
-    fn write_to_file(&self, settings: &FileSettings) {
-        println!("writing to file");
-        let mut buffer: Vec<String> = Vec::with_capacity(self.buffer_size);
-        let mut current_file_buffer: Option<BufWriter<File>> = None;
+    /// Handle every item waiting in queue, `true` when one of them stops worker.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn drain(&self, channels: &mut [Channel], buffer: &mut Buffer) -> bool {
+        let mut stopped = false;
+        while let Some(item) = self.queue.try_recv() {
+            match item {
+                Item::Message(message) => self.handle(message, channels, buffer),
+                Item::Control(Control::Stop) => stopped = true,
+                Item::Control(Control::Wake) => {}
+            }
+        }
+        stopped
+    }
 
-        loop {
-            match &self.receiver.recv() {
-                Ok(message) => {
-                    buffer.push(format!("{}", self.settings.format_message(message)));
+    fn handle(&self, message: Message, channels: &mut [Channel], buffer: &mut Buffer) {
+        let time = SystemTime::now();
+        self.warn_backlog(time, channels, buffer);
+        // Macros send without settings at hand, level windows are known only here.
+        if !self
+            .settings
+            .is_enabled_at(message.level(), message.modules(), time)
+        {
+            return;
+        }
+        #[cfg(feature = "regex")]
+        if !TextFilter::keeps(self.settings.text_filters(), message.text()) {
+            return;
+        }
+        self.write(message, time, channels, buffer);
+    }
 
-                    if self.buffer_size > buffer.len() {
-                        continue;
-                    }
+    /// Write backlog warning, if it is due, past levels and filters.
+    fn warn_backlog(&self, time: SystemTime, channels: &mut [Channel], buffer: &mut Buffer) {
+        let (Some(warning), Some(capacity)) = (
+            self.settings.backlog_warning(),
+            self.settings.backlog_capacity(),
+        ) else {
+            return;
+        };
+        let mut state = self.backlog.get();
+        let message = warning.check(&mut state, self.queue.stats().queue_len(), capacity, time);
+        self.backlog.set(state);
+        if let Some(message) = message {
+            self.write(message, time, channels, buffer);
+        }
+    }
 
-                    if self.check_path_or_create(settings).is_err() {
-                        println!("Logger cant access to log dir.");
-                        return;
-                    };
-
-                    if current_file_buffer.is_none() {
-                        match self.get_create_current_log_file(settings) {
-                            Ok(val) => {
-                                current_file_buffer = Some(BufWriter::new(val));
-                            }
-                            Err(_) => {
-                                println!("Logger cant access to log file.");
-                                return;
-                            }
-                        };
-                    };
-
-                    if let Some(file_buffer) = current_file_buffer.as_mut() {
-                        match file_buffer.write(format!("{}\n", buffer.join("\n")).as_bytes()) {
-                            Ok(_) => {}
-                            Err(err) => {
-                                println!("Logger error to write to file. Error: {err}");
-                                return;
-                            }
-                        };
-
-                        match file_buffer.flush() {
-                            Ok(_) => {}
-                            Err(err) => {
-                                println!("Logger error to write to file. Error: {err}");
-                                return;
-                            }
-                        }
-                        buffer.clear();
-
-                        let _ = file_buffer.get_ref().sync_all();
-
-                        let file_size = match file_buffer.get_ref().metadata() {
-                            Ok(val) => val.len() * 8,
-                            Err(_) => {
-                                println!("Logger cant access to log file.");
-                                return;
-                            }
-                        };
-
-                        if file_size >= settings.file_size() {
-                            current_file_buffer = None;
-
-                            let mut logs = self.get_log_files(settings);
-
-                            if logs.len() >= settings.capacity() {
-                                logs = match self.delete_oldest_file(logs) {
-                                    Ok(val) => val,
-                                    Err(_) => {
-                                        println!("Logger cant delete old logs.");
-                                        return;
-                                    }
-                                };
-                            }
-                            match self.reorder_filenames(settings, logs) {
-                                Ok(_) => {}
-                                Err(_) => {
-                                    println!("Logger cant rotate logs.");
-                                    return;
-                                }
-                            };
-                        }
+    /// Write banner, if any, past levels and filters.
+    fn start(&self, channels: &mut [Channel], buffer: &mut Buffer) {
+        // Records spilled or buried by previous runs go first.
+        if let Some((path, max_in_memory)) = self.settings.spill()
+            && let Some(main) = channels.first_mut()
+            && let Err(err) =
+                spill::replay_leftovers(path, max_in_memory, main.sink.as_mut(), &self.queue)
+        {
+            println!("Logger cant replay spilled records. Error: {err}");
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some((path, _)) = self.settings.spill() {
+            let handle = |message| self.handle(message, channels, buffer);
+            if let Err(err) = spill::replay_leftover_items(path, self.queue.counters(), handle) {
+                println!("Logger cant replay spilled messages. Error: {err}");
+            }
+        }
+        if let Some((path, _)) = self.settings.dead_letter()
+            && let Some(main) = channels.first_mut()
+            && let Err(err) = dead_letter::replay(path, main.sink.as_mut())
+        {
+            println!("Logger cant replay dead letters. Error: {err}");
+        }
+        if let Some(banner) = self.settings.banner() {
+            self.write(banner.message(), SystemTime::now(), channels, buffer);
+        }
+    }
+
+    fn write(
+        &self,
+        message: Message,
+        time: SystemTime,
+        channels: &mut [Channel],
+        buffer: &mut Buffer,
+    ) {
+        let record = match buffer.previous.as_mut() {
+            Some(previous) if !self.settings.has_custom_format() => {
+                let deltas = previous.deltas(message.modules(), time);
+                let line = self
+                    .settings
+                    .formatter()
+                    .format_with_deltas(&message, time, deltas, None);
+                Record::new(message, line, time).with_deltas(deltas)
+            }
+            _ => {
+                let line = self.settings.format_message_at_system_time(&message, time);
+                Record::new(message, line, time)
+            }
+        };
+        buffer.push(record);
+        buffer.poll(channels);
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn console(&self) -> ConsoleSink {
+        if self.settings.json_output() {
+            return ConsoleSink::new(
+                self.settings.formatter().clone(),
+                None,
+                self.settings.console_writer().cloned(),
+            )
+            .with_json(true);
+        }
+        // Lines of user format are printed as they are.
+        if self.settings.has_custom_format() {
+            return ConsoleSink::new(
+                self.settings.formatter().clone(),
+                None,
+                self.settings.console_writer().cloned(),
+            );
+        }
+        let console = ConsoleSink::new(
+            self.settings.formatter().clone(),
+            self.settings.console_theme(),
+            self.settings.console_writer().cloned(),
+        )
+        .with_auto_widths(self.settings.auto_widths());
+        #[cfg(feature = "formatter")]
+        let console = console.with_grid(self.settings.console_grid());
+        console
+    }
+
+    /// Sink of file output or route, JSON one with `Settings::with_json_output`.
+    #[cfg(all(feature = "file", not(target_arch = "wasm32")))]
+    fn file(&self, file_settings: &FileSettings) -> FileSink {
+        match self.settings.json_output() {
+            true => FileSink::json(file_settings.clone()),
+            false => FileSink::new(file_settings.clone()).with_format(self.settings.line_format()),
+        }
+    }
+
+    /// Policy of outputs and sinks without own one.
+    fn buffer_policy(&self) -> FlushPolicy {
+        let policy = FlushPolicy::new(self.settings.buffer_size());
+        match self.settings.flush_interval() {
+            Some(interval) => policy.with_flush_interval(interval),
+            None => policy,
+        }
+    }
+
+    /// Channels of main and tee outputs followed by routed files and additional sinks.
+    fn channels(&self) -> Vec<Channel> {
+        let mut channels = vec![];
+
+        for (output, policy) in self.settings.output_policies() {
+            let buffered = policy.cloned().unwrap_or_else(|| self.buffer_policy());
+            let console = policy.cloned().unwrap_or_else(FlushPolicy::unbuffered);
+            match output {
+                #[cfg(all(feature = "file", not(target_arch = "wasm32")))]
+                OutputChannel::File(file_settings) => {
+                    channels.push(Channel::new(self.file(file_settings), buffered.clone()))
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                OutputChannel::Console | OutputChannel::WebConsole => {
+                    channels.push(Channel::new(self.console(), console.clone()))
+                }
+                #[cfg(all(feature = "file", not(target_arch = "wasm32")))]
+                OutputChannel::Auto(file_settings) => {
+                    if cfg!(debug_assertions) {
+                        channels.push(Channel::new(self.console(), console.clone()))
+                    } else {
+                        channels.push(Channel::new(self.file(file_settings), buffered.clone()))
                     }
                 }
-                Err(err) => {
-                    println!("Logger Channel closed. Error: {err}");
-                    return;
+                #[cfg(all(feature = "file", feature = "formatter", not(target_arch = "wasm32")))]
+                OutputChannel::DevDuo(file_settings) => {
+                    channels.push(Channel::new(
+                        ConsoleSink::new(
+                            MessageFormatter::pretty(),
+                            self.settings.console_theme(),
+                            self.settings.console_writer().cloned(),
+                        )
+                        .with_own_format(),
+                        FlushPolicy::unbuffered(),
+                    ));
+                    channels.push(Channel::new(
+                        FileSink::json(file_settings.clone()),
+                        buffered.clone(),
+                    ));
+                }
+                // Browser has neither files nor stdout.
+                #[cfg(all(feature = "file", target_arch = "wasm32"))]
+                OutputChannel::File(_) | OutputChannel::Auto(_) => {
+                    channels.push(Channel::new(WebConsoleSink, console.clone()))
+                }
+                #[cfg(all(feature = "file", feature = "formatter", target_arch = "wasm32"))]
+                OutputChannel::DevDuo(_) => {
+                    channels.push(Channel::new(WebConsoleSink, console.clone()))
+                }
+                #[cfg(target_arch = "wasm32")]
+                OutputChannel::Console | OutputChannel::WebConsole => {
+                    channels.push(Channel::new(WebConsoleSink, console.clone()))
                 }
+                #[cfg(unix)]
+                OutputChannel::UnixSocket(path) => channels.push(Channel::new(
+                    UnixSocketSink::new(path.clone()),
+                    buffered.clone(),
+                )),
+                #[cfg(unix)]
+                OutputChannel::Fifo(path) => {
+                    channels.push(Channel::new(FifoSink::new(path.clone()), buffered.clone()))
+                }
+                #[cfg(feature = "shm")]
+                OutputChannel::SharedMemory(path) => {
+                    channels.push(Channel::new(ShmSink::new(path.clone()), buffered.clone()))
+                }
+                OutputChannel::Aggregator(address) => channels.push(Channel::new(
+                    AggregatorSink::new(address.clone()),
+                    buffered.clone(),
+                )),
+                OutputChannel::Custom(sink) => {
+                    channels.push(Channel::new(sink.clone(), buffered.clone()))
+                }
+            }
+        }
+
+        let buffered = self.buffer_policy();
+
+        // Main output gets only records of modules not routed elsewhere.
+        #[cfg(all(feature = "file", not(target_arch = "wasm32")))]
+        if !self.settings.routes().is_empty() {
+            let routed = self
+                .settings
+                .routes()
+                .iter()
+                .map(|(prefix, _)| prefix.clone());
+            let filter = ModuleFilter::Exclude(routed.collect());
+            for channel in channels.iter_mut() {
+                channel.filter = Some(filter.clone());
+            }
+        }
+        #[cfg(all(feature = "file", not(target_arch = "wasm32")))]
+        for (prefix, file_settings) in self.settings.routes() {
+            let mut channel = Channel::new(self.file(file_settings), buffered.clone());
+            channel.filter = Some(ModuleFilter::Prefix(prefix.clone()));
+            channels.push(channel);
+        }
+
+        for (sink, policy) in self.settings.sinks() {
+            let policy = policy.clone().unwrap_or_else(|| buffered.clone());
+            channels.push(Channel::new(sink.clone(), policy));
+        }
+
+        if let Some((path, max_in_memory)) = self.settings.spill() {
+            let run = spill::run_id();
+            for (index, channel) in channels.iter_mut().enumerate() {
+                let name = format!("{run}-{index}.spill");
+                channel.spill = Some(SpillQueue::new(
+                    path.join(name),
+                    max_in_memory,
+                    self.queue.clone(),
+                ));
+            }
+        } else if let Some((path, max_attempts)) = self.settings.dead_letter() {
+            for channel in channels.iter_mut() {
+                channel.dead_letter = Some(DeadLetter::new(
+                    path.clone(),
+                    max_attempts,
+                    self.queue.clone(),
+                ));
             }
         }
+        channels
     }
+}
 
-    fn check_path_or_create(&self, settings: &FileSettings) -> Result<(), ()> {
-        match fs::exists(settings.path()) {
-            Ok(is_exist) => {
-                if is_exist {
-                    return Ok(());
-                }
-                match fs::create_dir(settings.path()) {
-                    Ok(_) => return Ok(()),
-                    Err(_) => return Err(()),
-                }
+/// Closes queue once worker stops, also by panic, so senders blocked by full queue
+/// and later ones don't wait for it, and answers flush requests nobody serves anymore.
+#[cfg(not(target_arch = "wasm32"))]
+struct Stopped(Arc<Queue>);
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Drop for Stopped {
+    fn drop(&mut self) {
+        self.0.close();
+        self.0.requests().finish();
+    }
+}
+
+/// Sink with its own position in the shared records buffer.
+struct Channel {
+    sink: Box<dyn Sink>,
+    buffer_size: usize,
+    flush_interval: Option<Duration>,
+    /// Time of last write, only tracked with flush interval.
+    last_write: Option<Instant>,
+    /// Number of buffered records already written to sink.
+    flushed: usize,
+    /// Number of buffered records already checked by `filter`.
+    seen: usize,
+    /// Records accepted by `filter` and not written yet.
+    matched: usize,
+    /// Records sink failed to write, dropped when not set.
+    spill: Option<SpillQueue>,
+    /// Records sink failed to write, used without `spill`.
+    dead_letter: Option<DeadLetter>,
+    /// Records written to sink, all when not set.
+    #[cfg_attr(
+        not(all(feature = "file", not(target_arch = "wasm32"))),
+        allow(dead_code)
+    )]
+    filter: Option<ModuleFilter>,
+}
+
+impl Channel {
+    fn new(sink: impl Sink + 'static, policy: FlushPolicy) -> Self {
+        let flush_interval = policy.flush_interval();
+        Self {
+            sink: Box::new(sink),
+            buffer_size: policy.buffer_size(),
+            flush_interval,
+            last_write: flush_interval.map(|_| Instant::now()),
+            flushed: 0,
+            seen: 0,
+            matched: 0,
+            spill: None,
+            dead_letter: None,
+            filter: None,
+        }
+    }
+
+    fn accepts(&self, record: &Record) -> bool {
+        match &self.filter {
+            Some(filter) => filter.accepts(record),
+            None => true,
+        }
+    }
+
+    /// Write pending records when sink buffer is full or flush interval passed,
+    /// or at once with `force`. Error of sink is also printed, records are not written again.
+    fn poll(&mut self, records: &[Record], force: bool) -> io::Result<()> {
+        for record in &records[self.seen..] {
+            if self.accepts(record) {
+                self.matched += 1;
             }
-            Err(_) => match fs::create_dir(settings.path()) {
-                Ok(_) => return Ok(()),
-                Err(_) => return Err(()),
-            },
-        }
-    }
-
-    fn get_create_current_log_file(&self, settings: &FileSettings) -> Result<File, ()> {
-        let filename = format!("{}.{}", settings.filename(), settings.file_extension());
-        let mut filepath = settings.path().clone();
-        filepath.push(filename);
-
-        match OpenOptions::new().append(true).create(true).open(filepath) {
-            Ok(file) => Ok(file),
-            Err(_) => Err(()),
-        }
-    }
-
-    fn get_log_files(&self, settings: &FileSettings) -> Vec<DirEntry> {
-        match fs::read_dir(&settings.path()) {
-            Ok(dir_content) => {
-                let mut filtered_files: Vec<DirEntry> = dir_content
-                    .filter(|file| {
-                        if let Ok(file) = file {
-                            file.file_name()
-                                .to_string_lossy()
-                                .starts_with(settings.filename())
-                                && file
-                                    .file_name()
-                                    .to_string_lossy()
-                                    .contains(&format!(".{}", settings.file_extension()))
-                        } else {
-                            false
-                        }
-                    })
-                    .map(|el| el.unwrap())
+        }
+        self.seen = records.len();
+        let pending = self.matched;
+        if pending == 0 {
+            // Nothing of this sink among records.
+            self.flushed = records.len();
+            return Ok(());
+        }
+        let is_due = match (self.flush_interval, self.last_write) {
+            (Some(interval), Some(last_write)) => pending > 0 && last_write.elapsed() >= interval,
+            _ => false,
+        };
+        if pending < self.buffer_size && !is_due && !force {
+            return Ok(());
+        }
+        let filtered: Vec<Record>;
+        let batch = match &self.filter {
+            Some(_) => {
+                filtered = records[self.flushed..]
+                    .iter()
+                    .filter(|record| self.accepts(record))
+                    .cloned()
                     .collect();
-
-                filtered_files.sort_by(|a, b| {
-                    a.file_name()
-                        .to_str()
-                        .unwrap()
-                        .cmp(b.file_name().to_str().unwrap())
-                });
-                return filtered_files;
+                &filtered
             }
-            Err(_) => vec![],
+            None => &records[self.flushed..],
+        };
+        let result = match (self.spill.as_mut(), self.dead_letter.as_mut()) {
+            (Some(spill), _) => spill.write(self.sink.as_mut(), batch),
+            (None, Some(dead_letter)) => dead_letter.write(self.sink.as_mut(), batch),
+            (None, None) => self.sink.write_batch(batch),
+        };
+        if let Err(err) = &result {
+            println!("Logger error to write records. Error: {err}");
         }
+        self.flushed = records.len();
+        self.matched = 0;
+        if self.flush_interval.is_some() {
+            self.last_write = Some(Instant::now());
+        }
+        result
+    }
+
+    /// Start over, with records buffer cleared.
+    fn reset(&mut self) {
+        self.flushed = 0;
+        self.seen = 0;
+        self.matched = 0;
     }
+}
+
+/// Which records go to channel, by their modules.
+#[derive(Clone)]
+#[cfg_attr(
+    not(all(feature = "file", not(target_arch = "wasm32"))),
+    allow(dead_code)
+)]
+enum ModuleFilter {
+    /// Modules starting with prefix.
+    Prefix(Vec<String>),
+    /// Modules starting with none of prefixes.
+    Exclude(Vec<Vec<String>>),
+}
 
-    fn delete_oldest_file(&self, mut logs: Vec<DirEntry>) -> Result<Vec<DirEntry>, ()> {
-        match fs::remove_file(logs.last().unwrap().path()) {
-            Ok(_) => {
-                logs.remove(logs.len() - 1);
-                Ok(logs)
+impl ModuleFilter {
+    fn accepts(&self, record: &Record) -> bool {
+        let modules = record.message().modules();
+        match self {
+            ModuleFilter::Prefix(prefix) => modules.starts_with(prefix),
+            ModuleFilter::Exclude(prefixes) => {
+                !prefixes.iter().any(|prefix| modules.starts_with(prefix))
             }
-            Err(_) => Err(()),
         }
     }
+}
+
+/// Records shared by all channels, until each of them wrote them.
+struct Buffer {
+    records: Vec<Record>,
+    /// Size of formatted lines in `records`.
+    bytes: usize,
+    max_bytes: Option<usize>,
+    pool: Option<MessagePool>,
+    /// Times of previous records, when format has `delta` masks.
+    previous: Option<PreviousTimes>,
+}
 
-    fn reorder_filenames(&self, settings: &FileSettings, logs: Vec<DirEntry>) -> Result<(), ()> {
-        for i in (0..logs.len()).rev() {
-            let filename = logs[i].file_name();
-            let split_name: Vec<&str> = filename.to_str().unwrap().split(".").collect();
-            let log_number = split_name
-                .last()
-                .unwrap()
-                .replace(settings.file_extension(), "");
+impl Buffer {
+    fn new(settings: &Settings) -> Self {
+        Self {
+            records: Vec::with_capacity(settings.buffer_size()),
+            bytes: 0,
+            max_bytes: settings.max_buffer_bytes(),
+            pool: settings.message_pool().cloned(),
+            previous: settings
+                .formatter()
+                .uses_deltas()
+                .then(PreviousTimes::default),
+        }
+    }
 
-            let new_log_number = match log_number.parse::<u32>() {
-                Ok(val) => val + 1,
-                Err(_) => 0,
-            };
+    fn push(&mut self, record: Record) {
+        self.bytes += record.line().len();
+        self.records.push(record);
+    }
 
-            let new_filename = format!(
-                "{}.{}{new_log_number}",
-                split_name.first().unwrap(),
-                settings.file_extension()
-            );
+    /// Let every channel write what it needs, drop records written by all of them.
+    /// Every channel writes when buffer is over `max_bytes`.
+    fn poll(&mut self, channels: &mut [Channel]) {
+        let force = self
+            .max_bytes
+            .is_some_and(|max_bytes| self.bytes >= max_bytes);
+        let _ = self.write(channels, force);
+    }
 
-            match fs::rename(
-                format!("./{}", logs[i].path().to_str().unwrap()),
-                format!("./{}/{}", settings.path().to_string_lossy(), new_filename),
-            ) {
-                Ok(_) => continue,
-                Err(_) => return Err(()),
+    /// Write every record to every channel and flush their sinks,
+    /// first error of any of them is returned.
+    fn flush(&mut self, channels: &mut [Channel]) -> io::Result<()> {
+        let mut result = self.write(channels, true);
+        for channel in channels.iter_mut() {
+            if let Err(err) = channel.sink.flush() {
+                println!("Logger cant flush sink. Error: {err}");
+                result = result.and(Err(err));
             }
         }
+        result
+    }
 
-        Ok(())
+    fn write(&mut self, channels: &mut [Channel], force: bool) -> io::Result<()> {
+        let mut result = Ok(());
+        for channel in channels.iter_mut() {
+            result = result.and(channel.poll(&self.records, force));
+        }
+
+        if channels
+            .iter()
+            .all(|channel| channel.flushed == self.records.len())
+        {
+            match &self.pool {
+                Some(pool) => {
+                    for record in self.records.drain(..) {
+                        pool.recycle(record.into_message());
+                    }
+                }
+                None => self.records.clear(),
+            }
+            self.bytes = 0;
+            for channel in channels.iter_mut() {
+                channel.reset();
+            }
+        }
+        result
     }
 }
+
+/// Time of the last record of every modules and of any modules.
+#[derive(Default)]
+struct PreviousTimes {
+    modules: HashMap<Vec<String>, SystemTime>,
+    all: Option<SystemTime>,
+}
+
+impl PreviousTimes {
+    /// Time since previous records, remembering `time` as the last one.
+    fn deltas(&mut self, modules: &[String], time: SystemTime) -> Deltas {
+        let since = |previous: SystemTime| time.duration_since(previous).unwrap_or_default();
+        let module = match self.modules.get_mut(modules) {
+            Some(previous) => Some(since(std::mem::replace(previous, time))),
+            None => {
+                self.modules.insert(modules.to_vec(), time);
+                None
+            }
+        };
+        Deltas {
+            module,
+            all: self.all.replace(time).map(since),
+        }
+    }
+}
+
+/// Worker state kept on current thread, where worker thread can not be started.
+#[cfg(target_arch = "wasm32")]
+struct LocalWorker {
+    logger: EnabledLogger,
+    channels: Vec<Channel>,
+    buffer: Buffer,
+}
+
+#[cfg(target_arch = "wasm32")]
+thread_local! {
+    static LOCAL: std::cell::RefCell<Option<LocalWorker>> = const { std::cell::RefCell::new(None) };
+}
+
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn set_local(logger: EnabledLogger) {
+    let mut worker = LocalWorker {
+        channels: logger.channels(),
+        buffer: Buffer::new(&logger.settings),
+        logger,
+    };
+    worker
+        .logger
+        .start(&mut worker.channels, &mut worker.buffer);
+    LOCAL.with(|local| *local.borrow_mut() = Some(worker));
+}
+
+/// Process messages waiting in channel, then write and flush everything held.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn flush_local() -> io::Result<()> {
+    poll_local();
+    LOCAL.with(|local| match local.try_borrow_mut() {
+        Ok(mut local) => match local.as_mut() {
+            Some(worker) => worker.buffer.flush(&mut worker.channels),
+            None => Ok(()),
+        },
+        // Sink flushes by itself, outer call writes the rest.
+        Err(_) => Ok(()),
+    })
+}
+
+/// Write and flush everything held, then drop worker, see `Logger::shutdown`.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn stop_local() -> io::Result<()> {
+    let result = flush_local();
+    LOCAL.with(|local| {
+        if let Ok(mut local) = local.try_borrow_mut() {
+            local.take();
+        }
+    });
+    result
+}
+
+/// Process messages waiting in channel.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn poll_local() {
+    LOCAL.with(|local| {
+        // Already borrowed when sink logs by itself, message is handled by outer call.
+        if let Ok(mut local) = local.try_borrow_mut()
+            && let Some(worker) = local.as_mut()
+        {
+            while let Some(item) = worker.logger.queue.try_recv() {
+                match item {
+                    Item::Message(message) => {
+                        worker
+                            .logger
+                            .handle(message, &mut worker.channels, &mut worker.buffer)
+                    }
+                }
+            }
+        }
+    });
+}
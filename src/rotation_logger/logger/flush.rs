@@ -0,0 +1,402 @@
+//! # Flush of running worker requested by other threads.
+//!
+//! Worker owns buffers and sinks, so other threads only ask it to flush and wait for it.
+//! Requests of worker are numbered and kept in its queue, worker looks at the last number
+//! after every item of queue and answers requests up to it. Idle worker is woken up by
+//! `Control::Wake`. Shutdown is the last request, worker stops once it is served.
+//!
+use std::{
+    future::Future,
+    io,
+    pin::Pin,
+    sync::{Arc, Mutex, MutexGuard, PoisonError, atomic::AtomicBool},
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+    sync::{
+        Condvar,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Instant,
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::rotation_logger::logger::queue::Control;
+use crate::rotation_logger::logger::queue::{self, Queue};
+
+/// How long exit waits for worker to write what it holds.
+const EXIT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Set once exit hook is registered by `flush_on_exit`.
+static HOOKED: AtomicBool = AtomicBool::new(false);
+
+/// Called with result of writing and flushing records logged before request.
+#[cfg(not(target_arch = "wasm32"))]
+type Callback = Box<dyn FnOnce(&io::Result<()>) + Send>;
+
+/// Flush guard of `flush_on_exit`, flushes logger once more when dropped.
+pub struct FlushGuard {
+    _private: (),
+}
+
+impl Drop for FlushGuard {
+    fn drop(&mut self) {
+        let _ = request(EXIT_TIMEOUT);
+    }
+}
+
+/// Write and sync to disk everything running logger holds when process exits,
+/// also by `std::process::exit`, which skips destructors. Call after `Logger::run_async`,
+/// keep returned guard in `main` to flush on its return where exit hooks are not available.
+///
+/// Exit waits up to 5 seconds for logger, e.g. for network sinks.
+///
+/// # Example:
+///
+/// ```no_run
+/// # use rotation_logger::{Logger, Settings};
+/// Logger::new(Settings::default()).run_async();
+/// let _guard = rotation_logger::flush_on_exit();
+///
+/// std::process::exit(1);
+/// ```
+pub fn flush_on_exit() -> FlushGuard {
+    if !HOOKED.swap(true, std::sync::atomic::Ordering::AcqRel) {
+        register_exit_hook();
+    }
+    FlushGuard { _private: () }
+}
+
+/// Future of `Logger::flush`, ready when records logged before it are written and flushed.
+pub struct FlushFuture {
+    answer: Option<Arc<Answer>>,
+}
+
+impl FlushFuture {
+    /// Wait up to `timeout` for worker, error when it did not answer in time
+    /// or failed to write or flush.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn wait(self, timeout: Duration) -> io::Result<()> {
+        match self.answer {
+            Some(answer) => answer.wait(timeout),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Future for FlushFuture {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        match &self.answer {
+            Some(answer) => answer.poll(cx),
+            None => Poll::Ready(()),
+        }
+    }
+}
+
+/// Answer of worker to single request, for thread or task waiting for it.
+#[derive(Default)]
+struct Answer {
+    state: Mutex<AnswerState>,
+    #[cfg(not(target_arch = "wasm32"))]
+    answered: Condvar,
+}
+
+#[derive(Default)]
+struct AnswerState {
+    /// Set once worker answered.
+    result: Option<io::Result<()>>,
+    waker: Option<Waker>,
+}
+
+impl Answer {
+    fn state(&self) -> MutexGuard<'_, AnswerState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Callback of request, wakes up thread or task waiting for it.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn callback(self: &Arc<Self>) -> Callback {
+        let answer = self.clone();
+        Box::new(move |result| {
+            let waker = {
+                let mut state = answer.state();
+                state.result = Some(copy(result));
+                state.waker.take()
+            };
+            answer.answered.notify_all();
+            if let Some(waker) = waker {
+                waker.wake();
+            }
+        })
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn wait(&self, timeout: Duration) -> io::Result<()> {
+        let (mut state, _) = self
+            .answered
+            .wait_timeout_while(self.state(), timeout, |state| state.result.is_none())
+            .unwrap_or_else(PoisonError::into_inner);
+        state.result.take().unwrap_or_else(|| {
+            Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "logger did not answer in time",
+            ))
+        })
+    }
+
+    fn poll(&self, cx: &mut Context<'_>) -> Poll<()> {
+        let mut state = self.state();
+        if state.result.is_some() {
+            return Poll::Ready(());
+        }
+        state.waker = Some(cx.waker().clone());
+        Poll::Pending
+    }
+}
+
+/// Flush requests of one worker, kept in its queue.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Default)]
+pub(crate) struct Requests {
+    /// Number of the last request, looked at by worker after every message.
+    requested: AtomicU64,
+    /// Number of the last request served.
+    served: AtomicU64,
+    /// Callbacks waiting for request numbers, threads and tasks waiting for them included.
+    callbacks: Mutex<Vec<(u64, Callback)>>,
+    /// Set once worker stopped, later requests are answered at once.
+    finished: AtomicBool,
+    /// Shutdown waits here for worker to stop.
+    stopped: Condvar,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Requests {
+    /// Take next request number, `callback` is called once it is served. Number is taken
+    /// under lock of callbacks, so worker never serves it before callback is there.
+    /// Called at once when worker stopped already.
+    pub(crate) fn push(&self, callback: Callback) {
+        let mut callbacks = self
+            .callbacks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if self.finished.load(Ordering::Acquire) {
+            drop(callbacks);
+            callback(&Err(stopped()));
+            return;
+        }
+        let request = self.requested.fetch_add(1, Ordering::AcqRel) + 1;
+        callbacks.push((request, callback));
+    }
+
+    /// Number of the last request, when it is not served yet.
+    pub(crate) fn pending(&self) -> Option<u64> {
+        let requested = self.requested.load(Ordering::Acquire);
+        (requested > self.served.load(Ordering::Acquire)).then_some(requested)
+    }
+
+    /// Call callbacks of requests up to `request` with `result` of flush, on current thread.
+    pub(crate) fn served(&self, request: u64, result: &io::Result<()>) {
+        let ready: Vec<_> = {
+            let mut callbacks = self
+                .callbacks
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            self.served.fetch_max(request, Ordering::AcqRel);
+            // Tickets are pushed under lock in order they are taken, so callbacks are sorted.
+            let ready = callbacks.partition_point(|(ticket, _)| *ticket <= request);
+            callbacks.drain(..ready).collect()
+        };
+        for (_, callback) in ready {
+            callback(result);
+        }
+    }
+
+    /// Worker stopped, also by panic, nobody serves requests left anymore.
+    /// Their callbacks are called on current thread, so no one waits for them.
+    pub(crate) fn finish(&self) {
+        let left = {
+            let mut callbacks = self
+                .callbacks
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            self.finished.store(true, Ordering::Release);
+            std::mem::take(&mut *callbacks)
+        };
+        self.stopped.notify_all();
+        let result = Err(stopped());
+        for (_, callback) in left {
+            callback(&result);
+        }
+    }
+
+    /// Wait up to `timeout` until worker stopped, `false` when it did not in time.
+    fn wait_finished(&self, timeout: Duration) -> bool {
+        let callbacks = self
+            .callbacks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let finished = |_: &mut Vec<(u64, Callback)>| !self.finished.load(Ordering::Acquire);
+        let (_callbacks, result) = self
+            .stopped
+            .wait_timeout_while(callbacks, timeout, finished)
+            .unwrap_or_else(PoisonError::into_inner);
+        !result.timed_out()
+    }
+}
+
+/// Request flush of running worker and return future of it, see `Logger::flush`.
+pub(crate) fn flush() -> FlushFuture {
+    match queue::running() {
+        Some(queue) => request_on(&queue),
+        None => FlushFuture { answer: None },
+    }
+}
+
+/// Ask worker of `queue` to flush, future is ready at once when there is nothing to flush.
+pub(crate) fn request_on(queue: &Queue) -> FlushFuture {
+    #[cfg(target_arch = "wasm32")]
+    {
+        let _ = (
+            queue,
+            crate::rotation_logger::logger::enabled::flush_local(),
+        );
+        FlushFuture { answer: None }
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        // Nothing logged yet, worker is not started for flush.
+        if queue.lazy().is_pending() {
+            return FlushFuture { answer: None };
+        }
+        let answer = Arc::new(Answer::default());
+        queue.requests().push(answer.callback());
+        queue.control(Control::Wake);
+        FlushFuture {
+            answer: Some(answer),
+        }
+    }
+}
+
+/// Request flush and call `callback` when it is served, see `Logger::flush_with`.
+pub(crate) fn flush_with(callback: impl FnOnce() + Send + 'static) {
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(queue) = queue::running()
+        && !queue.lazy().is_pending()
+    {
+        queue.requests().push(Box::new(move |_| callback()));
+        queue.control(Control::Wake);
+        return;
+    }
+    #[cfg(target_arch = "wasm32")]
+    let _ = crate::rotation_logger::logger::enabled::flush_local();
+    callback();
+}
+
+/// Ask worker to flush and wait for it up to `timeout`, error when it did not answer in time
+/// or failed to write or flush. Nothing to wait for without running worker.
+pub(crate) fn request(timeout: Duration) -> io::Result<()> {
+    match queue::running() {
+        Some(queue) => request_on_queue(&queue, timeout),
+        None => Ok(()),
+    }
+}
+
+/// Same as `request` for worker of `queue`.
+pub(crate) fn request_on_queue(queue: &Queue, timeout: Duration) -> io::Result<()> {
+    #[cfg(target_arch = "wasm32")]
+    {
+        let _ = (queue, timeout);
+        crate::rotation_logger::logger::enabled::flush_local()
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    request_on(queue).wait(timeout)
+}
+
+/// Close queue of running worker, so later messages are dropped, and wait up to `timeout`
+/// until it writes and flushes messages sent before and stops, with its sinks dropped.
+/// Error when it did not in time or failed to write or flush.
+pub(crate) fn shutdown(timeout: Duration) -> io::Result<()> {
+    let Some(queue) = queue::take_running() else {
+        return Ok(());
+    };
+    #[cfg(target_arch = "wasm32")]
+    {
+        let _ = timeout;
+        queue.close();
+        crate::rotation_logger::logger::enabled::stop_local()
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        // Nothing was logged, worker is never started then.
+        if queue.lazy().cancel() {
+            queue.close();
+            return Ok(());
+        }
+        let deadline = Instant::now() + timeout;
+        let answer = Arc::new(Answer::default());
+        queue.requests().push(answer.callback());
+        let sent = queue.control(Control::Stop);
+        queue.close();
+        // Worker is gone already, when stop is not sent.
+        let result = match sent {
+            true => answer.wait(timeout),
+            false => Ok(()),
+        };
+        let left = deadline.saturating_duration_since(Instant::now());
+        match queue.requests().wait_finished(left) {
+            true => result,
+            false => Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "logger did not stop in time",
+            )),
+        }
+    }
+}
+
+/// Error of requests worker did not serve before it stopped.
+#[cfg(not(target_arch = "wasm32"))]
+fn stopped() -> io::Error {
+    io::Error::new(io::ErrorKind::BrokenPipe, "logger stopped")
+}
+
+/// Copy of `result` for single waiter, errors are not `Clone`.
+#[cfg(not(target_arch = "wasm32"))]
+fn copy(result: &io::Result<()>) -> io::Result<()> {
+    match result {
+        Ok(()) => Ok(()),
+        Err(err) => Err(io::Error::new(err.kind(), err.to_string())),
+    }
+}
+
+#[cfg(any(unix, windows))]
+extern "C" fn flush_at_exit() {
+    let _ = request(EXIT_TIMEOUT);
+}
+
+#[cfg(unix)]
+fn register_exit_hook() {
+    // Safety: handler is a plain function living for whole process.
+    unsafe {
+        libc::atexit(flush_at_exit);
+    }
+}
+
+#[cfg(windows)]
+fn register_exit_hook() {
+    unsafe extern "C" {
+        fn atexit(callback: extern "C" fn()) -> i32;
+    }
+    // Safety: handler is a plain function living for whole process.
+    unsafe {
+        atexit(flush_at_exit);
+    }
+}
+
+/// No exit hooks here, only `FlushGuard` flushes.
+#[cfg(not(any(unix, windows)))]
+fn register_exit_hook() {}
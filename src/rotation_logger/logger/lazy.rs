@@ -0,0 +1,58 @@
+//! # Worker started by first message.
+//!
+//! With `Settings::with_lazy_start` queue is the running one right away, while worker,
+//! with its thread and files, waits in queue until `Queue::send` takes first message,
+//! whether logged or received by aggregator. Closing queue drops worker not started yet.
+//!
+use std::sync::{
+    Mutex, PoisonError,
+    atomic::{AtomicBool, Ordering},
+};
+
+/// Start of worker kept until first message.
+#[derive(Default)]
+pub(crate) struct LazyStart {
+    /// Set while worker waits for first message, so senders look at `worker` only then.
+    pending: AtomicBool,
+    worker: Mutex<Option<Box<dyn FnOnce() + Send>>>,
+}
+
+impl LazyStart {
+    /// Keep `start` of worker until first message, replacing one not started yet.
+    pub(crate) fn defer(&self, start: impl FnOnce() + Send + 'static) {
+        *self.worker.lock().unwrap_or_else(PoisonError::into_inner) = Some(Box::new(start));
+        self.pending.store(true, Ordering::Release);
+    }
+
+    /// Worker is not started yet, nothing was logged.
+    pub(crate) fn is_pending(&self) -> bool {
+        self.pending.load(Ordering::Acquire)
+    }
+
+    /// Drop deferred worker, `true` when there was one.
+    pub(crate) fn cancel(&self) -> bool {
+        if !self.pending.swap(false, Ordering::AcqRel) {
+            return false;
+        }
+        self.worker
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        true
+    }
+
+    /// Start deferred worker, if any.
+    pub(crate) fn start(&self) {
+        if !self.pending.swap(false, Ordering::AcqRel) {
+            return;
+        }
+        let start = self
+            .worker
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        if let Some(start) = start {
+            start();
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use crate::rotation_logger::{
+    logger::{Level, Logger, Message, scope},
+    settings::module_path,
+};
+
+/// Logger of module in hierarchy, e.g. `app::db` is child of `app`.
+/// Writes through parent `Logger`, so output and formatter are shared,
+/// and adds own path to modules of every message.
+///
+/// Level is resolved like in log4j: level of the closest module set with
+/// `Settings::with_module_level`, otherwise `Settings::min_level`.
+/// Handle can narrow it further with `with_level`, which its children inherit.
+///
+/// # Example:
+///
+/// ```
+/// # use rotation_logger::{Level, Logger, Settings};
+/// let logger = Logger::new(Settings::default().with_module_level("app::db", Level::Warn));
+///
+/// let app = logger.get("app");
+/// let db = app.get("db");
+/// db.log("dropped"); // `Info` is below `Warn` of `app::db`
+/// app.log("started");
+/// ```
+#[derive(Clone)]
+pub struct ModuleLogger {
+    logger: Logger,
+    modules: Vec<String>,
+    /// Level set on this handle or its parent.
+    level: Option<Level>,
+}
+
+impl ModuleLogger {
+    pub(crate) fn new(logger: Logger, modules: Vec<String>) -> Self {
+        Self {
+            logger,
+            modules,
+            level: None,
+        }
+    }
+
+    /// Logger of child module, `name` is path relative to this one, e.g. `db::pool`.
+    pub fn get(&self, name: &str) -> ModuleLogger {
+        let mut modules = self.modules.clone();
+        modules.extend(module_path(name));
+        Self {
+            logger: self.logger.clone(),
+            modules,
+            level: self.level,
+        }
+    }
+
+    /// Logger of child with `modules` after modules of this one.
+    pub fn child<I>(&self, modules: I) -> ModuleLogger
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        let mut child = self.clone();
+        child.modules.extend(modules.into_iter().map(Into::into));
+        child
+    }
+
+    /// Least important level written by this logger and its children.
+    /// Can't be more verbose than settings allow for the module.
+    pub fn with_level(mut self, level: Level) -> Self {
+        self.level = Some(level);
+        self
+    }
+
+    pub fn modules(&self) -> &[String] {
+        &self.modules
+    }
+
+    /// Least important level written by this logger, `None` for disabled logger.
+    pub fn level(&self) -> Option<Level> {
+        match &self.logger {
+            Logger::Enabled(settings) => {
+                let level = settings.module_level(&self.modules);
+                Some(self.level.map_or(level, |own| own.min(level)))
+            }
+            Logger::Disabled => None,
+        }
+    }
+
+    pub fn log(&self, text: &str) {
+        self.log_with_level(Level::Info, text)
+    }
+
+    /// Would message of `level` be written, same as `Logger::is_enabled` for modules of this logger.
+    pub fn is_enabled(&self, level: Level) -> bool {
+        self.is_enabled_for(level, &self.modules)
+    }
+
+    fn is_enabled_for(&self, level: Level, modules: &[String]) -> bool {
+        self.level.is_none_or(|own| level <= own) && self.logger.is_enabled(level, modules)
+    }
+
+    pub fn log_with_level(&self, level: Level, text: &str) {
+        if self.is_enabled(level) {
+            let modules = scope::scoped_modules(&self.modules);
+            Logger::send(Message::new(&modules, text).with_level(level));
+        }
+    }
+
+    /// Log prepared message, its modules go after modules of this logger.
+    pub fn log_message(&self, message: Message) {
+        let message = message.with_module_prefix(&self.modules);
+        if self.is_enabled_for(message.level(), message.modules()) {
+            Logger::send(scope::scoped_message(message))
+        }
+    }
+}
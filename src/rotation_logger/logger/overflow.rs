@@ -0,0 +1,15 @@
+/// What happens to message logged while queue of worker is full,
+/// see `Settings::with_queue_capacity`.
+///
+/// Dropped messages are counted by `LoggerStats::dropped`. Messages worker sends to itself,
+/// e.g. when sink logs, and requests to flush or stop are never held back.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Logging thread waits until worker takes message from queue, or it stops.
+    #[default]
+    Block,
+    /// New message is dropped, queue keeps the older ones.
+    DropNewest,
+    /// The oldest message waiting in queue is dropped to make room for new one.
+    DropOldest,
+}
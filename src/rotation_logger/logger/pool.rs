@@ -0,0 +1,65 @@
+use std::sync::{Arc, Mutex};
+
+use crate::rotation_logger::logger::Message;
+
+/// Pool of `Message`s recycled by worker after they are written, so `Logger::log`
+/// reuses their allocations instead of allocating new ones on every call.
+/// Shared between `Logger` clones, attach with `Settings::with_message_pool`.
+///
+/// # Example:
+///
+/// ```
+/// # use rotation_logger::{MessagePool, Settings};
+/// let settings = Settings::default().with_message_pool(MessagePool::new(1024));
+/// ```
+#[derive(Debug, Clone)]
+pub struct MessagePool {
+    messages: Arc<Mutex<Vec<Message>>>,
+    capacity: usize,
+}
+
+impl MessagePool {
+    /// Pool keeping up to `capacity` messages, others are dropped.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            messages: Arc::new(Mutex::new(Vec::with_capacity(capacity))),
+            capacity,
+        }
+    }
+
+    /// Message with `modules` and `text`, recycled one if pool has any.
+    pub fn message(&self, modules: &[String], text: &str) -> Message {
+        let recycled = match self.messages.lock() {
+            Ok(mut messages) => messages.pop(),
+            Err(_) => None,
+        };
+        match recycled {
+            Some(mut message) => {
+                message.reset(modules, text);
+                message
+            }
+            None => Message::new(modules, text),
+        }
+    }
+
+    /// Return message to pool.
+    pub fn recycle(&self, message: Message) {
+        if let Ok(mut messages) = self.messages.lock()
+            && messages.len() < self.capacity
+        {
+            messages.push(message);
+        }
+    }
+
+    /// Number of messages ready for reuse.
+    pub fn len(&self) -> usize {
+        self.messages
+            .lock()
+            .map(|messages| messages.len())
+            .unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
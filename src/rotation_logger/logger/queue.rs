@@ -0,0 +1,343 @@
+//! # Queue between logging threads and worker.
+//!
+//! Every message goes through `Queue::send`, whether it is logged or received by aggregator,
+//! and every one is taken by worker through `Queue::recv`, so counters of `Logger::stats`
+//! are kept in one place, next to queue they describe. Commands of worker, e.g. flush,
+//! are items of their own sent by `Queue::control`, no message is ever taken for one.
+//!
+//! With `Settings::with_queue_capacity` queue is bounded, full queue blocks senders
+//! or drops messages, see `OverflowPolicy`. With `Settings::with_spill` items past
+//! `max_in_memory` waiting ones go to spill file and are read back as worker catches up.
+//!
+//! Queue of logger started by `Logger::run_async` is the running one, used by macros,
+//! which filter messages by its levels without settings at hand.
+//! Starting other logger closes previous queue, its worker stops once it took the rest,
+//! or is never started, see `Settings::with_lazy_start`.
+//!
+#[cfg(not(target_arch = "wasm32"))]
+use std::{cell::Cell, sync::mpsc::RecvTimeoutError, time::Duration};
+use std::{
+    collections::VecDeque,
+    path::PathBuf,
+    sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError, RwLock},
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::rotation_logger::logger::{flush::Requests, lazy::LazyStart, spill::ItemSpill};
+use crate::rotation_logger::{
+    logger::{LoggerStats, Message, OverflowPolicy, stats::QueueCounters},
+    settings::LevelFilter,
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+thread_local! {
+    /// Worker never waits for itself, e.g. when sink logs.
+    static ON_WORKER: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Mark current thread as worker.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn enter_worker() {
+    ON_WORKER.with(|on_worker| on_worker.set(true));
+}
+
+/// Queue of running logger, see `Logger::run_async`.
+static RUNNING: RwLock<Option<Arc<Queue>>> = RwLock::new(None);
+
+/// Queue of running logger, if any.
+pub(crate) fn running() -> Option<Arc<Queue>> {
+    RUNNING
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
+
+/// Stop using running queue, e.g. on shutdown, it is not closed here.
+pub(crate) fn take_running() -> Option<Arc<Queue>> {
+    RUNNING
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .take()
+}
+
+/// Make `queue` the running one, previous one is closed.
+pub(crate) fn set_running(queue: Arc<Queue>) {
+    let previous = RUNNING
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .replace(queue);
+    if let Some(previous) = previous {
+        previous.close();
+    }
+}
+
+/// Command of worker, sent by `Queue::control`.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Control {
+    /// Wake up idle worker, so it serves flush requests.
+    Wake,
+    /// Write everything and stop, see `Logger::shutdown`.
+    Stop,
+}
+
+/// Entry of queue taken by worker.
+#[derive(Debug)]
+pub(crate) enum Item {
+    Message(Message),
+    #[cfg(not(target_arch = "wasm32"))]
+    Control(Control),
+}
+
+/// Messages waiting for worker of single logger.
+#[derive(Default)]
+pub(crate) struct Queue {
+    state: Mutex<State>,
+    /// Worker waits here for messages.
+    filled: Condvar,
+    /// Senders blocked by full queue wait here for room.
+    emptied: Condvar,
+    /// Largest number of waiting messages, unbounded on `None`.
+    bound: Option<(usize, OverflowPolicy)>,
+    /// Levels of logger, see `Logger::is_running_enabled`.
+    levels: LevelFilter,
+    counters: QueueCounters,
+    #[cfg(not(target_arch = "wasm32"))]
+    lazy: LazyStart,
+    /// Flush requests worker answers, see `Logger::flush`.
+    #[cfg(not(target_arch = "wasm32"))]
+    requests: Requests,
+}
+
+#[derive(Default)]
+struct State {
+    items: VecDeque<Item>,
+    /// No more messages are taken, worker stops once it took the rest.
+    closed: bool,
+    /// Items sent after `items`, see `Queue::with_spill`.
+    #[cfg(not(target_arch = "wasm32"))]
+    spill: Option<ItemSpill>,
+}
+
+impl State {
+    /// Number of waiting items, spilled ones too.
+    fn len(&self) -> usize {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(spill) = &self.spill {
+            return self.items.len() + spill.len();
+        }
+        self.items.len()
+    }
+
+    /// Keep `item` after waiting ones, in spill file once it takes items.
+    /// When file fails, item waits in memory.
+    fn push_back(&mut self, item: Item) {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(spill) = self.spill.as_mut()
+            && spill.takes(self.items.len())
+        {
+            match spill.push(&item) {
+                Ok(()) => return,
+                Err(err) => println!("Logger cant spill messages. Error: {err}"),
+            }
+        }
+        self.items.push_back(item);
+    }
+
+    /// The next item, read back from spill file once memory is empty.
+    fn pop_front(&mut self, counters: &QueueCounters) -> Option<Item> {
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.items.is_empty()
+            && let Some(spill) = self.spill.as_mut()
+        {
+            self.items.extend(spill.load(counters));
+        }
+        #[cfg(target_arch = "wasm32")]
+        let _ = counters;
+        self.items.pop_front()
+    }
+}
+
+impl Queue {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep at most `capacity` messages, past it `policy` tells what happens to new ones.
+    /// Ignored on `wasm32`, where messages are written as they are logged.
+    pub(crate) fn with_bound(mut self, bound: Option<(usize, OverflowPolicy)>) -> Self {
+        if cfg!(not(target_arch = "wasm32")) {
+            self.bound = bound;
+        }
+        self
+    }
+
+    /// Past `max_in_memory` waiting items the next ones go to spill file in `dir`,
+    /// see `Settings::with_spill`. Ignored on `wasm32`, where nothing waits.
+    pub(crate) fn with_spill(mut self, spill: Option<(&PathBuf, usize)>) -> Self {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let state = self.state.get_mut().unwrap_or_else(PoisonError::into_inner);
+            state.spill = spill.map(|(dir, max_in_memory)| ItemSpill::new(dir, max_in_memory));
+        }
+        #[cfg(target_arch = "wasm32")]
+        let _ = spill;
+        self
+    }
+
+    pub(crate) fn with_levels(mut self, levels: LevelFilter) -> Self {
+        self.levels = levels;
+        self
+    }
+
+    pub(crate) fn levels(&self) -> &LevelFilter {
+        &self.levels
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Pass `message` to worker, `false` when it is dropped, by closed queue
+    /// or by full one with `OverflowPolicy::DropNewest`. Full queue may block instead.
+    /// First message starts deferred worker.
+    pub(crate) fn send(&self, message: Message) -> bool {
+        let state = self.state();
+        #[cfg(not(target_arch = "wasm32"))]
+        let Some(state) = self.make_room(state) else {
+            return false;
+        };
+        if !self.push(state, Item::Message(message)) {
+            return false;
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        self.lazy.start();
+        true
+    }
+
+    /// Room for one more message in full queue, `None` when the message is dropped.
+    /// Control items go past bound, only messages are dropped to make room.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn make_room<'a>(&'a self, mut state: MutexGuard<'a, State>) -> Option<MutexGuard<'a, State>> {
+        let Some((capacity, policy)) = self.bound else {
+            return Some(state);
+        };
+        match policy {
+            OverflowPolicy::Block if !ON_WORKER.with(Cell::get) => {
+                state = self
+                    .emptied
+                    .wait_while(state, |state| !state.closed && state.len() >= capacity)
+                    .unwrap_or_else(PoisonError::into_inner);
+            }
+            OverflowPolicy::Block => {}
+            OverflowPolicy::DropNewest => {
+                if state.len() >= capacity {
+                    self.counters.dropped(1);
+                    return None;
+                }
+            }
+            OverflowPolicy::DropOldest => {
+                if state.len() >= capacity
+                    && let Some(oldest) = state
+                        .items
+                        .iter()
+                        .position(|item| matches!(item, Item::Message(_)))
+                {
+                    state.items.remove(oldest);
+                    self.counters.dropped(1);
+                }
+            }
+        }
+        Some(state)
+    }
+
+    /// Pass `control` to worker, past bound of queue, `false` when queue is closed.
+    /// Deferred worker is not started by it.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn control(&self, control: Control) -> bool {
+        self.push(self.state(), Item::Control(control))
+    }
+
+    fn push(&self, mut state: MutexGuard<'_, State>, item: Item) -> bool {
+        if state.closed {
+            return false;
+        }
+        state.push_back(item);
+        self.counters.sent(state.len());
+        drop(state);
+        self.filled.notify_one();
+        true
+    }
+
+    /// Wait up to `timeout`, forever on `None`, for the next item.
+    /// Disconnected once queue is closed and empty.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn recv(&self, timeout: Option<Duration>) -> Result<Item, RecvTimeoutError> {
+        let waiting = |state: &mut State| state.len() == 0 && !state.closed;
+        let mut state = match timeout {
+            Some(timeout) => {
+                let (state, result) = self
+                    .filled
+                    .wait_timeout_while(self.state(), timeout, waiting)
+                    .unwrap_or_else(PoisonError::into_inner);
+                if result.timed_out() {
+                    return Err(RecvTimeoutError::Timeout);
+                }
+                state
+            }
+            None => self
+                .filled
+                .wait_while(self.state(), waiting)
+                .unwrap_or_else(PoisonError::into_inner),
+        };
+        self.take(&mut state).ok_or(RecvTimeoutError::Disconnected)
+    }
+
+    /// The next item, if any waits.
+    pub(crate) fn try_recv(&self) -> Option<Item> {
+        self.take(&mut self.state())
+    }
+
+    fn take(&self, state: &mut State) -> Option<Item> {
+        let item = state.pop_front(&self.counters)?;
+        if self.bound.is_some() {
+            self.emptied.notify_one();
+        }
+        Some(item)
+    }
+
+    /// No more messages are taken, e.g. logger was replaced or its worker stopped.
+    pub(crate) fn is_closed(&self) -> bool {
+        self.state().closed
+    }
+
+    /// Refuse new messages, worker stops once it took the rest.
+    /// Called by worker when it stops, so blocked senders don't wait for it anymore.
+    /// Deferred worker is dropped, it has nothing to write.
+    pub(crate) fn close(&self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        self.lazy.cancel();
+        self.state().closed = true;
+        self.filled.notify_all();
+        self.emptied.notify_all();
+    }
+
+    /// Worker started by first message, see `Settings::with_lazy_start`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn lazy(&self) -> &LazyStart {
+        &self.lazy
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn requests(&self) -> &Requests {
+        &self.requests
+    }
+
+    pub(crate) fn counters(&self) -> &QueueCounters {
+        &self.counters
+    }
+
+    pub(crate) fn stats(&self) -> LoggerStats {
+        self.counters.stats(self.state().len())
+    }
+}
@@ -0,0 +1,88 @@
+use std::{cell::RefCell, marker::PhantomData, ops::Deref};
+
+use smallvec::SmallVec;
+
+use crate::rotation_logger::logger::{Logger, Message};
+
+thread_local! {
+    /// Modules of scopes alive on current thread, the outermost first.
+    static SCOPE: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Guard of `Logger::scoped`, while it lives messages logged with `Logger` on this thread
+/// get its module in front of own ones. Scopes nest, inner module goes after outer,
+/// dropping scope closes scopes opened inside it too.
+///
+/// # Example:
+///
+/// ```
+/// # use rotation_logger::{Logger, Settings};
+/// let logger = Logger::new(Settings::default());
+///
+/// let http = logger.scoped("HTTP");
+/// let request = http.scoped("REQUEST");
+/// request.log_display(["PARSE"], &"done"); // modules: HTTP, REQUEST, PARSE
+/// ```
+pub struct ScopedLogger<'a> {
+    logger: &'a Logger,
+    /// Number of scopes open before this one, its module is at this index.
+    depth: usize,
+    /// Scope belongs to thread it was opened on.
+    _not_send: PhantomData<*const ()>,
+}
+
+impl<'a> ScopedLogger<'a> {
+    pub(crate) fn new(logger: &'a Logger, module: &str) -> Self {
+        let depth = SCOPE.with_borrow_mut(|scope| {
+            scope.push(module.into());
+            scope.len() - 1
+        });
+        Self {
+            logger,
+            depth,
+            _not_send: PhantomData,
+        }
+    }
+}
+
+impl Deref for ScopedLogger<'_> {
+    type Target = Logger;
+
+    fn deref(&self) -> &Logger {
+        self.logger
+    }
+}
+
+impl Drop for ScopedLogger<'_> {
+    fn drop(&mut self) {
+        SCOPE.with_borrow_mut(|scope| scope.truncate(self.depth));
+    }
+}
+
+/// `modules` after modules of open scopes.
+pub(crate) fn scoped_modules(modules: &[impl AsRef<str>]) -> Vec<String> {
+    SCOPE.with_borrow(|scope| {
+        let modules = modules.iter().map(|module| module.as_ref().to_string());
+        scope.iter().cloned().chain(modules).collect()
+    })
+}
+
+/// Call `check` with `modules` after modules of open scopes, without copying them.
+pub(crate) fn with_scoped_modules<R>(
+    modules: &[impl AsRef<str>],
+    check: impl FnOnce(&[&str]) -> R,
+) -> R {
+    SCOPE.with_borrow(|scope| {
+        let modules: SmallVec<[&str; 8]> = scope
+            .iter()
+            .map(String::as_str)
+            .chain(modules.iter().map(AsRef::as_ref))
+            .collect();
+        check(&modules)
+    })
+}
+
+/// Message with modules of open scopes in front.
+pub(crate) fn scoped_message(message: Message) -> Message {
+    SCOPE.with_borrow(|scope| message.with_module_prefix(scope))
+}
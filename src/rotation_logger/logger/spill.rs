@@ -0,0 +1,509 @@
+//! # Overflow of queue and of records sink failed to write.
+//!
+//! With `Settings::with_spill` items sent while `max_in_memory` of them wait for worker,
+//! e.g. for slow sink, are appended to spill file of queue instead of memory, and so is every
+//! item after them until worker reads them back, so worker takes items in order they were sent.
+//!
+//! Without spill records of failed batch are dropped. With it they are held in memory
+//! up to `max_in_memory`, the rest is appended to spill file of channel.
+//! On every next write held records go first, in order they were logged, then new ones.
+//! Lines of spill file which can't be read back are dropped and counted.
+//!
+//! Spill file is named by run and locked while it is used. When worker starts, files left
+//! by other runs, e.g. crashed ones, are written before any new record and removed, records
+//! to main output, messages through levels and filters. Files locked by running processes
+//! are left alone.
+//!
+//! Spill file of channel keeps one record per line: time, formatted line, level, message text,
+//! target, number of modules, modules and field pairs, separated by tabs. Field value starts
+//! with type tag of `FieldValue`, e.g. `1:200`. Spill file of queue keeps one item per line,
+//! message as `message` followed by the same parts from level on, control items by names.
+//!
+#[cfg(not(target_arch = "wasm32"))]
+use std::io::BufWriter;
+use std::{
+    collections::VecDeque,
+    fs::{self, File, OpenOptions, TryLockError},
+    io::{self, BufRead, BufReader, ErrorKind, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::rotation_logger::logger::{
+    queue::{Control, Item},
+    stats::QueueCounters,
+};
+use crate::rotation_logger::{
+    FieldValue,
+    logger::{Level, Message, queue::Queue},
+    sink::{Record, Sink},
+};
+
+/// Extension of spill files of channels, keeping records.
+const RECORDS_EXTENSION: &str = "spill";
+/// Extension of spill files of queue, keeping items.
+#[cfg(not(target_arch = "wasm32"))]
+const ITEMS_EXTENSION: &str = "queue";
+/// First part of line of queue item keeping message.
+#[cfg(not(target_arch = "wasm32"))]
+const MESSAGE_ITEM: &str = "message";
+#[cfg(not(target_arch = "wasm32"))]
+const WAKE_ITEM: &str = "wake";
+#[cfg(not(target_arch = "wasm32"))]
+const STOP_ITEM: &str = "stop";
+
+const LEVELS: [Level; 5] = [
+    Level::Error,
+    Level::Warn,
+    Level::Info,
+    Level::Debug,
+    Level::Trace,
+];
+
+/// Records held for single channel until its sink recovers.
+pub(crate) struct SpillQueue {
+    path: PathBuf,
+    max_in_memory: usize,
+    memory: VecDeque<Record>,
+    /// Spill file, open and locked while it holds records not read back yet.
+    file: Option<File>,
+    /// Number of records in spill file not read back yet.
+    spilled: usize,
+    /// Position of the first not read record in spill file.
+    offset: u64,
+    /// Queue of worker, counting records lost by spill file.
+    queue: Arc<Queue>,
+}
+
+impl SpillQueue {
+    pub(crate) fn new(path: PathBuf, max_in_memory: usize, queue: Arc<Queue>) -> Self {
+        Self {
+            path,
+            max_in_memory: max_in_memory.max(1),
+            memory: VecDeque::new(),
+            file: None,
+            spilled: 0,
+            offset: 0,
+            queue,
+        }
+    }
+
+    /// Write held records followed by `records`, hold whatever sink failed to write.
+    pub(crate) fn write(&mut self, sink: &mut dyn Sink, records: &[Record]) -> io::Result<()> {
+        let result = self.replay(sink).and_then(|_| sink.write_batch(records));
+        if result.is_err() {
+            self.hold(records)?;
+        }
+        result
+    }
+
+    fn replay(&mut self, sink: &mut dyn Sink) -> io::Result<()> {
+        while !self.memory.is_empty() {
+            sink.write_batch(self.memory.make_contiguous())?;
+            self.memory.clear();
+            self.load()?;
+        }
+        Ok(())
+    }
+
+    fn hold(&mut self, records: &[Record]) -> io::Result<()> {
+        for record in records {
+            // Memory keeps the oldest records, so it is filled only while file is empty.
+            if self.spilled == 0 && self.memory.len() < self.max_in_memory {
+                self.memory.push_back(record.clone());
+                continue;
+            }
+            if self.file.is_none() {
+                self.file = Some(open_locked(&self.path)?);
+            }
+            if let Some(file) = self.file.as_mut() {
+                writeln!(file, "{}", encode(record))?;
+                self.spilled += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Move next records of spill file into memory.
+    fn load(&mut self) -> io::Result<()> {
+        let Some(file) = self.file.as_mut() else {
+            return Ok(());
+        };
+        file.seek(SeekFrom::Start(self.offset))?;
+        let mut reader = BufReader::new(&*file);
+        let mut line = String::new();
+        while self.spilled > 0 && self.memory.len() < self.max_in_memory {
+            line.clear();
+            let read = reader.read_line(&mut line)?;
+            if read == 0 {
+                // File lost records, nothing more to read.
+                self.queue.counters().dropped(self.spilled);
+                self.spilled = 0;
+                break;
+            }
+            self.offset += read as u64;
+            self.spilled -= 1;
+            match decode(line.trim_end_matches('\n')) {
+                Some(record) => self.memory.push_back(record),
+                None => self.queue.counters().dropped(1),
+            }
+        }
+        if self.spilled == 0 {
+            // Removed while it is locked, so no other process replays records read back.
+            let removed = fs::remove_file(&self.path);
+            self.file = None;
+            self.offset = 0;
+            removed?;
+        }
+        Ok(())
+    }
+}
+
+/// Items of queue sent past `max_in_memory` waiting ones, kept in file until worker
+/// catches up, in order they were sent.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) struct ItemSpill {
+    path: PathBuf,
+    max_in_memory: usize,
+    /// Spill file, open and locked while it holds items not read back yet.
+    file: Option<BufWriter<File>>,
+    /// Number of items in spill file not read back yet.
+    len: usize,
+    /// Position of the first not read item in spill file.
+    offset: u64,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl ItemSpill {
+    /// Spill of queue into file in `dir`, named by run like spill files of channels.
+    pub(crate) fn new(dir: &Path, max_in_memory: usize) -> Self {
+        Self {
+            path: dir.join(format!("{}.{ITEMS_EXTENSION}", run_id())),
+            max_in_memory: max_in_memory.max(1),
+            file: None,
+            len: 0,
+            offset: 0,
+        }
+    }
+
+    /// Number of items in file.
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Item sent while `in_memory` items wait goes to file, also every one after it,
+    /// until file is read back.
+    pub(crate) fn takes(&self, in_memory: usize) -> bool {
+        self.len > 0 || in_memory >= self.max_in_memory
+    }
+
+    pub(crate) fn push(&mut self, item: &Item) -> io::Result<()> {
+        if self.file.is_none() {
+            self.file = Some(BufWriter::new(open_locked(&self.path)?));
+        }
+        if let Some(file) = self.file.as_mut() {
+            writeln!(file, "{}", encode_item(item))?;
+            self.len += 1;
+        }
+        Ok(())
+    }
+
+    /// Read back next items, up to `max_in_memory`. Items which can't be read back
+    /// are dropped and counted, all the rest when file fails.
+    pub(crate) fn load(&mut self, counters: &QueueCounters) -> Vec<Item> {
+        let mut items = vec![];
+        if let Err(err) = self.read(&mut items, counters) {
+            println!("Logger cant read spilled messages. Error: {err}");
+            counters.dropped(self.len);
+            self.len = 0;
+        }
+        if self.len == 0 && self.file.is_some() {
+            // Removed while it is locked, so no other process replays items read back.
+            if let Err(err) = fs::remove_file(&self.path) {
+                println!("Logger cant remove spill file. Error: {err}");
+            }
+            self.file = None;
+            self.offset = 0;
+        }
+        items
+    }
+
+    fn read(&mut self, items: &mut Vec<Item>, counters: &QueueCounters) -> io::Result<()> {
+        let Some(file) = self.file.as_mut() else {
+            return Ok(());
+        };
+        file.flush()?;
+        let mut file = file.get_ref();
+        file.seek(SeekFrom::Start(self.offset))?;
+        let mut reader = BufReader::new(file);
+        let mut line = String::new();
+        while self.len > 0 && items.len() < self.max_in_memory {
+            line.clear();
+            let read = reader.read_line(&mut line)?;
+            if read == 0 {
+                // File lost items, nothing more to read.
+                counters.dropped(self.len);
+                self.len = 0;
+                break;
+            }
+            self.offset += read as u64;
+            self.len -= 1;
+            match decode_item(line.trim_end_matches('\n')) {
+                Some(item) => items.push(item),
+                None => counters.dropped(1),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Id of worker run in names of its spill files, unique for every run,
+/// so file left by other process, e.g. with the same PID, is never appended to.
+pub(crate) fn run_id() -> String {
+    let started = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    format!("{}-{}", std::process::id(), started.as_nanos())
+}
+
+/// Open spill file for appending and reading back, locked while it is used,
+/// so processes sharing spill directory leave it alone.
+fn open_locked(path: &Path) -> io::Result<File> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let file = OpenOptions::new()
+        .read(true)
+        .append(true)
+        .create(true)
+        .open(path)?;
+    file.try_lock()?;
+    Ok(file)
+}
+
+/// Write records of spill files left in `dir` by runs which did not write them,
+/// e.g. crashed ones, to `sink` in batches of `max_in_memory` and remove the files.
+/// Files locked by running processes are left alone. Number of records written.
+pub(crate) fn replay_leftovers(
+    dir: &Path,
+    max_in_memory: usize,
+    sink: &mut dyn Sink,
+    queue: &Queue,
+) -> io::Result<usize> {
+    let mut written = 0;
+    for (path, file) in leftovers(dir, RECORDS_EXTENSION)? {
+        written += replay_file(&path, &file, max_in_memory.max(1), sink, queue)?;
+    }
+    Ok(written)
+}
+
+/// Pass messages of queue spill files left in `dir` by runs which did not write them,
+/// e.g. crashed ones, to `handle` and remove the files. Control items are not served anymore,
+/// they are skipped. Files locked by running processes, this one too, are left alone.
+/// Number of messages passed.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn replay_leftover_items(
+    dir: &Path,
+    counters: &QueueCounters,
+    mut handle: impl FnMut(Message),
+) -> io::Result<usize> {
+    let mut passed = 0;
+    for (path, file) in leftovers(dir, ITEMS_EXTENSION)? {
+        for line in BufReader::new(&file).lines() {
+            match decode_item(&line?) {
+                Some(Item::Message(message)) => {
+                    handle(message);
+                    passed += 1;
+                }
+                Some(Item::Control(_)) => {}
+                None => counters.dropped(1),
+            }
+        }
+        fs::remove_file(path)?;
+    }
+    Ok(passed)
+}
+
+/// Files with `extension` in `dir` no process holds lock of, locked now.
+fn leftovers(dir: &Path, extension: &str) -> io::Result<Vec<(PathBuf, File)>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(vec![]),
+        Err(err) => return Err(err),
+    };
+    let mut leftovers = vec![];
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().is_none_or(|found| found != extension) {
+            continue;
+        }
+        let file = OpenOptions::new().read(true).append(true).open(&path)?;
+        match file.try_lock() {
+            Ok(()) => leftovers.push((path, file)),
+            Err(TryLockError::WouldBlock) => {}
+            Err(TryLockError::Error(err)) => return Err(err),
+        }
+    }
+    Ok(leftovers)
+}
+
+/// Write records of locked spill `file`, remove it once all are written.
+/// When sink fails, file keeps only records not written yet.
+fn replay_file(
+    path: &Path,
+    file: &File,
+    max_in_memory: usize,
+    sink: &mut dyn Sink,
+    queue: &Queue,
+) -> io::Result<usize> {
+    let mut reader = BufReader::new(file);
+    let mut line = String::new();
+    let mut batch = Vec::with_capacity(max_in_memory);
+    let mut written = 0;
+    // Position of line read last and of the first record not written yet.
+    let (mut position, mut pending) = (0, 0);
+    loop {
+        line.clear();
+        let len = reader.read_line(&mut line)?;
+        position += len as u64;
+        if len > 0 {
+            match decode(line.trim_end_matches('\n')) {
+                Some(record) => batch.push(record),
+                None => queue.counters().dropped(1),
+            }
+        }
+        if batch.len() >= max_in_memory || (len == 0 && !batch.is_empty()) {
+            if let Err(err) = sink.write_batch(&batch) {
+                keep_from(path, file, pending)?;
+                return Err(err);
+            }
+            written += batch.len();
+            pending = position;
+            batch.clear();
+        }
+        if len == 0 {
+            break;
+        }
+    }
+    fs::remove_file(path)?;
+    Ok(written)
+}
+
+/// Cut records before `offset` from locked spill `file`, through copy of the rest
+/// renamed over it.
+fn keep_from(path: &Path, mut file: &File, offset: u64) -> io::Result<()> {
+    let rest = path.with_extension(format!("{RECORDS_EXTENSION}.rest"));
+    file.seek(SeekFrom::Start(offset))?;
+    io::copy(&mut file, &mut File::create(&rest)?)?;
+    fs::rename(rest, path)
+}
+
+fn encode(record: &Record) -> String {
+    let since_epoch = record.time().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let mut parts = vec![
+        format!("{}.{}", since_epoch.as_secs(), since_epoch.subsec_nanos()),
+        escape(record.line()),
+    ];
+    encode_message(&mut parts, record.message());
+    parts.join("\t")
+}
+
+fn decode(line: &str) -> Option<Record> {
+    let mut parts = line.split('\t').map(unescape);
+    let (secs, nanos) = parts
+        .next()?
+        .split_once('.')
+        .map(|(secs, nanos)| (secs.parse::<u64>(), nanos.parse::<u32>()))?;
+    let time = UNIX_EPOCH + Duration::new(secs.ok()?, nanos.ok()?);
+    let formatted = parts.next()?;
+    let message = decode_message(parts)?;
+    Some(Record::new(message, formatted, time))
+}
+
+/// Queue item as line of spill file, control ones by their names.
+#[cfg(not(target_arch = "wasm32"))]
+fn encode_item(item: &Item) -> String {
+    match item {
+        Item::Message(message) => {
+            let mut parts = vec![MESSAGE_ITEM.to_string()];
+            encode_message(&mut parts, message);
+            parts.join("\t")
+        }
+        Item::Control(Control::Wake) => WAKE_ITEM.into(),
+        Item::Control(Control::Stop) => STOP_ITEM.into(),
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn decode_item(line: &str) -> Option<Item> {
+    let mut parts = line.split('\t').map(unescape);
+    match parts.next()?.as_str() {
+        MESSAGE_ITEM => decode_message(parts).map(Item::Message),
+        WAKE_ITEM => Some(Item::Control(Control::Wake)),
+        STOP_ITEM => Some(Item::Control(Control::Stop)),
+        _ => None,
+    }
+}
+
+/// Append level, text, target, number of modules, modules and field pairs of `message`.
+fn encode_message(parts: &mut Vec<String>, message: &Message) {
+    parts.push((message.level() as u8).to_string());
+    parts.push(escape(message.text()));
+    parts.push(escape(message.target().unwrap_or_default()));
+    parts.push(message.modules().len().to_string());
+    parts.extend(message.modules().iter().map(|module| escape(module)));
+    for (key, value) in message.fields() {
+        parts.push(escape(key));
+        parts.push(format!("{}:{}", value.tag(), escape(&value.to_string())));
+    }
+}
+
+fn decode_message(mut parts: impl Iterator<Item = String>) -> Option<Message> {
+    let level = *LEVELS.get(parts.next()?.parse::<usize>().ok()?)?;
+    let text = parts.next()?;
+    let target = parts.next()?;
+    let modules_count = parts.next()?.parse::<usize>().ok()?;
+    let modules = parts.by_ref().take(modules_count).collect::<Vec<String>>();
+
+    let mut message = Message::new(&modules, &text).with_level(level);
+    if !target.is_empty() {
+        message = message.with_target(&target);
+    }
+    while let (Some(key), Some(value)) = (parts.next(), parts.next()) {
+        let (tag, value) = value.split_once(':')?;
+        message = message.with_field(
+            &key,
+            FieldValue::from_tagged(tag.parse().ok()?, value.into()),
+        );
+    }
+    Some(message)
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+fn unescape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(char) = chars.next() {
+        if char != '\\' {
+            result.push(char);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => result.push('\t'),
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some(other) => result.push(other),
+            None => {}
+        }
+    }
+    result
+}
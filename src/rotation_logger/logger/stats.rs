@@ -0,0 +1,87 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Snapshot of logger queue, see `Logger::stats`.
+/// Queue length growing over time means worker falls behind, e.g. slow sink.
+///
+/// # Example:
+///
+/// ```
+/// # use rotation_logger::Logger;
+/// let stats = Logger::stats();
+/// if stats.queue_len() > 10_000 {
+///     eprintln!("logger falls behind, {} messages waiting", stats.queue_len());
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LoggerStats {
+    sent: u64,
+    queue_len: u64,
+    high_water: u64,
+    dead_letters: u64,
+    dropped: u64,
+}
+
+impl LoggerStats {
+    /// Messages sent to worker since start.
+    pub fn sent(&self) -> u64 {
+        self.sent
+    }
+
+    /// Messages sent, but not taken by worker yet.
+    pub fn queue_len(&self) -> u64 {
+        self.queue_len
+    }
+
+    /// Longest queue seen since start.
+    pub fn high_water(&self) -> u64 {
+        self.high_water
+    }
+
+    /// Records written to dead-letter file, see `Settings::with_dead_letter`.
+    pub fn dead_letters(&self) -> u64 {
+        self.dead_letters
+    }
+
+    /// Messages dropped because queue was full, see `Settings::with_queue_capacity`,
+    /// failed records dead-letter file could not take either and records spill file lost,
+    /// see `Settings::with_spill`.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+}
+
+/// Counters of single worker queue, queue length is known by queue itself.
+#[derive(Default)]
+pub(crate) struct QueueCounters {
+    sent: AtomicU64,
+    high_water: AtomicU64,
+    dead_letters: AtomicU64,
+    dropped: AtomicU64,
+}
+
+impl QueueCounters {
+    /// Message sent, `len` messages wait with it.
+    pub(crate) fn sent(&self, len: usize) {
+        self.sent.fetch_add(1, Ordering::Relaxed);
+        self.high_water.fetch_max(len as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn dropped(&self, messages: usize) {
+        self.dropped.fetch_add(messages as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn dead_lettered(&self, records: usize) {
+        self.dead_letters
+            .fetch_add(records as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn stats(&self, queue_len: usize) -> LoggerStats {
+        LoggerStats {
+            sent: self.sent.load(Ordering::Relaxed),
+            queue_len: queue_len as u64,
+            high_water: self.high_water.load(Ordering::Relaxed),
+            dead_letters: self.dead_letters.load(Ordering::Relaxed),
+            dropped: self.dropped.load(Ordering::Relaxed),
+        }
+    }
+}
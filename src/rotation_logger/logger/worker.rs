@@ -0,0 +1,127 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+    io,
+    thread::{Builder, JoinHandle},
+};
+
+/// Thread of logger worker, shown by name in debuggers and profilers.
+/// Attach with `Settings::with_worker_thread`, there is no thread on `wasm32`.
+///
+/// Lower priority keeps worker out of the way of latency-sensitive threads:
+/// nice 10 on Linux and Android, utility QoS on Apple, below normal on Windows, ignored elsewhere.
+///
+/// # Example:
+///
+/// ```
+/// # use rotation_logger::{Settings, WorkerThread};
+/// let worker = WorkerThread::default()
+///     .with_name("app-logger")
+///     .with_stack_size(256 * 1024)
+///     .with_low_priority(true);
+/// let settings = Settings::default().with_worker_thread(worker);
+/// ```
+#[derive(Debug, Clone)]
+pub struct WorkerThread {
+    name: String,
+    /// Default of `std::thread` when not set.
+    stack_size: Option<usize>,
+    low_priority: bool,
+}
+
+impl Default for WorkerThread {
+    fn default() -> Self {
+        Self {
+            name: "rotation-logger".into(),
+            stack_size: None,
+            low_priority: false,
+        }
+    }
+}
+
+impl WorkerThread {
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Stack size in bytes.
+    pub fn with_stack_size(mut self, stack_size: usize) -> Self {
+        self.stack_size = Some(stack_size);
+        self
+    }
+
+    pub fn with_low_priority(mut self, low_priority: bool) -> Self {
+        self.low_priority = low_priority;
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn stack_size(&self) -> Option<usize> {
+        self.stack_size
+    }
+    pub fn low_priority(&self) -> bool {
+        self.low_priority
+    }
+
+    /// Start thread running `run`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn spawn(&self, run: impl FnOnce() + Send + 'static) -> io::Result<JoinHandle<()>> {
+        let mut builder = Builder::new().name(self.name.clone());
+        if let Some(stack_size) = self.stack_size {
+            builder = builder.stack_size(stack_size);
+        }
+        let low_priority = self.low_priority;
+        builder.spawn(move || {
+            if low_priority && let Err(err) = lower_priority() {
+                println!("Logger cant lower worker priority. Error: {err}");
+            }
+            run()
+        })
+    }
+}
+
+/// Linux threads have own nice values, `0` is calling thread.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn lower_priority() -> io::Result<()> {
+    match unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, 10) } {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+#[cfg(target_vendor = "apple")]
+fn lower_priority() -> io::Result<()> {
+    match unsafe { libc::pthread_set_qos_class_self_np(libc::qos_class_t::QOS_CLASS_UTILITY, 0) } {
+        0 => Ok(()),
+        code => Err(io::Error::from_raw_os_error(code)),
+    }
+}
+
+#[cfg(windows)]
+fn lower_priority() -> io::Result<()> {
+    use std::ffi::c_void;
+
+    const THREAD_PRIORITY_BELOW_NORMAL: i32 = -1;
+    unsafe extern "system" {
+        fn GetCurrentThread() -> *mut c_void;
+        fn SetThreadPriority(thread: *mut c_void, priority: i32) -> i32;
+    }
+    // Safety: pseudo handle of current thread needs no closing.
+    match unsafe { SetThreadPriority(GetCurrentThread(), THREAD_PRIORITY_BELOW_NORMAL) } {
+        0 => Err(io::Error::last_os_error()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_vendor = "apple",
+    windows,
+    target_arch = "wasm32"
+)))]
+fn lower_priority() -> io::Result<()> {
+    Ok(())
+}
@@ -5,12 +5,14 @@
 //! Example as simple log:
 //!
 //! ```
+//! # use rotation_logger::log;
 //! log!("Some important data.");
 //! ```
 //!
 //! Example as logger with modules text:
 //! Text modules must be surrounded by `[..]` brackets.
 //! ```
+//! # use rotation_logger::log;
 //! log!(
 //!     ["MODULE_01", "MODULE_02"],
 //!     "Some important data."
@@ -20,43 +22,179 @@
 //! Example as logger with modules ident:
 //! Ident modules must be surrounded by `(..)` brackets.
 //! ```
+//! # use rotation_logger::log;
 //! log!((RAW_MODULE, RAW_MODULE2, RAW_MODULE3), "some");
 //! ```
 //!
+//! Example with lazy message, closure is called only if message is written:
+//! ```
+//! # use rotation_logger::log;
+//! # let expensive_summary = || String::from("summary");
+//! log!(["MODULE_01"], || expensive_summary());
+//! ```
+//!
+//! Example with target, kept apart from modules as `target` of `log` crate,
+//! text is built with `format!` arguments:
+//! ```
+//! # use rotation_logger::log;
+//! # let n = 42;
+//! log!(target: "wire", "sent {} bytes", n);
+//! log!(target: "wire", ["HTTP"], "sent {n} bytes");
+//! ```
+//!
+//! Same arguments with level of message, `log!` is `info!`:
+//! ```
+//! # use rotation_logger::{debug, error, trace, warn};
+//! error!(["DB"], "connection lost");
+//! warn!(target: "wire", "retry {} of {}", 1, 3);
+//! debug!((CACHE), "miss");
+//! trace!(|| String::from("expensive"));
+//! ```
+//!
+//! Example of skipping expensive message, modules are optional:
+//! ```
+//! # use rotation_logger::{Level, log, log_enabled};
+//! if log_enabled!(Level::Debug, ["DB"]) {
+//!     log!(["DB"], "Some expensive data.");
+//! }
+//! ```
+//!
 
 /// Thread safe macros to log messages.
+/// Messages are logged with `Level::Info`, calls are compiled out when it is above `STATIC_MAX_LEVEL`.
+/// Message can be closure, it is called only when running logger writes the message.
 #[macro_export]
 macro_rules! log {
-    ([$($modules:expr),*], $message:expr) => {
-        let prt = rotation_logger::LOG_SENDER.load(std::sync::atomic::Ordering::Acquire);
-        let modules = vec![$($modules.to_string()),+];
-        if !prt.is_null() {
-            unsafe {
-                let sender = &*prt;
-                let message = rotation_logger::Message::new(&modules, $message);
-                let _ = sender.send(message);
+    ($($arg:tt)+) => {
+        $crate::__log_at!($crate::Level::Info, $($arg)+)
+    };
+}
+
+/// Same as `log!` with `Level::Error`.
+#[macro_export]
+macro_rules! error {
+    ($($arg:tt)+) => {
+        $crate::__log_at!($crate::Level::Error, $($arg)+)
+    };
+}
+
+/// Same as `log!` with `Level::Warn`.
+#[macro_export]
+macro_rules! warn {
+    ($($arg:tt)+) => {
+        $crate::__log_at!($crate::Level::Warn, $($arg)+)
+    };
+}
+
+/// Same as `log!`, which logs with `Level::Info` too.
+#[macro_export]
+macro_rules! info {
+    ($($arg:tt)+) => {
+        $crate::__log_at!($crate::Level::Info, $($arg)+)
+    };
+}
+
+/// Same as `log!` with `Level::Debug`.
+#[macro_export]
+macro_rules! debug {
+    ($($arg:tt)+) => {
+        $crate::__log_at!($crate::Level::Debug, $($arg)+)
+    };
+}
+
+/// Same as `log!` with `Level::Trace`.
+#[macro_export]
+macro_rules! trace {
+    ($($arg:tt)+) => {
+        $crate::__log_at!($crate::Level::Trace, $($arg)+)
+    };
+}
+
+/// Body of `log!` and level macros.
+/// Levels are checked with borrowed modules, nothing is allocated for dropped messages.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __log_at {
+    ($level:expr, target: $target:expr, [$($modules:expr),*], $($arg:tt)+) => {
+        if $level.is_statically_enabled() {
+            // Temporary modules, e.g. `format!` ones, live until the end of `match`.
+            match [$(::core::convert::AsRef::<str>::as_ref(&$modules)),*] {
+                modules => {
+                    if $crate::Logger::is_running_enabled($level, &modules) {
+                        $crate::Logger::send(
+                            $crate::Logger::scoped_message(
+                                &modules,
+                                &::std::format!($($arg)+),
+                            )
+                            .with_target($target)
+                            .with_level($level),
+                        );
+                    }
+                }
             }
         }
     };
-    (($($modules:ident),*), $message:expr) => {{
-        let prt = rotation_logger::LOG_SENDER.load(std::sync::atomic::Ordering::Acquire);
-        if !prt.is_null() {
-            unsafe {
-                let sender = &*prt;
-                let modules = vec![$(stringify!($modules).to_string()),*];
-                let message = rotation_logger::Message::new(&modules, $message);
-                let _ = sender.send(message);
+    ($level:expr, target: $target:expr, $($arg:tt)+) => {
+        if $level.is_statically_enabled() && $crate::Logger::is_running_enabled($level, &[]) {
+            $crate::Logger::send(
+                $crate::Logger::scoped_message(&[], &::std::format!($($arg)+))
+                    .with_target($target)
+                    .with_level($level),
+            );
+        }
+    };
+    ($level:expr, [$($modules:expr),*], $message:expr) => {
+        if $level.is_statically_enabled() {
+            match [$(::core::convert::AsRef::<str>::as_ref(&$modules)),*] {
+                modules => {
+                    if $crate::Logger::is_running_enabled($level, &modules) {
+                        $crate::Logger::send(
+                            $crate::Logger::scoped_message(
+                                &modules,
+                                &$crate::LogText::into_text($message),
+                            )
+                            .with_level($level),
+                        );
+                    }
+                }
             }
         }
-    }};
-    ($message:expr) => {
-        let prt = rotation_logger::LOG_SENDER.load(std::sync::atomic::Ordering::Acquire);
-        if !prt.is_null() {
-            unsafe {
-                let sender = &*prt;
-                let message = rotation_logger::Message::new(&vec![], $message);
-                let _ = sender.send(message);
+    };
+    ($level:expr, ($($modules:ident),*), $message:expr) => {{
+        if $level.is_statically_enabled() {
+            let modules = [$(stringify!($modules)),*];
+            if $crate::Logger::is_running_enabled($level, &modules) {
+                $crate::Logger::send(
+                    $crate::Logger::scoped_message(
+                        &modules,
+                        &$crate::LogText::into_text($message),
+                    )
+                    .with_level($level),
+                );
             }
         }
+    }};
+    ($level:expr, $message:expr) => {
+        if $level.is_statically_enabled() && $crate::Logger::is_running_enabled($level, &[]) {
+            $crate::Logger::send(
+                $crate::Logger::scoped_message(&[], &$crate::LogText::into_text($message))
+                    .with_level($level),
+            );
+        }
+    };
+}
+
+/// Would message of level and modules be written by running logger, same as `Logger::is_enabled`.
+/// `false` when no logger runs, always `false` for level above `STATIC_MAX_LEVEL`.
+#[macro_export]
+macro_rules! log_enabled {
+    ($level:expr, [$($modules:expr),*]) => {
+        $crate::Logger::is_running_enabled(
+            $level,
+            &[$(::core::convert::AsRef::<str>::as_ref(&$modules)),*],
+        )
+    };
+    ($level:expr) => {
+        $crate::Logger::is_running_enabled($level, &[])
     };
 }
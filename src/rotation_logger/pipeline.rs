@@ -0,0 +1,233 @@
+//! # Post-rotation pipeline.
+//!
+//! Every time the active log file is rotated, the worker hands the path of the rotated file
+//! to the `RotationPipeline` configured in `FileSettings`. The pipeline runs its stages in
+//! order, each stage receiving the path produced by the previous one, e.g.:
+//! compress -> checksum -> upload -> delete.
+//!
+//! Progress is recorded per stage in a journal file (`.<filename>.pipeline`) next to the logs.
+//! Failed or interrupted stages are retried on the next rotation and on logger restart.
+//!
+//! # Example:
+//!
+//! ```
+//! # use rotation_logger::{DeleteStage, FileSettings, FileSize, RotationPipeline};
+//! let pipeline = RotationPipeline::new().stage(DeleteStage);
+//!
+//! let file_settings = FileSettings::new(
+//!     "./logs".into(),
+//!     10,
+//!     FileSize::from_megabytes(5),
+//!     "new_logger".into(),
+//!     "log".into(),
+//! )
+//! .with_pipeline(pipeline);
+//! ```
+//!
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs;
+use std::{
+    fmt::Debug,
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::rotation_logger::settings::FileSettings;
+
+#[cfg(feature = "ssh")]
+mod ssh;
+mod stages;
+
+#[cfg(feature = "ssh")]
+pub use ssh::SshAuth;
+#[cfg(feature = "ssh")]
+pub use ssh::SshTransfer;
+#[cfg(feature = "ssh")]
+pub use ssh::SshUploadStage;
+#[cfg(feature = "checksum")]
+pub use stages::ChecksumStage;
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+pub use stages::CompressStage;
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+pub use stages::Compression;
+pub use stages::DeleteStage;
+
+/// Single step of the post-rotation pipeline.
+pub trait RotationStage: Send + Sync {
+    /// Stage name, used in journal and error reports.
+    fn name(&self) -> &str;
+
+    /// Process rotated file.
+    /// Returns path of the file which must be passed to next stage,
+    /// or `None` if file was consumed and pipeline must stop.
+    fn process(&self, path: &Path) -> io::Result<Option<PathBuf>>;
+}
+
+/// Ordered list of stages applied to each rotated file.
+#[derive(Clone, Default)]
+pub struct RotationPipeline {
+    stages: Vec<Arc<dyn RotationStage>>,
+}
+
+impl RotationPipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append stage to the end of pipeline.
+    pub fn stage(mut self, stage: impl RotationStage + 'static) -> Self {
+        self.stages.push(Arc::new(stage));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.stages.len()
+    }
+}
+
+impl Debug for RotationPipeline {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.stages.iter().map(|stage| stage.name()))
+            .finish()
+    }
+}
+
+/// Rotated file waiting for pipeline stages.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PipelineJob {
+    /// Index of next stage to run.
+    pub(crate) stage: usize,
+    /// Current path of the file.
+    pub(crate) path: PathBuf,
+}
+
+/// Worker side of the pipeline: runs stages and keeps journal of progress.
+/// There are no files to rotate on `wasm32`.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) struct PipelineRunner {
+    pipeline: RotationPipeline,
+    journal: PathBuf,
+    jobs: Vec<PipelineJob>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl PipelineRunner {
+    /// Create runner and restore unfinished jobs from journal.
+    pub(crate) fn load(settings: &FileSettings) -> Self {
+        let journal = settings
+            .path()
+            .join(format!(".{}.pipeline", settings.filename()));
+
+        let jobs = match fs::read_to_string(&journal) {
+            Ok(content) => content
+                .lines()
+                .filter_map(|line| {
+                    let (stage, path) = line.split_once('\t')?;
+                    Some(PipelineJob {
+                        stage: stage.parse().ok()?,
+                        path: PathBuf::from(path),
+                    })
+                })
+                .collect(),
+            Err(_) => vec![],
+        };
+
+        Self {
+            pipeline: settings.pipeline().clone(),
+            journal,
+            jobs,
+        }
+    }
+
+    /// Queue rotated file and run all pending jobs.
+    pub(crate) fn submit(&mut self, path: PathBuf) {
+        if self.pipeline.is_empty() {
+            return;
+        }
+        self.jobs.push(PipelineJob { stage: 0, path });
+        self.save();
+        self.process();
+    }
+
+    /// Update paths of pending jobs after rotation renamed files.
+    pub(crate) fn relocate(&mut self, renames: &[(PathBuf, PathBuf)]) {
+        let mut changed = false;
+        for job in self.jobs.iter_mut() {
+            if let Some((_, new_path)) = renames.iter().find(|(old, _)| *old == job.path) {
+                job.path = new_path.clone();
+                changed = true;
+            }
+        }
+        if changed {
+            self.save();
+        }
+    }
+
+    /// Run pending stages for every job.
+    /// Job stays in journal from the first failed stage, to be retried later.
+    pub(crate) fn process(&mut self) {
+        let mut index = 0;
+        while index < self.jobs.len() {
+            if self.run_job(index) {
+                self.jobs.remove(index);
+            } else {
+                index += 1;
+            }
+            self.save();
+        }
+    }
+
+    /// Returns `true` when job is finished and can be removed.
+    fn run_job(&mut self, index: usize) -> bool {
+        loop {
+            let job = &mut self.jobs[index];
+            if !job.path.exists() {
+                return true;
+            }
+            let stage = match self.pipeline.stages.get(job.stage) {
+                Some(stage) => stage.clone(),
+                None => return true,
+            };
+
+            match stage.process(&job.path) {
+                Ok(Some(path)) => {
+                    job.path = path;
+                    job.stage += 1;
+                }
+                Ok(None) => return true,
+                Err(err) => {
+                    println!(
+                        "Logger rotation stage `{}` failed for {}. Error: {err}",
+                        stage.name(),
+                        job.path.to_string_lossy()
+                    );
+                    return false;
+                }
+            }
+            self.save();
+        }
+    }
+
+    fn save(&self) {
+        if self.jobs.is_empty() {
+            let _ = fs::remove_file(&self.journal);
+            return;
+        }
+        let content: String = self
+            .jobs
+            .iter()
+            .map(|job| format!("{}\t{}\n", job.stage, job.path.to_string_lossy()))
+            .collect();
+        if let Err(err) = fs::write(&self.journal, content) {
+            println!("Logger cant write rotation journal. Error: {err}");
+        }
+    }
+}
@@ -0,0 +1,231 @@
+use std::{
+    fs::File,
+    io::{self, BufReader},
+    net::{TcpStream, ToSocketAddrs},
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use chrono::{DateTime, Local};
+use ssh2::{CheckResult, KnownHostFileKind, Session};
+
+use crate::rotation_logger::pipeline::RotationStage;
+
+/// Protocol used to copy files to remote host.
+#[derive(Debug, Clone, Default)]
+pub enum SshTransfer {
+    /// Upload to temporary `.part` file and rename it when completed.
+    #[default]
+    Sftp,
+    /// Plain SCP copy, for hosts without SFTP subsystem.
+    Scp,
+}
+
+/// Authentication method for remote host.
+#[derive(Debug, Clone)]
+pub enum SshAuth {
+    Password(String),
+    /// Private key file with optional passphrase.
+    KeyFile {
+        private_key: PathBuf,
+        passphrase: Option<String>,
+    },
+    /// Keys from running ssh-agent.
+    Agent,
+}
+
+/// Upload rotated file to remote host over SSH.
+/// Every rotated file starts as generation `0`, so remote name is prefixed with file
+/// modification time: `<remote_dir>/20250101-120000.123-<file name>`.
+/// Remote host key is verified against `~/.ssh/known_hosts`, see `with_known_hosts`.
+/// File passed to the next stage unchanged, so `DeleteStage` can follow if local copy not needed.
+#[derive(Debug, Clone)]
+pub struct SshUploadStage {
+    host: String,
+    port: u16,
+    username: String,
+    auth: SshAuth,
+    remote_dir: PathBuf,
+    transfer: SshTransfer,
+    known_hosts: Option<PathBuf>,
+    accept_any_host_key: bool,
+    timeout: Duration,
+}
+
+impl SshUploadStage {
+    pub fn new(host: &str, username: &str, auth: SshAuth, remote_dir: PathBuf) -> Self {
+        Self {
+            host: host.into(),
+            port: 22,
+            username: username.into(),
+            auth,
+            remote_dir,
+            transfer: Default::default(),
+            known_hosts: None,
+            accept_any_host_key: false,
+            timeout: Duration::from_secs(30),
+        }
+    }
+
+    pub fn with_port(mut self, port: u16) -> Self {
+        self.port = port;
+        self
+    }
+
+    pub fn with_transfer(mut self, transfer: SshTransfer) -> Self {
+        self.transfer = transfer;
+        self
+    }
+
+    /// Verify remote host key against OpenSSH `known_hosts` file,
+    /// instead of `~/.ssh/known_hosts` used by default.
+    pub fn with_known_hosts(mut self, known_hosts: PathBuf) -> Self {
+        self.known_hosts = Some(known_hosts);
+        self
+    }
+
+    /// Accept any remote host key, without verification.
+    /// Only for trusted networks, otherwise anyone between can pose as remote host.
+    pub fn with_insecure_accept_any_host_key(mut self) -> Self {
+        self.accept_any_host_key = true;
+        self
+    }
+
+    /// `known_hosts` file remote host key is verified against, none when any key is accepted.
+    pub(crate) fn known_hosts_file(&self) -> io::Result<Option<PathBuf>> {
+        if self.accept_any_host_key {
+            return Ok(None);
+        }
+        if let Some(known_hosts) = &self.known_hosts {
+            return Ok(Some(known_hosts.clone()));
+        }
+        let home = std::env::home_dir().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "no home dir for known_hosts, set it with `with_known_hosts`",
+            )
+        })?;
+        Ok(Some(home.join(".ssh").join("known_hosts")))
+    }
+
+    /// Refuse `key` of remote host unless it is in `known_hosts` file.
+    pub(crate) fn verify_host_key(
+        &self,
+        session: &Session,
+        known_hosts_file: &Path,
+        key: &[u8],
+    ) -> io::Result<()> {
+        let mut known_hosts = session.known_hosts()?;
+        known_hosts.read_file(known_hosts_file, KnownHostFileKind::OpenSSH)?;
+        match known_hosts.check_port(&self.host, self.port, key) {
+            CheckResult::Match => Ok(()),
+            result => Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!("host key verification failed: {result:?}"),
+            )),
+        }
+    }
+
+    /// Timeout for connecting to every address of host and for every blocking SSH operation.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    fn connect(&self) -> io::Result<Session> {
+        let tcp = self.connect_tcp()?;
+        let mut session = Session::new()?;
+        session.set_tcp_stream(tcp);
+        session.set_timeout(self.timeout.as_millis().min(u32::MAX as u128) as u32);
+        session.handshake()?;
+
+        if let Some(known_hosts_file) = self.known_hosts_file()? {
+            let (key, _) = session
+                .host_key()
+                .ok_or_else(|| io::Error::other("remote host sent no key"))?;
+            self.verify_host_key(&session, &known_hosts_file, key)?;
+        }
+
+        match &self.auth {
+            SshAuth::Password(password) => session.userauth_password(&self.username, password)?,
+            SshAuth::KeyFile {
+                private_key,
+                passphrase,
+            } => session.userauth_pubkey_file(
+                &self.username,
+                None,
+                private_key,
+                passphrase.as_deref(),
+            )?,
+            SshAuth::Agent => session.userauth_agent(&self.username)?,
+        }
+
+        if !session.authenticated() {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "ssh authentication failed",
+            ));
+        }
+        Ok(session)
+    }
+
+    /// Connect to addresses of host in turn, error of the last one when none accepts.
+    fn connect_tcp(&self) -> io::Result<TcpStream> {
+        let mut last_err = None;
+        for address in (self.host.as_str(), self.port).to_socket_addrs()? {
+            match TcpStream::connect_timeout(&address, self.timeout) {
+                Ok(tcp) => return Ok(tcp),
+                Err(err) => last_err = Some(err),
+            }
+        }
+        Err(last_err.unwrap_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "host resolved to no address")
+        }))
+    }
+}
+
+impl RotationStage for SshUploadStage {
+    fn name(&self) -> &str {
+        "ssh-upload"
+    }
+
+    fn process(&self, path: &Path) -> io::Result<Option<PathBuf>> {
+        let file_name = path
+            .file_name()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+        let mut local = BufReader::new(File::open(path)?);
+        let modified: DateTime<Local> = local.get_ref().metadata()?.modified()?.into();
+        let remote_path = self.remote_dir.join(format!(
+            "{}-{}",
+            modified.format("%Y%m%d-%H%M%S%.3f"),
+            file_name.to_string_lossy()
+        ));
+
+        let session = self.connect()?;
+
+        match self.transfer {
+            SshTransfer::Sftp => {
+                let sftp = session.sftp()?;
+                let mut part_path = remote_path.as_os_str().to_owned();
+                part_path.push(".part");
+                let part_path = PathBuf::from(part_path);
+
+                let mut remote = sftp.create(&part_path)?;
+                io::copy(&mut local, &mut remote)?;
+                drop(remote);
+                sftp.rename(&part_path, &remote_path, None)?;
+            }
+            SshTransfer::Scp => {
+                let size = local.get_ref().metadata()?.len();
+                let mut channel = session.scp_send(&remote_path, 0o644, size, None)?;
+                io::copy(&mut local, &mut channel)?;
+                channel.send_eof()?;
+                channel.wait_eof()?;
+                channel.close()?;
+                channel.wait_close()?;
+            }
+        }
+
+        Ok(Some(path.to_path_buf()))
+    }
+}
@@ -0,0 +1,145 @@
+#[cfg(any(feature = "gzip", feature = "zstd", feature = "checksum"))]
+use std::fs::File;
+#[cfg(any(feature = "gzip", feature = "zstd", feature = "checksum"))]
+use std::io::BufReader;
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+use std::io::BufWriter;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::rotation_logger::pipeline::RotationStage;
+
+/// Remove rotated file. Usually the last stage, after upload.
+#[derive(Debug, Clone, Default)]
+pub struct DeleteStage;
+
+impl RotationStage for DeleteStage {
+    fn name(&self) -> &str {
+        "delete"
+    }
+
+    fn process(&self, path: &Path) -> io::Result<Option<PathBuf>> {
+        fs::remove_file(path)?;
+        Ok(None)
+    }
+}
+
+/// Compression algorithm for archived logs, one variant per feature.
+/// Default is gzip level 6, or zstd level 3 with `zstd` feature only.
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+#[derive(Debug, Clone)]
+pub enum Compression {
+    /// Gzip with level from 0 to 9.
+    #[cfg(feature = "gzip")]
+    Gzip { level: u32 },
+    /// Zstandard with level from 1 to 22, `0` is default of zstd.
+    /// Much smaller files than gzip for repetitive structured logs.
+    #[cfg(feature = "zstd")]
+    Zstd { level: i32 },
+}
+
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+impl Default for Compression {
+    fn default() -> Self {
+        #[cfg(feature = "gzip")]
+        return Self::Gzip { level: 6 };
+        #[cfg(not(feature = "gzip"))]
+        Self::Zstd { level: 3 }
+    }
+}
+
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+impl Compression {
+    /// Extension appended to compressed file name.
+    pub fn extension(&self) -> &str {
+        match self {
+            #[cfg(feature = "gzip")]
+            Compression::Gzip { .. } => "gz",
+            #[cfg(feature = "zstd")]
+            Compression::Zstd { .. } => "zst",
+        }
+    }
+}
+
+/// Compress rotated file, replacing it with `<file>.<compression extension>`.
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+#[derive(Debug, Clone, Default)]
+pub struct CompressStage {
+    compression: Compression,
+}
+
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+impl CompressStage {
+    pub fn new(compression: Compression) -> Self {
+        Self { compression }
+    }
+}
+
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+impl RotationStage for CompressStage {
+    fn name(&self) -> &str {
+        "compress"
+    }
+
+    fn process(&self, path: &Path) -> io::Result<Option<PathBuf>> {
+        let mut target = path.as_os_str().to_owned();
+        target.push(format!(".{}", self.compression.extension()));
+        let target = PathBuf::from(target);
+
+        let mut reader = BufReader::new(File::open(path)?);
+        let writer = BufWriter::new(File::create(&target)?);
+
+        match self.compression {
+            #[cfg(feature = "gzip")]
+            Compression::Gzip { level } => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(writer, flate2::Compression::new(level));
+                io::copy(&mut reader, &mut encoder)?;
+                encoder.finish()?.into_inner()?.sync_all()?;
+            }
+            #[cfg(feature = "zstd")]
+            Compression::Zstd { level } => {
+                let mut encoder = zstd::stream::write::Encoder::new(writer, level)?;
+                io::copy(&mut reader, &mut encoder)?;
+                encoder.finish()?.into_inner()?.sync_all()?;
+            }
+        }
+
+        fs::remove_file(path)?;
+        Ok(Some(target))
+    }
+}
+
+/// Write hex SHA-256 of rotated file to `<file>.sha256` sidecar.
+/// Sidecar is rotated together with the file, so it holds digest only, without file name.
+/// File passed to the next stage unchanged.
+#[cfg(feature = "checksum")]
+#[derive(Debug, Clone, Default)]
+pub struct ChecksumStage;
+
+#[cfg(feature = "checksum")]
+impl RotationStage for ChecksumStage {
+    fn name(&self) -> &str {
+        "checksum"
+    }
+
+    fn process(&self, path: &Path) -> io::Result<Option<PathBuf>> {
+        use sha2::Digest;
+
+        let mut hasher = sha2::Sha256::new();
+        io::copy(&mut BufReader::new(File::open(path)?), &mut hasher)?;
+        let digest: String = hasher
+            .finalize()
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+
+        let mut sidecar = path.as_os_str().to_owned();
+        sidecar.push(".sha256");
+        fs::write(sidecar, format!("{digest}\n"))?;
+
+        Ok(Some(path.to_path_buf()))
+    }
+}
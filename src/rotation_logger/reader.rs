@@ -0,0 +1,310 @@
+//! # Reading of written log files.
+//!
+//! Files of `FileSettings::with_schema_header` start with header line naming schema version
+//! and record format, e.g. `#rotation_logger schema=2 format=json`. JSON records carry
+//! `schema` key too, so reader picks parser by version of every record.
+//! Files without header and records without `schema` are read as schema `0`.
+//!
+//! Text lines are returned as they are, since format string of writer is unknown.
+//! Files compressed by `CompressStage` are decompressed while read, with `gzip` or `zstd` feature.
+//!
+//! # Example:
+//!
+//! ```no_run
+//! # use rotation_logger::{LogEntry, LogReader};
+//! let reader = LogReader::open("./logs/app.log").unwrap();
+//! for entry in reader {
+//!     match entry.unwrap() {
+//!         LogEntry::Line(line) => println!("{line}"),
+//!         LogEntry::Record(record) => println!("{}", record.message().text()),
+//!     }
+//! }
+//! ```
+//!
+#[cfg(all(feature = "file", not(target_arch = "wasm32")))]
+use std::path::PathBuf;
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, Read},
+    path::Path,
+    time::{Duration, UNIX_EPOCH},
+};
+
+use crate::rotation_logger::{FieldValue, Level, Message, Record, SCHEMA_VERSION};
+#[cfg(all(feature = "file", not(target_arch = "wasm32")))]
+use crate::rotation_logger::{FileSettings, sink::LogGeneration};
+
+pub(crate) mod json;
+#[cfg(all(feature = "file", not(target_arch = "wasm32")))]
+mod search;
+#[cfg(all(feature = "file", not(target_arch = "wasm32")))]
+mod verify;
+
+use json::Json;
+#[cfg(all(feature = "file", not(target_arch = "wasm32")))]
+pub use search::{Search, SearchMatch, SearchOptions, SearchPattern};
+#[cfg(all(feature = "file", not(target_arch = "wasm32")))]
+pub use verify::verify_log_set;
+
+const HEADER_PREFIX: &str = "#rotation_logger";
+
+/// How records are written to file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordFormat {
+    /// Lines of `MessageFormatter`.
+    Text,
+    /// JSON object per line, see `Record::to_json`.
+    Json,
+}
+
+/// First line of log file, see `FileSettings::with_schema_header`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileHeader {
+    schema: u32,
+    format: RecordFormat,
+}
+
+impl FileHeader {
+    /// Header of files written by this version.
+    pub fn new(format: RecordFormat) -> Self {
+        Self {
+            schema: SCHEMA_VERSION,
+            format,
+        }
+    }
+
+    /// Header from line, `None` if it is not a header.
+    pub fn parse(line: &str) -> Option<Self> {
+        let mut schema = None;
+        let mut format = RecordFormat::Text;
+        for pair in line.strip_prefix(HEADER_PREFIX)?.split_whitespace() {
+            match pair.split_once('=')? {
+                ("schema", value) => schema = value.parse().ok(),
+                ("format", "json") => format = RecordFormat::Json,
+                // Keys of later versions are skipped.
+                _ => {}
+            }
+        }
+        Some(Self {
+            schema: schema?,
+            format,
+        })
+    }
+
+    pub fn line(&self) -> String {
+        let format = match self.format {
+            RecordFormat::Text => "text",
+            RecordFormat::Json => "json",
+        };
+        format!("{HEADER_PREFIX} schema={} format={format}", self.schema)
+    }
+
+    pub fn schema(&self) -> u32 {
+        self.schema
+    }
+
+    pub fn format(&self) -> RecordFormat {
+        self.format
+    }
+}
+
+/// Entry of log file.
+#[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
+pub enum LogEntry {
+    /// Text line, or record of schema this version can't read.
+    Line(String),
+    /// JSON record, its line is JSON as written.
+    Record(Record),
+}
+
+/// Iterator over entries of log file, skipping header.
+pub struct LogReader {
+    reader: BufReader<Box<dyn Read + Send>>,
+    /// Position of next line in content of file.
+    offset: u64,
+    header: Option<FileHeader>,
+    /// First line with its position, when it is not header.
+    first: Option<(u64, String)>,
+}
+
+impl LogReader {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut reader = Self {
+            reader: BufReader::new(open_content(path.as_ref())?),
+            offset: 0,
+            header: None,
+            first: None,
+        };
+        // Byte order mark of `Encoding::Utf8Bom` files.
+        let first = reader.read_line().transpose()?.map(|(offset, line)| {
+            match line.strip_prefix('\u{feff}') {
+                Some(rest) => (offset, rest.into()),
+                None => (offset, line),
+            }
+        });
+        reader.header = first.as_ref().and_then(|(_, line)| FileHeader::parse(line));
+        if reader.header.is_none() {
+            reader.first = first;
+        }
+        Ok(reader)
+    }
+
+    /// Next line without line break, with its position.
+    fn read_line(&mut self) -> Option<io::Result<(u64, String)>> {
+        let mut line = String::new();
+        let offset = self.offset;
+        match self.reader.read_line(&mut line) {
+            Ok(0) => None,
+            Ok(read) => {
+                self.offset += read as u64;
+                if line.ends_with('\n') {
+                    line.pop();
+                    if line.ends_with('\r') {
+                        line.pop();
+                    }
+                }
+                Some(Ok((offset, line)))
+            }
+            Err(err) => Some(Err(err)),
+        }
+    }
+
+    /// Next entry with position of its line in content of file.
+    pub(crate) fn next_entry(&mut self) -> Option<io::Result<(u64, LogEntry)>> {
+        let (offset, line) = match self.first.take() {
+            Some(first) => first,
+            None => match self.read_line()? {
+                Ok(line) => line,
+                Err(err) => return Some(Err(err)),
+            },
+        };
+        let is_text = self
+            .header
+            .is_some_and(|header| header.format == RecordFormat::Text);
+        if is_text || !line.starts_with('{') {
+            return Some(Ok((offset, LogEntry::Line(line))));
+        }
+        let entry = match parse_record(&line) {
+            Some(record) => LogEntry::Record(record),
+            None => LogEntry::Line(line),
+        };
+        Some(Ok((offset, entry)))
+    }
+
+    pub fn header(&self) -> Option<&FileHeader> {
+        self.header.as_ref()
+    }
+
+    /// Schema of file header, `0` for files without it.
+    pub fn schema(&self) -> u32 {
+        self.header.map_or(0, |header| header.schema)
+    }
+
+    /// Files of rotation set in path of `settings`, from the oldest generation to active file.
+    /// Checksum sidecars are skipped.
+    #[cfg(all(feature = "file", not(target_arch = "wasm32")))]
+    pub fn set_files(settings: &FileSettings) -> io::Result<Vec<PathBuf>> {
+        let mut files = vec![];
+        for entry in std::fs::read_dir(settings.path())? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            if let Some(generation) = LogGeneration::parse(settings, &name)
+                && !generation.suffix.ends_with(".sha256")
+            {
+                files.push((generation.index, entry.path()));
+            }
+        }
+        // Active file has no index and goes last.
+        files.sort_by(|(a, _), (b, _)| match (a, b) {
+            (Some(a), Some(b)) => b.cmp(a),
+            _ => a.is_none().cmp(&b.is_none()),
+        });
+        Ok(files.into_iter().map(|(_, path)| path).collect())
+    }
+}
+
+/// Content of log file, decompressed when it has compression extension.
+pub(crate) fn open_content(path: &Path) -> io::Result<Box<dyn Read + Send>> {
+    let file = File::open(path)?;
+    match path.extension().and_then(|extension| extension.to_str()) {
+        #[cfg(feature = "gzip")]
+        Some("gz") => Ok(Box::new(flate2::read::GzDecoder::new(file))),
+        #[cfg(not(feature = "gzip"))]
+        Some("gz") => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "gzip feature is required to read compressed logs",
+        )),
+        #[cfg(feature = "zstd")]
+        Some("zst") => Ok(Box::new(zstd::stream::read::Decoder::new(file)?)),
+        #[cfg(not(feature = "zstd"))]
+        Some("zst") => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "zstd feature is required to read compressed logs",
+        )),
+        _ => Ok(Box::new(file)),
+    }
+}
+
+impl Iterator for LogReader {
+    type Item = io::Result<LogEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(self.next_entry()?.map(|(_, entry)| entry))
+    }
+}
+
+/// Record of JSON line, dispatched on its `schema`.
+pub(crate) fn parse_record(line: &str) -> Option<Record> {
+    let json = json::parse(line)?;
+    let schema = json.get("schema").and_then(Json::as_f64).unwrap_or(0.0);
+    match schema as u32 {
+        // Schema `1` only added `schema` key, `2` added optional `target`,
+        // `3` wrote field values as JSON of their type.
+        0..=3 => parse_record_v0(&json, line),
+        _ => None,
+    }
+}
+
+fn parse_record_v0(json: &Json, line: &str) -> Option<Record> {
+    let time = match json.get("timestamp")? {
+        Json::Number(seconds) => UNIX_EPOCH + Duration::from_secs_f64(*seconds),
+        #[cfg(feature = "timestamps")]
+        Json::String(timestamp) => chrono::DateTime::parse_from_rfc3339(timestamp).ok()?.into(),
+        _ => return None,
+    };
+    let level = Level::from_name(json.get("level")?.as_str()?)?;
+    let modules = match json.get("modules")? {
+        Json::Array(modules) => modules
+            .iter()
+            .map(|module| module.as_str().map(String::from))
+            .collect::<Option<Vec<String>>>()?,
+        _ => return None,
+    };
+    let mut message = Message::new(&modules, json.get("message")?.as_str()?).with_level(level);
+    if let Some(Json::Object(fields)) = json.get("fields") {
+        for (key, value) in fields {
+            message = message.with_field(key, field_value(value));
+        }
+    }
+    if let Some(target) = json.get("target") {
+        message = message.with_target(target.as_str()?);
+    }
+    Some(Record::new(message, line.into(), time))
+}
+
+/// Field value of JSON type, nested values are kept as JSON text without `serde` feature.
+fn field_value(value: &Json) -> FieldValue {
+    match value {
+        Json::Integer(value) => FieldValue::I64(*value),
+        Json::Number(value) => FieldValue::F64(*value),
+        Json::Bool(value) => FieldValue::Bool(*value),
+        Json::String(value) => FieldValue::Str(value.clone()),
+        #[cfg(feature = "serde")]
+        value => serde_json::from_str(&value.to_text())
+            .map(FieldValue::Json)
+            .unwrap_or_else(|_| FieldValue::Str(value.to_text())),
+        #[cfg(not(feature = "serde"))]
+        value => FieldValue::Str(value.to_text()),
+    }
+}
@@ -0,0 +1,222 @@
+//! Minimal JSON parser and writer for records of `Record::to_json`.
+use std::{iter::Peekable, str::Chars};
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Json {
+    Null,
+    Bool(bool),
+    /// Number without fraction and exponent.
+    Integer(i64),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Value of object key, `None` for other values.
+    pub(crate) fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(entries) => entries
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_f64(&self) -> Option<f64> {
+        match self {
+            Json::Number(value) => Some(*value),
+            Json::Integer(value) => Some(*value as f64),
+            _ => None,
+        }
+    }
+
+    /// Value written back as compact JSON.
+    pub(crate) fn to_text(&self) -> String {
+        match self {
+            Json::Null => "null".into(),
+            Json::Bool(value) => value.to_string(),
+            Json::Integer(value) => value.to_string(),
+            Json::Number(value) => number(*value),
+            Json::String(value) => string(value),
+            Json::Array(items) => {
+                let items = items.iter().map(Json::to_text).collect::<Vec<String>>();
+                format!("[{}]", items.join(","))
+            }
+            Json::Object(entries) => {
+                let entries = entries
+                    .iter()
+                    .map(|(key, value)| format!("{}:{}", string(key), value.to_text()))
+                    .collect::<Vec<String>>();
+                format!("{{{}}}", entries.join(","))
+            }
+        }
+    }
+}
+
+/// Parse whole `text` as one value, `None` if it is not valid JSON.
+pub(crate) fn parse(text: &str) -> Option<Json> {
+    let mut chars = text.chars().peekable();
+    let value = parse_value(&mut chars)?;
+    skip_whitespace(&mut chars);
+    chars.peek().is_none().then_some(value)
+}
+
+fn skip_whitespace(chars: &mut Peekable<Chars>) {
+    while chars.next_if(|char| char.is_whitespace()).is_some() {}
+}
+
+fn parse_value(chars: &mut Peekable<Chars>) -> Option<Json> {
+    skip_whitespace(chars);
+    match chars.peek()? {
+        '{' => parse_object(chars),
+        '[' => parse_array(chars),
+        '"' => parse_string(chars).map(Json::String),
+        't' => parse_word(chars, "true", Json::Bool(true)),
+        'f' => parse_word(chars, "false", Json::Bool(false)),
+        'n' => parse_word(chars, "null", Json::Null),
+        _ => parse_number(chars),
+    }
+}
+
+fn parse_word(chars: &mut Peekable<Chars>, word: &str, value: Json) -> Option<Json> {
+    for expected in word.chars() {
+        if chars.next()? != expected {
+            return None;
+        }
+    }
+    Some(value)
+}
+
+fn parse_number(chars: &mut Peekable<Chars>) -> Option<Json> {
+    let mut number = String::new();
+    while let Some(char) =
+        chars.next_if(|char| char.is_ascii_digit() || matches!(char, '-' | '+' | '.' | 'e' | 'E'))
+    {
+        number.push(char);
+    }
+    if !number.contains(['.', 'e', 'E'])
+        && let Ok(integer) = number.parse()
+    {
+        return Some(Json::Integer(integer));
+    }
+    number.parse().ok().map(Json::Number)
+}
+
+fn parse_string(chars: &mut Peekable<Chars>) -> Option<String> {
+    if chars.next()? != '"' {
+        return None;
+    }
+    let mut result = String::new();
+    loop {
+        match chars.next()? {
+            '"' => return Some(result),
+            '\\' => match chars.next()? {
+                'n' => result.push('\n'),
+                'r' => result.push('\r'),
+                't' => result.push('\t'),
+                'b' => result.push('\u{8}'),
+                'f' => result.push('\u{c}'),
+                'u' => {
+                    let code = parse_hex(chars)?;
+                    let code = if (0xD800..0xDC00).contains(&code) {
+                        // High surrogate, low one follows as next escape.
+                        if chars.next()? != '\\' || chars.next()? != 'u' {
+                            return None;
+                        }
+                        0x10000 + ((code - 0xD800) << 10) + (parse_hex(chars)? - 0xDC00)
+                    } else {
+                        code
+                    };
+                    result.push(char::from_u32(code)?);
+                }
+                other => result.push(other),
+            },
+            char => result.push(char),
+        }
+    }
+}
+
+fn parse_hex(chars: &mut Peekable<Chars>) -> Option<u32> {
+    let hex = (0..4).map(|_| chars.next()).collect::<Option<String>>()?;
+    u32::from_str_radix(&hex, 16).ok()
+}
+
+fn parse_array(chars: &mut Peekable<Chars>) -> Option<Json> {
+    chars.next();
+    let mut items = vec![];
+    skip_whitespace(chars);
+    if chars.next_if_eq(&']').is_some() {
+        return Some(Json::Array(items));
+    }
+    loop {
+        items.push(parse_value(chars)?);
+        skip_whitespace(chars);
+        match chars.next()? {
+            ',' => continue,
+            ']' => return Some(Json::Array(items)),
+            _ => return None,
+        }
+    }
+}
+
+fn parse_object(chars: &mut Peekable<Chars>) -> Option<Json> {
+    chars.next();
+    let mut entries = vec![];
+    skip_whitespace(chars);
+    if chars.next_if_eq(&'}').is_some() {
+        return Some(Json::Object(entries));
+    }
+    loop {
+        skip_whitespace(chars);
+        let key = parse_string(chars)?;
+        skip_whitespace(chars);
+        if chars.next()? != ':' {
+            return None;
+        }
+        entries.push((key, parse_value(chars)?));
+        skip_whitespace(chars);
+        match chars.next()? {
+            ',' => continue,
+            '}' => return Some(Json::Object(entries)),
+            _ => return None,
+        }
+    }
+}
+
+/// Quoted JSON string.
+pub(crate) fn string(value: &str) -> String {
+    let mut result = String::with_capacity(value.len() + 2);
+    result.push('"');
+    for char in value.chars() {
+        match char {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            char if (char as u32) < 0x20 => result.push_str(&format!("\\u{:04x}", char as u32)),
+            char => result.push(char),
+        }
+    }
+    result.push('"');
+    result
+}
+
+/// JSON number, with fraction even for whole values so it is read back as float,
+/// `null` for infinity and NaN.
+pub(crate) fn number(value: f64) -> String {
+    match value.is_finite() {
+        true => format!("{value:?}"),
+        false => "null".into(),
+    }
+}
@@ -0,0 +1,236 @@
+//! # Search over rotation set.
+//!
+//! Programmatic counterpart of `rotlog grep`: files are read from the oldest generation
+//! to active one, matches are returned as they are found, with file and position of line.
+//!
+//! Pattern is matched against message text of records and whole text lines.
+//! Text lines have no modules, level or time, so they are skipped when any of these filters is set.
+//!
+use std::{
+    collections::VecDeque,
+    io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+#[cfg(feature = "regex")]
+use regex::Regex;
+
+use crate::rotation_logger::{
+    FileSettings, Level, LogEntry, LogReader, Record, settings::module_path,
+};
+
+/// What `LogReader::search` looks for.
+#[derive(Debug, Clone)]
+pub enum SearchPattern {
+    Substring(String),
+    #[cfg(feature = "regex")]
+    Regex(Regex),
+}
+
+impl SearchPattern {
+    fn matches(&self, text: &str) -> bool {
+        match self {
+            SearchPattern::Substring(pattern) => text.contains(pattern.as_str()),
+            #[cfg(feature = "regex")]
+            SearchPattern::Regex(pattern) => pattern.is_match(text),
+        }
+    }
+}
+
+impl From<&str> for SearchPattern {
+    fn from(pattern: &str) -> Self {
+        Self::Substring(pattern.into())
+    }
+}
+
+#[cfg(feature = "regex")]
+impl From<Regex> for SearchPattern {
+    fn from(pattern: Regex) -> Self {
+        Self::Regex(pattern)
+    }
+}
+
+/// Filters of `LogReader::search`, every entry matches when none is set.
+#[derive(Debug, Clone, Default)]
+pub struct SearchOptions {
+    modules: Option<Vec<String>>,
+    level: Option<Level>,
+    since: Option<SystemTime>,
+    until: Option<SystemTime>,
+}
+
+impl SearchOptions {
+    /// Records of module `path` and its submodules, e.g. `app::db`.
+    pub fn with_module(mut self, path: &str) -> Self {
+        self.modules = Some(module_path(path));
+        self
+    }
+
+    /// Records of `level` and more important ones.
+    pub fn with_level(mut self, level: Level) -> Self {
+        self.level = Some(level);
+        self
+    }
+
+    /// Records logged at `since` or later.
+    pub fn with_since(mut self, since: SystemTime) -> Self {
+        self.since = Some(since);
+        self
+    }
+
+    /// Records logged before `until`.
+    pub fn with_until(mut self, until: SystemTime) -> Self {
+        self.until = Some(until);
+        self
+    }
+
+    pub fn modules(&self) -> Option<&[String]> {
+        self.modules.as_deref()
+    }
+    pub fn level(&self) -> Option<Level> {
+        self.level
+    }
+    pub fn since(&self) -> Option<SystemTime> {
+        self.since
+    }
+    pub fn until(&self) -> Option<SystemTime> {
+        self.until
+    }
+
+    fn is_set(&self) -> bool {
+        self.modules.is_some()
+            || self.level.is_some()
+            || self.since.is_some()
+            || self.until.is_some()
+    }
+
+    fn accepts(&self, record: &Record) -> bool {
+        let message = record.message();
+        self.modules
+            .as_ref()
+            .is_none_or(|modules| message.modules().starts_with(modules))
+            && self.level.is_none_or(|level| message.level() <= level)
+            && self.since.is_none_or(|since| record.time() >= since)
+            && self.until.is_none_or(|until| record.time() < until)
+    }
+}
+
+/// Entry found by `LogReader::search`.
+#[derive(Debug, Clone)]
+pub struct SearchMatch {
+    path: PathBuf,
+    offset: u64,
+    entry: LogEntry,
+}
+
+impl SearchMatch {
+    /// File of entry.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Position of entry line in file, in decompressed content for compressed files.
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    pub fn entry(&self) -> &LogEntry {
+        &self.entry
+    }
+
+    pub fn into_entry(self) -> LogEntry {
+        self.entry
+    }
+}
+
+/// Iterator over matches of `LogReader::search`.
+pub struct Search {
+    files: VecDeque<PathBuf>,
+    current: Option<(PathBuf, LogReader)>,
+    pattern: SearchPattern,
+    options: SearchOptions,
+}
+
+impl Search {
+    pub(crate) fn new(files: Vec<PathBuf>, pattern: SearchPattern, options: SearchOptions) -> Self {
+        Self {
+            files: files.into(),
+            current: None,
+            pattern,
+            options,
+        }
+    }
+}
+
+fn matches(pattern: &SearchPattern, options: &SearchOptions, entry: &LogEntry) -> bool {
+    match entry {
+        LogEntry::Line(line) => !options.is_set() && pattern.matches(line),
+        LogEntry::Record(record) => {
+            options.accepts(record) && pattern.matches(record.message().text())
+        }
+    }
+}
+
+impl Iterator for Search {
+    type Item = io::Result<SearchMatch>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let Some((path, reader)) = self.current.as_mut() else {
+                let path = self.files.pop_front()?;
+                match LogReader::open(&path) {
+                    Ok(reader) => self.current = Some((path, reader)),
+                    Err(err) => return Some(Err(err)),
+                }
+                continue;
+            };
+            match reader.next_entry() {
+                Some(Ok((offset, entry))) => {
+                    if matches(&self.pattern, &self.options, &entry) {
+                        let path = path.clone();
+                        return Some(Ok(SearchMatch {
+                            path,
+                            offset,
+                            entry,
+                        }));
+                    }
+                }
+                Some(Err(err)) => {
+                    self.current = None;
+                    return Some(Err(err));
+                }
+                None => self.current = None,
+            }
+        }
+    }
+}
+
+impl LogReader {
+    /// Entries of rotation set of `settings` matching `pattern` and `options`, the oldest first.
+    ///
+    /// # Example:
+    ///
+    /// ```no_run
+    /// # use rotation_logger::{FileSettings, FileSize, Level, LogReader, SearchOptions};
+    /// let settings = FileSettings::new(
+    ///     "./logs".into(), 10, FileSize::from_megabytes(5), "app".into(), "log".into(),
+    /// );
+    /// let options = SearchOptions::default().with_module("app::db").with_level(Level::Warn);
+    /// for found in LogReader::search(&settings, "timeout", options).unwrap() {
+    ///     let found = found.unwrap();
+    ///     println!("{}:{}", found.path().display(), found.offset());
+    /// }
+    /// ```
+    pub fn search(
+        settings: &FileSettings,
+        pattern: impl Into<SearchPattern>,
+        options: SearchOptions,
+    ) -> io::Result<Search> {
+        Ok(Search::new(
+            Self::set_files(settings)?,
+            pattern.into(),
+            options,
+        ))
+    }
+}
@@ -0,0 +1,182 @@
+//! # Integrity of rotation set.
+//!
+//! Generations must be numbered `0..n` without holes, one file per generation.
+//! With `FileSettings::with_rotation_records` every file starts with record of rotated one,
+//! their `seq range` must continue each other, range starting from `0` is new process.
+//! With `checksum` feature digests of `.sha256` sidecars are checked, against decompressed
+//! content when file was compressed after checksum.
+//! Log files are not signed, so there are no HMAC chains to check yet.
+//!
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, BufRead, BufReader},
+    path::{Path, PathBuf},
+};
+
+use crate::rotation_logger::{
+    FileSettings, ValidationReport, reader::open_content, sink::LogGeneration,
+};
+
+const SIDECAR: &str = ".sha256";
+
+/// Check log files of `settings` in `dir`, e.g. backup of log directory.
+/// Every problem found goes to report, error only when `dir` can't be read.
+///
+/// # Example:
+///
+/// ```no_run
+/// # use rotation_logger::{FileSettings, FileSize, verify_log_set};
+/// let settings = FileSettings::new(
+///     "./logs".into(), 10, FileSize::from_megabytes(5), "app".into(), "log".into(),
+/// );
+/// let report = verify_log_set("/backup/logs", &settings).unwrap();
+/// assert!(report.is_ok(), "{report}");
+/// ```
+pub fn verify_log_set(
+    dir: impl AsRef<Path>,
+    settings: &FileSettings,
+) -> io::Result<ValidationReport> {
+    let dir = dir.as_ref();
+    let mut report = ValidationReport::default();
+    let mut generations: BTreeMap<Option<u32>, Vec<PathBuf>> = BTreeMap::new();
+    let mut sidecars = vec![];
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some(generation) = LogGeneration::parse(settings, &name) else {
+            continue;
+        };
+        if generation.suffix.ends_with(SIDECAR) {
+            sidecars.push(entry.path());
+        } else {
+            generations
+                .entry(generation.index)
+                .or_default()
+                .push(entry.path());
+        }
+    }
+
+    let indexes: Vec<u32> = generations.keys().flatten().copied().collect();
+    for (expected, index) in (0..).zip(&indexes) {
+        if *index != expected {
+            report.push(format!("generations {expected}..{index} are missing"));
+            break;
+        }
+    }
+    for (index, paths) in &generations {
+        if paths.len() > 1 {
+            let names: Vec<String> = paths.iter().map(|path| name(path)).collect();
+            report.push(format!(
+                "generation {} has several files: {}",
+                index.map_or("active".into(), |index| index.to_string()),
+                names.join(", ")
+            ));
+        }
+    }
+
+    // From the oldest file to active one.
+    let mut previous: Option<(u64, u64)> = None;
+    for paths in generations.values().rev() {
+        let Some(path) = paths.first() else {
+            continue;
+        };
+        let range = match first_lines(path) {
+            Ok(Some(lines)) => lines.iter().find_map(|line| sequence_range(line)),
+            Ok(None) => None,
+            Err(err) => {
+                report.push(format!("{} can't be read: {err}", name(path)));
+                None
+            }
+        };
+        if let Some((first, last)) = range {
+            if let Some((_, previous_last)) = previous
+                && first != 0
+                && first != previous_last + 1
+            {
+                report.push(format!(
+                    "{}: sequence jumps from {previous_last} to {first}",
+                    name(path)
+                ));
+            }
+            previous = Some((first, last));
+        }
+    }
+
+    for sidecar in sidecars {
+        if let Some(problem) = check_sidecar(&sidecar) {
+            report.push(problem);
+        }
+    }
+    Ok(report)
+}
+
+fn name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// `first..last` of rotation record line.
+fn sequence_range(line: &str) -> Option<(u64, u64)> {
+    let rest = &line[line.find("seq range ")? + "seq range ".len()..];
+    let (first, rest) = rest.split_once("..")?;
+    let digits = rest.chars().take_while(char::is_ascii_digit).count();
+    Some((first.parse().ok()?, rest[..digits].parse().ok()?))
+}
+
+/// Header and first record lines of file, `None` for files which can't be decompressed.
+fn first_lines(path: &Path) -> io::Result<Option<Vec<String>>> {
+    let reader = match open_content(path) {
+        Ok(reader) => reader,
+        Err(err) if err.kind() == io::ErrorKind::Unsupported => return Ok(None),
+        Err(err) => return Err(err),
+    };
+    let lines = BufReader::new(reader)
+        .lines()
+        .take(2)
+        .collect::<io::Result<Vec<String>>>()?;
+    Ok(Some(lines))
+}
+
+/// Problem of digest in `sidecar`, if any.
+#[cfg(feature = "checksum")]
+fn check_sidecar(sidecar: &Path) -> Option<String> {
+    use sha2::Digest;
+
+    let expected = match fs::read_to_string(sidecar) {
+        Ok(digest) => digest.trim().to_string(),
+        Err(err) => return Some(format!("{} can't be read: {err}", name(sidecar))),
+    };
+    let file = sidecar.with_extension("");
+    let compressed = ["gz", "zst"].map(|extension| {
+        let mut compressed = file.as_os_str().to_owned();
+        compressed.push(format!(".{extension}"));
+        PathBuf::from(compressed)
+    });
+    let path = match compressed.into_iter().find(|path| path.exists()) {
+        Some(compressed) if !file.exists() => compressed,
+        _ => file,
+    };
+    let mut reader = match open_content(&path) {
+        Ok(reader) => reader,
+        Err(err) if err.kind() == io::ErrorKind::Unsupported => return None,
+        Err(_) => return Some(format!("{} has no log file", name(sidecar))),
+    };
+    let mut hasher = sha2::Sha256::new();
+    if let Err(err) = io::copy(&mut reader, &mut hasher) {
+        return Some(format!("{} can't be read: {err}", name(&path)));
+    }
+    let digest: String = hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect();
+    (digest != expected).then(|| format!("{}: checksum mismatch", name(&path)))
+}
+
+/// Digests are not checked without `checksum` feature.
+#[cfg(not(feature = "checksum"))]
+fn check_sidecar(_sidecar: &Path) -> Option<String> {
+    None
+}
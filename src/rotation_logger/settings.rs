@@ -1,36 +1,22 @@
 //! # Settings and support data for `Logger` setup.
 //!
-//! `Logs Formatter` support five `Mask Types`(mask_type) you can operate with:
-//! - timestamp: represent timestamp of logged data. Time will be taken when logged message received by logger, so it not 100% accurate when event occurred.
-//! - splitter: represent splitter symbol which will separate every `Mask`
-//! - modules: list of modules that was source of log data
-//! - message: log message it self
+//! Message format is set with `MessageFormatter`.
 //!
-//! Each `Mask Type` except `splitter` accept format syntax after `:` char:
-//! `{<mask_type:<mask_length>_<mask_width>_<mask_align>>}`
-//! - mask_length: length of string. On positive value limit string length from begin, on negative value from end.
-//! - mask_width: width of column for this Mask Type.
-//! - mask_align: vertical align for text on this column. Possible values: left, center, right.
-//!
-//! # Example:
-//!
-//! ```
-//! MessageFormatter::new(
-//!     "::",
-//!     "{timestamp:-6:30:right}{splitter}{modules:_:_:left}{splitter}{message}",
-//!     "%Y-%m-%d %H:%M:%S.%f",
-//! );
-//!
-//! ```
-//!
-//! `Logs Output` supported options: file, console, auto
-//! - file: all logs data will be store to logs file with declared settings.
+//! `Logs Output` supported options: file, console, auto, unix_socket, fifo, shared_memory, aggregator, web_console
+//! - file: all logs data will be store to logs file with declared settings (feature `file`).
 //! - console: output to console
-//! - auto: will use console when in develop mode and file on release.
+//! - auto: will use console when in develop mode and file on release (feature `file`).
+//! - dev_duo: colored multi-line console output and JSON lines in rotated files at the same time (features `file` and `formatter`).
+//! - unix_socket: newline-delimited lines to local socket, e.g. of Vector or Fluent Bit (unix only).
+//! - fifo: lines to pre-created named pipe, dropped while there is no reader (unix only).
+//! - shared_memory: messages to ring of `ShmConsumer` in other process, which writes them to its files (feature `shm`).
+//! - aggregator: messages to `Logger` of other process, started with `Settings::with_aggregator`.
+//! - web_console: browser console on `wasm32`, where `file`, `console` and `auto` fall back to it as well.
 //!
 //! # Example:
 //!
 //! ```
+//! # use rotation_logger::{FileSize, OutputChannel};
 //! OutputChannel::file(
 //!     "./".into(),
 //!     10,
@@ -40,11 +26,41 @@
 //! );
 //! ```
 //!
-use std::{cmp::min, path::PathBuf};
+#[cfg(feature = "file")]
+use std::borrow::Cow;
+#[cfg(not(target_arch = "wasm32"))]
+use std::io::IsTerminal;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+#[cfg(feature = "timestamps")]
+use chrono::{DateTime, Local};
 
-use chrono::Local;
+#[cfg(feature = "formatter")]
+use crate::rotation_logger::Grid;
+#[cfg(feature = "file")]
+use crate::rotation_logger::JsonLayout;
+#[cfg(feature = "timestamps")]
+use crate::rotation_logger::LevelWindow;
+#[cfg(feature = "file")]
+use crate::rotation_logger::RotationPipeline;
+#[cfg(feature = "regex")]
+use crate::rotation_logger::TextFilter;
+use crate::rotation_logger::{
+    AggregatorAddress, AggregatorLimits, BacklogWarning, Banner, ColorChoice, ColorTheme, Format,
+    MessageFormatter, MessagePool, OverflowPolicy, WorkerThread,
+    core::SharedFormat,
+    logger::{Level, Message},
+    sink::{ConsoleWriter, SharedConsoleWriter, SharedSink, Sink},
+};
 
-use crate::rotation_logger::logger::Message;
+mod builder;
+mod validation;
+
+#[cfg(feature = "file")]
+pub use builder::FileSettingsBuilder;
+pub use builder::SettingsBuilder;
+pub use validation::ValidationReport;
 
 /// Settings for data format and output of `Logger`.
 /// All Settings must be set before `Logger` start and cant be changed during work.
@@ -55,11 +71,65 @@ pub struct Settings {
     is_enabled: bool,
     /// Format for output logging string
     formatter: MessageFormatter,
+    /// User rendering of lines instead of `formatter`.
+    custom_format: Option<SharedFormat>,
+    /// Console and files write JSON objects instead of formatted lines.
+    json_output: bool,
     /// Output direction to store logs
     output: OutputChannel,
+    /// Outputs getting every line along with `output`, with own flush policy if set.
+    tee_outputs: Vec<(OutputChannel, Option<FlushPolicy>)>,
     /// Accumulating buffer size.
     /// Buffer actually is a `Vec<String>::len` window, which will be accumulated before flushing into file.
     buffer_size: usize,
+    /// Longest time records wait in buffer of outputs and sinks without own flush policy.
+    flush_interval: Option<Duration>,
+    /// Size in bytes of formatted lines after which buffer is written, whatever `buffer_size` is.
+    max_buffer_bytes: Option<usize>,
+    /// Additional sinks, receiving every record along with `output`,
+    /// with own flush policy if set.
+    sinks: Vec<(SharedSink, Option<FlushPolicy>)>,
+    /// Files for records of modules starting with prefix, instead of `output`.
+    #[cfg(feature = "file")]
+    routes: Vec<(Vec<String>, FileSettings)>,
+    /// Directory and number of records held in memory for sinks failed to write.
+    spill: Option<(PathBuf, usize)>,
+    /// File and number of failed writes after which records are moved to it.
+    dead_letter: Option<(PathBuf, usize)>,
+    /// Messages recycled after they are written.
+    message_pool: Option<MessagePool>,
+    /// Flush policy of `output`, instead of `buffer_size`.
+    output_policy: Option<FlushPolicy>,
+    /// Address to accept messages of other processes on.
+    aggregator: Option<AggregatorAddress>,
+    /// Connections, read timeout and message size accepted by `aggregator`.
+    aggregator_limits: AggregatorLimits,
+    /// Record written by worker on start.
+    banner: Option<Banner>,
+    /// Largest queue of worker and what happens to messages past it, unbounded when not set.
+    queue_capacity: Option<(usize, OverflowPolicy)>,
+    /// Warning written when queue of worker grows.
+    backlog_warning: Option<BacklogWarning>,
+    /// Name, stack size and priority of worker thread.
+    worker_thread: WorkerThread,
+    /// Worker is started by first message, not by `Logger::run_async`.
+    lazy_start: bool,
+    /// Least important levels written, more verbose messages are dropped before formatting.
+    levels: LevelFilter,
+    /// Rules keeping or dropping messages by text, checked by worker.
+    #[cfg(feature = "regex")]
+    text_filters: Vec<TextFilter>,
+    /// Colors of console output, plain text when not set.
+    color_theme: Option<ColorTheme>,
+    /// Whether `color_theme` is used.
+    color_choice: ColorChoice,
+    /// Where console lines go instead of stdout.
+    console_writer: Option<SharedConsoleWriter>,
+    /// Largest width of console columns growing to values, fixed widths when not set.
+    auto_widths: Option<usize>,
+    /// Console output as table.
+    #[cfg(feature = "formatter")]
+    console_grid: Option<Grid>,
 }
 
 impl Settings {
@@ -72,19 +142,417 @@ impl Settings {
         Self {
             is_enabled,
             output,
+            tee_outputs: vec![],
             formatter,
+            custom_format: None,
+            json_output: false,
             buffer_size,
+            flush_interval: None,
+            max_buffer_bytes: None,
+            sinks: vec![],
+            #[cfg(feature = "file")]
+            routes: vec![],
+            spill: None,
+            dead_letter: None,
+            message_pool: None,
+            output_policy: None,
+            aggregator: None,
+            aggregator_limits: Default::default(),
+            banner: None,
+            queue_capacity: None,
+            backlog_warning: None,
+            worker_thread: Default::default(),
+            lazy_start: false,
+            levels: Default::default(),
+            #[cfg(feature = "regex")]
+            text_filters: vec![],
+            color_theme: None,
+            color_choice: Default::default(),
+            console_writer: None,
+            auto_widths: None,
+            #[cfg(feature = "formatter")]
+            console_grid: None,
+        }
+    }
+
+    /// Settings step by step, what is not set stays as in `Settings::default`.
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// # use rotation_logger::{MessageFormatter, Settings};
+    /// let settings = Settings::builder()
+    ///     .enabled(true)
+    ///     .buffer_size(512)
+    ///     .file("./logs")
+    ///     .formatter(MessageFormatter::default())
+    ///     .build();
+    /// ```
+    pub fn builder() -> SettingsBuilder {
+        SettingsBuilder::default()
+    }
+
+    /// Same as `new`, with every problem `validate` finds instead of settings,
+    /// e.g. `buffer_size` of 0 together with file output which can't be written.
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// # use rotation_logger::{MessageFormatter, OutputChannel, Settings};
+    /// let report = Settings::try_new(true, 0, OutputChannel::Console, MessageFormatter::default())
+    ///     .err()
+    ///     .unwrap();
+    /// assert_eq!(report.problems(), ["buffer_size is 0"]);
+    /// ```
+    pub fn try_new(
+        is_enabled: bool,
+        buffer_size: usize,
+        output: OutputChannel,
+        formatter: MessageFormatter,
+    ) -> Result<Self, ValidationReport> {
+        let settings = Self::new(is_enabled, buffer_size, output, formatter);
+        let report = settings.validate();
+        match report.is_ok() {
+            true => Ok(settings),
+            false => Err(report),
         }
     }
 
+    /// Add sink which will receive every record in addition to `output`.
+    pub fn with_sink(mut self, sink: impl Sink + 'static) -> Self {
+        self.sinks.push((SharedSink::new(sink), None));
+        self
+    }
+
+    /// Write buffered records to every output and sink once their lines take `max_buffer_bytes`,
+    /// so few huge messages do not hold unexpected amount of memory.
+    pub fn with_max_buffer_bytes(mut self, max_buffer_bytes: usize) -> Self {
+        self.max_buffer_bytes = Some(max_buffer_bytes);
+        self
+    }
+
+    /// Write records of modules starting with `prefix` to own rotated files instead of `output`,
+    /// e.g. `["DB"]` to `db.log`. Record goes to every route it matches.
+    #[cfg(feature = "file")]
+    pub fn with_route(mut self, prefix: &[String], file: FileSettings) -> Self {
+        self.routes.push((prefix.to_vec(), file));
+        self
+    }
+
+    /// Keep messages sent while `max_in_memory` of them wait for worker, e.g. for slow sink,
+    /// in spill file in `path` instead of memory, worker reads them back in order as it catches up.
+    /// Records output or sink failed to write, e.g. during network outage, are kept the same way,
+    /// up to `max_in_memory` of each channel in memory, and written before new ones once it recovers.
+    /// Without spill such records are dropped.
+    /// Spill files left in `path` by other runs, e.g. crashed ones, are written to `output`
+    /// on next start, before new records.
+    pub fn with_spill(mut self, path: PathBuf, max_in_memory: usize) -> Self {
+        self.spill = Some((path, max_in_memory));
+        self
+    }
+
+    /// Append records output or sink failed to write `max_attempts` times in a row to `path`,
+    /// as plain lines, instead of dropping them. Until then they are written again with next batches.
+    /// Not used for channels with `with_spill`, they keep records until sink recovers.
+    /// Lines left in `path` are written to `output` on next start, before new records.
+    pub fn with_dead_letter(mut self, path: PathBuf, max_attempts: usize) -> Self {
+        self.dead_letter = Some((path, max_attempts));
+        self
+    }
+
+    /// Recycle written messages through `pool`, `Logger::log` takes them from it.
+    pub fn with_message_pool(mut self, pool: MessagePool) -> Self {
+        self.message_pool = Some(pool);
+        self
+    }
+
+    /// Same as `with_sink`, with own buffer size and flush interval.
+    pub fn with_sink_policy(mut self, sink: impl Sink + 'static, policy: FlushPolicy) -> Self {
+        self.sinks.push((SharedSink::new(sink), Some(policy)));
+        self
+    }
+
+    /// Write partially filled buffer once `flush_interval` passed since last write, so records
+    /// of quiet app do not wait for `buffer_size` others. Applies to output, routes and sinks
+    /// without own `FlushPolicy`. Worker wakes up by itself to check it.
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use rotation_logger::Settings;
+    /// let settings = Settings::default().with_flush_interval(Duration::from_millis(500));
+    /// ```
+    pub fn with_flush_interval(mut self, flush_interval: Duration) -> Self {
+        self.flush_interval = Some(flush_interval);
+        self
+    }
+
+    /// Buffer size and flush interval of `output`, tee outputs take own one with
+    /// `with_tee_output_policy`. By default console is unbuffered and other outputs
+    /// use `buffer_size`. For `OutputChannel::DevDuo` applies to its files.
+    pub fn with_output_policy(mut self, policy: FlushPolicy) -> Self {
+        self.output_policy = Some(policy);
+        self
+    }
+
+    /// Also accept messages from other processes on `address`,
+    /// they use `OutputChannel::Aggregator` and are written to `output` with `source` field.
+    pub fn with_aggregator(mut self, address: AggregatorAddress) -> Self {
+        self.aggregator = Some(address);
+        self
+    }
+
+    /// Limits of `with_aggregator` instead of default ones, see `AggregatorLimits`.
+    pub fn with_aggregator_limits(mut self, limits: AggregatorLimits) -> Self {
+        self.aggregator_limits = limits;
+        self
+    }
+
+    /// Write `banner` to every output when worker starts, see `Banner`.
+    pub fn with_banner(mut self, banner: Banner) -> Self {
+        self.banner = Some(banner);
+        self
+    }
+
+    /// Write warning to every output when worker falls behind, see `BacklogWarning`.
+    pub fn with_backlog_warning(mut self, warning: BacklogWarning) -> Self {
+        self.backlog_warning = Some(warning);
+        self
+    }
+
+    /// Keep at most `capacity` messages waiting for worker, so bursts of logging can't take
+    /// all memory. Messages past it wait or are dropped as `policy` tells, see `OverflowPolicy`.
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// # use rotation_logger::{OverflowPolicy, Settings};
+    /// let settings = Settings::default().with_queue_capacity(100_000, OverflowPolicy::DropOldest);
+    /// ```
+    pub fn with_queue_capacity(mut self, capacity: usize, policy: OverflowPolicy) -> Self {
+        self.queue_capacity = Some((capacity.max(1), policy));
+        self
+    }
+
+    /// Start worker on thread set up by `worker`, see `WorkerThread`.
+    pub fn with_worker_thread(mut self, worker: WorkerThread) -> Self {
+        self.worker_thread = worker;
+        self
+    }
+
+    /// Start worker thread and open files on first message, e.g. for tools which rarely log.
+    /// `Logger::run_async` returns no handle then.
+    pub fn with_lazy_start(mut self, lazy_start: bool) -> Self {
+        self.lazy_start = lazy_start;
+        self
+    }
+
+    /// Drop messages more verbose than `min_level`.
+    pub fn with_min_level(mut self, min_level: Level) -> Self {
+        self.levels.min_level = min_level;
+        self
+    }
+
+    /// Level of module `path`, e.g. `app::db`, and its children, instead of `min_level`.
+    /// The longest matching path wins, so `app::db` overrides `app`.
+    pub fn with_module_level(mut self, path: &str, level: Level) -> Self {
+        self.levels.module_levels.push((module_path(path), level));
+        self
+    }
+
+    /// Level of every message during daily window, see `LevelWindow`.
+    /// The first window containing current time is used.
+    #[cfg(feature = "timestamps")]
+    pub fn with_level_window(mut self, window: LevelWindow) -> Self {
+        self.levels.windows.push(window);
+        self
+    }
+
+    /// Write only messages of module `path` and its children, along with other allowed ones.
+    pub fn with_allowed_module(mut self, path: &str) -> Self {
+        self.levels.allowed.push(module_path(path));
+        self
+    }
+
+    /// Drop messages of module `path` and its children, even if they are allowed.
+    pub fn with_denied_module(mut self, path: &str) -> Self {
+        self.levels.denied.push(module_path(path));
+        self
+    }
+
+    /// Keep or drop messages by text, see `TextFilter`.
+    #[cfg(feature = "regex")]
+    pub fn with_text_filter(mut self, filter: TextFilter) -> Self {
+        self.text_filters.push(filter);
+        self
+    }
+
+    /// Paint console lines with `theme`.
+    pub fn with_color_theme(mut self, theme: ColorTheme) -> Self {
+        self.color_theme = Some(theme);
+        self
+    }
+
+    /// Override detection of color support, by default theme is used only on terminal
+    /// and without `NO_COLOR` environment variable.
+    pub fn with_color_choice(mut self, color_choice: ColorChoice) -> Self {
+        self.color_choice = color_choice;
+        self
+    }
+
+    /// Pass console lines to `writer` instead of printing them,
+    /// so they can cooperate with progress bars or other terminal UI.
+    pub fn with_console_writer(mut self, writer: impl ConsoleWriter + 'static) -> Self {
+        self.console_writer = Some(SharedConsoleWriter::new(writer));
+        self
+    }
+
+    /// Console columns grow to the longest value seen so far, up to `max_width`,
+    /// instead of widths of format string. Masks of width `0` are left as is.
+    /// Lines written to other outputs keep widths of format string.
+    pub fn with_auto_widths(mut self, max_width: usize) -> Self {
+        self.auto_widths = Some(max_width);
+        self
+    }
+
+    /// Print console lines as table of `grid` columns, with header row, see `Grid`.
+    #[cfg(feature = "formatter")]
+    pub fn with_console_grid(mut self, grid: Grid) -> Self {
+        self.console_grid = Some(grid);
+        self
+    }
+
+    /// Render lines with `format` instead of `MessageFormatter`, see `Format`.
+    /// Console colors, column widths and grid need masks, so console prints lines as is then.
+    pub fn with_custom_format(mut self, format: Box<dyn Format>) -> Self {
+        self.custom_format = Some(SharedFormat::new(format));
+        self
+    }
+
+    /// Write every line to `output` too, along with output given on creation,
+    /// e.g. console during development and files at once. Called again, adds one more output.
+    /// Flush policy, JSON output and routes apply to every output.
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// # use rotation_logger::{FileSize, MessageFormatter, OutputChannel, Settings};
+    /// let file = OutputChannel::file(
+    ///     "./logs".into(), 10, FileSize::from_megabytes(5), "app".into(), "log".into(),
+    /// );
+    /// let settings = Settings::new(true, 5, OutputChannel::Console, MessageFormatter::default())
+    ///     .with_tee_output(file);
+    /// assert_eq!(settings.outputs().count(), 2);
+    /// ```
+    pub fn with_tee_output(mut self, output: OutputChannel) -> Self {
+        self.tee_outputs.push((output, None));
+        self
+    }
+
+    /// Same as `with_tee_output`, with own buffer size and flush interval,
+    /// e.g. unbuffered console next to batched file.
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use rotation_logger::{FlushPolicy, OutputChannel, Settings};
+    /// let settings = Settings::default()
+    ///     .with_output_policy(FlushPolicy::new(512).with_flush_interval(Duration::from_secs(1)))
+    ///     .with_tee_output_policy(OutputChannel::Console, FlushPolicy::unbuffered());
+    /// assert_eq!(settings.outputs().count(), 2);
+    /// ```
+    pub fn with_tee_output_policy(mut self, output: OutputChannel, policy: FlushPolicy) -> Self {
+        self.tee_outputs.push((output, Some(policy)));
+        self
+    }
+
+    /// Console and files of output and routes write one JSON object per record instead of
+    /// formatted line, see `Record::to_json`, e.g. for ELK ingesting logs without parsing.
+    /// Files use layout of `FileSettings::with_json_layout`, console the default one.
+    /// Additional sinks are not changed.
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// # use rotation_logger::Settings;
+    /// let settings = Settings::default().with_json_output(true);
+    /// // {"schema":3,"timestamp":"...","level":"INFO","modules":["db"],"message":"up","fields":{}}
+    /// ```
+    pub fn with_json_output(mut self, json_output: bool) -> Self {
+        self.json_output = json_output;
+        self
+    }
+
     pub fn format_message(&self, message: &Message) -> String {
-        self.formatter.format(message)
+        match &self.custom_format {
+            Some(format) => format.line(message),
+            None => self.formatter.format(message),
+        }
+    }
+
+    #[cfg(feature = "timestamps")]
+    pub fn format_message_at(&self, message: &Message, timestamp: &DateTime<Local>) -> String {
+        match &self.custom_format {
+            Some(format) => format.line(message),
+            None => self.formatter.format_at(message, timestamp),
+        }
+    }
+
+    pub fn format_message_at_system_time(&self, message: &Message, time: SystemTime) -> String {
+        match &self.custom_format {
+            Some(format) => format.line(message),
+            None => self.formatter.format_at_system_time(message, time),
+        }
+    }
+
+    /// Problems of settings and directories they use, found without writing logs,
+    /// e.g. to fail deploy instead of losing logs later.
+    pub fn validate(&self) -> ValidationReport {
+        validation::validate(self)
+    }
+
+    /// Same as `validate`, also writes every file output in temporary directory
+    /// and rotates it once, with pipeline and writer of output.
+    #[cfg(all(feature = "file", not(target_arch = "wasm32")))]
+    pub fn dry_run(&self) -> ValidationReport {
+        validation::dry_run(self)
     }
 
     pub fn buffer_size(&self) -> usize {
         self.buffer_size
     }
 
+    pub fn flush_interval(&self) -> Option<Duration> {
+        self.flush_interval
+    }
+
+    pub fn max_buffer_bytes(&self) -> Option<usize> {
+        self.max_buffer_bytes
+    }
+
+    #[cfg(feature = "file")]
+    pub fn routes(&self) -> &Vec<(Vec<String>, FileSettings)> {
+        &self.routes
+    }
+
+    pub fn spill(&self) -> Option<(&PathBuf, usize)> {
+        self.spill
+            .as_ref()
+            .map(|(path, max_in_memory)| (path, *max_in_memory))
+    }
+
+    pub fn dead_letter(&self) -> Option<(&PathBuf, usize)> {
+        self.dead_letter
+            .as_ref()
+            .map(|(path, max_attempts)| (path, *max_attempts))
+    }
+
+    pub fn message_pool(&self) -> Option<&MessagePool> {
+        self.message_pool.as_ref()
+    }
+
     pub fn is_enabled(&self) -> bool {
         self.is_enabled
     }
@@ -92,6 +560,175 @@ impl Settings {
     pub fn output(&self) -> &OutputChannel {
         &self.output
     }
+
+    pub fn tee_outputs(&self) -> impl Iterator<Item = &OutputChannel> {
+        self.tee_outputs.iter().map(|(output, _)| output)
+    }
+
+    /// Output given on creation followed by tee outputs.
+    pub fn outputs(&self) -> impl Iterator<Item = &OutputChannel> {
+        std::iter::once(&self.output).chain(self.tee_outputs())
+    }
+
+    /// Same as `outputs`, with flush policy of each if set.
+    pub(crate) fn output_policies(
+        &self,
+    ) -> impl Iterator<Item = (&OutputChannel, Option<&FlushPolicy>)> {
+        let tee_outputs = self.tee_outputs.iter();
+        std::iter::once((&self.output, self.output_policy.as_ref()))
+            .chain(tee_outputs.map(|(output, policy)| (output, policy.as_ref())))
+    }
+
+    pub(crate) fn sinks(&self) -> &Vec<(SharedSink, Option<FlushPolicy>)> {
+        &self.sinks
+    }
+
+    /// Flush policy of `output`, not of tee outputs.
+    pub fn output_policy(&self) -> Option<&FlushPolicy> {
+        self.output_policy.as_ref()
+    }
+
+    pub fn aggregator(&self) -> Option<&AggregatorAddress> {
+        self.aggregator.as_ref()
+    }
+
+    pub fn aggregator_limits(&self) -> &AggregatorLimits {
+        &self.aggregator_limits
+    }
+
+    pub fn banner(&self) -> Option<&Banner> {
+        self.banner.as_ref()
+    }
+
+    pub fn queue_capacity(&self) -> Option<(usize, OverflowPolicy)> {
+        self.queue_capacity
+    }
+
+    pub fn backlog_warning(&self) -> Option<&BacklogWarning> {
+        self.backlog_warning.as_ref()
+    }
+
+    /// Capacity backlog warning is checked against, the one of queue first.
+    pub(crate) fn backlog_capacity(&self) -> Option<u64> {
+        match self.queue_capacity {
+            Some((capacity, _)) => Some(capacity as u64),
+            None => self.backlog_warning.as_ref()?.capacity(),
+        }
+    }
+
+    pub fn worker_thread(&self) -> &WorkerThread {
+        &self.worker_thread
+    }
+
+    pub fn lazy_start(&self) -> bool {
+        self.lazy_start
+    }
+
+    pub fn json_output(&self) -> bool {
+        self.json_output
+    }
+
+    pub fn color_theme(&self) -> Option<&ColorTheme> {
+        self.color_theme.as_ref()
+    }
+
+    pub fn color_choice(&self) -> ColorChoice {
+        self.color_choice
+    }
+
+    /// Theme console output is painted with, after `color_choice` is applied.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn console_theme(&self) -> Option<ColorTheme> {
+        match self.color_choice {
+            ColorChoice::Always => Some(self.color_theme.clone().unwrap_or_default()),
+            ColorChoice::Auto => self
+                .color_theme
+                .clone()
+                .or_else(|| self.is_dev_duo().then(ColorTheme::default))
+                .filter(|_| {
+                    auto_color(
+                        std::env::var_os("NO_COLOR"),
+                        std::io::stdout().is_terminal(),
+                    )
+                }),
+            ColorChoice::Never => None,
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn console_writer(&self) -> Option<&SharedConsoleWriter> {
+        self.console_writer.as_ref()
+    }
+
+    pub fn auto_widths(&self) -> Option<usize> {
+        self.auto_widths
+    }
+
+    #[cfg(feature = "formatter")]
+    pub fn console_grid(&self) -> Option<&Grid> {
+        self.console_grid.as_ref()
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn is_dev_duo(&self) -> bool {
+        #[cfg(all(feature = "file", feature = "formatter"))]
+        return matches!(self.output, OutputChannel::DevDuo(_));
+        #[cfg(not(all(feature = "file", feature = "formatter")))]
+        false
+    }
+
+    pub fn formatter(&self) -> &MessageFormatter {
+        &self.formatter
+    }
+
+    pub fn has_custom_format(&self) -> bool {
+        self.custom_format.is_some()
+    }
+
+    /// Format of lines shown in manifest of file set, `custom` for user format.
+    #[cfg(all(feature = "file", not(target_arch = "wasm32")))]
+    pub(crate) fn line_format(&self) -> String {
+        match self.custom_format {
+            Some(_) => "custom".into(),
+            None => self.formatter.format_string(),
+        }
+    }
+
+    pub fn min_level(&self) -> Level {
+        self.levels.min_level
+    }
+
+    pub fn is_level_enabled(&self, level: Level) -> bool {
+        level <= self.levels.min_level
+    }
+
+    /// Least important level written for `modules`, see `with_module_level`.
+    pub fn module_level(&self, modules: &[String]) -> Level {
+        self.levels.module_level(modules)
+    }
+
+    pub fn is_enabled_for(&self, level: Level, modules: &[String]) -> bool {
+        self.levels.is_enabled_for(level, modules)
+    }
+
+    /// Same as `is_enabled_for`, with level of window containing `time` if any.
+    #[cfg_attr(not(feature = "timestamps"), allow(unused_variables))]
+    pub fn is_enabled_at(&self, level: Level, modules: &[String], time: SystemTime) -> bool {
+        #[cfg(feature = "timestamps")]
+        if let Some(window) = self.levels.window_at(time) {
+            return level <= window.level() && self.levels.is_listed(modules);
+        }
+        level <= self.levels.module_level(modules) && self.levels.is_listed(modules)
+    }
+
+    #[cfg(feature = "regex")]
+    pub fn text_filters(&self) -> &Vec<TextFilter> {
+        &self.text_filters
+    }
+
+    pub(crate) fn levels(&self) -> &LevelFilter {
+        &self.levels
+    }
 }
 
 impl Default for Settings {
@@ -99,289 +736,258 @@ impl Default for Settings {
         Self {
             is_enabled: true,
             output: Default::default(),
+            tee_outputs: vec![],
             formatter: Default::default(),
+            custom_format: None,
+            json_output: false,
             buffer_size: 2048,
+            flush_interval: None,
+            max_buffer_bytes: None,
+            sinks: vec![],
+            #[cfg(feature = "file")]
+            routes: vec![],
+            spill: None,
+            dead_letter: None,
+            message_pool: None,
+            output_policy: None,
+            aggregator: None,
+            aggregator_limits: Default::default(),
+            banner: None,
+            queue_capacity: None,
+            backlog_warning: None,
+            worker_thread: Default::default(),
+            lazy_start: false,
+            levels: Default::default(),
+            #[cfg(feature = "regex")]
+            text_filters: vec![],
+            color_theme: None,
+            color_choice: Default::default(),
+            console_writer: None,
+            auto_widths: None,
+            #[cfg(feature = "formatter")]
+            console_grid: None,
         }
     }
 }
 
-/// File Size wrapper for easier declaration
-/// Store bits size.
-/// Inner data stored as Bits value.
+/// Levels and module lists of `Settings`, shared with senders to drop messages before they are built.
 #[derive(Debug, Clone)]
-pub struct FileSize {
-    size: usize,
+pub(crate) struct LevelFilter {
+    min_level: Level,
+    /// Levels of modules and their children, instead of `min_level`.
+    module_levels: Vec<(Vec<String>, Level)>,
+    /// Module prefixes written, any when empty.
+    allowed: Vec<Vec<String>>,
+    /// Module prefixes dropped.
+    denied: Vec<Vec<String>>,
+    /// Levels of daily windows, instead of other levels.
+    #[cfg(feature = "timestamps")]
+    windows: Vec<LevelWindow>,
 }
 
-impl FileSize {
-    pub fn from_bytes(bytes: usize) -> Self {
-        Self { size: bytes * 8 }
+impl LevelFilter {
+    pub(crate) fn module_level(&self, modules: &[impl AsRef<str>]) -> Level {
+        self.module_levels
+            .iter()
+            .filter(|(path, _)| starts_with(modules, path))
+            .max_by_key(|(path, _)| path.len())
+            .map_or(self.min_level, |(_, level)| *level)
     }
 
-    pub fn from_kilobytes(kilobytes: usize) -> Self {
-        Self {
-            size: kilobytes * 8 * 1000,
-        }
+    /// The most verbose level written for any module or window, e.g. limit of `log` macros.
+    #[cfg(feature = "log")]
+    pub(crate) fn max_level(&self) -> Level {
+        let levels = self.module_levels.iter().map(|(_, level)| *level);
+        #[cfg(feature = "timestamps")]
+        let levels = levels.chain(self.windows.iter().map(LevelWindow::level));
+        levels.fold(self.min_level, Level::max)
     }
-    pub fn from_megabytes(megabytes: usize) -> Self {
-        Self {
-            size: megabytes * 8 * 1000 * 1000,
+
+    /// Senders don't know time of worker, so let through levels of every window too.
+    pub(crate) fn is_enabled_for(&self, level: Level, modules: &[impl AsRef<str>]) -> bool {
+        #[cfg(feature = "timestamps")]
+        if self.windows.iter().any(|window| level <= window.level()) {
+            return self.is_listed(modules);
         }
+        level <= self.module_level(modules) && self.is_listed(modules)
     }
-    pub fn from_gigabytes(gigabytes: usize) -> Self {
-        Self {
-            size: gigabytes * 8 * 1000 * 1000 * 1000,
+
+    /// Is module allowed and not denied.
+    pub(crate) fn is_listed(&self, modules: &[impl AsRef<str>]) -> bool {
+        (self.allowed.is_empty() || self.allowed.iter().any(|path| starts_with(modules, path)))
+            && !self.denied.iter().any(|path| starts_with(modules, path))
+    }
+
+    #[cfg(feature = "timestamps")]
+    pub(crate) fn window_at(&self, time: SystemTime) -> Option<&LevelWindow> {
+        if self.windows.is_empty() {
+            return None;
         }
+        let time = DateTime::<Local>::from(time).time();
+        self.windows.iter().find(|window| window.contains(time))
     }
 }
 
-impl Default for FileSize {
-    fn default() -> Self {
-        Self::from_megabytes(2)
-    }
+/// `ColorChoice::Auto` paints terminal, unless `NO_COLOR` is set to anything but empty string.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn auto_color(no_color: Option<std::ffi::OsString>, is_terminal: bool) -> bool {
+    is_terminal && no_color.is_none_or(|value| value.is_empty())
 }
 
-impl PartialEq<u64> for FileSize {
-    fn eq(&self, other: &u64) -> bool {
-        *other == self.size as u64
-    }
+/// `modules` are `path` or its children, e.g. `app, db, pool` of `app, db`.
+fn starts_with(modules: &[impl AsRef<str>], path: &[String]) -> bool {
+    modules.len() >= path.len()
+        && modules
+            .iter()
+            .zip(path)
+            .all(|(module, part)| module.as_ref() == part)
 }
 
-/// Formatted for Log Message.
-#[derive(Debug, Clone)]
-pub struct MessageFormatter {
-    /// Timestamp format.
-    /// Support Chrono timestamp formats.
-    timestamp: String,
-    /// List of parsed Mask with set format values.
-    _masks: Vec<FormatMask>,
-    /// SPlitter symbols
-    splitter: String,
+/// Modules of `app::db` like path.
+pub(crate) fn module_path(path: &str) -> Vec<String> {
+    path.split("::")
+        .filter(|module| !module.is_empty())
+        .map(String::from)
+        .collect()
 }
 
-impl Default for MessageFormatter {
+impl Default for LevelFilter {
     fn default() -> Self {
-        let format = "{timestamp} {splitter} {modules} {splitter} {message}";
         Self {
-            timestamp: "%Y-%m-%d %H:%M:%S.%f".to_string(),
-            splitter: "::".into(),
-            _masks: Self::_set_masks(format),
+            min_level: Level::Trace,
+            module_levels: vec![],
+            allowed: vec![],
+            denied: vec![],
+            #[cfg(feature = "timestamps")]
+            windows: vec![],
         }
     }
 }
 
-impl MessageFormatter {
-    pub fn new(splitter: &str, format: &str, timestamp: &str) -> Self {
+/// When records buffered for single output or sink are written.
+/// Records are written once `buffer_size` of them are collected,
+/// or, with flush interval, when the oldest written batch is older than interval.
+///
+/// # Example:
+///
+/// ```
+/// # use std::time::Duration;
+/// # use rotation_logger::{FlushPolicy, Settings};
+/// let settings = Settings::default()
+///     .with_output_policy(FlushPolicy::new(512).with_flush_interval(Duration::from_secs(1)));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlushPolicy {
+    buffer_size: usize,
+    flush_interval: Option<Duration>,
+}
+
+impl FlushPolicy {
+    pub fn new(buffer_size: usize) -> Self {
         Self {
-            timestamp: timestamp.into(),
-            splitter: splitter.into(),
-            _masks: Self::_set_masks(format),
+            buffer_size: buffer_size.max(1),
+            flush_interval: None,
         }
     }
 
-    /// Process input message with rules.
-    pub fn format(&self, message: &Message) -> String {
-        let mut result = "".to_string();
-
-        let timestamp = if !self.timestamp.is_empty() {
-            let timestamp = Local::now().format(&self.timestamp).to_string();
-            // let timestamp = timestamp[0..timestamp.len() - self.timestamp.limiter()].to_string();
-            timestamp
-        } else {
-            "".to_string()
-        };
-
-        for mask in &self._masks {
-            match &mask.mask_type {
-                MaskType::Raw(value) => result = format!("{result}{value}"),
-                MaskType::Timestamp => {
-                    let timestamp = self._format_by_length(&timestamp, &mask.length);
-                    let timestamp =
-                        self._format_by_width_align(&timestamp, &mask.width, &mask.align);
-                    result = format!("{result}{timestamp}");
-                }
-                MaskType::Message => {
-                    let message = self._format_by_length(&message.text(), &mask.length);
-                    let message = self._format_by_width_align(&message, &mask.width, &mask.align);
-                    result = format!("{result}{message}");
-                }
-                MaskType::Splitter => {
-                    result = format!("{result}{}", self.splitter);
-                }
-                MaskType::Modules => {
-                    let modules = message
-                        .modules()
-                        .join(format!("{}", self.splitter).as_str());
-
-                    let modules = self._format_by_length(&modules, &mask.length);
-                    let modules = self._format_by_width_align(&modules, &mask.width, &mask.align);
-                    result = format!("{result}{modules}");
-                }
-            }
-        }
-        result
+    /// Write every record at once.
+    pub fn unbuffered() -> Self {
+        Self::new(1)
     }
 
-    fn _format_by_length(&self, value: &str, length: &i32) -> String {
-        if *length > 0 {
-            value[0..min(*length as usize, value.len())].to_string()
-        } else {
-            value[0..value.len() - min(length.unsigned_abs() as usize, value.len())].to_string()
-        }
+    /// Also write partially filled buffer when `flush_interval` passed since last write.
+    pub fn with_flush_interval(mut self, flush_interval: Duration) -> Self {
+        self.flush_interval = Some(flush_interval);
+        self
     }
 
-    fn _format_by_width_align(&self, value: &str, width: &usize, align: &TextAlign) -> String {
-        if value.len() >= *width {
-            return value[0..*width].to_string();
-        };
-
-        let free_space = width - value.len();
-        let (left_space, right_space) = match align {
-            TextAlign::Left => ("".to_string(), " ".repeat(free_space)),
-            TextAlign::Center => {
-                let half = (free_space / 2) as usize;
-                (" ".repeat(half), " ".repeat(free_space - half))
-            }
-            TextAlign::Right => (" ".repeat(free_space), "".to_string()),
-        };
-        format!("{left_space}{value}{right_space}")
-    }
-
-    fn _set_masks(format: &str) -> Vec<FormatMask> {
-        let mut result = vec![];
-        let format = format.to_string();
-        let mut format = format.as_str();
-        if !format.contains("{") || !format.contains("}") {
-            panic!("Format String wrong syntax: {format}")
-        }
-        while !format.is_empty() {
-            let opening_delimiter = format.find("{");
-            if let None = opening_delimiter {
-                result.push(FormatMask::from(format));
-                return result;
-            }
-            let opening_delimiter = opening_delimiter.unwrap();
-            if format[0..opening_delimiter].to_string() != "" {
-                result.push(FormatMask::from(&format[0..opening_delimiter]));
-            }
-
-            let close_delimiter = format.find("}");
-            if let None = close_delimiter {
-                result.push(FormatMask::from(format));
-                return result;
-            }
-            let close_delimiter = close_delimiter.unwrap();
-            let scoped_value = &format[opening_delimiter + 1..close_delimiter];
-            result.push(FormatMask::from(scoped_value));
-            format = &format[close_delimiter + 1..format.len()];
-        }
-        result
+    pub fn buffer_size(&self) -> usize {
+        self.buffer_size
+    }
+
+    pub fn flush_interval(&self) -> Option<Duration> {
+        self.flush_interval
     }
 }
 
-/// Format Mask with rules.
+/// File Size wrapper for easier declaration
+/// Store bits size.
+/// Inner data stored as Bits value.
+#[cfg(feature = "file")]
 #[derive(Debug, Clone)]
-struct FormatMask {
-    mask_type: MaskType,
-    length: i32,
-    width: usize,
-    align: TextAlign,
+pub struct FileSize {
+    size: usize,
 }
 
-impl From<&str> for FormatMask {
-    fn from(value: &str) -> Self {
-        let splitted_data: Vec<&str> = value.split(":").collect();
-        if splitted_data.len() > 4 {
-            panic!("Wrong Mask format: {value}")
-        }
-
-        let default_width = 30;
-        let default_length = 30;
-        let mask_type = splitted_data[0];
-        let length = splitted_data
-            .get(1)
-            .unwrap_or(&default_length.to_string().as_str())
-            .parse::<i32>()
-            .unwrap_or(default_length);
-        let width = splitted_data
-            .get(2)
-            .unwrap_or(&default_width.to_string().as_str())
-            .parse::<usize>()
-            .unwrap_or(default_width as usize);
-        let align = *splitted_data.get(3).unwrap_or(&"center");
+#[cfg(feature = "file")]
+impl FileSize {
+    pub fn from_bytes(bytes: usize) -> Self {
+        Self { size: bytes * 8 }
+    }
 
+    pub fn from_kilobytes(kilobytes: usize) -> Self {
         Self {
-            mask_type: MaskType::from(mask_type),
-            length,
-            width,
-            align: TextAlign::from(align),
+            size: kilobytes * 8 * 1000,
         }
     }
-}
-
-/// Type of Format Masks
-#[derive(Debug, Clone)]
-enum MaskType {
-    Raw(String),
-    Timestamp,
-    Message,
-    Splitter,
-    Modules,
-}
-
-impl From<&str> for MaskType {
-    fn from(value: &str) -> Self {
-        if value.to_lowercase() == "timestamp" {
-            Self::Timestamp
-        } else if value.to_lowercase() == "splitter" {
-            Self::Splitter
-        } else if value.to_lowercase() == "modules" {
-            Self::Modules
-        } else if value.to_lowercase() == "message" {
-            Self::Message
-        } else {
-            Self::Raw(value.to_string())
+    pub fn from_megabytes(megabytes: usize) -> Self {
+        Self {
+            size: megabytes * 8 * 1000 * 1000,
+        }
+    }
+    pub fn from_gigabytes(gigabytes: usize) -> Self {
+        Self {
+            size: gigabytes * 8 * 1000 * 1000 * 1000,
         }
     }
 }
 
-/// Text horizontal align.
-#[derive(Debug, Clone)]
-enum TextAlign {
-    Left,
-    Center,
-    Right,
+#[cfg(feature = "file")]
+impl Default for FileSize {
+    fn default() -> Self {
+        Self::from_megabytes(2)
+    }
 }
 
-impl From<&str> for TextAlign {
-    fn from(value: &str) -> Self {
-        if value.to_lowercase() == "left" {
-            Self::Left
-        } else if value.to_lowercase() == "right" {
-            Self::Right
-        } else if value.to_lowercase() == "center" {
-            Self::Center
-        } else {
-            Self::Center
-        }
+#[cfg(feature = "file")]
+impl PartialEq<u64> for FileSize {
+    fn eq(&self, other: &u64) -> bool {
+        *other == self.size as u64
     }
 }
 
 /// Output Types for Logger.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub enum OutputChannel {
     /// Store to files.
+    #[cfg(feature = "file")]
     File(FileSettings),
     /// Output to stdout.
+    #[default]
     Console,
     /// If dev mode -> stdout, If release -> file
+    #[cfg(feature = "file")]
     Auto(FileSettings),
-}
-
-impl Default for OutputChannel {
-    fn default() -> Self {
-        Self::Console
-    }
+    /// Human-friendly multi-line stdout and JSON lines in files, both at once.
+    /// Console is painted with default theme unless other is set or color is turned off.
+    #[cfg(all(feature = "file", feature = "formatter"))]
+    DevDuo(FileSettings),
+    /// Stream lines to local collector through Unix domain socket.
+    #[cfg(unix)]
+    UnixSocket(PathBuf),
+    /// Write lines to pre-created named pipe.
+    #[cfg(unix)]
+    Fifo(PathBuf),
+    /// Pass messages to shared memory ring of `ShmConsumer` process, which owns log files.
+    #[cfg(feature = "shm")]
+    SharedMemory(PathBuf),
+    /// Send messages to aggregator `Logger` of other process.
+    Aggregator(AggregatorAddress),
+    /// Browser console on `wasm32`, by level: `console.error`, `console.warn` or `console.log`.
+    /// Stdout on other targets.
+    WebConsole,
+    /// User sink as main output, e.g. database, socket or test buffer, see `OutputChannel::custom`.
+    Custom(SharedSink),
 }
 
 impl OutputChannel {
@@ -391,6 +997,68 @@ impl OutputChannel {
     pub fn auto() -> Self {
         Self::Console
     }
+    #[cfg(unix)]
+    pub fn unix_socket(path: PathBuf) -> Self {
+        Self::UnixSocket(path)
+    }
+    #[cfg(unix)]
+    pub fn fifo(path: PathBuf) -> Self {
+        Self::Fifo(path)
+    }
+    #[cfg(feature = "shm")]
+    pub fn shared_memory(path: PathBuf) -> Self {
+        Self::SharedMemory(path)
+    }
+    /// Write records to own `sink` instead of built-in output. Unlike `Settings::with_sink`,
+    /// sink gets flush policy of output and only records of modules not routed elsewhere.
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// # use rotation_logger::{MessageFormatter, OutputChannel, Record, Settings, Sink};
+    /// struct Lines(Vec<String>);
+    ///
+    /// impl Sink for Lines {
+    ///     fn write_batch(&mut self, records: &[Record]) -> std::io::Result<()> {
+    ///         self.0.extend(records.iter().map(|record| record.line().to_string()));
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// let output = OutputChannel::custom(Lines(vec![]));
+    /// let settings = Settings::new(true, 5, output, MessageFormatter::default());
+    /// ```
+    pub fn custom(sink: impl Sink + 'static) -> Self {
+        Self::Custom(SharedSink::new(sink))
+    }
+    pub fn aggregator(address: AggregatorAddress) -> Self {
+        Self::Aggregator(address)
+    }
+    pub fn web_console() -> Self {
+        Self::WebConsole
+    }
+    #[cfg(all(feature = "file", feature = "formatter"))]
+    pub fn dev_duo(file_settings: FileSettings) -> Self {
+        Self::DevDuo(file_settings)
+    }
+    /// Rotated files in `logs` directory of Android application private files dir
+    /// (`/data/data/<package>/files`, same as `Context.getFilesDir()`).
+    #[cfg(feature = "android")]
+    pub fn android_files(
+        package: &str,
+        capacity: usize,
+        file_size: FileSize,
+        filename: String,
+    ) -> Self {
+        Self::file(
+            PathBuf::from(format!("/data/data/{package}/files/logs")),
+            capacity,
+            file_size,
+            filename,
+            "log".into(),
+        )
+    }
+    #[cfg(feature = "file")]
     pub fn file(
         path: PathBuf,
         capacity: usize,
@@ -407,16 +1075,29 @@ impl OutputChannel {
         ))
     }
 
+    #[cfg(feature = "file")]
     pub fn settings(&self) -> Option<&FileSettings> {
         match &self {
             OutputChannel::File(file_output) => Some(file_output),
             OutputChannel::Console => None,
             OutputChannel::Auto(file_output) => Some(file_output),
+            #[cfg(feature = "formatter")]
+            OutputChannel::DevDuo(file_output) => Some(file_output),
+            #[cfg(unix)]
+            OutputChannel::UnixSocket(_) => None,
+            #[cfg(unix)]
+            OutputChannel::Fifo(_) => None,
+            #[cfg(feature = "shm")]
+            OutputChannel::SharedMemory(_) => None,
+            OutputChannel::Aggregator(_) => None,
+            OutputChannel::WebConsole => None,
+            OutputChannel::Custom(_) => None,
         }
     }
 }
 
 /// Settings for logs files and rotation.
+#[cfg(feature = "file")]
 #[derive(Debug, Clone)]
 pub struct FileSettings {
     path: PathBuf,
@@ -424,8 +1105,29 @@ pub struct FileSettings {
     file_size: FileSize,
     filename: String,
     file_extension: String,
+    /// Stages applied to every rotated file.
+    pipeline: RotationPipeline,
+    /// How active file is written.
+    writer: FileWriter,
+    /// Reserve `file_size` on disk for every new active file.
+    preallocation: bool,
+    /// What to do when other process writes same log files.
+    lock_policy: LockPolicy,
+    /// Start every new file with `FileHeader`.
+    schema_header: bool,
+    /// Keep `<filename>.manifest.json` next to log files.
+    manifest: bool,
+    /// Start every new file after rotation with record describing rotated file.
+    rotation_records: bool,
+    /// Layout of JSON records, when files are written as JSON.
+    json_layout: JsonLayout,
+    /// Terminator of lines.
+    line_ending: LineEnding,
+    /// Encoding of text.
+    encoding: Encoding,
 }
 
+#[cfg(feature = "file")]
 impl FileSettings {
     pub fn new(
         path: PathBuf,
@@ -440,8 +1142,138 @@ impl FileSettings {
             file_size,
             filename,
             file_extension,
+            pipeline: Default::default(),
+            writer: Default::default(),
+            preallocation: false,
+            lock_policy: Default::default(),
+            schema_header: false,
+            manifest: false,
+            rotation_records: false,
+            json_layout: Default::default(),
+            line_ending: Default::default(),
+            encoding: Default::default(),
+        }
+    }
+
+    /// File settings step by step, what is not set stays as in `FileSettings::default`.
+    ///
+    /// # Example:
+    ///
+    /// ```
+    /// # use rotation_logger::{FileSettings, FileSize, Settings};
+    /// let file = FileSettings::builder()
+    ///     .path("./logs")
+    ///     .capacity(5)
+    ///     .file_size(FileSize::from_megabytes(1))
+    ///     .filename("app")
+    ///     .build();
+    /// let settings = Settings::builder().file_settings(file).build();
+    /// ```
+    pub fn builder() -> FileSettingsBuilder {
+        FileSettingsBuilder::default()
+    }
+
+    /// Same as `new`, with every problem of file settings instead of settings:
+    /// capacity or size of 0, empty filename, directory which can't be created or written.
+    pub fn try_new(
+        path: PathBuf,
+        capacity: usize,
+        file_size: FileSize,
+        filename: String,
+        file_extension: String,
+    ) -> Result<Self, ValidationReport> {
+        let settings = Self::new(path, capacity, file_size, filename, file_extension);
+        let mut report = ValidationReport::default();
+        validation::validate_file("file", &settings, &mut report);
+        match report.is_ok() {
+            true => Ok(settings),
+            false => Err(report),
         }
     }
+
+    /// Set post-rotation pipeline.
+    pub fn with_pipeline(mut self, pipeline: RotationPipeline) -> Self {
+        self.pipeline = pipeline;
+        self
+    }
+
+    /// Set writer of active file, see `FileWriter`.
+    pub fn with_writer(mut self, writer: FileWriter) -> Self {
+        self.writer = writer;
+        self
+    }
+
+    /// Reserve disk space of `file_size` when active file is opened, so files are less fragmented
+    /// and lack of space is reported before file is written rather than in the middle of it.
+    /// Linux and Android only, file length is not changed.
+    pub fn with_preallocation(mut self, preallocation: bool) -> Self {
+        self.preallocation = preallocation;
+        self
+    }
+
+    /// Lock log files, so they are not written by several processes at once.
+    pub fn with_lock_policy(mut self, lock_policy: LockPolicy) -> Self {
+        self.lock_policy = lock_policy;
+        self
+    }
+
+    /// Start every new file with header line of schema version and record format,
+    /// so `LogReader` of later versions knows how to read it. See `FileHeader`.
+    pub fn with_schema_header(mut self, schema_header: bool) -> Self {
+        self.schema_header = schema_header;
+        self
+    }
+
+    /// Keep `<filename>.manifest.json` next to log files, describing format of lines,
+    /// rotation settings and files from active to the oldest with milliseconds since Unix epoch
+    /// of their first and last records. Rewritten after every batch.
+    pub fn with_manifest(mut self, manifest: bool) -> Self {
+        self.manifest = manifest;
+        self
+    }
+
+    /// Start every new file after rotation with record
+    /// `rotated from app.log0 at 1760000000000, 4096 bytes, seq range 0..41`:
+    /// name of rotated file, milliseconds since Unix epoch, its size and sequence numbers
+    /// of first and last record in it, so files can be checked for gaps.
+    /// Records are numbered by the sink from 0 since start, rotation records are not numbered.
+    /// Same values are in fields `rotated_from`, `rotated_at_ms`, `bytes`, `first_seq` and `last_seq`
+    /// of JSON records.
+    pub fn with_rotation_records(mut self, rotation_records: bool) -> Self {
+        self.rotation_records = rotation_records;
+        self
+    }
+
+    /// Layout of records of JSON files, e.g. of `OutputChannel::DevDuo`, see `JsonLayout`.
+    pub fn with_json_layout(mut self, json_layout: JsonLayout) -> Self {
+        self.json_layout = json_layout;
+        self
+    }
+
+    /// Terminator of every line, `\n` by default.
+    pub fn with_line_ending(mut self, line_ending: LineEnding) -> Self {
+        self.line_ending = line_ending;
+        self
+    }
+
+    /// Encoding of written text, UTF-8 by default, see `Encoding`.
+    pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Add `-<instance>` to filename, e.g. `worker-2.log`, for replicas running on one host.
+    /// Every instance rotates own files, with own `capacity`.
+    pub fn with_instance_id(mut self, instance: &str) -> Self {
+        self.filename = format!("{}-{instance}", self.filename);
+        self
+    }
+
+    /// Add PID of current process to filename, e.g. `worker-1234.log`, see `with_instance_id`.
+    pub fn with_pid_suffix(self) -> Self {
+        self.with_instance_id(&std::process::id().to_string())
+    }
+
     pub fn path(&self) -> &PathBuf {
         &self.path
     }
@@ -457,8 +1289,39 @@ impl FileSettings {
     pub fn capacity(&self) -> usize {
         self.capacity
     }
+    pub fn pipeline(&self) -> &RotationPipeline {
+        &self.pipeline
+    }
+    pub fn writer(&self) -> FileWriter {
+        self.writer
+    }
+    pub fn preallocation(&self) -> bool {
+        self.preallocation
+    }
+    pub fn lock_policy(&self) -> LockPolicy {
+        self.lock_policy
+    }
+    pub fn schema_header(&self) -> bool {
+        self.schema_header
+    }
+    pub fn manifest(&self) -> bool {
+        self.manifest
+    }
+    pub fn rotation_records(&self) -> bool {
+        self.rotation_records
+    }
+    pub fn json_layout(&self) -> &JsonLayout {
+        &self.json_layout
+    }
+    pub fn line_ending(&self) -> LineEnding {
+        self.line_ending
+    }
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
 }
 
+#[cfg(feature = "file")]
 impl Default for FileSettings {
     fn default() -> Self {
         Self {
@@ -467,6 +1330,145 @@ impl Default for FileSettings {
             file_size: Default::default(),
             filename: "logger".into(),
             file_extension: "log".into(),
+            pipeline: Default::default(),
+            writer: Default::default(),
+            preallocation: false,
+            lock_policy: Default::default(),
+            schema_header: false,
+            manifest: false,
+            rotation_records: false,
+            json_layout: Default::default(),
+            line_ending: Default::default(),
+            encoding: Default::default(),
+        }
+    }
+}
+
+/// Writer of active log file.
+#[cfg(feature = "file")]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum FileWriter {
+    /// `BufWriter` over file opened for append, synced after every batch.
+    #[default]
+    Buffered,
+    /// Memory map of file, grown by `region_size` bytes ahead of written data
+    /// and cut to written size on rotation. File ends with zeroed region while it is active.
+    #[cfg(feature = "mmap")]
+    Mmap {
+        region_size: usize,
+        sync: MsyncPolicy,
+    },
+}
+
+/// Terminator written after every line of log file.
+#[cfg(feature = "file")]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    /// `\r\n`, for Windows tools which want it.
+    CrLf,
+    /// Lines are written back to back, for formats framing records on their own.
+    None,
+}
+
+#[cfg(feature = "file")]
+impl LineEnding {
+    pub fn as_bytes(&self) -> &'static [u8] {
+        match self {
+            LineEnding::Lf => b"\n",
+            LineEnding::CrLf => b"\r\n",
+            LineEnding::None => b"",
         }
     }
 }
+
+/// Encoding of text of log files, for consumers which can not read UTF-8.
+/// `LogReader` reads UTF-8 files only, with or without BOM.
+#[cfg(feature = "file")]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Encoding {
+    #[default]
+    Utf8,
+    /// UTF-8 with byte order mark at start of every new file.
+    Utf8Bom,
+    /// ISO-8859-1, characters out of it are replaced with close ASCII, e.g. `“` with `"`, or with `?`.
+    Latin1,
+}
+
+#[cfg(feature = "file")]
+impl Encoding {
+    /// Bytes at start of every new file.
+    pub fn preamble(&self) -> &'static [u8] {
+        match self {
+            Encoding::Utf8Bom => b"\xEF\xBB\xBF",
+            _ => b"",
+        }
+    }
+
+    /// `text` in this encoding.
+    pub fn encode<'a>(&self, text: &'a str) -> Cow<'a, [u8]> {
+        match self {
+            Encoding::Latin1 if !text.is_ascii() => Cow::Owned(latin1(text)),
+            _ => Cow::Borrowed(text.as_bytes()),
+        }
+    }
+}
+
+#[cfg(feature = "file")]
+fn latin1(text: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(text.len());
+    for char in text.chars() {
+        match u8::try_from(u32::from(char)) {
+            Ok(byte) => bytes.push(byte),
+            Err(_) => bytes.extend_from_slice(transliterate(char).as_bytes()),
+        }
+    }
+    bytes
+}
+
+/// Close ASCII of character out of Latin-1.
+#[cfg(feature = "file")]
+fn transliterate(char: char) -> &'static str {
+    match char {
+        '‘' | '’' | '‚' | '′' => "'",
+        '“' | '”' | '„' | '″' => "\"",
+        '‒' | '–' | '—' | '−' => "-",
+        '…' => "...",
+        '•' => "*",
+        '€' => "EUR",
+        '™' => "TM",
+        '→' => "->",
+        '←' => "<-",
+        '\u{2002}' | '\u{2003}' | '\u{2009}' | '\u{200A}' | '\u{202F}' => " ",
+        _ => "?",
+    }
+}
+
+/// Cross-process lock of log files. Lock is advisory, taken on hidden `.<filename>.lock` file
+/// in log directory and released when process exits.
+#[cfg(feature = "file")]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum LockPolicy {
+    /// Files are not locked.
+    #[default]
+    None,
+    /// Write nothing when other process owns the files, error is reported for every batch.
+    FailFast,
+    /// Write own files with PID added to filename, e.g. `app-1234.log`,
+    /// when other process owns the files.
+    PidSuffix,
+}
+
+/// When data written to memory map is flushed to disk.
+#[cfg(feature = "mmap")]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum MsyncPolicy {
+    /// Wait for every batch to reach disk.
+    EveryBatch,
+    /// Start flush of every batch without waiting for it.
+    #[default]
+    Async,
+    /// Leave it to OS, data is flushed on rotation.
+    Never,
+}
@@ -0,0 +1,100 @@
+//! # Builders of settings.
+//!
+//! Named steps instead of positional arguments of `Settings::new` and `FileSettings::new`,
+//! every step left out keeps value of `Default`. Other options are set on built value
+//! with its `with_*` methods.
+//!
+#[cfg(feature = "file")]
+use std::path::PathBuf;
+
+#[cfg(feature = "file")]
+use crate::rotation_logger::{FileSettings, FileSize};
+use crate::rotation_logger::{MessageFormatter, OutputChannel, Settings};
+
+/// Builder of `Settings`, see `Settings::builder`.
+#[derive(Debug, Clone, Default)]
+pub struct SettingsBuilder {
+    settings: Settings,
+}
+
+impl SettingsBuilder {
+    /// Logger does nothing with `false`, see `Logger::new`.
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.settings.is_enabled = enabled;
+        self
+    }
+
+    pub fn buffer_size(mut self, buffer_size: usize) -> Self {
+        self.settings.buffer_size = buffer_size;
+        self
+    }
+
+    pub fn output(mut self, output: OutputChannel) -> Self {
+        self.settings.output = output;
+        self
+    }
+
+    /// Files in `path`, with other file settings of `FileSettings::default`.
+    #[cfg(feature = "file")]
+    pub fn file(self, path: impl Into<PathBuf>) -> Self {
+        self.file_settings(FileSettings::builder().path(path).build())
+    }
+
+    /// Files of `file_settings`, e.g. built by `FileSettings::builder`.
+    #[cfg(feature = "file")]
+    pub fn file_settings(self, file_settings: FileSettings) -> Self {
+        self.output(OutputChannel::File(file_settings))
+    }
+
+    pub fn formatter(mut self, formatter: MessageFormatter) -> Self {
+        self.settings.formatter = formatter;
+        self
+    }
+
+    pub fn build(self) -> Settings {
+        self.settings
+    }
+}
+
+/// Builder of `FileSettings`, see `FileSettings::builder`.
+#[cfg(feature = "file")]
+#[derive(Debug, Clone, Default)]
+pub struct FileSettingsBuilder {
+    settings: FileSettings,
+}
+
+#[cfg(feature = "file")]
+impl FileSettingsBuilder {
+    /// Directory of log files.
+    pub fn path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.settings.path = path.into();
+        self
+    }
+
+    /// Number of files kept, active one included.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.settings.capacity = capacity;
+        self
+    }
+
+    /// Size after which active file is rotated.
+    pub fn file_size(mut self, file_size: FileSize) -> Self {
+        self.settings.file_size = file_size;
+        self
+    }
+
+    pub fn filename(mut self, filename: &str) -> Self {
+        self.settings.filename = filename.into();
+        self
+    }
+
+    /// Extension without dot, e.g. `log`.
+    pub fn file_extension(mut self, file_extension: &str) -> Self {
+        self.settings.file_extension = file_extension.into();
+        self
+    }
+
+    pub fn build(self) -> FileSettings {
+        self.settings
+    }
+}
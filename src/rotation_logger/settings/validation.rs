@@ -0,0 +1,200 @@
+//! # Checks of settings before logger starts.
+//!
+//! `Settings::validate` looks at settings and directories they point to without writing logs.
+//! `Settings::dry_run` also writes few records to every file output in temporary directory,
+//! with tiny file size, so rotation happens once.
+//!
+use std::{
+    fmt,
+    fs::{self, OpenOptions},
+    path::Path,
+};
+
+#[cfg(feature = "file")]
+use crate::rotation_logger::FileSettings;
+use crate::rotation_logger::{Settings, logger::Message};
+
+/// Problems found by `Settings::validate`, `Settings::dry_run` or `verify_log_set`,
+/// one line per problem.
+///
+/// # Example:
+///
+/// ```
+/// # use rotation_logger::Settings;
+/// let report = Settings::default().validate();
+/// if !report.is_ok() {
+///     eprintln!("logger misconfigured:\n{report}");
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationReport {
+    problems: Vec<String>,
+}
+
+impl ValidationReport {
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    pub fn problems(&self) -> &[String] {
+        &self.problems
+    }
+
+    pub(crate) fn push(&mut self, problem: String) {
+        self.problems.push(problem);
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for problem in &self.problems {
+            writeln!(f, "{problem}")?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn validate(settings: &Settings) -> ValidationReport {
+    let mut report = ValidationReport::default();
+    if settings.buffer_size == 0 {
+        report.push("buffer_size is 0".into());
+    }
+    if let Some(format) = &settings.custom_format
+        && format.try_line(&sample()).is_err()
+    {
+        report.push("custom format failed on sample message".into());
+    }
+    #[cfg(feature = "file")]
+    for (name, file) in file_outputs(settings) {
+        validate_file(&name, file, &mut report);
+    }
+    if settings.backlog_warning().is_some() && settings.backlog_capacity().is_none() {
+        report.push("backlog warning without queue capacity or its own".into());
+    }
+    if let Some((path, _)) = settings.spill() {
+        check_dir(path, &mut report, "spill");
+    }
+    if let Some((path, _)) = settings.dead_letter()
+        && let Some(dir) = path.parent()
+    {
+        check_dir(dir, &mut report, "dead letter");
+    }
+    report
+}
+
+/// Rotate every file output once in temporary directory, after `validate`.
+#[cfg(all(feature = "file", not(target_arch = "wasm32")))]
+pub(crate) fn dry_run(settings: &Settings) -> ValidationReport {
+    use crate::rotation_logger::{FileSize, sink::FileSink, sink::Record, sink::Sink};
+
+    let mut report = validate(settings);
+    for (index, (name, file)) in file_outputs(settings).into_iter().enumerate() {
+        let dir = std::env::temp_dir().join(format!(
+            "rotation_logger_dry_run_{}_{index}",
+            std::process::id()
+        ));
+        let mut file = file.clone();
+        file.path = dir.clone();
+        file.file_size = FileSize::from_bytes(64);
+
+        let mut sink = FileSink::new(file.clone());
+        for _ in 0..3 {
+            let message = sample();
+            let line = settings.format_message(&message);
+            let record = Record::new(message, line, std::time::SystemTime::now());
+            if let Err(err) = sink.write_batch(&[record]) {
+                report.push(format!("{name}: write failed: {err}"));
+                break;
+            }
+        }
+        if let Err(err) = sink.flush() {
+            report.push(format!("{name}: flush failed: {err}"));
+        }
+        // Active file is opened again only by next write, rotated one is left.
+        let written = fs::read_dir(&dir).is_ok_and(|entries| {
+            entries.filter_map(Result::ok).any(|entry| {
+                entry
+                    .file_name()
+                    .to_string_lossy()
+                    .starts_with(file.filename())
+            })
+        });
+        if !written {
+            report.push(format!("{name}: no log files after rotation"));
+        }
+        drop(sink);
+        let _ = fs::remove_dir_all(dir);
+    }
+    report
+}
+
+fn sample() -> Message {
+    Message::new(&["rotation_logger".into()], "dry run").with_field("key", "value")
+}
+
+/// File outputs with names for report.
+#[cfg(feature = "file")]
+fn file_outputs(settings: &Settings) -> Vec<(String, &FileSettings)> {
+    let mut outputs = vec![];
+    if let Some(file) = settings.output.settings() {
+        outputs.push(("output".to_string(), file));
+    }
+    for (index, output) in settings.tee_outputs().enumerate() {
+        if let Some(file) = output.settings() {
+            outputs.push((format!("tee output {index}"), file));
+        }
+    }
+    for (prefix, file) in settings.routes() {
+        outputs.push((format!("route {}", prefix.join("::")), file));
+    }
+    outputs
+}
+
+#[cfg(feature = "file")]
+pub(super) fn validate_file(name: &str, file: &FileSettings, report: &mut ValidationReport) {
+    if file.capacity() == 0 {
+        report.push(format!("{name}: capacity is 0"));
+    }
+    if file.file_size() == 0 {
+        report.push(format!("{name}: file size is 0"));
+    }
+    if file.filename().is_empty() {
+        report.push(format!("{name}: filename is empty"));
+    }
+    // Only the last directory of path is created by file sink.
+    let path = file.path();
+    if !path.exists()
+        && let Some(parent) = path.parent()
+        && !parent.as_os_str().is_empty()
+        && !parent.is_dir()
+    {
+        report.push(format!("{name}: {} does not exist", parent.display()));
+        return;
+    }
+    check_dir(path, report, name);
+}
+
+/// Directory is writable, or its closest existing parent when it is created on start.
+fn check_dir(path: &Path, report: &mut ValidationReport, name: &str) {
+    let existing = path
+        .ancestors()
+        .map(|dir| match dir.as_os_str().is_empty() {
+            true => Path::new("."),
+            false => dir,
+        })
+        .find(|dir| dir.exists());
+    let Some(dir) = existing else {
+        return;
+    };
+    if !dir.is_dir() {
+        report.push(format!("{name}: {} is not a directory", dir.display()));
+        return;
+    }
+    let probe = dir.join(format!(".rotation_logger_probe_{}", std::process::id()));
+    match OpenOptions::new().write(true).create_new(true).open(&probe) {
+        Ok(_) => {
+            let _ = fs::remove_file(probe);
+        }
+        Err(err) => report.push(format!("{name}: {} is not writable: {err}", dir.display())),
+    }
+}
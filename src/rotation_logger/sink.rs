@@ -0,0 +1,335 @@
+//! # Sinks for formatted log records.
+//!
+//! Worker formats every `Message` once and hands batches of `Record`s to each configured sink.
+//! `OutputChannel` decides the main sink (console or rotated files),
+//! additional sinks can be attached with `Settings::with_sink`.
+//!
+//! Every sink receives records in batches of `buffer_size`, except console which is unbuffered.
+//!
+use std::{
+    fmt::Debug,
+    io,
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+#[cfg(feature = "timestamps")]
+use chrono::{DateTime, Local};
+
+use crate::rotation_logger::{
+    FieldValue, SCHEMA_VERSION, core::Deltas, logger::Message, reader::json,
+};
+
+#[cfg(feature = "azure")]
+mod azure;
+#[cfg(feature = "chat")]
+mod chat;
+#[cfg(feature = "cloudwatch")]
+pub(crate) mod cloudwatch;
+#[cfg(not(target_arch = "wasm32"))]
+mod console;
+#[cfg(unix)]
+mod fifo;
+#[cfg(all(feature = "file", not(target_arch = "wasm32")))]
+mod file;
+#[cfg(feature = "gcp")]
+mod gcp;
+mod json_layout;
+#[cfg(all(feature = "android", target_os = "android"))]
+mod logcat;
+#[cfg(all(feature = "oslog", target_vendor = "apple"))]
+mod oslog;
+#[cfg(feature = "sentry")]
+mod sentry;
+#[cfg(feature = "shm")]
+mod shm;
+#[cfg(feature = "smtp")]
+pub(crate) mod smtp;
+#[cfg(unix)]
+mod unix_socket;
+#[cfg(target_arch = "wasm32")]
+mod web_console;
+#[cfg(feature = "webhook")]
+mod webhook;
+// Helpers shared by network sinks, each sink uses only part of them.
+#[cfg(feature = "http")]
+#[allow(dead_code)]
+pub(crate) mod http;
+#[cfg(feature = "signing")]
+#[allow(dead_code)]
+mod signing;
+
+#[cfg(feature = "azure")]
+pub use azure::AzureLogAnalyticsSink;
+#[cfg(feature = "chat")]
+pub use chat::ChatNotifierSink;
+#[cfg(feature = "chat")]
+pub use chat::ChatService;
+#[cfg(feature = "cloudwatch")]
+pub use cloudwatch::AwsCredentials;
+#[cfg(feature = "cloudwatch")]
+pub use cloudwatch::AwsCredentialsSource;
+#[cfg(feature = "cloudwatch")]
+pub use cloudwatch::CloudWatchSink;
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) use console::ConsoleSink;
+#[cfg(unix)]
+pub(crate) use fifo::FifoSink;
+#[cfg(all(feature = "file", not(target_arch = "wasm32")))]
+pub use file::LogImport;
+#[cfg(all(feature = "file", not(target_arch = "wasm32")))]
+pub(crate) use file::{FileSink, LogGeneration};
+#[cfg(feature = "gcp")]
+pub use gcp::GcpCredentials;
+#[cfg(feature = "gcp")]
+pub use gcp::GcpLoggingSink;
+#[cfg(feature = "http")]
+pub use http::RetryPolicy;
+pub use json_layout::JsonLayout;
+#[cfg(all(feature = "android", target_os = "android"))]
+pub use logcat::LogcatSink;
+#[cfg(all(feature = "oslog", target_vendor = "apple"))]
+pub use oslog::OsLogSink;
+#[cfg(feature = "sentry")]
+pub use sentry::SentrySink;
+#[cfg(feature = "shm")]
+pub use shm::ShmConsumer;
+#[cfg(feature = "shm")]
+pub(crate) use shm::ShmSink;
+#[cfg(feature = "smtp")]
+pub use smtp::SmtpAlertSink;
+#[cfg(unix)]
+pub(crate) use unix_socket::UnixSocketSink;
+#[cfg(target_arch = "wasm32")]
+pub(crate) use web_console::WebConsoleSink;
+#[cfg(feature = "webhook")]
+pub use webhook::WebhookSink;
+
+/// Destination for log records.
+pub trait Sink: Send {
+    /// Write batch of records. Records are in the order they were logged.
+    fn write_batch(&mut self, records: &[Record]) -> io::Result<()>;
+
+    /// Flush any data buffered inside sink.
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Destination of console lines instead of stdout, e.g. to print above progress bars:
+///
+/// ```no_run
+/// # use rotation_logger::{OutputChannel, Settings};
+/// # struct ProgressBar;
+/// # impl ProgressBar { fn println(&self, _: &str) {} }
+/// let bar = std::sync::Arc::new(ProgressBar);
+/// let settings = Settings::default()
+///     .with_console_writer(move |line: &str| bar.println(line));
+/// ```
+pub trait ConsoleWriter: Send + Sync {
+    /// Write single line, without line ending.
+    fn write_line(&self, line: &str);
+}
+
+impl<F: Fn(&str) + Send + Sync> ConsoleWriter for F {
+    fn write_line(&self, line: &str) {
+        self(line)
+    }
+}
+
+/// Console writer stored in `Settings`.
+#[derive(Clone)]
+pub(crate) struct SharedConsoleWriter(Arc<dyn ConsoleWriter>);
+
+impl SharedConsoleWriter {
+    pub(crate) fn new(writer: impl ConsoleWriter + 'static) -> Self {
+        Self(Arc::new(writer))
+    }
+}
+
+impl ConsoleWriter for SharedConsoleWriter {
+    fn write_line(&self, line: &str) {
+        self.0.write_line(line)
+    }
+}
+
+impl Debug for SharedConsoleWriter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ConsoleWriter")
+    }
+}
+
+/// Log message together with its formatted line.
+#[derive(Debug, Clone)]
+pub struct Record {
+    message: Message,
+    line: String,
+    time: SystemTime,
+    /// Time since previous records, for `delta` masks of sinks formatting lines again.
+    deltas: Deltas,
+}
+
+impl Record {
+    pub fn new(message: Message, line: String, time: impl Into<SystemTime>) -> Self {
+        Self {
+            message,
+            line,
+            time: time.into(),
+            deltas: Deltas::default(),
+        }
+    }
+
+    pub(crate) fn with_deltas(mut self, deltas: Deltas) -> Self {
+        self.deltas = deltas;
+        self
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn deltas(&self) -> Deltas {
+        self.deltas
+    }
+
+    pub fn message(&self) -> &Message {
+        &self.message
+    }
+
+    pub fn into_message(self) -> Message {
+        self.message
+    }
+
+    /// Message formatted by `MessageFormatter`, without line ending.
+    pub fn line(&self) -> &str {
+        &self.line
+    }
+
+    /// Time when message received by logger.
+    pub fn time(&self) -> SystemTime {
+        self.time
+    }
+
+    /// Time when message received by logger, in local time zone.
+    #[cfg(feature = "timestamps")]
+    pub fn timestamp(&self) -> DateTime<Local> {
+        self.time.into()
+    }
+
+    /// Record as single line JSON object with `schema`, `timestamp`, `level`, `modules`, `message`
+    /// and `fields`. Timestamp is RFC 3339, or seconds since Unix epoch without `timestamps` feature.
+    /// Schema is `SCHEMA_VERSION`, readers pick parser by it.
+    pub fn to_json(&self) -> String {
+        self.to_json_with(&JsonLayout::default())
+    }
+
+    /// Record as JSON object of `layout`, see `to_json`.
+    pub fn to_json_with(&self, layout: &JsonLayout) -> String {
+        let message = &self.message;
+        #[cfg(feature = "timestamps")]
+        let timestamp = format!(
+            "\"{}\"",
+            self.timestamp()
+                .to_rfc3339_opts(chrono::SecondsFormat::Micros, false)
+        );
+        #[cfg(not(feature = "timestamps"))]
+        let timestamp = {
+            let since_epoch = self
+                .time
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default();
+            format!(
+                "{}.{:06}",
+                since_epoch.as_secs(),
+                since_epoch.subsec_micros()
+            )
+        };
+        let modules = message
+            .modules()
+            .iter()
+            .map(|module| json::string(module))
+            .collect::<Vec<String>>()
+            .join(",");
+        let fields = message
+            .fields()
+            .iter()
+            .map(|(key, value)| (json::string(key), field_json(value)))
+            .collect::<Vec<(String, String)>>();
+
+        let mut entries = vec![
+            ("schema", SCHEMA_VERSION.to_string()),
+            ("timestamp", timestamp),
+            ("level", format!("\"{}\"", message.level())),
+        ];
+        if let Some(target) = message.target() {
+            entries.push(("target", json::string(target)));
+        }
+        entries.push(("modules", format!("[{modules}]")));
+        entries.push(("message", json::string(message.text())));
+        entries.push(("fields", json_object(&fields, layout.pretty(), 1)));
+        let entries = entries
+            .into_iter()
+            .map(|(key, value)| (json::string(layout.key(key)), value))
+            .collect::<Vec<(String, String)>>();
+        json_object(&entries, layout.pretty(), 0)
+    }
+}
+
+/// JSON object of already written keys and values, indented at `depth` when `pretty`.
+fn json_object(entries: &[(String, String)], pretty: bool, depth: usize) -> String {
+    if !pretty || entries.is_empty() {
+        let entries = entries
+            .iter()
+            .map(|(key, value)| format!("{key}:{value}"))
+            .collect::<Vec<String>>();
+        return format!("{{{}}}", entries.join(","));
+    }
+    let indent = "  ".repeat(depth + 1);
+    let entries = entries
+        .iter()
+        .map(|(key, value)| format!("{indent}{key}: {value}"))
+        .collect::<Vec<String>>();
+    format!("{{\n{}\n{}}}", entries.join(",\n"), "  ".repeat(depth))
+}
+
+/// Field value as JSON of its type.
+fn field_json(value: &FieldValue) -> String {
+    match value {
+        FieldValue::I64(value) => value.to_string(),
+        FieldValue::F64(value) => json::number(*value),
+        FieldValue::Bool(value) => value.to_string(),
+        FieldValue::Str(value) => json::string(value),
+        #[cfg(feature = "serde")]
+        FieldValue::Json(value) => value.to_string(),
+    }
+}
+
+/// User sink stored in `Settings` or `OutputChannel::Custom`, shared between `Logger` clones.
+/// Created by `OutputChannel::custom`.
+#[derive(Clone)]
+pub struct SharedSink(Arc<Mutex<dyn Sink>>);
+
+impl SharedSink {
+    pub(crate) fn new(sink: impl Sink + 'static) -> Self {
+        Self(Arc::new(Mutex::new(sink)))
+    }
+}
+
+impl Sink for SharedSink {
+    fn write_batch(&mut self, records: &[Record]) -> io::Result<()> {
+        match self.0.lock() {
+            Ok(mut sink) => sink.write_batch(records),
+            Err(err) => Err(io::Error::other(err.to_string())),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.0.lock() {
+            Ok(mut sink) => sink.flush(),
+            Err(err) => Err(io::Error::other(err.to_string())),
+        }
+    }
+}
+
+impl Debug for SharedSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Sink")
+    }
+}
@@ -0,0 +1,124 @@
+use std::{io, time::Duration};
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use chrono::{SecondsFormat, Utc};
+use serde_json::json;
+
+use crate::rotation_logger::sink::{
+    Record, Sink,
+    http::{HttpError, RetryPolicy, truncate},
+    signing::hmac_sha256,
+};
+
+/// Data Collector API accepts up to 30 MB per post, keep some margin.
+const MAX_BATCH_BYTES: usize = 25 * 1024 * 1024;
+/// Longer field values are truncated by Log Analytics.
+const MAX_FIELD_BYTES: usize = 32 * 1024 - 2;
+
+/// Writes records to Azure Monitor Log Analytics workspace with HTTP Data Collector API.
+///
+/// Records become rows of custom table `<log_type>_CL` with `level`, `modules` and `message`
+/// columns, and `timestamp` used as `TimeGenerated`.
+/// Requests are signed with workspace shared key and retried by `RetryPolicy`.
+pub struct AzureLogAnalyticsSink {
+    workspace_id: String,
+    shared_key: Vec<u8>,
+    log_type: String,
+    endpoint: String,
+    retry: RetryPolicy,
+    agent: ureq::Agent,
+}
+
+impl AzureLogAnalyticsSink {
+    /// `shared_key` is the base64 primary or secondary key of the workspace.
+    pub fn new(workspace_id: &str, shared_key: &str, log_type: &str) -> io::Result<Self> {
+        let shared_key = STANDARD
+            .decode(shared_key.trim())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+
+        Ok(Self {
+            workspace_id: workspace_id.into(),
+            shared_key,
+            log_type: log_type.into(),
+            endpoint: format!("https://{workspace_id}.ods.opinsights.azure.com/api/logs"),
+            retry: Default::default(),
+            agent: ureq::AgentBuilder::new()
+                .timeout(Duration::from_secs(30))
+                .build(),
+        })
+    }
+
+    /// Override API url, e.g. for sovereign clouds (`*.ods.opinsights.azure.us`).
+    pub fn with_endpoint(mut self, endpoint: &str) -> Self {
+        self.endpoint = endpoint.into();
+        self
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Serialized JSON arrays, each small enough for single post.
+    pub(crate) fn batches(records: &[Record]) -> Vec<String> {
+        let mut batches = vec![];
+        let mut batch: Vec<String> = vec![];
+        let mut batch_bytes = 0;
+
+        for record in records {
+            let message = record.message();
+            let row = json!({
+                "timestamp": record.timestamp().to_rfc3339_opts(SecondsFormat::Millis, true),
+                "level": message.level().as_str(),
+                "modules": message.modules().join("::"),
+                "message": truncate(message.text(), MAX_FIELD_BYTES),
+            })
+            .to_string();
+
+            if !batch.is_empty() && batch_bytes + row.len() + 1 > MAX_BATCH_BYTES {
+                batches.push(format!("[{}]", batch.join(",")));
+                batch.clear();
+                batch_bytes = 0;
+            }
+            batch_bytes += row.len() + 1;
+            batch.push(row);
+        }
+        if !batch.is_empty() {
+            batches.push(format!("[{}]", batch.join(",")));
+        }
+        batches
+    }
+
+    /// `Authorization` header value for body of given length.
+    pub(crate) fn authorization(&self, date: &str, content_length: usize) -> String {
+        let string_to_sign =
+            format!("POST\n{content_length}\napplication/json\nx-ms-date:{date}\n/api/logs");
+        let signature = STANDARD.encode(hmac_sha256(&self.shared_key, &string_to_sign));
+        format!("SharedKey {}:{signature}", self.workspace_id)
+    }
+
+    fn send(&self, body: &str) -> io::Result<()> {
+        self.retry.run(|| {
+            let date = Utc::now().format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+            self.agent
+                .post(&format!("{}?api-version=2016-04-01", self.endpoint))
+                .set("Authorization", &self.authorization(&date, body.len()))
+                .set("Content-Type", "application/json")
+                .set("Log-Type", &self.log_type)
+                .set("x-ms-date", &date)
+                .set("time-generated-field", "timestamp")
+                .send_string(body)
+                .map(|_| ())
+                .map_err(HttpError::from)
+        })
+    }
+}
+
+impl Sink for AzureLogAnalyticsSink {
+    fn write_batch(&mut self, records: &[Record]) -> io::Result<()> {
+        for body in Self::batches(records) {
+            self.send(&body)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,165 @@
+use std::{
+    io,
+    time::{Duration, Instant},
+};
+
+use serde_json::json;
+
+use crate::rotation_logger::{
+    logger::Level,
+    sink::{
+        Record, Sink,
+        http::{HttpError, RetryPolicy, truncate},
+    },
+};
+
+const MINUTE: Duration = Duration::from_secs(60);
+
+/// Chat service of incoming webhook.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChatService {
+    Slack,
+    Discord,
+}
+
+impl ChatService {
+    /// Maximal text length of single message, with some margin.
+    fn max_len(&self) -> usize {
+        match self {
+            ChatService::Slack => 3900,
+            ChatService::Discord => 1900,
+        }
+    }
+
+    fn payload(&self, text: &str) -> String {
+        match self {
+            ChatService::Slack => json!({ "text": text }),
+            ChatService::Discord => json!({ "content": text }),
+        }
+        .to_string()
+    }
+}
+
+/// Posts important records to Slack or Discord channel through incoming webhook.
+///
+/// Formatted lines of records are packed into code blocks, as few messages as possible.
+/// No more than `per_minute` messages are posted, records above this budget are dropped
+/// and their count is reported in next message.
+pub struct ChatNotifierSink {
+    service: ChatService,
+    url: String,
+    min_level: Level,
+    per_minute: u32,
+    window_start: Instant,
+    sent: u32,
+    suppressed: usize,
+    retry: RetryPolicy,
+    agent: ureq::Agent,
+}
+
+impl ChatNotifierSink {
+    pub fn new(service: ChatService, url: &str) -> Self {
+        Self {
+            service,
+            url: url.into(),
+            min_level: Level::Warn,
+            per_minute: 10,
+            window_start: Instant::now(),
+            sent: 0,
+            suppressed: 0,
+            retry: Default::default(),
+            agent: ureq::AgentBuilder::new()
+                .timeout(Duration::from_secs(30))
+                .build(),
+        }
+    }
+
+    pub fn slack(url: &str) -> Self {
+        Self::new(ChatService::Slack, url)
+    }
+
+    pub fn discord(url: &str) -> Self {
+        Self::new(ChatService::Discord, url)
+    }
+
+    /// Least important level to post, `Level::Warn` by default.
+    pub fn with_min_level(mut self, min_level: Level) -> Self {
+        self.min_level = min_level;
+        self
+    }
+
+    /// Maximal number of messages per minute, 10 by default.
+    pub fn with_per_minute(mut self, per_minute: u32) -> Self {
+        self.per_minute = per_minute.max(1);
+        self
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Message texts to post now, within the budget.
+    pub(crate) fn messages(&mut self, records: &[Record]) -> Vec<String> {
+        // Code block fences and suppressed notice are kept out of the limit.
+        let max_len = self.service.max_len() - 100;
+        let mut chunks: Vec<(String, usize)> = vec![];
+
+        for record in records {
+            if record.message().level() > self.min_level {
+                continue;
+            }
+            let line = truncate(record.line(), max_len);
+            match chunks.last_mut() {
+                Some((text, count)) if text.len() + line.len() < max_len => {
+                    text.push('\n');
+                    text.push_str(line);
+                    *count += 1;
+                }
+                _ => chunks.push((line.to_string(), 1)),
+            }
+        }
+
+        let mut messages = vec![];
+        for (text, count) in chunks {
+            if self.window_start.elapsed() >= MINUTE {
+                self.window_start = Instant::now();
+                self.sent = 0;
+            }
+            if self.sent >= self.per_minute {
+                self.suppressed += count;
+                continue;
+            }
+
+            self.sent += 1;
+            let mut message = String::new();
+            if self.suppressed > 0 {
+                message.push_str(&format!(
+                    "_{} records suppressed by rate limit_\n",
+                    self.suppressed
+                ));
+                self.suppressed = 0;
+            }
+            message.push_str(&format!("```\n{text}\n```"));
+            messages.push(message);
+        }
+        messages
+    }
+}
+
+impl Sink for ChatNotifierSink {
+    fn write_batch(&mut self, records: &[Record]) -> io::Result<()> {
+        for message in self.messages(records) {
+            let body = self.service.payload(&message);
+            self.retry.run(|| {
+                self.agent
+                    .post(&self.url)
+                    .set("Content-Type", "application/json")
+                    .send_string(&body)
+                    .map(|_| ())
+                    .map_err(HttpError::from)
+            })?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,440 @@
+use std::{
+    env, io,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+
+use crate::rotation_logger::sink::{
+    Record, Sink,
+    http::{HttpError, RetryPolicy, truncate},
+    signing::{hex, hmac_sha256},
+};
+
+/// PutLogEvents limits, see CloudWatch Logs quotas.
+const MAX_BATCH_EVENTS: usize = 10_000;
+const MAX_BATCH_BYTES: usize = 1_048_576;
+const EVENT_OVERHEAD_BYTES: usize = 26;
+const MAX_EVENT_BYTES: usize = 262_144 - EVENT_OVERHEAD_BYTES;
+const MAX_BATCH_SPAN_MS: i64 = 24 * 60 * 60 * 1000;
+
+const IMDS_URL: &str = "http://169.254.169.254/latest";
+
+/// AWS access keys.
+#[derive(Clone)]
+pub struct AwsCredentials {
+    access_key_id: String,
+    secret_access_key: String,
+    session_token: Option<String>,
+}
+
+impl AwsCredentials {
+    pub fn new(access_key_id: &str, secret_access_key: &str, session_token: Option<&str>) -> Self {
+        Self {
+            access_key_id: access_key_id.into(),
+            secret_access_key: secret_access_key.into(),
+            session_token: session_token.map(|token| token.into()),
+        }
+    }
+
+    /// Read `AWS_ACCESS_KEY_ID`, `AWS_SECRET_ACCESS_KEY` and optional `AWS_SESSION_TOKEN`,
+    /// as provided by Lambda runtime.
+    pub fn from_env() -> Option<Self> {
+        Some(Self {
+            access_key_id: env::var("AWS_ACCESS_KEY_ID").ok()?,
+            secret_access_key: env::var("AWS_SECRET_ACCESS_KEY").ok()?,
+            session_token: env::var("AWS_SESSION_TOKEN").ok(),
+        })
+    }
+}
+
+impl std::fmt::Debug for AwsCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AwsCredentials")
+            .field("access_key_id", &self.access_key_id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Where `CloudWatchSink` takes credentials from.
+#[derive(Debug, Clone, Default)]
+pub enum AwsCredentialsSource {
+    /// Environment variables, see `AwsCredentials::from_env`.
+    #[default]
+    Environment,
+    /// Instance role from EC2 metadata service (IMDSv2).
+    InstanceMetadata,
+    Static(AwsCredentials),
+}
+
+/// Writes records to AWS CloudWatch Logs stream with `PutLogEvents`.
+///
+/// Event message is the formatted line, so the stream mirrors local rotated file.
+/// Batches are split by CloudWatch limits (events count, bytes, 24 hours span),
+/// throttling and server errors are retried by `RetryPolicy`,
+/// sequence token is tracked and recovered from `InvalidSequenceTokenException`.
+pub struct CloudWatchSink {
+    region: String,
+    log_group: String,
+    log_stream: String,
+    create_stream: bool,
+    credentials: AwsCredentialsSource,
+    endpoint: Option<String>,
+    retry: RetryPolicy,
+    agent: ureq::Agent,
+    cached_credentials: Option<(AwsCredentials, Option<Instant>)>,
+    sequence_token: Option<String>,
+}
+
+impl CloudWatchSink {
+    pub fn new(region: &str, log_group: &str, log_stream: &str) -> Self {
+        Self {
+            region: region.into(),
+            log_group: log_group.into(),
+            log_stream: log_stream.into(),
+            create_stream: true,
+            credentials: Default::default(),
+            endpoint: None,
+            retry: Default::default(),
+            agent: ureq::AgentBuilder::new()
+                .timeout(Duration::from_secs(30))
+                .build(),
+            cached_credentials: None,
+            sequence_token: None,
+        }
+    }
+
+    pub fn with_credentials(mut self, credentials: AwsCredentialsSource) -> Self {
+        self.credentials = credentials;
+        self
+    }
+
+    /// Create log stream when it does not exist. Enabled by default.
+    pub fn with_create_stream(mut self, create_stream: bool) -> Self {
+        self.create_stream = create_stream;
+        self
+    }
+
+    /// Override API url, for VPC endpoints or local emulators.
+    pub fn with_endpoint(mut self, endpoint: &str) -> Self {
+        self.endpoint = Some(endpoint.into());
+        self
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Url of API with host and path request is signed for.
+    /// Host keeps port of endpoint other than default one of scheme, same as `Host` header sent.
+    fn target(&self) -> Result<(String, String, String), HttpError> {
+        let url = match &self.endpoint {
+            Some(endpoint) => endpoint.clone(),
+            None => format!("https://logs.{}.amazonaws.com/", self.region),
+        };
+        let parsed = self
+            .agent
+            .post(&url)
+            .request_url()
+            .map_err(|err| HttpError::Fatal(io::Error::other(err)))?;
+        let host = match parsed.port() {
+            Some(port) => format!("{}:{port}", parsed.host()),
+            None => parsed.host().to_string(),
+        };
+        let path = parsed.path().to_string();
+        Ok((url, host, path))
+    }
+
+    /// Split records to batches accepted by single `PutLogEvents` call.
+    pub(crate) fn batches(records: &[Record]) -> Vec<Vec<Value>> {
+        let mut batches = vec![];
+        let mut batch: Vec<Value> = vec![];
+        let mut batch_bytes = 0;
+        let mut batch_start = 0;
+
+        for record in records {
+            let message = truncate(record.line(), MAX_EVENT_BYTES);
+            let timestamp = record.timestamp().timestamp_millis();
+            let size = message.len() + EVENT_OVERHEAD_BYTES;
+
+            if !batch.is_empty()
+                && (batch.len() >= MAX_BATCH_EVENTS
+                    || batch_bytes + size > MAX_BATCH_BYTES
+                    || timestamp - batch_start > MAX_BATCH_SPAN_MS)
+            {
+                batches.push(std::mem::take(&mut batch));
+                batch_bytes = 0;
+            }
+            if batch.is_empty() {
+                batch_start = timestamp;
+            }
+            batch.push(json!({ "timestamp": timestamp, "message": message }));
+            batch_bytes += size;
+        }
+        if !batch.is_empty() {
+            batches.push(batch);
+        }
+        batches
+    }
+
+    fn credentials(&mut self) -> Result<AwsCredentials, HttpError> {
+        if let Some((credentials, expires)) = &self.cached_credentials
+            && expires.is_none_or(|expires| Instant::now() < expires)
+        {
+            return Ok(credentials.clone());
+        }
+
+        let (credentials, expires) = match &self.credentials {
+            AwsCredentialsSource::Static(credentials) => (credentials.clone(), None),
+            AwsCredentialsSource::Environment => match AwsCredentials::from_env() {
+                Some(credentials) => (credentials, None),
+                None => {
+                    return Err(HttpError::Fatal(io::Error::other(
+                        "AWS credentials not found in environment",
+                    )));
+                }
+            },
+            AwsCredentialsSource::InstanceMetadata => self.instance_credentials()?,
+        };
+        self.cached_credentials = Some((credentials.clone(), expires));
+        Ok(credentials)
+    }
+
+    fn instance_credentials(&self) -> Result<(AwsCredentials, Option<Instant>), HttpError> {
+        let token = self
+            .agent
+            .put(&format!("{IMDS_URL}/api/token"))
+            .set("X-aws-ec2-metadata-token-ttl-seconds", "21600")
+            .call()?
+            .into_string()
+            .map_err(|err| HttpError::Retry(err, None))?;
+        let get = |path: &str| -> Result<String, HttpError> {
+            self.agent
+                .get(&format!(
+                    "{IMDS_URL}/meta-data/iam/security-credentials/{path}"
+                ))
+                .set("X-aws-ec2-metadata-token", &token)
+                .call()?
+                .into_string()
+                .map_err(|err| HttpError::Retry(err, None))
+        };
+
+        let role = get("")?;
+        let role = role.lines().next().unwrap_or_default().trim();
+        let response: Value = serde_json::from_str(&get(role)?)
+            .map_err(|err| HttpError::Fatal(io::Error::other(err)))?;
+
+        let field = |name: &str| -> Result<String, HttpError> {
+            response[name]
+                .as_str()
+                .map(|value| value.to_string())
+                .ok_or_else(|| HttpError::retry(format!("instance metadata has no {name}")))
+        };
+        let credentials = AwsCredentials {
+            access_key_id: field("AccessKeyId")?,
+            secret_access_key: field("SecretAccessKey")?,
+            session_token: Some(field("Token")?),
+        };
+        // Refresh five minutes before expiration.
+        let expires = response["Expiration"]
+            .as_str()
+            .and_then(|value| DateTime::parse_from_rfc3339(value).ok())
+            .and_then(|expiration| (expiration.with_timezone(&Utc) - Utc::now()).to_std().ok())
+            .map(|left| Instant::now() + left.saturating_sub(Duration::from_secs(300)));
+        Ok((credentials, expires))
+    }
+
+    /// Signed call of CloudWatch Logs API action.
+    fn call(&mut self, action: &str, body: &Value) -> Result<Value, HttpError> {
+        let credentials = self.credentials()?;
+        let body = body.to_string();
+        let (url, host, path) = self.target()?;
+        let target = format!("Logs_20140328.{action}");
+        let amz_date = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+
+        let mut headers = vec![
+            ("content-type", "application/x-amz-json-1.1".to_string()),
+            ("host", host.clone()),
+            ("x-amz-date", amz_date.clone()),
+            ("x-amz-target", target),
+        ];
+        if let Some(token) = &credentials.session_token {
+            headers.push(("x-amz-security-token", token.clone()));
+        }
+        let authorization = sign_v4(
+            &credentials,
+            &self.region,
+            "logs",
+            &amz_date,
+            &path,
+            &headers,
+            body.as_bytes(),
+        );
+
+        let mut request = self.agent.post(&url).set("Authorization", &authorization);
+        for (name, value) in headers.iter().filter(|(name, _)| *name != "host") {
+            request = request.set(name, value);
+        }
+
+        match request.send_string(&body) {
+            Ok(response) => {
+                let text = response.into_string().unwrap_or_default();
+                Ok(serde_json::from_str(&text).unwrap_or(Value::Null))
+            }
+            Err(ureq::Error::Status(status, response)) => {
+                let text = response.into_string().unwrap_or_default();
+                let error: Value = serde_json::from_str(&text).unwrap_or(Value::Null);
+                Err(api_error(status, error))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn put_events(&mut self, events: Vec<Value>) -> io::Result<()> {
+        let retry = self.retry.clone();
+        let events = Value::Array(events);
+        retry.run(|| {
+            let mut body = json!({
+                "logGroupName": self.log_group,
+                "logStreamName": self.log_stream,
+                "logEvents": events,
+            });
+            if let Some(token) = &self.sequence_token {
+                body["sequenceToken"] = json!(token);
+            }
+
+            match self.call("PutLogEvents", &body) {
+                Ok(response) => {
+                    self.sequence_token = response["nextSequenceToken"]
+                        .as_str()
+                        .map(|token| token.to_string());
+                    Ok(())
+                }
+                Err(HttpError::Fatal(err)) => {
+                    if err.to_string().contains("DataAlreadyAcceptedException") {
+                        self.sequence_token = expected_sequence_token(&err.to_string());
+                        return Ok(());
+                    }
+                    Err(self.recover(err))
+                }
+                Err(err) => Err(err),
+            }
+        })
+    }
+
+    /// Fix state after rejected `PutLogEvents` and decide if it worth to repeat.
+    fn recover(&mut self, err: io::Error) -> HttpError {
+        let message = err.to_string();
+        if message.contains("InvalidSequenceTokenException") {
+            self.sequence_token = expected_sequence_token(&message);
+            return HttpError::Retry(err, Some(Duration::ZERO));
+        }
+        if message.contains("ResourceNotFoundException") && self.create_stream {
+            let body = json!({
+                "logGroupName": self.log_group,
+                "logStreamName": self.log_stream,
+            });
+            return match self.call("CreateLogStream", &body) {
+                Ok(_) => {
+                    self.sequence_token = None;
+                    HttpError::Retry(err, Some(Duration::ZERO))
+                }
+                Err(create_err) => create_err,
+            };
+        }
+        HttpError::Fatal(err)
+    }
+}
+
+impl Sink for CloudWatchSink {
+    fn write_batch(&mut self, records: &[Record]) -> io::Result<()> {
+        for events in Self::batches(records) {
+            self.put_events(events)?;
+        }
+        Ok(())
+    }
+}
+
+fn api_error(status: u16, error: Value) -> HttpError {
+    let kind = error["__type"].as_str().unwrap_or_default();
+    let kind = kind.rsplit('#').next().unwrap_or(kind);
+    let message = error["message"]
+        .as_str()
+        .or(error["Message"].as_str())
+        .unwrap_or_default();
+    let err = io::Error::other(format!("{kind}: {message}"));
+
+    if status >= 500
+        || status == 429
+        || kind == "ThrottlingException"
+        || kind == "ServiceUnavailableException"
+    {
+        HttpError::Retry(err, None)
+    } else {
+        HttpError::Fatal(err)
+    }
+}
+
+/// Token at the end of sequence token errors, e.g.
+/// "The next expected sequenceToken is: 4956..." or "...sent with sequenceToken: null".
+fn expected_sequence_token(message: &str) -> Option<String> {
+    message
+        .split_whitespace()
+        .last()
+        .filter(|token| token.chars().all(|c| c.is_ascii_digit()))
+        .map(|token| token.to_string())
+}
+
+/// AWS Signature Version 4 signing key.
+pub(crate) fn signing_key(secret: &str, date: &str, region: &str, service: &str) -> Vec<u8> {
+    let key = hmac_sha256(format!("AWS4{secret}").as_bytes(), date);
+    let key = hmac_sha256(&key, region);
+    let key = hmac_sha256(&key, service);
+    hmac_sha256(&key, "aws4_request")
+}
+
+/// `Authorization` header value for POST to `path` with given headers.
+/// Header names must be lowercase.
+pub(crate) fn sign_v4(
+    credentials: &AwsCredentials,
+    region: &str,
+    service: &str,
+    amz_date: &str,
+    path: &str,
+    headers: &[(&str, String)],
+    payload: &[u8],
+) -> String {
+    let date = &amz_date[..8];
+    let mut headers: Vec<&(&str, String)> = headers.iter().collect();
+    headers.sort_by_key(|(name, _)| *name);
+
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{name}:{}\n", value.trim()))
+        .collect();
+    let signed_headers = headers
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(";");
+    let canonical_request = format!(
+        "POST\n{path}\n\n{canonical_headers}\n{signed_headers}\n{}",
+        hex(&Sha256::digest(payload))
+    );
+
+    let scope = format!("{date}/{region}/{service}/aws4_request");
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+        hex(&Sha256::digest(canonical_request.as_bytes()))
+    );
+    let key = signing_key(&credentials.secret_access_key, date, region, service);
+    let signature = hex(&hmac_sha256(&key, &string_to_sign));
+
+    format!(
+        "AWS4-HMAC-SHA256 Credential={}/{scope}, SignedHeaders={signed_headers}, Signature={signature}",
+        credentials.access_key_id
+    )
+}
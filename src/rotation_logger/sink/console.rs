@@ -0,0 +1,178 @@
+use std::io;
+
+use crate::rotation_logger::{
+    ColorTheme, MessageFormatter,
+    sink::{ConsoleWriter, Record, SharedConsoleWriter, Sink},
+};
+#[cfg(feature = "formatter")]
+use crate::rotation_logger::{Grid, core::Columns};
+
+/// Prints every record to stdout, or passes it to console writer.
+/// With color theme line is formatted again, with painted parts.
+pub(crate) struct ConsoleSink {
+    formatter: MessageFormatter,
+    theme: Option<ColorTheme>,
+    writer: Option<SharedConsoleWriter>,
+    /// Records are printed as JSON objects, see `Record::to_json`.
+    json: bool,
+    /// Widths of columns grown so far, with `Settings::with_auto_widths`.
+    #[cfg(feature = "formatter")]
+    columns: Option<Columns>,
+    /// Lines of records are of other format, every line is formatted again.
+    #[cfg(feature = "formatter")]
+    own_format: bool,
+    /// Header of grid, printed before the first line.
+    #[cfg(feature = "formatter")]
+    header: Option<[String; 2]>,
+}
+
+impl ConsoleSink {
+    pub(crate) fn new(
+        formatter: MessageFormatter,
+        theme: Option<ColorTheme>,
+        writer: Option<SharedConsoleWriter>,
+    ) -> Self {
+        // Old Windows consoles print escape sequences as is, plain text is better there.
+        let theme = theme.filter(|_| enable_ansi());
+        Self {
+            formatter,
+            theme,
+            writer,
+            json: false,
+            #[cfg(feature = "formatter")]
+            columns: None,
+            #[cfg(feature = "formatter")]
+            own_format: false,
+            #[cfg(feature = "formatter")]
+            header: None,
+        }
+    }
+
+    fn write_line(&self, line: &str) {
+        match &self.writer {
+            Some(writer) => writer.write_line(line),
+            None => println!("{line}"),
+        }
+    }
+
+    /// Print records as JSON objects instead of lines.
+    pub(crate) fn with_json(mut self, json: bool) -> Self {
+        self.json = json;
+        self
+    }
+
+    /// Columns growing up to `max_width`, fixed widths of format string on `None`.
+    #[cfg_attr(not(feature = "formatter"), allow(unused_mut, unused_variables))]
+    pub(crate) fn with_auto_widths(mut self, max_width: Option<usize>) -> Self {
+        #[cfg(feature = "formatter")]
+        {
+            self.columns = max_width.map(Columns::new);
+        }
+        self
+    }
+
+    /// Format every line again with own formatter, when it differs from formatter of records.
+    #[cfg(feature = "formatter")]
+    pub(crate) fn with_own_format(mut self) -> Self {
+        self.own_format = true;
+        self
+    }
+
+    /// Print lines as table of `grid`, see `Grid`.
+    #[cfg(feature = "formatter")]
+    pub(crate) fn with_grid(mut self, grid: Option<&Grid>) -> Self {
+        if let Some(grid) = grid {
+            self.formatter = self.formatter.grid(grid);
+            self.own_format = true;
+            self.header = grid.header_lines();
+        }
+        self
+    }
+}
+
+/// Terminals other than Windows console understand ANSI sequences anyway.
+#[cfg(not(windows))]
+fn enable_ansi() -> bool {
+    true
+}
+
+/// Turn on virtual terminal processing of Windows 10+ console, `false` when output is no console.
+#[cfg(windows)]
+fn enable_ansi() -> bool {
+    use std::{ffi::c_void, sync::OnceLock};
+
+    const STD_OUTPUT_HANDLE: u32 = -11i32 as u32;
+    const ENABLE_VIRTUAL_TERMINAL_PROCESSING: u32 = 0x0004;
+
+    #[link(name = "kernel32")]
+    unsafe extern "system" {
+        fn GetStdHandle(std_handle: u32) -> *mut c_void;
+        fn GetConsoleMode(console: *mut c_void, mode: *mut u32) -> i32;
+        fn SetConsoleMode(console: *mut c_void, mode: u32) -> i32;
+    }
+
+    static ENABLED: OnceLock<bool> = OnceLock::new();
+    *ENABLED.get_or_init(|| unsafe {
+        let handle = GetStdHandle(STD_OUTPUT_HANDLE);
+        if handle.is_null() || handle as isize == -1 {
+            return false;
+        }
+        let mut mode = 0;
+        if GetConsoleMode(handle, &mut mode) == 0 {
+            // Not a console, e.g. output redirected to file, which gets no escape sequences.
+            return false;
+        }
+        mode & ENABLE_VIRTUAL_TERMINAL_PROCESSING != 0
+            || SetConsoleMode(handle, mode | ENABLE_VIRTUAL_TERMINAL_PROCESSING) != 0
+    })
+}
+
+impl Sink for ConsoleSink {
+    fn write_batch(&mut self, records: &[Record]) -> io::Result<()> {
+        #[cfg(feature = "formatter")]
+        if let Some(header) = self.header.take() {
+            for line in header {
+                self.write_line(&line);
+            }
+        }
+        for record in records {
+            if self.json {
+                self.write_line(&record.to_json());
+                continue;
+            }
+            #[cfg(feature = "formatter")]
+            if let Some(columns) = self.columns.as_mut() {
+                let line = self.formatter.format_in_columns(
+                    record.message(),
+                    record.time(),
+                    record.deltas(),
+                    self.theme.as_ref(),
+                    columns,
+                );
+                self.write_line(&line);
+                continue;
+            }
+            #[cfg(feature = "formatter")]
+            if self.own_format {
+                let line = self.formatter.format_with_deltas(
+                    record.message(),
+                    record.time(),
+                    record.deltas(),
+                    self.theme.as_ref(),
+                );
+                self.write_line(&line);
+                continue;
+            }
+            let colored = self.theme.as_ref().map(|theme| {
+                self.formatter.format_with_deltas(
+                    record.message(),
+                    record.time(),
+                    record.deltas(),
+                    Some(theme),
+                )
+            });
+            self.write_line(colored.as_deref().unwrap_or(record.line()));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,84 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    os::unix::fs::{FileTypeExt, OpenOptionsExt},
+    path::PathBuf,
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+use crate::rotation_logger::sink::{Record, Sink};
+
+/// How long to wait for slow reader, before batch is dropped.
+const BLOCK_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Writes lines to pre-created named pipe, e.g. for legacy log processors.
+///
+/// Pipe is opened in non-blocking mode, so missing reader does not hold logger:
+/// records are dropped until reader appears. When pipe is full writer waits
+/// up to `BLOCK_TIMEOUT` for reader to catch up, then rest of batch is dropped.
+pub(crate) struct FifoSink {
+    path: PathBuf,
+    file: Option<File>,
+}
+
+impl FifoSink {
+    pub(crate) fn new(path: PathBuf) -> Self {
+        Self { path, file: None }
+    }
+
+    fn open(&self) -> io::Result<File> {
+        if !self.path.metadata()?.file_type().is_fifo() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} is not a named pipe", self.path.display()),
+            ));
+        }
+        // Fails with ENXIO while no process has pipe open for reading.
+        OpenOptions::new()
+            .write(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(&self.path)
+    }
+}
+
+impl Sink for FifoSink {
+    fn write_batch(&mut self, records: &[Record]) -> io::Result<()> {
+        if self.file.is_none() {
+            self.file = Some(self.open()?);
+        }
+        let Some(file) = &mut self.file else {
+            return Ok(());
+        };
+
+        let mut data = Vec::new();
+        for record in records {
+            data.extend_from_slice(record.line().as_bytes());
+            data.push(b'\n');
+        }
+
+        let started = Instant::now();
+        let mut written = 0;
+        while written < data.len() {
+            match file.write(&data[written..]) {
+                Ok(count) => written += count,
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                    if started.elapsed() >= BLOCK_TIMEOUT {
+                        return Err(io::Error::new(
+                            io::ErrorKind::TimedOut,
+                            format!("pipe is full, {} bytes dropped", data.len() - written),
+                        ));
+                    }
+                    sleep(Duration::from_millis(10));
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => {
+                    // Reader is gone (EPIPE), reopen on next batch.
+                    self.file = None;
+                    return Err(err);
+                }
+            }
+        }
+        Ok(())
+    }
+}
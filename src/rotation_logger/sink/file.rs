@@ -0,0 +1,533 @@
+use std::{
+    borrow::Cow,
+    fs::{self, DirEntry, File, OpenOptions, TryLockError},
+    io::{self, BufWriter, IoSlice, Write},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+#[cfg(feature = "mmap")]
+use crate::rotation_logger::FileWriter;
+use crate::rotation_logger::{
+    FileHeader, Level, LockPolicy, Message, RecordFormat,
+    pipeline::PipelineRunner,
+    settings::FileSettings,
+    sink::{Record, Sink},
+};
+
+mod import;
+mod manifest;
+#[cfg(feature = "mmap")]
+mod mmap;
+
+pub use import::LogImport;
+use manifest::Manifest;
+#[cfg(feature = "mmap")]
+use mmap::MmapWriter;
+
+/// Writes records to the active log file and rotates it when `file_size` reached.
+pub(crate) struct FileSink {
+    settings: FileSettings,
+    current_file_buffer: Option<ActiveFile>,
+    pipeline: PipelineRunner,
+    /// Write records as JSON lines instead of formatted lines.
+    json: bool,
+    /// Lock of log set, released when sink is dropped.
+    /// Error when other process owns it, nothing is written then.
+    lock: io::Result<Option<File>>,
+    /// Sidecar manifest of set, if enabled in settings.
+    manifest: Option<Manifest>,
+    /// Number of records written since start.
+    sequence: u64,
+    /// Sequence number of first record in active file.
+    file_sequence: u64,
+    /// Rotation record waiting for the next active file.
+    rotation: Option<Record>,
+}
+
+impl FileSink {
+    pub(crate) fn new(mut settings: FileSettings) -> Self {
+        let lock = match settings.lock_policy() {
+            LockPolicy::None => Ok(None),
+            LockPolicy::FailFast => try_lock(&settings).map(Some),
+            LockPolicy::PidSuffix => try_lock(&settings).map(Some).or_else(|_| {
+                settings = settings.clone().with_pid_suffix();
+                try_lock(&settings).map(Some)
+            }),
+        };
+        if let Err(err) = &lock {
+            println!("Logger cant lock log files. Error: {err}");
+        }
+
+        let mut pipeline = PipelineRunner::load(&settings);
+        // Rotated files belong to process owning the lock.
+        if lock.is_ok() {
+            pipeline.process();
+        }
+
+        Self {
+            settings,
+            current_file_buffer: None,
+            pipeline,
+            json: false,
+            lock,
+            manifest: None,
+            sequence: 0,
+            file_sequence: 0,
+            rotation: None,
+        }
+    }
+
+    /// Sink writing one JSON object per record, see `Record::to_json_with`.
+    pub(crate) fn json(settings: FileSettings) -> Self {
+        Self {
+            json: true,
+            ..Self::new(settings)
+        }
+        .with_format("json".into())
+    }
+
+    /// Format of lines, shown in manifest of set.
+    pub(crate) fn with_format(mut self, format: String) -> Self {
+        if self.settings.manifest() {
+            self.manifest = Some(Manifest::load(&self.settings, format));
+        }
+        self
+    }
+
+    /// Rotate active file, returns its new path.
+    fn rotate(&mut self) -> io::Result<Option<PathBuf>> {
+        self.current_file_buffer = None;
+        let settings = &self.settings;
+
+        let mut logs = self.get_log_files(settings);
+
+        while count_generations(&logs) >= settings.capacity().max(1) {
+            logs = self
+                .delete_oldest_file(logs)
+                .map_err(|_| io::Error::other("Logger cant delete old logs."))?;
+        }
+        let renames = self
+            .reorder_filenames(settings, logs)
+            .map_err(|_| io::Error::other("Logger cant rotate logs."))?;
+
+        if let Some(manifest) = self.manifest.as_mut() {
+            manifest.rotate();
+        }
+        self.pipeline.relocate(&renames);
+        let rotated = renames.last().map(|(_, rotated)| rotated.clone());
+        if let Some(rotated) = &rotated {
+            self.pipeline.submit(rotated.clone());
+        }
+        Ok(rotated)
+    }
+
+    /// Record describing rotated file, see `FileSettings::with_rotation_records`.
+    fn rotation_record(&self, rotated: &Path, bytes: u64) -> Record {
+        let time = SystemTime::now();
+        let millis = time
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let name = rotated
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let last_sequence = self.sequence.saturating_sub(1);
+        let text = format!(
+            "rotated from {name} at {millis}, {bytes} bytes, seq range {}..{last_sequence}",
+            self.file_sequence
+        );
+        let message = Message::new(&["rotation_logger".into()], &text)
+            .with_level(Level::Info)
+            .with_field("rotated_from", name)
+            .with_field("rotated_at_ms", millis)
+            .with_field("bytes", bytes)
+            .with_field("first_seq", self.file_sequence)
+            .with_field("last_seq", last_sequence);
+        Record::new(message, text, time)
+    }
+
+    fn check_path_or_create(&self, settings: &FileSettings) -> Result<(), ()> {
+        match fs::exists(settings.path()) {
+            Ok(is_exist) => {
+                if is_exist {
+                    return Ok(());
+                }
+                match fs::create_dir(settings.path()) {
+                    Ok(_) => Ok(()),
+                    Err(_) => Err(()),
+                }
+            }
+            Err(_) => match fs::create_dir(settings.path()) {
+                Ok(_) => Ok(()),
+                Err(_) => Err(()),
+            },
+        }
+    }
+
+    fn get_create_current_log_file(&self, settings: &FileSettings) -> Result<ActiveFile, ()> {
+        let filename = format!("{}.{}", settings.filename(), settings.file_extension());
+        let mut filepath = settings.path().clone();
+        filepath.push(filename);
+
+        #[cfg(feature = "mmap")]
+        if let FileWriter::Mmap { region_size, sync } = settings.writer() {
+            return match MmapWriter::open(&filepath, region_size, sync) {
+                Ok(writer) => Ok(ActiveFile::Mapped(writer)),
+                Err(_) => Err(()),
+            };
+        }
+
+        match OpenOptions::new().append(true).create(true).open(filepath) {
+            Ok(file) => Ok(ActiveFile::Buffered(BufWriter::new(file))),
+            Err(_) => Err(()),
+        }
+    }
+
+    /// Log files of the rotation set, ordered from active file to the oldest generation.
+    fn get_log_files(&self, settings: &FileSettings) -> Vec<(LogGeneration, DirEntry)> {
+        match fs::read_dir(settings.path()) {
+            Ok(dir_content) => {
+                let mut filtered_files: Vec<(LogGeneration, DirEntry)> = dir_content
+                    .filter_map(|file| file.ok())
+                    .filter_map(|file| {
+                        let generation =
+                            LogGeneration::parse(settings, &file.file_name().to_string_lossy())?;
+                        Some((generation, file))
+                    })
+                    .collect();
+
+                filtered_files.sort_by(|a, b| {
+                    a.0.index
+                        .cmp(&b.0.index)
+                        .then_with(|| a.0.suffix.cmp(&b.0.suffix))
+                });
+                filtered_files
+            }
+            Err(_) => vec![],
+        }
+    }
+
+    /// Delete every file of the oldest generation.
+    fn delete_oldest_file(
+        &self,
+        mut logs: Vec<(LogGeneration, DirEntry)>,
+    ) -> Result<Vec<(LogGeneration, DirEntry)>, ()> {
+        let oldest = match logs.last() {
+            Some((generation, _)) => generation.index,
+            None => return Ok(logs),
+        };
+        while let Some((generation, file)) = logs.last() {
+            if generation.index != oldest {
+                break;
+            }
+            if fs::remove_file(file.path()).is_err() {
+                return Err(());
+            }
+            logs.pop();
+        }
+        Ok(logs)
+    }
+
+    /// Shift every generation by one. Active file becomes generation `0`.
+    /// Returns list of renamed paths, active file is always the last one.
+    fn reorder_filenames(
+        &self,
+        settings: &FileSettings,
+        logs: Vec<(LogGeneration, DirEntry)>,
+    ) -> Result<Vec<(PathBuf, PathBuf)>, ()> {
+        let mut renames = vec![];
+        for (generation, file) in logs.iter().rev() {
+            let new_log_number = match generation.index {
+                Some(val) => val + 1,
+                None => 0,
+            };
+
+            let new_filename = format!(
+                "{}.{}{new_log_number}{}",
+                settings.filename(),
+                settings.file_extension(),
+                generation.suffix
+            );
+            let new_path = settings.path().join(new_filename);
+
+            match fs::rename(file.path(), &new_path) {
+                Ok(_) => renames.push((file.path(), new_path)),
+                Err(_) => return Err(()),
+            }
+        }
+
+        Ok(renames)
+    }
+}
+
+impl Sink for FileSink {
+    fn write_batch(&mut self, records: &[Record]) -> io::Result<()> {
+        if let Err(err) = &self.lock {
+            return Err(io::Error::new(err.kind(), err.to_string()));
+        }
+        if self.check_path_or_create(&self.settings).is_err() {
+            return Err(io::Error::other("Logger cant access to log dir."));
+        };
+
+        if self.current_file_buffer.is_none() {
+            match self.get_create_current_log_file(&self.settings) {
+                Ok(val) => {
+                    // Out of space is reported here, before anything is written to the new file.
+                    if self.settings.preallocation() {
+                        preallocate(val.file(), self.settings.file_size() / 8)?;
+                    }
+                    let active = self.current_file_buffer.insert(val);
+                    if active.len()? == 0 {
+                        let preamble = self.settings.encoding().preamble();
+                        if !preamble.is_empty() {
+                            active.write_lines(&[preamble], b"")?;
+                        }
+                        if self.settings.schema_header() {
+                            let format = match self.json {
+                                true => RecordFormat::Json,
+                                false => RecordFormat::Text,
+                            };
+                            active.write_lines(
+                                &[FileHeader::new(format).line().as_bytes()],
+                                self.settings.line_ending().as_bytes(),
+                            )?;
+                        }
+                    }
+                }
+                Err(_) => return Err(io::Error::other("Logger cant access to log file.")),
+            };
+        };
+
+        let Some(file_buffer) = self.current_file_buffer.as_mut() else {
+            return Ok(());
+        };
+
+        let rotation = self.rotation.take();
+        let records_with_rotation = rotation.iter().chain(records);
+        let json: Vec<String> = if self.json {
+            records_with_rotation
+                .clone()
+                .map(|record| record.to_json_with(self.settings.json_layout()))
+                .collect()
+        } else {
+            vec![]
+        };
+        let encoding = self.settings.encoding();
+        let encoded: Vec<Cow<[u8]>> = if self.json {
+            json.iter().map(|line| encoding.encode(line)).collect()
+        } else {
+            records_with_rotation
+                .map(|record| encoding.encode(record.line()))
+                .collect()
+        };
+        let lines: Vec<&[u8]> = encoded.iter().map(|line| line.as_ref()).collect();
+        file_buffer.write_lines(&lines, self.settings.line_ending().as_bytes())?;
+        self.sequence += records.len() as u64;
+        if let (Some(manifest), Some(first), Some(last)) =
+            (self.manifest.as_mut(), records.first(), records.last())
+        {
+            manifest.extend(first.time(), last.time());
+        }
+
+        let bytes = match file_buffer.len() {
+            Ok(val) => val,
+            Err(_) => return Err(io::Error::other("Logger cant access to log file.")),
+        };
+
+        if bytes * 8 >= self.settings.file_size()
+            && let Some(rotated) = self.rotate()?
+        {
+            if self.settings.rotation_records() {
+                self.rotation = Some(self.rotation_record(&rotated, bytes));
+            }
+            self.file_sequence = self.sequence;
+        }
+        if self.manifest.is_some() {
+            let files = self.get_log_files(&self.settings);
+            if let Some(manifest) = self.manifest.as_mut() {
+                manifest.write(&self.settings, &files)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if let Some(file_buffer) = self.current_file_buffer.as_mut() {
+            file_buffer.flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// Active log file, written through buffer or memory map.
+enum ActiveFile {
+    Buffered(BufWriter<File>),
+    #[cfg(feature = "mmap")]
+    Mapped(MmapWriter),
+}
+
+impl ActiveFile {
+    /// Write lines each followed by `ending` and sync them to disk.
+    fn write_lines(&mut self, lines: &[&[u8]], ending: &[u8]) -> io::Result<()> {
+        match self {
+            ActiveFile::Buffered(file_buffer) => {
+                // Lines are passed to the file as they are, without joining them into one string.
+                let mut slices = Vec::with_capacity(lines.len() * 2);
+                for line in lines {
+                    slices.push(IoSlice::new(line));
+                    slices.push(IoSlice::new(ending));
+                }
+                write_all_vectored(file_buffer, &mut slices)?;
+                file_buffer.flush()?;
+                let _ = file_buffer.get_ref().sync_all();
+                Ok(())
+            }
+            #[cfg(feature = "mmap")]
+            ActiveFile::Mapped(writer) => writer.write_lines(lines, ending),
+        }
+    }
+
+    /// Write what is buffered and sync it to disk.
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            ActiveFile::Buffered(file_buffer) => {
+                file_buffer.flush()?;
+                file_buffer.get_ref().sync_all()
+            }
+            #[cfg(feature = "mmap")]
+            ActiveFile::Mapped(writer) => writer.flush(),
+        }
+    }
+
+    fn file(&self) -> &File {
+        match self {
+            ActiveFile::Buffered(file_buffer) => file_buffer.get_ref(),
+            #[cfg(feature = "mmap")]
+            ActiveFile::Mapped(writer) => writer.file(),
+        }
+    }
+
+    /// Size of written data.
+    fn len(&self) -> io::Result<u64> {
+        match self {
+            ActiveFile::Buffered(file_buffer) => Ok(file_buffer.get_ref().metadata()?.len()),
+            #[cfg(feature = "mmap")]
+            ActiveFile::Mapped(writer) => Ok(writer.len()),
+        }
+    }
+}
+
+/// Position of a file in rotation set: `<filename>.<extension><index><suffix>`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct LogGeneration {
+    /// `None` for active file, otherwise number of rotations file went through.
+    pub(crate) index: Option<u32>,
+    /// Anything appended by rotation pipeline, e.g. `.gz`.
+    pub(crate) suffix: String,
+}
+
+impl LogGeneration {
+    /// Parse file name, `None` if file is not part of rotation set.
+    pub(crate) fn parse(settings: &FileSettings, file_name: &str) -> Option<Self> {
+        let rest = file_name.strip_prefix(&format!(
+            "{}.{}",
+            settings.filename(),
+            settings.file_extension()
+        ))?;
+        let digits = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+        let (index, suffix) = rest.split_at(digits);
+
+        if index.is_empty() {
+            // Active file has no suffix.
+            return suffix.is_empty().then(|| Self {
+                index: None,
+                suffix: "".into(),
+            });
+        }
+        if !suffix.is_empty() && !suffix.starts_with('.') {
+            return None;
+        }
+        Some(Self {
+            index: Some(index.parse().ok()?),
+            suffix: suffix.to_string(),
+        })
+    }
+}
+
+/// Take advisory lock of log set, on hidden `.<filename>.lock` file next to log files.
+fn try_lock(settings: &FileSettings) -> io::Result<File> {
+    fs::create_dir_all(settings.path())?;
+    let file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(
+            settings
+                .path()
+                .join(format!(".{}.lock", settings.filename())),
+        )?;
+    match file.try_lock() {
+        Ok(_) => Ok(file),
+        Err(TryLockError::WouldBlock) => Err(io::Error::new(
+            io::ErrorKind::WouldBlock,
+            "log files are used by other process",
+        )),
+        Err(TryLockError::Error(err)) => Err(err),
+    }
+}
+
+/// Reserve disk space for `size` bytes of file, without changing its length.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn preallocate(file: &File, size: u64) -> io::Result<()> {
+    use std::os::fd::AsRawFd;
+
+    let result = unsafe {
+        libc::fallocate(
+            file.as_raw_fd(),
+            libc::FALLOC_FL_KEEP_SIZE,
+            0,
+            size as libc::off_t,
+        )
+    };
+    if result == 0 {
+        return Ok(());
+    }
+    let err = io::Error::last_os_error();
+    // File system can not preallocate, file is written as without it.
+    if err.raw_os_error() == Some(libc::EOPNOTSUPP) {
+        return Ok(());
+    }
+    Err(err)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn preallocate(_: &File, _: u64) -> io::Result<()> {
+    Ok(())
+}
+
+/// Write every slice, `Write::write_all_vectored` is not stable yet.
+fn write_all_vectored(writer: &mut impl Write, mut slices: &mut [IoSlice]) -> io::Result<()> {
+    while !slices.is_empty() {
+        match writer.write_vectored(slices) {
+            Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+            Ok(written) => IoSlice::advance_slices(&mut slices, written),
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(())
+}
+
+fn count_generations(logs: &[(LogGeneration, DirEntry)]) -> usize {
+    let mut count = 0;
+    let mut last = None;
+    for (generation, _) in logs {
+        if last != Some(generation.index) {
+            count += 1;
+            last = Some(generation.index);
+        }
+    }
+    count
+}
@@ -0,0 +1,142 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+use crate::rotation_logger::{CompressStage, Compression, pipeline::RotationStage};
+use crate::rotation_logger::{FileSettings, sink::file::LogGeneration};
+
+/// Split existing log file into rotated files of `FileSettings`, so history of app
+/// written before adopting this crate is rotated, read and searched with the rest of set.
+///
+/// Chunks are not larger than `file_size`, unless single line is, and are split between lines.
+/// They go after the oldest generation already in directory, the newest chunk first.
+/// Source file is left as it is.
+///
+/// # Example:
+///
+/// ```no_run
+/// # use rotation_logger::{FileSettings, FileSize, LogImport};
+/// let settings = FileSettings::new(
+///     "./logs".into(), 100, FileSize::from_megabytes(5), "app".into(), "log".into(),
+/// );
+/// let files = LogImport::new(settings).run("./legacy/app.log").unwrap();
+/// println!("imported into {} files", files.len());
+/// ```
+#[derive(Debug, Clone)]
+pub struct LogImport {
+    settings: FileSettings,
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    compression: Option<Compression>,
+}
+
+impl LogImport {
+    pub fn new(settings: FileSettings) -> Self {
+        Self {
+            settings,
+            #[cfg(any(feature = "gzip", feature = "zstd"))]
+            compression: None,
+        }
+    }
+
+    /// Compress every imported file, same as `CompressStage`.
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = Some(compression);
+        self
+    }
+
+    pub fn settings(&self) -> &FileSettings {
+        &self.settings
+    }
+
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    pub fn compression(&self) -> Option<&Compression> {
+        self.compression.as_ref()
+    }
+
+    /// Import `source`, returns files created from the newest to the oldest.
+    pub fn run(&self, source: impl AsRef<Path>) -> io::Result<Vec<PathBuf>> {
+        let dir = self.settings.path();
+        fs::create_dir_all(dir)?;
+        let chunks = self.split(source.as_ref())?;
+
+        // Imported history is older than every file of set.
+        let first_index = fs::read_dir(dir)?
+            .filter_map(Result::ok)
+            .filter_map(|entry| {
+                LogGeneration::parse(&self.settings, &entry.file_name().to_string_lossy())?.index
+            })
+            .max()
+            .map_or(0, |index| index + 1);
+
+        let mut files = Vec::with_capacity(chunks.len());
+        for (position, chunk) in chunks.iter().rev().enumerate() {
+            let path = dir.join(format!(
+                "{}.{}{}",
+                self.settings.filename(),
+                self.settings.file_extension(),
+                first_index + position as u32
+            ));
+            fs::rename(chunk, &path)?;
+            files.push(self.compress(path)?);
+        }
+        Ok(files)
+    }
+
+    /// Write chunks of `source` to temporary files, the oldest first.
+    fn split(&self, source: &Path) -> io::Result<Vec<PathBuf>> {
+        let max_bytes = (self.settings.file_size() / 8).max(1);
+        let mut reader = BufReader::new(File::open(source)?);
+        let mut chunks = vec![];
+        let mut chunk: Option<(BufWriter<File>, u64)> = None;
+        let mut line = vec![];
+        loop {
+            line.clear();
+            let read = reader.read_until(b'\n', &mut line)? as u64;
+            if read == 0 {
+                break;
+            }
+            let full = chunk
+                .as_ref()
+                .is_some_and(|(_, written)| written + read > max_bytes);
+            if full && let Some((mut writer, _)) = chunk.take() {
+                writer.flush()?;
+            }
+            if chunk.is_none() {
+                let path = self.settings.path().join(format!(
+                    ".{}.import{}",
+                    self.settings.filename(),
+                    chunks.len()
+                ));
+                chunk = Some((BufWriter::new(File::create(&path)?), 0));
+                chunks.push(path);
+            }
+            if let Some((writer, written)) = chunk.as_mut() {
+                writer.write_all(&line)?;
+                *written += read;
+            }
+        }
+        if let Some((mut writer, _)) = chunk {
+            writer.flush()?;
+        }
+        Ok(chunks)
+    }
+
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    fn compress(&self, path: PathBuf) -> io::Result<PathBuf> {
+        match &self.compression {
+            Some(compression) => Ok(CompressStage::new(compression.clone())
+                .process(&path)?
+                .unwrap_or(path)),
+            None => Ok(path),
+        }
+    }
+
+    #[cfg(not(any(feature = "gzip", feature = "zstd")))]
+    fn compress(&self, path: PathBuf) -> io::Result<PathBuf> {
+        Ok(path)
+    }
+}
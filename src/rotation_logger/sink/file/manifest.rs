@@ -0,0 +1,121 @@
+use std::{
+    collections::BTreeMap,
+    fs::{self, DirEntry},
+    io,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::rotation_logger::{
+    SCHEMA_VERSION, reader::json, settings::FileSettings, sink::file::LogGeneration,
+};
+
+/// `<filename>.manifest.json` next to rotation set: format, rotation policy
+/// and files from active to the oldest with time range of their records.
+pub(super) struct Manifest {
+    path: PathBuf,
+    /// Format string of lines, or `json`.
+    format: String,
+    /// Milliseconds since Unix epoch of first and last record of every generation.
+    ranges: BTreeMap<Option<u32>, (u64, u64)>,
+}
+
+impl Manifest {
+    /// Manifest of set, with ranges of manifest written before if any.
+    pub(super) fn load(settings: &FileSettings, format: String) -> Self {
+        let path = settings
+            .path()
+            .join(format!("{}.manifest.json", settings.filename()));
+        let mut ranges = BTreeMap::new();
+        let previous = fs::read_to_string(&path).ok();
+        if let Some(json::Json::Array(files)) = previous
+            .as_deref()
+            .and_then(json::parse)
+            .as_ref()
+            .and_then(|manifest| manifest.get("files"))
+        {
+            for file in files {
+                let generation = file
+                    .get("generation")
+                    .and_then(json::Json::as_f64)
+                    .map(|index| index as u32);
+                let time = |key| file.get(key).and_then(json::Json::as_f64);
+                if let (Some(first), Some(last)) = (time("first_ms"), time("last_ms")) {
+                    ranges
+                        .entry(generation)
+                        .or_insert((first as u64, last as u64));
+                }
+            }
+        }
+        Self {
+            path,
+            format,
+            ranges,
+        }
+    }
+
+    /// Extend range of active file with time of written records.
+    pub(super) fn extend(&mut self, first: SystemTime, last: SystemTime) {
+        let millis = |time: SystemTime| {
+            time.duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64
+        };
+        let range = self
+            .ranges
+            .entry(None)
+            .or_insert((millis(first), millis(last)));
+        range.0 = range.0.min(millis(first));
+        range.1 = range.1.max(millis(last));
+    }
+
+    /// Shift generations, same as rotation renames files.
+    pub(super) fn rotate(&mut self) {
+        self.ranges = std::mem::take(&mut self.ranges)
+            .into_iter()
+            .map(|(index, range)| (Some(index.map_or(0, |index| index + 1)), range))
+            .collect();
+    }
+
+    /// Write manifest of `files`, replacing previous one at once.
+    pub(super) fn write(
+        &mut self,
+        settings: &FileSettings,
+        files: &[(LogGeneration, DirEntry)],
+    ) -> io::Result<()> {
+        // Generations deleted by rotation are forgotten.
+        self.ranges.retain(|index, _| {
+            files
+                .iter()
+                .any(|(generation, _)| generation.index == *index)
+        });
+        let files = files
+            .iter()
+            .map(|(generation, file)| {
+                let generation_json = generation
+                    .index
+                    .map_or("null".to_string(), |index| index.to_string());
+                let range = match self.ranges.get(&generation.index) {
+                    Some((first, last)) => format!(",\"first_ms\":{first},\"last_ms\":{last}"),
+                    None => String::new(),
+                };
+                format!(
+                    "{{\"name\":{},\"generation\":{generation_json}{range}}}",
+                    json::string(&file.file_name().to_string_lossy())
+                )
+            })
+            .collect::<Vec<String>>()
+            .join(",");
+        let manifest = format!(
+            "{{\"schema\":{SCHEMA_VERSION},\"format\":{},\"rotation\":{{\"capacity\":{},\"file_size\":{},\"filename\":{},\"extension\":{}}},\"files\":[{files}]}}\n",
+            json::string(&self.format),
+            settings.capacity(),
+            settings.file_size() / 8,
+            json::string(settings.filename()),
+            json::string(settings.file_extension()),
+        );
+        let temporary = self.path.with_extension("json.tmp");
+        fs::write(&temporary, manifest)?;
+        fs::rename(&temporary, &self.path)
+    }
+}
@@ -0,0 +1,112 @@
+use std::{
+    fs::{File, OpenOptions},
+    io,
+    path::Path,
+};
+
+use memmap2::{Mmap, MmapMut};
+
+use crate::rotation_logger::MsyncPolicy;
+
+/// Appends lines to file through memory map.
+/// File is grown by `region_size` ahead of write cursor and cut to written size when closed.
+pub(super) struct MmapWriter {
+    file: File,
+    /// Unmapped only while file is resized, Windows can not resize mapped file.
+    map: Option<MmapMut>,
+    cursor: usize,
+    region_size: usize,
+    sync: MsyncPolicy,
+}
+
+impl MmapWriter {
+    pub(super) fn open(path: &Path, region_size: usize, sync: MsyncPolicy) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        // Process may die before file is cut, so unused zeroed region is skipped.
+        let cursor = match file.metadata()?.len() {
+            0 => 0,
+            _ => {
+                let map = unsafe { Mmap::map(&file)? };
+                map.iter()
+                    .rposition(|byte| *byte != 0)
+                    .map_or(0, |last| last + 1)
+            }
+        };
+        let mut writer = Self {
+            file,
+            map: None,
+            cursor,
+            region_size: region_size.max(1),
+            sync,
+        };
+        writer.resize(cursor + writer.region_size)?;
+        Ok(writer)
+    }
+
+    /// Copy lines each followed by `ending` after write cursor, growing file when needed.
+    pub(super) fn write_lines(&mut self, lines: &[&[u8]], ending: &[u8]) -> io::Result<()> {
+        let size = lines
+            .iter()
+            .map(|line| line.len() + ending.len())
+            .sum::<usize>();
+        let mapped = self.map.as_ref().map_or(0, |map| map.len());
+        if self.cursor + size > mapped {
+            self.resize(self.cursor + size + self.region_size)?;
+        }
+        let Some(map) = self.map.as_mut() else {
+            return Err(io::Error::other("Logger cant map log file."));
+        };
+
+        let start = self.cursor;
+        for line in lines {
+            map[self.cursor..self.cursor + line.len()].copy_from_slice(line);
+            self.cursor += line.len();
+            map[self.cursor..self.cursor + ending.len()].copy_from_slice(ending);
+            self.cursor += ending.len();
+        }
+        match self.sync {
+            MsyncPolicy::EveryBatch => map.flush_range(start, size),
+            MsyncPolicy::Async => map.flush_async_range(start, size),
+            MsyncPolicy::Never => Ok(()),
+        }
+    }
+
+    pub(super) fn flush(&mut self) -> io::Result<()> {
+        match self.map.as_ref() {
+            Some(map) => map.flush(),
+            None => Ok(()),
+        }
+    }
+
+    pub(super) fn file(&self) -> &File {
+        &self.file
+    }
+
+    /// Size of written data, without preallocated region.
+    pub(super) fn len(&self) -> u64 {
+        self.cursor as u64
+    }
+
+    fn resize(&mut self, size: usize) -> io::Result<()> {
+        if let Some(map) = self.map.take() {
+            map.flush()?;
+        }
+        self.file.set_len(size as u64)?;
+        self.map = Some(unsafe { MmapMut::map_mut(&self.file)? });
+        Ok(())
+    }
+}
+
+impl Drop for MmapWriter {
+    fn drop(&mut self) {
+        if let Some(map) = self.map.take() {
+            let _ = map.flush();
+        }
+        let _ = self.file.set_len(self.cursor as u64);
+    }
+}
@@ -0,0 +1,229 @@
+use std::{
+    collections::BTreeMap,
+    io,
+    time::{Duration, Instant},
+};
+
+use chrono::SecondsFormat;
+use serde_json::{Value, json};
+
+use crate::rotation_logger::{
+    logger::Level,
+    sink::{
+        Record, Sink,
+        http::{HttpError, RetryPolicy},
+    },
+};
+
+const ENTRIES_WRITE_URL: &str = "https://logging.googleapis.com/v2/entries:write";
+const METADATA_TOKEN_URL: &str =
+    "http://metadata.google.internal/computeMetadata/v1/instance/service-accounts/default/token";
+
+/// Source of OAuth access token for Cloud Logging API.
+#[derive(Debug, Clone, Default)]
+pub enum GcpCredentials {
+    /// Token of default service account from instance metadata server (GCE, GKE, Cloud Run).
+    #[default]
+    MetadataServer,
+    /// Token obtained by application, e.g. `gcloud auth print-access-token`.
+    AccessToken(String),
+}
+
+/// Writes records to Google Cloud Logging `entries:write` API.
+///
+/// Message text goes to `textPayload`, modules to `modules` label, and `Level` mapped to severity.
+/// Records are sent in batches of up to `max_batch` entries, retried by `RetryPolicy`.
+pub struct GcpLoggingSink {
+    project_id: String,
+    log_id: String,
+    resource_type: String,
+    resource_labels: BTreeMap<String, String>,
+    labels: BTreeMap<String, String>,
+    credentials: GcpCredentials,
+    endpoint: String,
+    max_batch: usize,
+    retry: RetryPolicy,
+    agent: ureq::Agent,
+    token: Option<(String, Instant)>,
+}
+
+impl GcpLoggingSink {
+    pub fn new(project_id: &str, log_id: &str) -> Self {
+        Self {
+            project_id: project_id.into(),
+            log_id: log_id.into(),
+            resource_type: "global".into(),
+            resource_labels: BTreeMap::new(),
+            labels: BTreeMap::new(),
+            credentials: Default::default(),
+            endpoint: ENTRIES_WRITE_URL.into(),
+            max_batch: 500,
+            retry: Default::default(),
+            agent: ureq::AgentBuilder::new()
+                .timeout(Duration::from_secs(30))
+                .build(),
+            token: None,
+        }
+    }
+
+    /// Monitored resource, e.g. `gce_instance` with `instance_id` and `zone` labels,
+    /// or `k8s_container` with `cluster_name`, `namespace_name`, `pod_name`, `container_name`.
+    pub fn with_resource<K: Into<String>, V: Into<String>>(
+        mut self,
+        resource_type: &str,
+        labels: impl IntoIterator<Item = (K, V)>,
+    ) -> Self {
+        self.resource_type = resource_type.into();
+        self.resource_labels = labels
+            .into_iter()
+            .map(|(key, value)| (key.into(), value.into()))
+            .collect();
+        self
+    }
+
+    /// Label added to every entry.
+    pub fn with_label(mut self, key: &str, value: &str) -> Self {
+        self.labels.insert(key.into(), value.into());
+        self
+    }
+
+    pub fn with_credentials(mut self, credentials: GcpCredentials) -> Self {
+        self.credentials = credentials;
+        self
+    }
+
+    /// Override API url, for private endpoints or emulators.
+    pub fn with_endpoint(mut self, endpoint: &str) -> Self {
+        self.endpoint = endpoint.into();
+        self
+    }
+
+    /// Maximal number of entries per request.
+    pub fn with_max_batch(mut self, max_batch: usize) -> Self {
+        self.max_batch = max_batch.max(1);
+        self
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Cloud Logging severity for `Level`.
+    pub fn severity(level: Level) -> &'static str {
+        match level {
+            Level::Error => "ERROR",
+            Level::Warn => "WARNING",
+            Level::Info => "INFO",
+            Level::Debug | Level::Trace => "DEBUG",
+        }
+    }
+
+    pub(crate) fn request_body(&self, records: &[Record]) -> Value {
+        let entries: Vec<Value> = records
+            .iter()
+            .map(|record| {
+                let message = record.message();
+                let mut entry = json!({
+                    "severity": Self::severity(message.level()),
+                    "timestamp": record.timestamp().to_rfc3339_opts(SecondsFormat::Micros, true),
+                    "textPayload": message.text(),
+                });
+                if !message.modules().is_empty() {
+                    entry["labels"] = json!({ "modules": message.modules().join("::") });
+                }
+                entry
+            })
+            .collect();
+
+        json!({
+            "logName": format!("projects/{}/logs/{}", self.project_id, url_encode(&self.log_id)),
+            "resource": {
+                "type": self.resource_type,
+                "labels": self.resource_labels,
+            },
+            "labels": self.labels,
+            "entries": entries,
+            "partialSuccess": true,
+        })
+    }
+
+    fn access_token(&mut self) -> Result<String, HttpError> {
+        if let Some((token, expires)) = &self.token
+            && Instant::now() < *expires
+        {
+            return Ok(token.clone());
+        }
+
+        match &self.credentials {
+            GcpCredentials::AccessToken(token) => Ok(token.clone()),
+            GcpCredentials::MetadataServer => {
+                let response: Value = self
+                    .agent
+                    .get(METADATA_TOKEN_URL)
+                    .set("Metadata-Flavor", "Google")
+                    .call()?
+                    .into_string()
+                    .map_err(|err| HttpError::Retry(err, None))
+                    .and_then(|body| {
+                        serde_json::from_str(&body)
+                            .map_err(|err| HttpError::Fatal(io::Error::other(err)))
+                    })?;
+
+                let token = response["access_token"]
+                    .as_str()
+                    .ok_or_else(|| HttpError::retry("metadata server returned no access token"))?
+                    .to_string();
+                let expires_in = response["expires_in"].as_u64().unwrap_or(300);
+                // Refresh a minute before actual expiration.
+                let expires = Instant::now() + Duration::from_secs(expires_in.saturating_sub(60));
+                self.token = Some((token.clone(), expires));
+                Ok(token)
+            }
+        }
+    }
+
+    fn send(&mut self, body: &str) -> io::Result<()> {
+        let retry = self.retry.clone();
+        retry.run(|| {
+            let token = self.access_token()?;
+            match self
+                .agent
+                .post(&self.endpoint)
+                .set("Authorization", &format!("Bearer {token}"))
+                .set("Content-Type", "application/json")
+                .send_string(body)
+            {
+                Ok(_) => Ok(()),
+                Err(ureq::Error::Status(401, _)) => {
+                    self.token = None;
+                    Err(HttpError::retry("access token rejected"))
+                }
+                Err(err) => Err(err.into()),
+            }
+        })
+    }
+}
+
+impl Sink for GcpLoggingSink {
+    fn write_batch(&mut self, records: &[Record]) -> io::Result<()> {
+        for chunk in records.chunks(self.max_batch) {
+            let body = self.request_body(chunk).to_string();
+            self.send(&body)?;
+        }
+        Ok(())
+    }
+}
+
+/// Percent-encode everything except unreserved URL characters.
+fn url_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{byte:02X}"),
+        })
+        .collect()
+}
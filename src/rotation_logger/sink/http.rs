@@ -0,0 +1,127 @@
+use std::{
+    io,
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+/// Retry rules for network sinks.
+/// Delay doubles after every failed attempt, up to `max_backoff`.
+///
+/// Sink retries on worker thread, which writes nothing else meanwhile, so retries stop
+/// once the next one would start after `max_elapsed`. Default gives up after 4 seconds,
+/// before 5 seconds `flush_on_exit` waits for worker.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    max_elapsed: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(2),
+            max_elapsed: Duration::from_secs(4),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new(max_attempts: u32, initial_backoff: Duration, max_backoff: Duration) -> Self {
+        Self {
+            max_attempts,
+            initial_backoff,
+            max_backoff,
+            ..Default::default()
+        }
+    }
+
+    /// No retry starts later than `max_elapsed` after the first attempt.
+    pub fn with_max_elapsed(mut self, max_elapsed: Duration) -> Self {
+        self.max_elapsed = max_elapsed;
+        self
+    }
+
+    /// Single attempt, without retries.
+    pub fn never() -> Self {
+        Self::new(1, Duration::ZERO, Duration::ZERO)
+    }
+
+    /// Run request until it succeed, fail with not retryable error or attempts are exhausted.
+    pub(crate) fn run<T>(
+        &self,
+        mut request: impl FnMut() -> Result<T, HttpError>,
+    ) -> io::Result<T> {
+        let started = Instant::now();
+        let mut backoff = self.initial_backoff;
+        let mut attempt = 1;
+        loop {
+            match request() {
+                Ok(value) => return Ok(value),
+                Err(HttpError::Fatal(err)) => return Err(err),
+                Err(HttpError::Retry(err, delay)) => {
+                    let delay = delay.unwrap_or(backoff).min(self.max_backoff);
+                    if attempt >= self.max_attempts || started.elapsed() + delay > self.max_elapsed
+                    {
+                        return Err(err);
+                    }
+                    sleep(delay);
+                    backoff = (backoff * 2).min(self.max_backoff);
+                    attempt += 1;
+                }
+            }
+        }
+    }
+}
+
+/// Failed request, split by whether repeating it makes sense.
+pub(crate) enum HttpError {
+    /// Temporary failure, with delay requested by server if any.
+    Retry(io::Error, Option<Duration>),
+    Fatal(io::Error),
+}
+
+impl HttpError {
+    pub(crate) fn retry(message: impl Into<String>) -> Self {
+        Self::Retry(io::Error::other(message.into()), None)
+    }
+}
+
+impl From<ureq::Error> for HttpError {
+    fn from(value: ureq::Error) -> Self {
+        match value {
+            ureq::Error::Status(status, response) => {
+                let retry_after = response
+                    .header("Retry-After")
+                    .and_then(|value| value.trim().parse::<u64>().ok())
+                    .map(Duration::from_secs);
+                let body = response.into_string().unwrap_or_default();
+                let err = io::Error::other(format!("http status {status}: {body}"));
+
+                if status == 408 || status == 429 || status >= 500 {
+                    Self::Retry(err, retry_after)
+                } else {
+                    Self::Fatal(err)
+                }
+            }
+            ureq::Error::Transport(transport) => {
+                Self::Retry(io::Error::other(transport.to_string()), None)
+            }
+        }
+    }
+}
+
+/// Cut string to at most `max` bytes at char boundary.
+pub(crate) fn truncate(value: &str, max: usize) -> &str {
+    if value.len() <= max {
+        return value;
+    }
+    let mut end = max;
+    while !value.is_char_boundary(end) {
+        end -= 1;
+    }
+    &value[..end]
+}
@@ -0,0 +1,48 @@
+use std::collections::HashMap;
+
+/// Layout of JSON records, see `Record::to_json_with`.
+/// Renames top-level keys to match existing index mappings, e.g. Elasticsearch,
+/// and switches between single line and indented objects.
+///
+/// `LogReader` reads only compact records with original keys.
+///
+/// # Example:
+///
+/// ```
+/// # use rotation_logger::JsonLayout;
+/// let layout = JsonLayout::default()
+///     .with_key("message", "msg")
+///     .with_key("timestamp", "@timestamp");
+/// assert_eq!(layout.key("message"), "msg");
+/// assert_eq!(layout.key("level"), "level");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JsonLayout {
+    pretty: bool,
+    /// Original key to key written.
+    keys: HashMap<String, String>,
+}
+
+impl JsonLayout {
+    /// Object over several lines with two spaces indent, single line when `false`.
+    pub fn with_pretty(mut self, pretty: bool) -> Self {
+        self.pretty = pretty;
+        self
+    }
+
+    /// Write top-level key `from` as `to`. Keys are `schema`, `timestamp`, `level`, `target`,
+    /// `modules`, `message` and `fields`.
+    pub fn with_key(mut self, from: &str, to: &str) -> Self {
+        self.keys.insert(from.into(), to.into());
+        self
+    }
+
+    pub fn pretty(&self) -> bool {
+        self.pretty
+    }
+
+    /// Key written for original `key`.
+    pub fn key<'a>(&'a self, key: &'a str) -> &'a str {
+        self.keys.get(key).map(String::as_str).unwrap_or(key)
+    }
+}
@@ -0,0 +1,71 @@
+use std::{
+    ffi::{CString, c_char, c_int},
+    io,
+};
+
+use crate::rotation_logger::{
+    logger::Level,
+    sink::{Record, Sink},
+};
+
+/// Tags longer than this are rejected by logcat before Android 8.
+const MAX_TAG_LEN: usize = 23;
+
+#[link(name = "log")]
+unsafe extern "C" {
+    fn __android_log_write(prio: c_int, tag: *const c_char, text: *const c_char) -> c_int;
+}
+
+/// Writes records to Android logcat.
+///
+/// Modules joined with `::` are used as tag, or default tag for messages without modules.
+/// Logcat adds its own time and level, so only message text is written.
+pub struct LogcatSink {
+    tag: String,
+}
+
+impl LogcatSink {
+    /// `tag` is used for messages without modules, e.g. application name.
+    pub fn new(tag: &str) -> Self {
+        Self { tag: tag.into() }
+    }
+
+    fn priority(level: Level) -> c_int {
+        // android/log.h priorities
+        match level {
+            Level::Error => 6,
+            Level::Warn => 5,
+            Level::Info => 4,
+            Level::Debug => 3,
+            Level::Trace => 2,
+        }
+    }
+}
+
+impl Sink for LogcatSink {
+    fn write_batch(&mut self, records: &[Record]) -> io::Result<()> {
+        for record in records {
+            let message = record.message();
+            let mut tag = if message.modules().is_empty() {
+                self.tag.clone()
+            } else {
+                message.modules().join("::")
+            };
+            if tag.len() > MAX_TAG_LEN {
+                let mut end = MAX_TAG_LEN;
+                while !tag.is_char_boundary(end) {
+                    end -= 1;
+                }
+                tag.truncate(end);
+            }
+
+            // Interior nul would cut message, drop it instead.
+            let tag = CString::new(tag.replace('\0', "")).unwrap_or_default();
+            let text = CString::new(message.text().replace('\0', "")).unwrap_or_default();
+            unsafe {
+                __android_log_write(Self::priority(message.level()), tag.as_ptr(), text.as_ptr());
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,7 @@
+// os_log is a macro, so it is called from C with message passed as public string.
+#include <os/log.h>
+#include <stdint.h>
+
+void rotation_logger_os_log(os_log_t log, uint8_t type, const char *message) {
+    os_log_with_type(log, (os_log_type_t)type, "%{public}s", message);
+}
@@ -0,0 +1,81 @@
+use std::{
+    collections::HashMap,
+    ffi::{CString, c_char, c_void},
+    io,
+};
+
+use crate::rotation_logger::{
+    logger::Level,
+    sink::{Record, Sink},
+};
+
+unsafe extern "C" {
+    fn os_log_create(subsystem: *const c_char, category: *const c_char) -> *mut c_void;
+    /// Defined in `oslog.c`.
+    fn rotation_logger_os_log(log: *mut c_void, log_type: u8, message: *const c_char);
+}
+
+/// `os_log_t` handle, which lives until the process exits.
+struct OsLog(*mut c_void);
+
+// Handles are thread safe by os_log documentation.
+unsafe impl Send for OsLog {}
+
+/// Writes records to Apple unified logging, so they show up in Console.app.
+///
+/// `subsystem` is usually reverse DNS name of application, category is made
+/// from modules joined with `::` (or `default` without modules).
+pub struct OsLogSink {
+    subsystem: CString,
+    logs: HashMap<String, OsLog>,
+}
+
+impl OsLogSink {
+    /// Subsystem such as `com.example.app`.
+    pub fn new(subsystem: &str) -> Self {
+        Self {
+            subsystem: to_cstring(subsystem),
+            logs: HashMap::new(),
+        }
+    }
+
+    fn log_type(level: Level) -> u8 {
+        // os_log_type_t values
+        match level {
+            Level::Error => 0x10,
+            Level::Warn => 0x00,
+            Level::Info => 0x01,
+            Level::Debug | Level::Trace => 0x02,
+        }
+    }
+}
+
+impl Sink for OsLogSink {
+    fn write_batch(&mut self, records: &[Record]) -> io::Result<()> {
+        for record in records {
+            let message = record.message();
+            let category = if message.modules().is_empty() {
+                "default".to_string()
+            } else {
+                message.modules().join("::")
+            };
+
+            let subsystem = &self.subsystem;
+            let log = self.logs.entry(category).or_insert_with_key(|category| {
+                let category = to_cstring(category);
+                OsLog(unsafe { os_log_create(subsystem.as_ptr(), category.as_ptr()) })
+            });
+
+            let text = to_cstring(message.text());
+            unsafe {
+                rotation_logger_os_log(log.0, Self::log_type(message.level()), text.as_ptr())
+            };
+        }
+        Ok(())
+    }
+}
+
+/// Interior nul would cut string, drop it instead.
+fn to_cstring(value: &str) -> CString {
+    CString::new(value.replace('\0', "")).unwrap_or_default()
+}
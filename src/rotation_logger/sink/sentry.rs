@@ -0,0 +1,81 @@
+use std::{io, time::Duration};
+
+use sentry_core::protocol::Event;
+
+use crate::rotation_logger::{
+    logger::Level,
+    sink::{Record, Sink},
+};
+
+/// Reports important records to Sentry as events.
+///
+/// Sentry client must be initialized by application (`sentry::init`),
+/// sink sends events through the current hub.
+/// Modules are attached as `modules` tag and message fields as extra data.
+/// Records below `min_level` are skipped, by default only `Level::Error` is reported.
+pub struct SentrySink {
+    min_level: Level,
+}
+
+impl Default for SentrySink {
+    fn default() -> Self {
+        Self {
+            min_level: Level::Error,
+        }
+    }
+}
+
+impl SentrySink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Least important level to report, e.g. `Level::Warn` to include warnings.
+    pub fn with_min_level(mut self, min_level: Level) -> Self {
+        self.min_level = min_level;
+        self
+    }
+
+    pub(crate) fn event(record: &Record) -> Event<'static> {
+        let message = record.message();
+        let mut event = Event {
+            level: match message.level() {
+                Level::Error => sentry_core::Level::Error,
+                Level::Warn => sentry_core::Level::Warning,
+                Level::Info => sentry_core::Level::Info,
+                Level::Debug | Level::Trace => sentry_core::Level::Debug,
+            },
+            message: Some(message.text().clone()),
+            logger: Some("rotation_logger".into()),
+            timestamp: record.time(),
+            ..Default::default()
+        };
+        if !message.modules().is_empty() {
+            event
+                .tags
+                .insert("modules".into(), message.modules().join("::"));
+        }
+        for (key, value) in message.fields() {
+            event.extra.insert(key.clone(), value.to_json_value());
+        }
+        event
+    }
+}
+
+impl Sink for SentrySink {
+    fn write_batch(&mut self, records: &[Record]) -> io::Result<()> {
+        for record in records {
+            if record.message().level() <= self.min_level {
+                sentry_core::capture_event(Self::event(record));
+            }
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if let Some(client) = sentry_core::Hub::current().client() {
+            client.flush(Some(Duration::from_secs(2)));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,312 @@
+use std::{
+    fs::{File, OpenOptions},
+    io,
+    path::{Path, PathBuf},
+    ptr,
+    sync::atomic::{AtomicU32, AtomicU64, Ordering},
+    thread::{self, JoinHandle, sleep},
+    time::{Duration, Instant},
+};
+
+use memmap2::MmapMut;
+
+use crate::rotation_logger::{
+    Logger,
+    logger::Message,
+    sink::{Record, Sink},
+};
+
+/// "RLOGSHM1"
+const MAGIC: u64 = 0x524c_4f47_5348_4d31;
+/// Magic, capacity, write and read positions, each on its own 8 bytes.
+const HEADER_SIZE: usize = 64;
+const WRITE_POS: usize = 16;
+const READ_POS: usize = 24;
+/// Slot state flags, low bits hold slot size.
+const COMMITTED: u32 = 1 << 31;
+const PADDING: u32 = 1 << 30;
+const SIZE_MASK: u32 = PADDING - 1;
+/// State and payload length.
+const SLOT_HEADER_SIZE: usize = 8;
+/// Slot reserved but not committed for this long is treated as left by crashed producer.
+const STALL_TIMEOUT: Duration = Duration::from_secs(1);
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// Multi-producer single-consumer ring of byte messages in memory mapped file.
+///
+/// Positions only grow, offset in data region is position modulo capacity.
+/// Producer reserves slot by moving write position, fills it and sets `COMMITTED` flag,
+/// consumer copies committed slots in order, zeroes them and moves read position.
+/// Slot which does not fit before end of region is preceded by `PADDING` slot.
+pub(crate) struct ShmRing {
+    mmap: MmapMut,
+    capacity: u64,
+}
+
+impl ShmRing {
+    pub(crate) fn create(path: &Path, capacity: usize) -> io::Result<Self> {
+        let capacity = capacity.next_multiple_of(8).max(4096) as u64;
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        file.set_len(HEADER_SIZE as u64 + capacity)?;
+
+        let ring = Self::map(&file, capacity)?;
+        ring.atomic_u64(8).store(capacity, Ordering::Relaxed);
+        ring.atomic_u64(0).store(MAGIC, Ordering::Release);
+        Ok(ring)
+    }
+
+    pub(crate) fn open(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        let len = file.metadata()?.len();
+        if len <= HEADER_SIZE as u64 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not a log ring"));
+        }
+
+        let ring = Self::map(&file, len - HEADER_SIZE as u64)?;
+        if ring.atomic_u64(0).load(Ordering::Acquire) != MAGIC
+            || ring.atomic_u64(8).load(Ordering::Relaxed) != ring.capacity
+        {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not a log ring"));
+        }
+        Ok(ring)
+    }
+
+    fn map(file: &File, capacity: u64) -> io::Result<Self> {
+        let mmap = unsafe { MmapMut::map_mut(file)? };
+        Ok(Self { mmap, capacity })
+    }
+
+    fn atomic_u64(&self, offset: usize) -> &AtomicU64 {
+        // Header fields are 8 bytes aligned within page aligned mapping.
+        unsafe { &*(self.mmap.as_ptr().add(offset) as *const AtomicU64) }
+    }
+
+    fn slot_state(&self, position: u64) -> &AtomicU32 {
+        let offset = HEADER_SIZE + (position % self.capacity) as usize;
+        unsafe { &*(self.mmap.as_ptr().add(offset) as *const AtomicU32) }
+    }
+
+    fn slot_ptr(&self, position: u64, offset: usize) -> *mut u8 {
+        let offset = HEADER_SIZE + (position % self.capacity) as usize + offset;
+        unsafe { self.mmap.as_ptr().add(offset) as *mut u8 }
+    }
+
+    /// Put message to ring, fails when there is not enough free space.
+    pub(crate) fn push(&self, data: &[u8]) -> io::Result<()> {
+        let size = (SLOT_HEADER_SIZE + data.len()).next_multiple_of(8) as u64;
+        if size > self.capacity / 2 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "message is too big for log ring",
+            ));
+        }
+
+        let write_pos = self.atomic_u64(WRITE_POS);
+        let (position, padding) = loop {
+            let position = write_pos.load(Ordering::Acquire);
+            let read = self.atomic_u64(READ_POS).load(Ordering::Acquire);
+            let offset = position % self.capacity;
+            let padding = if offset + size > self.capacity {
+                self.capacity - offset
+            } else {
+                0
+            };
+
+            if position + padding + size - read > self.capacity {
+                return Err(io::Error::new(
+                    io::ErrorKind::WouldBlock,
+                    "log ring is full",
+                ));
+            }
+            if write_pos
+                .compare_exchange(
+                    position,
+                    position + padding + size,
+                    Ordering::AcqRel,
+                    Ordering::Acquire,
+                )
+                .is_ok()
+            {
+                break (position, padding);
+            }
+        };
+
+        if padding > 0 {
+            self.slot_state(position)
+                .store(COMMITTED | PADDING | padding as u32, Ordering::Release);
+        }
+        let position = position + padding;
+        let state = self.slot_state(position);
+        // Size first, so consumer can skip slot if this process dies before commit.
+        state.store(size as u32, Ordering::Release);
+        unsafe {
+            ptr::copy_nonoverlapping(
+                (data.len() as u32).to_le_bytes().as_ptr(),
+                self.slot_ptr(position, 4),
+                4,
+            );
+            ptr::copy_nonoverlapping(
+                data.as_ptr(),
+                self.slot_ptr(position, SLOT_HEADER_SIZE),
+                data.len(),
+            );
+        }
+        state.store(COMMITTED | size as u32, Ordering::Release);
+        Ok(())
+    }
+
+    /// Take next committed message, if any.
+    /// `stalled` tracks uncommitted slot at read position between calls.
+    pub(crate) fn pop(&self, stalled: &mut Option<(u64, Instant)>) -> Option<Vec<u8>> {
+        let read_pos = self.atomic_u64(READ_POS);
+        loop {
+            let position = read_pos.load(Ordering::Relaxed);
+            if position == self.atomic_u64(WRITE_POS).load(Ordering::Acquire) {
+                return None;
+            }
+
+            let state = self.slot_state(position).load(Ordering::Acquire);
+            let size = (state & SIZE_MASK) as u64;
+            if state & COMMITTED == 0 {
+                match stalled {
+                    Some((stalled_at, since))
+                        if *stalled_at == position && since.elapsed() >= STALL_TIMEOUT => {}
+                    Some((stalled_at, _)) if *stalled_at == position => return None,
+                    _ => {
+                        *stalled = Some((position, Instant::now()));
+                        return None;
+                    }
+                }
+                // Producer is gone, without known size rest of ring can not be read.
+                if size == 0 {
+                    return None;
+                }
+            }
+            *stalled = None;
+
+            let data = if state & (COMMITTED | PADDING) == COMMITTED {
+                let mut len = [0; 4];
+                unsafe {
+                    ptr::copy_nonoverlapping(self.slot_ptr(position, 4), len.as_mut_ptr(), 4);
+                }
+                let len = (u32::from_le_bytes(len) as usize).min(size as usize - SLOT_HEADER_SIZE);
+                let mut data = vec![0; len];
+                unsafe {
+                    ptr::copy_nonoverlapping(
+                        self.slot_ptr(position, SLOT_HEADER_SIZE),
+                        data.as_mut_ptr(),
+                        len,
+                    );
+                }
+                Some(data)
+            } else {
+                None
+            };
+
+            // Clean slot, so next round sees zero states.
+            unsafe { ptr::write_bytes(self.slot_ptr(position, 0), 0, size as usize) };
+            read_pos.store(position + size, Ordering::Release);
+
+            if data.is_some() {
+                return data;
+            }
+        }
+    }
+}
+
+// Mapping is owned by ring, all shared access goes through atomics or reserved slots.
+unsafe impl Send for ShmRing {}
+
+/// Sends messages to shared memory ring of consumer process instead of writing files.
+pub(crate) struct ShmSink {
+    path: PathBuf,
+    ring: Option<ShmRing>,
+}
+
+impl ShmSink {
+    pub(crate) fn new(path: PathBuf) -> Self {
+        Self { path, ring: None }
+    }
+}
+
+impl Sink for ShmSink {
+    fn write_batch(&mut self, records: &[Record]) -> io::Result<()> {
+        // Consumer may start later than producer, try to open ring on every batch.
+        if self.ring.is_none() {
+            self.ring = Some(ShmRing::open(&self.path)?);
+        }
+        let Some(ring) = &self.ring else {
+            return Ok(());
+        };
+
+        let mut dropped = 0;
+        for record in records {
+            if ring.push(&record.message().to_bytes()).is_err() {
+                dropped += 1;
+            }
+        }
+        if dropped > 0 {
+            return Err(io::Error::other(format!(
+                "{dropped} records dropped by shared memory ring"
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Reading side of shared memory transport, in the process which owns log files.
+///
+/// Producer processes use `OutputChannel::SharedMemory` with the same path,
+/// consumer forwards their messages to its own `Logger`, so all of them end up
+/// in single set of rotated files.
+///
+/// # Example:
+///
+/// ```no_run
+/// # use rotation_logger::{Logger, ShmConsumer};
+/// # let logger = Logger::disabled();
+/// ShmConsumer::create("/dev/shm/my_app.log".as_ref(), 4 * 1024 * 1024)
+///     .unwrap()
+///     .run_async(logger);
+/// ```
+pub struct ShmConsumer {
+    ring: ShmRing,
+    stalled: Option<(u64, Instant)>,
+}
+
+impl ShmConsumer {
+    /// Create ring file of `capacity` bytes, replacing existing one.
+    pub fn create(path: &Path, capacity: usize) -> io::Result<Self> {
+        Ok(Self {
+            ring: ShmRing::create(path, capacity)?,
+            stalled: None,
+        })
+    }
+
+    /// Next message from producers, if any.
+    pub fn try_recv(&mut self) -> Option<Message> {
+        while let Some(data) = self.ring.pop(&mut self.stalled) {
+            if let Some(message) = Message::from_bytes(&data) {
+                return Some(message);
+            }
+        }
+        None
+    }
+
+    /// Forward messages to `logger` on separate thread.
+    pub fn run_async(mut self, logger: Logger) -> JoinHandle<()> {
+        thread::spawn(move || {
+            loop {
+                while let Some(message) = self.try_recv() {
+                    logger.log_message(message);
+                }
+                sleep(POLL_INTERVAL);
+            }
+        })
+    }
+}
@@ -0,0 +1,298 @@
+use std::{
+    collections::VecDeque,
+    io,
+    sync::mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    thread,
+    time::{Duration, Instant},
+};
+
+use lettre::{
+    SmtpTransport, Transport,
+    message::{Mailbox, header::ContentType},
+    transport::smtp::authentication::Credentials,
+};
+
+use crate::rotation_logger::{
+    logger::Level,
+    sink::{Record, Sink},
+};
+
+const HOUR: Duration = Duration::from_secs(60 * 60);
+/// Lines kept in one email, rest are only counted.
+const MAX_LINES: usize = 200;
+
+/// Emails important records, so small deployments get alerts without extra infrastructure.
+///
+/// Records collected during `coalesce` window are sent as one email, and no more than
+/// `max_per_hour` emails are sent, further records wait for next allowed email.
+/// Records still over the limit when sink is dropped are discarded, their count is printed.
+/// Emails are sent from separate thread, so slow SMTP server does not hold logger.
+pub struct SmtpAlertSink {
+    min_level: Level,
+    alerter: Option<Alerter>,
+    sender: Option<Sender<String>>,
+}
+
+struct Alerter {
+    relay: String,
+    port: Option<u16>,
+    credentials: Option<Credentials>,
+    from: Mailbox,
+    to: Vec<Mailbox>,
+    subject: String,
+    coalesce: Duration,
+    max_per_hour: usize,
+}
+
+impl SmtpAlertSink {
+    /// Send through `relay` with STARTTLS, e.g. `smtp.example.com`.
+    pub fn new(relay: &str, from: &str, to: &str) -> io::Result<Self> {
+        Ok(Self {
+            min_level: Level::Error,
+            alerter: Some(Alerter {
+                relay: relay.into(),
+                port: None,
+                credentials: None,
+                from: parse_mailbox(from)?,
+                to: vec![parse_mailbox(to)?],
+                subject: "Log alert".into(),
+                coalesce: Duration::from_secs(60),
+                max_per_hour: 10,
+            }),
+            sender: None,
+        })
+    }
+
+    /// Additional recipient.
+    pub fn with_recipient(mut self, to: &str) -> io::Result<Self> {
+        let to = parse_mailbox(to)?;
+        self.alerter_mut().to.push(to);
+        Ok(self)
+    }
+
+    pub fn with_port(mut self, port: u16) -> Self {
+        self.alerter_mut().port = Some(port);
+        self
+    }
+
+    pub fn with_credentials(mut self, username: &str, password: &str) -> Self {
+        self.alerter_mut().credentials = Some(Credentials::new(username.into(), password.into()));
+        self
+    }
+
+    /// Least important level to send, `Level::Error` by default.
+    pub fn with_min_level(mut self, min_level: Level) -> Self {
+        self.min_level = min_level;
+        self
+    }
+
+    /// Email subject, number of records is appended.
+    pub fn with_subject(mut self, subject: &str) -> Self {
+        self.alerter_mut().subject = subject.into();
+        self
+    }
+
+    /// How long to wait for more records after first one before sending email.
+    pub fn with_coalesce(mut self, coalesce: Duration) -> Self {
+        self.alerter_mut().coalesce = coalesce;
+        self
+    }
+
+    /// Maximal number of emails per hour.
+    pub fn with_max_per_hour(mut self, max_per_hour: usize) -> Self {
+        self.alerter_mut().max_per_hour = max_per_hour.max(1);
+        self
+    }
+
+    fn alerter_mut(&mut self) -> &mut Alerter {
+        // Alerter is moved to its thread only on first write.
+        self.alerter
+            .as_mut()
+            .expect("SmtpAlertSink configured after start")
+    }
+
+    fn sender(&mut self) -> io::Result<&Sender<String>> {
+        if self.sender.is_none()
+            && let Some(alerter) = self.alerter.take()
+        {
+            let (sender, receiver) = mpsc::channel();
+            thread::Builder::new()
+                .name("rotation_logger-smtp".into())
+                .spawn(move || alerter.run(receiver))?;
+            self.sender = Some(sender);
+        }
+        self.sender
+            .as_ref()
+            .ok_or_else(|| io::Error::other("smtp alert thread is not running"))
+    }
+}
+
+impl Sink for SmtpAlertSink {
+    fn write_batch(&mut self, records: &[Record]) -> io::Result<()> {
+        let min_level = self.min_level;
+        for record in records {
+            if record.message().level() <= min_level {
+                self.sender()?
+                    .send(record.line().to_string())
+                    .map_err(|_| io::Error::other("smtp alert thread stopped"))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Alerter {
+    fn run(self, receiver: Receiver<String>) {
+        let transport = match self.transport() {
+            Ok(transport) => transport,
+            Err(err) => {
+                println!("Logger cant create SMTP transport. Error: {err}");
+                return;
+            }
+        };
+
+        let mut window = AlertWindow::new(self.coalesce, self.max_per_hour);
+        loop {
+            let received = match window.deadline() {
+                Some(deadline) => {
+                    receiver.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                }
+                None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            };
+
+            match received {
+                Ok(line) => window.push(line, Instant::now()),
+                Err(RecvTimeoutError::Timeout) => {
+                    if let Some((lines, omitted)) = window.take(Instant::now()) {
+                        self.send(&transport, &lines, omitted);
+                    }
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    // Sink dropped, pending lines go without waiting for window to close.
+                    if let Some((lines, omitted)) = window.flush(Instant::now()) {
+                        self.send(&transport, &lines, omitted);
+                    }
+                    let discarded = window.pending();
+                    if discarded > 0 {
+                        println!(
+                            "Logger cant send alert email over hourly limit, {discarded} records discarded"
+                        );
+                    }
+                    return;
+                }
+            }
+        }
+    }
+
+    fn transport(&self) -> Result<SmtpTransport, lettre::transport::smtp::Error> {
+        let mut builder = SmtpTransport::starttls_relay(&self.relay)?;
+        if let Some(port) = self.port {
+            builder = builder.port(port);
+        }
+        if let Some(credentials) = &self.credentials {
+            builder = builder.credentials(credentials.clone());
+        }
+        Ok(builder.build())
+    }
+
+    fn send(&self, transport: &SmtpTransport, lines: &[String], omitted: usize) {
+        let count = lines.len() + omitted;
+        let mut body = lines.join("\n");
+        if omitted > 0 {
+            body.push_str(&format!("\n... and {omitted} more records"));
+        }
+
+        let mut email = lettre::Message::builder()
+            .from(self.from.clone())
+            .subject(format!("{} ({count} records)", self.subject))
+            .header(ContentType::TEXT_PLAIN);
+        for to in &self.to {
+            email = email.to(to.clone());
+        }
+
+        let result = email
+            .body(body)
+            .map_err(|err| err.to_string())
+            .and_then(|email| transport.send(&email).map_err(|err| err.to_string()));
+        if let Err(err) = result {
+            println!("Logger cant send alert email. Error: {err}");
+        }
+    }
+}
+
+/// Records waiting for alert email, with `coalesce` window and `max_per_hour` limit.
+/// Time is passed in, so decisions do not depend on clock.
+pub(crate) struct AlertWindow {
+    coalesce: Duration,
+    max_per_hour: usize,
+    pending: Vec<String>,
+    omitted: usize,
+    deadline: Option<Instant>,
+    sent: VecDeque<Instant>,
+}
+
+impl AlertWindow {
+    pub(crate) fn new(coalesce: Duration, max_per_hour: usize) -> Self {
+        Self {
+            coalesce,
+            max_per_hour,
+            pending: vec![],
+            omitted: 0,
+            deadline: None,
+            sent: VecDeque::new(),
+        }
+    }
+
+    /// Keep line received at `now`, first one opens `coalesce` window.
+    pub(crate) fn push(&mut self, line: String, now: Instant) {
+        if self.pending.len() < MAX_LINES {
+            self.pending.push(line);
+        } else {
+            self.omitted += 1;
+        }
+        self.deadline.get_or_insert(now + self.coalesce);
+    }
+
+    /// When to take pending lines, none while nothing is pending.
+    pub(crate) fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// Lines to send at `now` with count of omitted ones, once window closed and
+    /// hourly limit allows. Over limit, deadline moves to when oldest email leaves the hour.
+    pub(crate) fn take(&mut self, now: Instant) -> Option<(Vec<String>, usize)> {
+        if now < self.deadline? {
+            return None;
+        }
+        self.flush(now)
+    }
+
+    /// Lines to send at `now` before window closed, when hourly limit allows.
+    pub(crate) fn flush(&mut self, now: Instant) -> Option<(Vec<String>, usize)> {
+        if self.pending() == 0 {
+            return None;
+        }
+        self.sent
+            .retain(|time| now.saturating_duration_since(*time) < HOUR);
+        if self.sent.len() >= self.max_per_hour {
+            self.deadline = Some(self.sent[0] + HOUR);
+            return None;
+        }
+
+        self.sent.push_back(now);
+        self.deadline = None;
+        let omitted = std::mem::take(&mut self.omitted);
+        Some((std::mem::take(&mut self.pending), omitted))
+    }
+
+    /// Number of records waiting, kept lines and omitted ones.
+    pub(crate) fn pending(&self) -> usize {
+        self.pending.len() + self.omitted
+    }
+}
+
+fn parse_mailbox(address: &str) -> io::Result<Mailbox> {
+    address
+        .parse()
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))
+}
@@ -0,0 +1,53 @@
+use std::{
+    io::{self, Write},
+    os::unix::net::UnixStream,
+    path::PathBuf,
+};
+
+use crate::rotation_logger::sink::{Record, Sink};
+
+/// Streams newline-delimited records to local collector socket, e.g. Vector or Fluent Bit.
+///
+/// Connection is opened on first write and reopened once when write fails,
+/// so collector restarts lose only records of a single batch.
+pub(crate) struct UnixSocketSink {
+    path: PathBuf,
+    stream: Option<UnixStream>,
+}
+
+impl UnixSocketSink {
+    pub(crate) fn new(path: PathBuf) -> Self {
+        Self { path, stream: None }
+    }
+
+    fn send(&mut self, data: &[u8]) -> io::Result<()> {
+        if self.stream.is_none() {
+            self.stream = Some(UnixStream::connect(&self.path)?);
+        }
+        let result = match &mut self.stream {
+            Some(stream) => stream.write_all(data),
+            None => Ok(()),
+        };
+        if result.is_err() {
+            self.stream = None;
+        }
+        result
+    }
+}
+
+impl Sink for UnixSocketSink {
+    fn write_batch(&mut self, records: &[Record]) -> io::Result<()> {
+        let mut data = Vec::new();
+        for record in records {
+            data.extend_from_slice(record.line().as_bytes());
+            data.push(b'\n');
+        }
+
+        // Stale connection is found out only on write, retry with new one.
+        let had_stream = self.stream.is_some();
+        match self.send(&data) {
+            Err(_) if had_stream => self.send(&data),
+            result => result,
+        }
+    }
+}
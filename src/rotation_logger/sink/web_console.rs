@@ -0,0 +1,26 @@
+use std::io;
+
+use wasm_bindgen::JsValue;
+use web_sys::console;
+
+use crate::rotation_logger::{
+    logger::Level,
+    sink::{Record, Sink},
+};
+
+/// Writes every record to browser console, method is chosen by level.
+pub(crate) struct WebConsoleSink;
+
+impl Sink for WebConsoleSink {
+    fn write_batch(&mut self, records: &[Record]) -> io::Result<()> {
+        for record in records {
+            let line = JsValue::from_str(record.line());
+            match record.message().level() {
+                Level::Error => console::error_1(&line),
+                Level::Warn => console::warn_1(&line),
+                _ => console::log_1(&line),
+            }
+        }
+        Ok(())
+    }
+}
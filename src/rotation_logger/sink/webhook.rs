@@ -0,0 +1,170 @@
+use std::{io, time::Duration};
+
+use chrono::SecondsFormat;
+use serde_json::Value;
+
+use crate::rotation_logger::{
+    logger::Level,
+    sink::{
+        Record, Sink,
+        http::{HttpError, RetryPolicy},
+    },
+};
+
+const DEFAULT_TEMPLATE: &str = r#"{"timestamp":"{timestamp}","level":"{level}","modules":"{modules}","message":"{message}","fields":{fields}}"#;
+
+/// POSTs records as JSON to any URL, for integration with internal systems.
+///
+/// Payload of each record is rendered from JSON template with placeholders:
+/// `{timestamp}`, `{level}`, `{modules}`, `{message}` and `{line}` (formatted line) are
+/// inserted as escaped string content, so should be quoted in template,
+/// `{fields}` is inserted as JSON object of message fields.
+/// Records are sent one per request, or as JSON array when batching is enabled.
+pub struct WebhookSink {
+    url: String,
+    template: String,
+    headers: Vec<(String, String)>,
+    min_level: Level,
+    batch: bool,
+    retry: RetryPolicy,
+    agent: ureq::Agent,
+}
+
+impl WebhookSink {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.into(),
+            template: DEFAULT_TEMPLATE.into(),
+            headers: vec![],
+            min_level: Level::Trace,
+            batch: false,
+            retry: Default::default(),
+            agent: ureq::AgentBuilder::new()
+                .timeout(Duration::from_secs(30))
+                .build(),
+        }
+    }
+
+    /// Payload template, fails if it does not render to valid JSON.
+    pub fn with_template(mut self, template: &str) -> io::Result<Self> {
+        self.template = template.into();
+
+        let sample = Record::new(
+            crate::Message::new(&["sample".into()], "sample").with_field("key", "value"),
+            "sample".into(),
+            chrono::Local::now(),
+        );
+        serde_json::from_str::<Value>(&self.render(&sample))
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+        Ok(self)
+    }
+
+    /// Header added to every request, e.g. `Authorization`.
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Least important level to send, all records by default.
+    pub fn with_min_level(mut self, min_level: Level) -> Self {
+        self.min_level = min_level;
+        self
+    }
+
+    /// Send all records of batch in single request as JSON array.
+    pub fn with_batch(mut self, batch: bool) -> Self {
+        self.batch = batch;
+        self
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Payload of single record.
+    pub(crate) fn render(&self, record: &Record) -> String {
+        let message = record.message();
+        let mut payload = String::with_capacity(self.template.len() + message.text().len());
+        let mut rest = self.template.as_str();
+
+        while let Some(start) = rest.find('{') {
+            payload.push_str(&rest[..start]);
+            rest = &rest[start..];
+
+            let placeholder = rest.find('}').map(|end| (&rest[1..end], end));
+            let value = match placeholder {
+                Some(("timestamp", _)) => escape(
+                    &record
+                        .timestamp()
+                        .to_rfc3339_opts(SecondsFormat::Millis, true),
+                ),
+                Some(("level", _)) => escape(message.level().as_str()),
+                Some(("modules", _)) => escape(&message.modules().join("::")),
+                Some(("message", _)) => escape(message.text()),
+                Some(("line", _)) => escape(record.line()),
+                Some(("fields", _)) => Value::Object(
+                    message
+                        .fields()
+                        .iter()
+                        .map(|(key, value)| (key.clone(), value.to_json_value()))
+                        .collect(),
+                )
+                .to_string(),
+                _ => {
+                    // Not a placeholder, e.g. JSON object brace.
+                    payload.push('{');
+                    rest = &rest[1..];
+                    continue;
+                }
+            };
+            payload.push_str(&value);
+            rest = &rest[placeholder.map_or(0, |(_, end)| end + 1)..];
+        }
+        payload.push_str(rest);
+        payload
+    }
+
+    fn send(&self, body: &str) -> io::Result<()> {
+        self.retry.run(|| {
+            let mut request = self
+                .agent
+                .post(&self.url)
+                .set("Content-Type", "application/json");
+            for (name, value) in &self.headers {
+                request = request.set(name, value);
+            }
+            request
+                .send_string(body)
+                .map(|_| ())
+                .map_err(HttpError::from)
+        })
+    }
+}
+
+impl Sink for WebhookSink {
+    fn write_batch(&mut self, records: &[Record]) -> io::Result<()> {
+        let payloads: Vec<String> = records
+            .iter()
+            .filter(|record| record.message().level() <= self.min_level)
+            .map(|record| self.render(record))
+            .collect();
+
+        if payloads.is_empty() {
+            return Ok(());
+        }
+        if self.batch {
+            return self.send(&format!("[{}]", payloads.join(",")));
+        }
+        for payload in payloads {
+            self.send(&payload)?;
+        }
+        Ok(())
+    }
+}
+
+/// JSON string content without surrounding quotes.
+fn escape(value: &str) -> String {
+    let quoted = Value::from(value).to_string();
+    quoted[1..quoted.len() - 1].to_string()
+}
@@ -1,4 +1,135 @@
-use crate::{Message, MessageFormatter};
+use std::{
+    ops::Deref,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use crate::rotation_logger::logger::queue::{Item, Queue};
+use crate::{FieldValue, Message, MessageFormatter, Record, Sink};
+
+/// Sink keeping texts of written records, batch by batch.
+#[derive(Clone, Default)]
+struct CaptureSink(Arc<Mutex<Vec<Vec<String>>>>);
+
+impl CaptureSink {
+    fn batches(&self) -> Vec<Vec<String>> {
+        self.0.lock().unwrap().clone()
+    }
+
+    fn texts(&self) -> Vec<String> {
+        self.batches().concat()
+    }
+}
+
+impl Sink for CaptureSink {
+    fn write_batch(&mut self, records: &[Record]) -> std::io::Result<()> {
+        let batch = records.iter().map(|record| record.message().text().clone());
+        self.0.lock().unwrap().push(batch.collect());
+        Ok(())
+    }
+}
+
+/// Fails first `usize` writes, then writes into `CaptureSink`.
+struct FlakySink(usize, CaptureSink);
+
+impl Sink for FlakySink {
+    fn write_batch(&mut self, records: &[Record]) -> std::io::Result<()> {
+        if self.0 > 0 {
+            self.0 -= 1;
+            return Err(std::io::Error::other("offline"));
+        }
+        self.1.write_batch(records)
+    }
+}
+
+/// Writes first `usize` batches into `CaptureSink`, then fails.
+struct FailingAfter(usize, CaptureSink);
+
+impl Sink for FailingAfter {
+    fn write_batch(&mut self, records: &[Record]) -> std::io::Result<()> {
+        if self.0 == 0 {
+            return Err(std::io::Error::other("offline"));
+        }
+        self.0 -= 1;
+        self.1.write_batch(records)
+    }
+}
+
+/// Directory of single test in temp dir, created empty and removed on drop,
+/// also when assertion of test fails.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let path =
+            std::env::temp_dir().join(format!("rotation_logger_{name}_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+}
+
+impl Deref for TempDir {
+    type Target = PathBuf;
+
+    fn deref(&self) -> &PathBuf {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Tests of logger started by `run_async` take turns, there is one for whole process.
+/// So do tests of `Logger::is_enabled`, kill switch turns it off for every logger.
+fn running_logger() -> std::sync::MutexGuard<'static, ()> {
+    static RUNNING: Mutex<()> = Mutex::new(());
+    RUNNING
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+/// Formatter of lines holding message text only.
+fn text_formatter() -> MessageFormatter {
+    MessageFormatter::new("::", "{message:0:0}", "")
+}
+
+/// Write `messages` by worker of `settings`, returns once it wrote them and stopped,
+/// so outputs are read without waiting for running logger. Queue is kept for its stats.
+fn run_worker(
+    settings: crate::Settings,
+    messages: impl IntoIterator<Item = Message>,
+) -> Arc<Queue> {
+    let queue = Arc::new(Queue::new());
+    for message in messages {
+        assert!(queue.send(message));
+    }
+    queue.close();
+    crate::rotation_logger::logger::EnabledLogger::new(settings, queue.clone()).run();
+    queue
+}
+
+/// Message of queue item, control items are not expected.
+fn message_of(item: Item) -> Message {
+    match item {
+        Item::Message(message) => message,
+        Item::Control(control) => panic!("unexpected control item {control:?}"),
+    }
+}
+
+/// Messages without modules of `texts`.
+fn messages<'a>(texts: impl IntoIterator<Item = &'a str>) -> impl Iterator<Item = Message> {
+    texts.into_iter().map(|text| Message::new(&[], text))
+}
 
 #[test]
 fn test_message_formatter_output() {
@@ -18,3 +149,3670 @@ fn test_message_formatter_output() {
         assert_eq!(formatted_message[cut..], result[cut..]);
     }
 }
+
+#[test]
+fn test_rotation_pipeline_resumes_from_journal() {
+    use std::{
+        fs, io,
+        path::{Path, PathBuf},
+    };
+
+    use crate::rotation_logger::pipeline::PipelineRunner;
+    use crate::{DeleteStage, FileSettings, FileSize, RotationPipeline, RotationStage};
+
+    struct FailingStage;
+
+    impl RotationStage for FailingStage {
+        fn name(&self) -> &str {
+            "failing"
+        }
+
+        fn process(&self, _: &Path) -> io::Result<Option<PathBuf>> {
+            Err(io::Error::other("must be skipped"))
+        }
+    }
+
+    let dir = TempDir::new("pipeline");
+
+    let rotated = dir.join("app.log0");
+    fs::write(&rotated, "line\n").unwrap();
+    // First stage already completed before restart.
+    let journal = dir.join(".app.pipeline");
+    fs::write(&journal, format!("1\t{}\n", rotated.to_string_lossy())).unwrap();
+
+    let settings = FileSettings::new(
+        dir.clone(),
+        3,
+        FileSize::from_kilobytes(1),
+        "app".into(),
+        "log".into(),
+    )
+    .with_pipeline(
+        RotationPipeline::new()
+            .stage(FailingStage)
+            .stage(DeleteStage),
+    );
+
+    let mut runner = PipelineRunner::load(&settings);
+    runner.process();
+
+    assert!(!rotated.exists());
+    assert!(!journal.exists());
+}
+
+#[test]
+fn test_sinks_receive_records_in_batches() {
+    use std::sync::{Arc, Mutex};
+
+    use crate::{FlushPolicy, OutputChannel, Settings};
+
+    let batches = CaptureSink::default();
+    let larger_batches = CaptureSink::default();
+    let lines = Arc::new(Mutex::new(vec![]));
+    let console = lines.clone();
+    let formatter = text_formatter();
+    let settings = Settings::new(true, 2, OutputChannel::Console, formatter)
+        .with_sink(batches.clone())
+        .with_sink_policy(larger_batches.clone(), FlushPolicy::new(3))
+        .with_console_writer(move |line: &str| console.lock().unwrap().push(line.to_string()));
+
+    run_worker(settings, messages(["a", "b", "c", "d"]));
+
+    assert_eq!(
+        batches.batches(),
+        vec![
+            vec!["a".to_string(), "b".into()],
+            vec!["c".into(), "d".into()]
+        ]
+    );
+    // The rest is written when channel is closed.
+    assert_eq!(
+        larger_batches.batches(),
+        vec![
+            vec!["a".to_string(), "b".into(), "c".into()],
+            vec!["d".into()]
+        ]
+    );
+    assert_eq!(*lines.lock().unwrap(), vec!["a", "b", "c", "d"]);
+
+    // Byte limit is reached before buffer size.
+    let batches = CaptureSink::default();
+    let formatter = text_formatter();
+    let settings = Settings::new(true, 100, OutputChannel::Console, formatter)
+        .with_console_writer(|_: &str| {})
+        .with_sink(batches.clone())
+        .with_max_buffer_bytes(4);
+
+    run_worker(settings, messages(["aa", "b", "cc", "d"]));
+
+    assert_eq!(
+        batches.batches(),
+        vec![
+            vec!["aa".to_string(), "b".into(), "cc".into()],
+            vec!["d".into()]
+        ]
+    );
+}
+
+#[cfg(feature = "gcp")]
+#[test]
+fn test_gcp_request_body() {
+    use crate::{GcpLoggingSink, Level, Record};
+
+    let sink = GcpLoggingSink::new("my-project", "app/main").with_label("env", "prod");
+    let message =
+        Message::new(&["HTTP".into(), "DB".into()], "query failed").with_level(Level::Error);
+    let record = Record::new(message, "".into(), chrono::Local::now());
+
+    let body = sink.request_body(&[record]);
+    assert_eq!(body["logName"], "projects/my-project/logs/app%2Fmain");
+    assert_eq!(body["labels"]["env"], "prod");
+    assert_eq!(body["entries"][0]["severity"], "ERROR");
+    assert_eq!(body["entries"][0]["textPayload"], "query failed");
+    assert_eq!(body["entries"][0]["labels"]["modules"], "HTTP::DB");
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn test_retry_policy_stops_at_max_elapsed() {
+    use crate::RetryPolicy;
+    use crate::rotation_logger::sink::http::HttpError;
+    use std::time::{Duration, Instant};
+
+    let policy = RetryPolicy::new(10, Duration::from_millis(100), Duration::from_millis(100))
+        .with_max_elapsed(Duration::from_millis(250));
+    let mut attempts = 0;
+    let started = Instant::now();
+    let result: std::io::Result<()> = policy.run(|| {
+        attempts += 1;
+        Err(HttpError::retry("unavailable"))
+    });
+
+    assert!(result.is_err());
+    assert_eq!(attempts, 3);
+    assert!(started.elapsed() < Duration::from_millis(250));
+}
+
+#[cfg(feature = "cloudwatch")]
+#[test]
+fn test_cloudwatch_signing_key_and_batches() {
+    use crate::rotation_logger::sink::cloudwatch::signing_key;
+    use crate::{CloudWatchSink, Record};
+
+    // Example from AWS "Deriving the signing key" documentation.
+    let key = signing_key(
+        "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY",
+        "20120215",
+        "us-east-1",
+        "iam",
+    );
+    let key: String = key.iter().map(|byte| format!("{byte:02x}")).collect();
+    assert_eq!(
+        key,
+        "f4780e2d9f65fa895f9c67b32ce1baf0b0d8a43505a000a1a9e090d414db404d"
+    );
+
+    let now = chrono::Local::now();
+    let record = |line: &str, hours: i64| {
+        Record::new(
+            Message::new(&[], ""),
+            line.into(),
+            now + chrono::Duration::hours(hours),
+        )
+    };
+    let records = vec![record("a", 0), record("b", 1), record("c", 25)];
+    let batches = CloudWatchSink::batches(&records);
+    assert_eq!(batches.len(), 2);
+    assert_eq!(batches[0].len(), 2);
+    assert_eq!(batches[1][0]["message"], "c");
+}
+
+#[cfg(feature = "cloudwatch")]
+#[test]
+fn test_cloudwatch_signs_custom_endpoint() {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::{net::TcpListener, thread};
+
+    use crate::rotation_logger::sink::cloudwatch::sign_v4;
+    use crate::{AwsCredentials, AwsCredentialsSource, CloudWatchSink, RetryPolicy};
+
+    // Emulator on port other than default one, answering single request.
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut headers = vec![];
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let Some((name, value)) = line.trim_end().split_once(": ") else {
+                if line.trim_end().is_empty() {
+                    break;
+                }
+                continue;
+            };
+            headers.push((name.to_lowercase(), value.to_string()));
+        }
+        let length = headers.iter().find(|(name, _)| name == "content-length");
+        let mut body = vec![0; length.unwrap().1.parse().unwrap()];
+        reader.read_exact(&mut body).unwrap();
+        let response = "HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\n{}";
+        reader.get_mut().write_all(response.as_bytes()).unwrap();
+        (headers, body)
+    });
+
+    let credentials = AwsCredentials::new("AKID", "secret", None);
+    let mut sink = CloudWatchSink::new("us-east-1", "app", "main")
+        .with_credentials(AwsCredentialsSource::Static(credentials.clone()))
+        .with_endpoint(&format!("http://127.0.0.1:{port}/logs"))
+        .with_retry(RetryPolicy::never());
+    let record = Record::new(Message::new(&[], ""), "line".into(), chrono::Local::now());
+    sink.write_batch(&[record]).unwrap();
+
+    // Signature matches request as server got it, with host and port of endpoint.
+    let (headers, body) = server.join().unwrap();
+    let header = |name: &str| {
+        let header = headers.iter().find(|(own, _)| own == name);
+        header.map(|(_, value)| value.clone()).unwrap()
+    };
+    assert_eq!(header("host"), format!("127.0.0.1:{port}"));
+    let signed: Vec<(&str, String)> = ["content-type", "host", "x-amz-date", "x-amz-target"]
+        .into_iter()
+        .map(|name| (name, header(name)))
+        .collect();
+    let authorization = sign_v4(
+        &credentials,
+        "us-east-1",
+        "logs",
+        &header("x-amz-date"),
+        "/logs",
+        &signed,
+        &body,
+    );
+    assert_eq!(header("authorization"), authorization);
+}
+
+#[cfg(feature = "azure")]
+#[test]
+fn test_azure_authorization() {
+    use crate::AzureLogAnalyticsSink;
+
+    let sink = AzureLogAnalyticsSink::new("ws", "c2VjcmV0LWtleQ==", "App").unwrap();
+    assert_eq!(
+        sink.authorization("Mon, 01 Jan 2024 00:00:00 GMT", 42),
+        "SharedKey ws:pfb8EPu5Qrzl7zas+TZmkzjUgkF96z2fgtmCI3okEsM="
+    );
+    assert!(AzureLogAnalyticsSink::new("ws", "not base64!", "App").is_err());
+}
+
+#[cfg(feature = "sentry")]
+#[test]
+fn test_sentry_event_tags_and_extras() {
+    use crate::{Level, Record, SentrySink};
+
+    let message = Message::new(&["db".into(), "pool".into()], "connection lost")
+        .with_level(Level::Error)
+        .with_field("attempt", 3);
+    let event = SentrySink::event(&Record::new(message, String::new(), chrono::Local::now()));
+
+    assert_eq!(event.level, sentry_core::Level::Error);
+    assert_eq!(event.message.as_deref(), Some("connection lost"));
+    assert_eq!(event.tags["modules"], "db::pool");
+    assert_eq!(event.extra["attempt"], 3);
+}
+
+#[cfg(feature = "webhook")]
+#[test]
+fn test_webhook_template() {
+    use crate::{Record, WebhookSink};
+
+    let sink = WebhookSink::new("http://localhost")
+        .with_template(r#"{"text": "[{level}] {message}", "extra": {fields}}"#)
+        .unwrap();
+    let message = Message::new(&[], "say \"hi\"").with_field("user", "bob");
+    let body: serde_json::Value =
+        serde_json::from_str(&sink.render(&Record::new(message, "".into(), chrono::Local::now())))
+            .unwrap();
+
+    assert_eq!(body["text"], "[INFO] say \"hi\"");
+    assert_eq!(body["extra"]["user"], "bob");
+    assert!(
+        WebhookSink::new("http://localhost")
+            .with_template("{message}")
+            .is_err()
+    );
+}
+
+#[cfg(feature = "chat")]
+#[test]
+fn test_chat_notifier_budget() {
+    use crate::{ChatNotifierSink, Level, Record};
+
+    let mut sink = ChatNotifierSink::discord("http://localhost").with_per_minute(1);
+    let record = |level, text: &str| {
+        let message = Message::new(&[], text).with_level(level);
+        Record::new(message, text.into(), chrono::Local::now())
+    };
+    let long = "x".repeat(1000);
+
+    let messages = sink.messages(&[
+        record(Level::Info, "skipped"),
+        record(Level::Warn, &long),
+        record(Level::Error, &long),
+    ]);
+    assert_eq!(messages, vec![format!("```\n{long}\n```")]);
+    assert!(sink.messages(&[record(Level::Error, "late")]).is_empty());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_unix_socket_output() {
+    use std::{io::Read, os::unix::net::UnixListener};
+
+    use crate::{OutputChannel, Settings};
+
+    let dir = TempDir::new("sock");
+    let path = dir.join("out.sock");
+    let listener = UnixListener::bind(&path).unwrap();
+
+    let formatter = MessageFormatter::new("::", "{message:_:_:left}", "");
+    let settings = Settings::new(true, 2, OutputChannel::unix_socket(path.clone()), formatter);
+    run_worker(settings, messages(["first", "second"]));
+
+    let mut received = String::new();
+    let (mut stream, _) = listener.accept().unwrap();
+    stream.read_to_string(&mut received).unwrap();
+
+    let lines: Vec<&str> = received.lines().map(str::trim_end).collect();
+    assert_eq!(lines, vec!["first", "second"]);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_fifo_output_without_and_with_reader() {
+    use std::{ffi::CString, fs::OpenOptions, io::Read, os::unix::fs::OpenOptionsExt};
+
+    use crate::Record;
+    use crate::rotation_logger::sink::{FifoSink, Sink};
+
+    let dir = TempDir::new("fifo");
+    let path = dir.join("out.fifo");
+    let c_path = CString::new(path.to_str().unwrap()).unwrap();
+    assert_eq!(unsafe { libc::mkfifo(c_path.as_ptr(), 0o600) }, 0);
+
+    let record = Record::new(
+        Message::new(&[], "hello"),
+        "hello".into(),
+        chrono::Local::now(),
+    );
+    let mut sink = FifoSink::new(path.clone());
+    assert!(sink.write_batch(std::slice::from_ref(&record)).is_err());
+
+    let mut reader = OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_NONBLOCK)
+        .open(&path)
+        .unwrap();
+    sink.write_batch(&[record]).unwrap();
+    let mut received = String::new();
+    reader.read_to_string(&mut received).ok();
+
+    assert_eq!(received, "hello\n");
+}
+
+#[cfg(feature = "shm")]
+#[test]
+fn test_shared_memory_ring_wraps() {
+    use crate::rotation_logger::sink::{ShmSink, Sink};
+    use crate::{Level, Record, ShmConsumer};
+
+    let dir = TempDir::new("shm");
+    let path = dir.join("out.shm");
+    let mut consumer = ShmConsumer::create(&path, 4096).unwrap();
+    let mut sink = ShmSink::new(path.clone());
+
+    // Several rounds of 4 KB ring with 1 KB messages.
+    for round in 0..10 {
+        let text = format!("{round}{}", "x".repeat(1000));
+        let message = Message::new(&["worker".into()], &text)
+            .with_level(Level::Warn)
+            .with_field("round", round);
+        let record = Record::new(message, "".into(), chrono::Local::now());
+        sink.write_batch(&[record.clone(), record]).unwrap();
+
+        for _ in 0..2 {
+            let received = consumer.try_recv().unwrap();
+            assert_eq!(received.text(), &text);
+            assert_eq!(received.level(), Level::Warn);
+            assert_eq!(received.modules(), &vec!["worker".to_string()]);
+            assert_eq!(
+                received.fields(),
+                &vec![("round".into(), FieldValue::I64(round))]
+            );
+        }
+        assert!(consumer.try_recv().is_none());
+    }
+}
+
+#[test]
+fn test_aggregator_adds_source_field() {
+    use std::{net::TcpListener, sync::mpsc::RecvTimeoutError, time::Duration};
+
+    use crate::rotation_logger::{aggregator, sink::Sink};
+    use crate::{AggregatorAddress, AggregatorLimits, Level, Record};
+
+    // Free port for aggregator.
+    let address = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let queue = Arc::new(Queue::new());
+    let limits = AggregatorLimits::default();
+    aggregator::listen(&AggregatorAddress::Tcp(address), &limits, queue.clone()).unwrap();
+
+    let mut sink = aggregator::AggregatorSink::new(AggregatorAddress::Tcp(address));
+    let message = Message::new(&["worker".into()], "done")
+        .with_level(Level::Warn)
+        .with_field("job", 7);
+    sink.write_batch(&[Record::new(message, "".into(), chrono::Local::now())])
+        .unwrap();
+
+    let received = message_of(queue.recv(Some(Duration::from_secs(5))).unwrap());
+    assert_eq!(received.text(), "done");
+    assert_eq!(received.level(), Level::Warn);
+    assert_eq!(received.fields()[0], ("job".into(), FieldValue::I64(7)));
+    assert_eq!(received.fields()[1].0, "source");
+    assert!(
+        received.fields()[1]
+            .1
+            .to_string()
+            .ends_with(&format!("[{}]", std::process::id()))
+    );
+    assert!(matches!(
+        queue.recv(Some(Duration::from_millis(50))),
+        Err(RecvTimeoutError::Timeout)
+    ));
+
+    let formatter = MessageFormatter::new("::", "{fields:100:_:left}", "");
+    assert!(formatter.format(&received).starts_with("job=7 source="));
+}
+
+#[test]
+fn test_aggregator_limits_are_reported() {
+    use std::{
+        io::Write,
+        net::{TcpListener, TcpStream},
+        time::Duration,
+    };
+
+    use crate::rotation_logger::aggregator;
+    use crate::{AggregatorAddress, AggregatorLimits, Level};
+
+    fn frame(stream: &mut TcpStream, len: u32, data: &[u8]) {
+        stream.write_all(&len.to_le_bytes()).unwrap();
+        stream.write_all(data).unwrap();
+    }
+
+    let address = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let queue = Arc::new(Queue::new());
+    let limits = AggregatorLimits::new()
+        .with_max_connections(2)
+        .with_read_timeout(Duration::from_millis(300))
+        .with_max_frame_size(64);
+    aggregator::listen(&AggregatorAddress::Tcp(address), &limits, queue.clone()).unwrap();
+
+    let mut idle = TcpStream::connect(address).unwrap();
+    frame(&mut idle, 4, b"idle");
+    let mut big = TcpStream::connect(address).unwrap();
+    frame(&mut big, 3, b"big");
+    let _refused = TcpStream::connect(address).unwrap();
+    frame(&mut big, 100, &[0; 100]);
+
+    let mut reports = (0..3)
+        .map(|_| {
+            let report = message_of(queue.recv(Some(Duration::from_secs(5))).unwrap());
+            assert_eq!(report.level(), Level::Warn);
+            assert_eq!(report.modules(), ["rotation_logger"]);
+            let source = report.fields().first().map(|(_, value)| value.to_string());
+            (source, report.text().to_string())
+        })
+        .collect::<Vec<_>>();
+    reports.sort();
+
+    assert_eq!(
+        reports[0],
+        (
+            None,
+            "logger aggregator refuses connections, limit of 2 reached".to_string()
+        )
+    );
+    assert_eq!(reports[1].0.as_deref(), Some("big"));
+    assert!(
+        reports[1]
+            .1
+            .ends_with("frame of 100 bytes is bigger than 64")
+    );
+    assert_eq!(reports[2].0.as_deref(), Some("idle"));
+    assert!(
+        reports[2]
+            .1
+            .starts_with("logger aggregator dropped connection. Error:")
+    );
+}
+
+#[test]
+fn test_aggregator_refuses_reserved_targets() {
+    use std::{net::TcpListener, time::Duration};
+
+    use crate::rotation_logger::{aggregator, sink::Sink};
+    use crate::{AggregatorAddress, AggregatorLimits, Level};
+
+    let address = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    let queue = Arc::new(Queue::new());
+    let limits = AggregatorLimits::default();
+    aggregator::listen(&AggregatorAddress::Tcp(address), &limits, queue.clone()).unwrap();
+
+    // Client can't pass for logger itself, e.g. by target of former shutdown message.
+    let mut sink = aggregator::AggregatorSink::new(AggregatorAddress::Tcp(address));
+    let control = Message::new(&[], "stop").with_target("\0rotation_logger::shutdown");
+    let record = |message| Record::new(message, "".into(), chrono::Local::now());
+    sink.write_batch(&[record(control), record(Message::new(&[], "after"))])
+        .unwrap();
+
+    let received = || message_of(queue.recv(Some(Duration::from_secs(5))).unwrap());
+    let report = received();
+    assert_eq!(report.level(), Level::Warn);
+    assert_eq!(report.modules(), ["rotation_logger"]);
+    assert_eq!(
+        report.text(),
+        r#"logger aggregator refused message with reserved target "\0rotation_logger::shutdown""#
+    );
+    assert_eq!(received().text(), "after");
+    assert!(!queue.is_closed());
+}
+
+#[test]
+fn test_core_logger_writes_to_transport() {
+    use crate::{CoreLogger, Transport};
+
+    struct Lines(Vec<String>);
+
+    impl Transport for Lines {
+        fn write_line(&mut self, line: &str) {
+            self.0.push(line.into());
+        }
+    }
+
+    let formatter = MessageFormatter::new(
+        "|",
+        "{timestamp:_:8:left}{splitter}{message:_:4:left}",
+        "%H:%M:%S",
+    );
+    let mut logger = CoreLogger::new(formatter, Lines(vec![]));
+    let timestamp = chrono::DateTime::from_timestamp(3600 + 62, 0).unwrap();
+    logger.log_at(&Message::new(&[], "boot"), &timestamp);
+    logger.log(&Message::new(&[], "up"));
+
+    assert_eq!(
+        logger.transport().0,
+        vec!["01:01:02|boot".to_string(), "        |up  ".to_string()]
+    );
+}
+
+#[test]
+fn test_template_matches_format_string() {
+    use crate::{Align, Template};
+
+    let message = Message::new(&["A".into(), "B".into()], "text").with_field("key", "value");
+    let parsed = MessageFormatter::new(
+        "|",
+        "[{modules:_:10:left}{splitter}{message:-1:6:right}] {fields}",
+        "",
+    );
+    let built = MessageFormatter::from_template(
+        "|",
+        Template::new()
+            .text("[")
+            .modules()
+            .width(10)
+            .align(Align::Left)
+            .splitter()
+            .then(
+                Template::new()
+                    .message()
+                    .length(-1)
+                    .width(6)
+                    .align(Align::Right),
+            )
+            .text("] ")
+            .fields(),
+        "",
+    );
+
+    assert_eq!(built.format(&message), parsed.format(&message));
+    assert_eq!(
+        built.format(&message),
+        "[A|B       |   tex]           key=value           "
+    );
+}
+
+#[test]
+fn test_cli_logging_from_args() {
+    use crate::{CliLogging, Level, OutputChannel};
+
+    let cli = CliLogging::from_args(["run", "-vv", "--log-file", "out/app.txt", "-q"]);
+    assert_eq!(cli.level(), Some(Level::Debug));
+
+    let settings = cli.settings();
+    assert_eq!(settings.min_level(), Level::Debug);
+    let Some(file) = settings.output().settings() else {
+        panic!("file output expected");
+    };
+    assert_eq!(file.path(), &std::path::PathBuf::from("out"));
+    assert_eq!(
+        (file.filename().as_str(), file.file_extension().as_str()),
+        ("app", "txt")
+    );
+
+    let settings = CliLogging::from_args(["-qq"]).settings();
+    assert!(!settings.is_enabled());
+    assert!(matches!(settings.output(), OutputChannel::Console));
+}
+
+#[cfg(feature = "yaml")]
+#[test]
+fn test_log4rs_yaml_config() {
+    use crate::{Level, Log4rsConfig};
+
+    let settings = Log4rsConfig::from_yaml(
+        r#"
+appenders:
+  stdout:
+    kind: console
+  roll:
+    kind: rolling_file
+    path: logs/app.log
+    encoder:
+      pattern: "[{M}] {h({m})} {X}{n}"
+    policy:
+      trigger:
+        kind: size
+        limit: 1 kb
+      roller:
+        kind: fixed_window
+        count: 3
+root:
+  level: warn
+  appenders: [roll, stdout]
+"#,
+    )
+    .unwrap()
+    .settings()
+    .unwrap();
+
+    assert_eq!(settings.min_level(), Level::Warn);
+    let file = settings.output().settings().unwrap();
+    assert_eq!((file.capacity(), file.file_size()), (3, 1024 * 8));
+    assert_eq!(file.filename(), "app");
+
+    let message = Message::new(&["db".into(), "pool".into()], "lost").with_field("id", 7);
+    assert_eq!(settings.format_message(&message), "[db::pool] lost id=7");
+
+    let unknown = Log4rsConfig::from_yaml("root:\n  appenders: [missing]\n").unwrap();
+    assert!(unknown.settings().is_err());
+}
+
+#[test]
+fn test_rust_log_level() {
+    use crate::Level;
+    use crate::rotation_logger::init::parse_level;
+
+    assert_eq!(parse_level(""), None);
+    assert_eq!(parse_level("debug"), Some(Some(Level::Debug)));
+    assert_eq!(parse_level("my_crate=trace, WARN"), Some(Some(Level::Warn)));
+    assert_eq!(parse_level("off"), Some(None));
+}
+
+#[cfg(feature = "slog")]
+#[test]
+fn test_slog_drain_writes_through_worker() {
+    use crate::{Level, Logger, OutputChannel, Settings, SlogDrain};
+
+    struct Messages(Arc<Mutex<Vec<Message>>>);
+
+    impl Sink for Messages {
+        fn write_batch(&mut self, records: &[Record]) -> std::io::Result<()> {
+            let messages = records.iter().map(|record| record.message().clone());
+            self.0.lock().unwrap().extend(messages);
+            Ok(())
+        }
+    }
+
+    let _running = running_logger();
+    let messages = Arc::new(Mutex::new(vec![]));
+    let output = OutputChannel::custom(Messages(messages.clone()));
+    let settings =
+        Settings::new(true, 1, output, MessageFormatter::default()).with_min_level(Level::Info);
+    let logger = Logger::new(settings);
+    let worker = logger.run_async().unwrap();
+
+    let root = slog::Logger::root(SlogDrain::new(&logger), slog::o!("app" => "demo"));
+    slog::warn!(root, "slow query"; "rows" => 3, "cached" => false);
+    slog::debug!(root, "below min level");
+    logger.shutdown().unwrap();
+    worker.join().unwrap();
+
+    let messages = messages.lock().unwrap();
+    if !Level::Warn.is_statically_enabled() {
+        assert!(messages.is_empty());
+        return;
+    }
+    assert_eq!(messages.len(), 1);
+    assert_eq!(
+        messages[0].modules(),
+        &["rotation_logger", "rotation_logger", "tests"]
+    );
+    assert_eq!(messages[0].level(), Level::Warn);
+    assert_eq!(messages[0].text(), "slow query");
+    assert_eq!(
+        messages[0].fields(),
+        &[
+            ("cached".to_string(), FieldValue::Bool(false)),
+            ("rows".to_string(), FieldValue::I64(3)),
+            ("app".to_string(), FieldValue::Str("demo".into())),
+        ]
+    );
+}
+
+#[cfg(feature = "tracing")]
+#[test]
+fn test_tracing_spans_become_modules() {
+    use std::sync::Mutex;
+
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use crate::{Level, Logger, Settings, TracingLayer};
+
+    static MESSAGES: Mutex<Vec<Message>> = Mutex::new(vec![]);
+
+    let layer = TracingLayer::new(&Logger::enabled(Settings::default()))
+        .with_send(|message| MESSAGES.lock().unwrap().push(message));
+    let subscriber = tracing_subscriber::registry().with(layer);
+
+    tracing::subscriber::with_default(subscriber, || {
+        let http = tracing::info_span!("HTTP");
+        let _http = http.enter();
+        let handler = tracing::info_span!("handler");
+        let _handler = handler.enter();
+        tracing::warn!(rows = 3, "slow query");
+    });
+
+    let messages = MESSAGES.lock().unwrap();
+    // Build with `max_level_*` features compiling `Warn` out drops the event.
+    if !Level::Warn.is_statically_enabled() {
+        assert!(messages.is_empty());
+        return;
+    }
+    assert_eq!(messages.len(), 1);
+    assert_eq!(
+        messages[0].modules(),
+        &["HTTP".to_string(), "handler".to_string()]
+    );
+    assert_eq!(messages[0].text(), "slow query");
+    assert_eq!(
+        messages[0].fields(),
+        &[("rows".to_string(), FieldValue::I64(3))]
+    );
+}
+
+#[cfg(feature = "tracing")]
+#[test]
+fn test_tracing_fields_in_text() {
+    use std::sync::Mutex;
+
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use crate::{Level, Logger, Settings, TracingLayer};
+
+    static MESSAGES: Mutex<Vec<Message>> = Mutex::new(vec![]);
+
+    let layer = TracingLayer::new(&Logger::enabled(Settings::default()))
+        .with_fields_in_text(true)
+        .with_send(|message| MESSAGES.lock().unwrap().push(message));
+    let subscriber = tracing_subscriber::registry().with(layer);
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::warn!(rows = 3, table = "users", "slow query");
+    });
+
+    let messages = MESSAGES.lock().unwrap();
+    // Build with `max_level_*` features compiling `Warn` out drops the event.
+    if !Level::Warn.is_statically_enabled() {
+        assert!(messages.is_empty());
+        return;
+    }
+    assert_eq!(messages[0].text(), "slow query rows=3 table=users");
+    assert!(messages[0].fields().is_empty());
+}
+
+#[test]
+fn test_color_theme_keeps_columns() {
+    use crate::{Color, ColorTheme, Level};
+
+    let formatter =
+        MessageFormatter::new("|", "{modules:_:6:left}{splitter}{message:_:6:left}", "");
+    let theme = ColorTheme::plain().with_level(Level::Error, Some(Color::Red));
+    let message = Message::new(&["db".into()], "lost").with_level(Level::Error);
+
+    let line = formatter.format_colored(&message, std::time::SystemTime::now(), &theme);
+    assert_eq!(line, "db    |\x1b[31mlost  \x1b[0m");
+
+    let line = formatter.format_colored(
+        &message,
+        std::time::SystemTime::now(),
+        &theme.with_module_hashing(true),
+    );
+    assert!(line.starts_with("\x1b[") && line.contains("db    \x1b[0m|"));
+}
+
+#[test]
+fn test_color_choice() {
+    use crate::rotation_logger::settings::auto_color;
+    use crate::{ColorChoice, ColorTheme, Settings};
+
+    let theme = ColorTheme::plain();
+    let always = Settings::default().with_color_choice(ColorChoice::Always);
+    assert_eq!(always.console_theme(), Some(ColorTheme::default()));
+    let always = always.with_color_theme(theme.clone());
+    assert_eq!(always.console_theme(), Some(theme.clone()));
+    let never = Settings::default()
+        .with_color_theme(theme)
+        .with_color_choice(ColorChoice::Never);
+    assert_eq!(never.console_theme(), None);
+    // Plain console is not painted even on terminal.
+    assert_eq!(Settings::default().console_theme(), None);
+
+    assert!(auto_color(None, true));
+    assert!(auto_color(Some("".into()), true));
+    assert!(!auto_color(Some("1".into()), true));
+    assert!(!auto_color(None, false));
+}
+
+#[test]
+fn test_console_writer_gets_lines() {
+    use crate::{ConsoleWriter, OutputChannel, Settings};
+
+    /// Progress bar printing lines above itself.
+    #[derive(Default)]
+    struct ProgressBar(Mutex<Vec<String>>);
+
+    impl ConsoleWriter for Arc<ProgressBar> {
+        fn write_line(&self, line: &str) {
+            self.0.lock().unwrap().push(line.to_string());
+        }
+    }
+
+    let bar = Arc::new(ProgressBar::default());
+    let formatter = MessageFormatter::new("::", "{modules:0:0}{splitter}{message:0:0}", "");
+    let settings =
+        Settings::new(true, 10, OutputChannel::Console, formatter).with_console_writer(bar.clone());
+    run_worker(
+        settings,
+        [
+            Message::new(&["DB".into()], "connected"),
+            Message::new(&["HTTP".into()], "ready"),
+        ],
+    );
+
+    assert_eq!(*bar.0.lock().unwrap(), ["DB::connected", "HTTP::ready"]);
+}
+
+#[test]
+fn test_record_json_line() {
+    use crate::{Level, Record};
+
+    let message = Message::new(&["db".into(), "pool".into()], "say \"hi\"\n")
+        .with_level(Level::Warn)
+        .with_field("id", 7)
+        .with_field("ratio", 1.0)
+        .with_field("cached", true);
+    let time = std::time::SystemTime::now();
+    let json = Record::new(message, String::new(), time).to_json();
+
+    assert!(json.starts_with("{\"schema\":3,\"timestamp\":\""));
+    assert!(json.ends_with(
+        "\"level\":\"WARN\",\"modules\":[\"db\",\"pool\"],\"message\":\"say \\\"hi\\\"\\n\",\"fields\":{\"id\":7,\"ratio\":1.0,\"cached\":true}}"
+    ));
+}
+
+#[test]
+fn test_json_layout_keys_and_pretty() {
+    use crate::{JsonLayout, Record};
+
+    let message = Message::new(&["db".into()], "up").with_field("id", 7);
+    let record = Record::new(message, String::new(), std::time::SystemTime::now());
+    let layout = JsonLayout::default()
+        .with_key("message", "msg")
+        .with_key("timestamp", "@timestamp");
+
+    let json = record.to_json_with(&layout);
+    assert!(json.starts_with("{\"schema\":3,\"@timestamp\":"));
+    assert!(json.ends_with("\"modules\":[\"db\"],\"msg\":\"up\",\"fields\":{\"id\":7}}"));
+
+    let json = record.to_json_with(&layout.with_pretty(true));
+    assert!(json.starts_with("{\n  \"schema\": 3,\n  \"@timestamp\": "));
+    assert!(json.ends_with("  \"msg\": \"up\",\n  \"fields\": {\n    \"id\": 7\n  }\n}"));
+}
+
+#[test]
+fn test_json_output_console_and_file() {
+    use std::sync::{Arc, Mutex};
+
+    use crate::{FileSettings, FileSize, LogEntry, LogReader, OutputChannel, Settings};
+
+    let lines = Arc::new(Mutex::new(vec![]));
+    let written = lines.clone();
+    let settings = Settings::new(true, 1, OutputChannel::Console, MessageFormatter::default())
+        .with_json_output(true)
+        .with_console_writer(move |line: &str| written.lock().unwrap().push(line.to_string()));
+    run_worker(settings, [Message::new(&["db".into()], "up")]);
+
+    let lines = lines.lock().unwrap();
+    assert!(lines[0].starts_with("{\"schema\":3,\"timestamp\":"));
+    assert!(lines[0].ends_with("\"modules\":[\"db\"],\"message\":\"up\",\"fields\":{}}"));
+
+    let dir = TempDir::new("json");
+    let file_settings = FileSettings::new(
+        dir.clone(),
+        2,
+        FileSize::from_megabytes(1),
+        "app".into(),
+        "log".into(),
+    );
+    let settings = Settings::new(
+        true,
+        1,
+        OutputChannel::File(file_settings),
+        MessageFormatter::default(),
+    )
+    .with_json_output(true);
+    run_worker(settings, [Message::new(&["db".into()], "up")]);
+
+    let entries = LogReader::open(dir.join("app.log"))
+        .unwrap()
+        .collect::<std::io::Result<Vec<LogEntry>>>()
+        .unwrap();
+    match &entries[..] {
+        [LogEntry::Record(record)] => assert_eq!(record.message().text(), "up"),
+        other => panic!("expected JSON record, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_dev_duo_pretty_console_and_json_file() {
+    use std::sync::{Arc, Mutex};
+
+    use crate::{FileSettings, FileSize, LogEntry, LogReader, OutputChannel, Settings};
+
+    let dir = TempDir::new("dev_duo");
+    let file_settings = FileSettings::new(
+        dir.clone(),
+        2,
+        FileSize::from_megabytes(1),
+        "app".into(),
+        "log".into(),
+    );
+    let lines = Arc::new(Mutex::new(vec![]));
+    let written = lines.clone();
+    let output = OutputChannel::dev_duo(file_settings);
+    let settings = Settings::new(true, 1, output, MessageFormatter::default())
+        .with_console_writer(move |line: &str| written.lock().unwrap().push(line.to_string()));
+    run_worker(
+        settings,
+        [Message::new(&["db".into()], "up").with_field("id", 7)],
+    );
+
+    // Console gets two lines of pretty formatter: time and modules, then message.
+    let lines = lines.lock().unwrap();
+    assert_eq!(lines.len(), 1);
+    assert!(!lines[0].starts_with('{'));
+    assert!(lines[0].contains(" db\n    up"));
+
+    // Text lines would be read as `LogEntry::Line`.
+    let entries = LogReader::open(dir.join("app.log"))
+        .unwrap()
+        .collect::<std::io::Result<Vec<LogEntry>>>()
+        .unwrap();
+    match &entries[..] {
+        [LogEntry::Record(record)] => {
+            assert_eq!(record.message().text(), "up");
+            assert_eq!(record.message().field("id"), Some(&FieldValue::I64(7)));
+        }
+        other => panic!("expected JSON record, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_spill_replays_records_in_order() {
+    use crate::{OutputChannel, Settings};
+
+    let dir = TempDir::new("spill");
+    let written = CaptureSink::default();
+    let formatter = text_formatter();
+    let settings = Settings::new(true, 1, OutputChannel::Console, formatter)
+        .with_console_writer(|_: &str| {})
+        .with_sink(FlakySink(3, written.clone()))
+        .with_spill(dir.clone(), 1);
+
+    let texts = ["a", "b\tx\ny", "c", "d"];
+    run_worker(
+        settings,
+        texts.map(|text| Message::new(&["m".into()], text).with_field("k", "v")),
+    );
+
+    assert_eq!(written.texts(), vec!["a", "b\tx\ny", "c", "d"]);
+}
+
+#[test]
+fn test_queue_spills_past_max_in_memory() {
+    use crate::rotation_logger::logger::queue::Control;
+
+    let dir = TempDir::new("spill_queue");
+    let files = || std::fs::read_dir(&*dir).unwrap().count();
+    let queue = Queue::new().with_spill(Some((&dir, 2)));
+    for text in ["a", "b", "c", "d"] {
+        assert!(queue.send(Message::new(&["m".into()], text).with_field("k", 1)));
+    }
+    // Control item goes after spilled messages, so flush or shutdown waits for them.
+    assert!(queue.control(Control::Stop));
+    assert_eq!(files(), 1);
+    assert_eq!(queue.stats().queue_len(), 5);
+    assert_eq!(queue.stats().high_water(), 5);
+
+    let mut texts = vec![];
+    while let Some(item) = queue.try_recv() {
+        texts.push(match item {
+            Item::Message(message) => {
+                assert_eq!(message.field("k"), Some(&FieldValue::I64(1)));
+                message.text().clone()
+            }
+            Item::Control(control) => format!("{control:?}"),
+        });
+    }
+    assert_eq!(texts, ["a", "b", "c", "d", "Stop"]);
+    assert_eq!(queue.stats().queue_len(), 0);
+    assert_eq!(files(), 0);
+
+    // Memory takes items again once file is read back.
+    assert!(queue.send(Message::new(&[], "e")));
+    assert_eq!(files(), 0);
+}
+
+#[test]
+fn test_queue_spill_leftovers_written_on_start() {
+    use crate::{Level, OutputChannel, Settings};
+
+    let dir = TempDir::new("spill_queue_leftovers");
+    // Crashed run left messages it did not take.
+    let crashed = Queue::new().with_spill(Some((&dir, 1)));
+    assert!(crashed.send(Message::new(&[], "held")));
+    for (text, level) in [
+        ("a", Level::Info),
+        ("hidden", Level::Trace),
+        ("b", Level::Warn),
+    ] {
+        assert!(crashed.send(Message::new(&[], text).with_level(level)));
+    }
+    drop(crashed);
+
+    let written = CaptureSink::default();
+    let settings = Settings::new(true, 1, OutputChannel::Console, text_formatter())
+        .with_console_writer(|_: &str| {})
+        .with_sink(written.clone())
+        .with_min_level(Level::Info)
+        .with_spill(dir.clone(), 1);
+    run_worker(settings, messages(["new"]));
+
+    assert_eq!(written.texts(), ["a", "b", "new"]);
+    assert_eq!(std::fs::read_dir(&*dir).unwrap().count(), 0);
+}
+
+#[test]
+fn test_spill_counts_unreadable_records() {
+    use crate::rotation_logger::logger::spill::SpillQueue;
+
+    let dir = TempDir::new("spill_unreadable");
+    let path = dir.join("app.spill");
+    let queue = Arc::new(Queue::new());
+    let mut spill = SpillQueue::new(path.clone(), 1, queue.clone());
+    let written = CaptureSink::default();
+    let mut sink = FlakySink(1, written.clone());
+    let records = |texts: &[&str]| {
+        let time = chrono::Local::now();
+        let records = texts
+            .iter()
+            .map(|text| Record::new(Message::new(&[], text), "".into(), time));
+        records.collect::<Vec<_>>()
+    };
+
+    // The first record is held in memory, the rest goes to file.
+    assert!(spill.write(&mut sink, &records(&["a", "b", "c"])).is_err());
+    let lines = std::fs::read_to_string(&path).unwrap();
+    let (_, rest) = lines.split_once('\n').unwrap();
+    std::fs::write(&path, format!("broken\n{rest}")).unwrap();
+
+    spill.write(&mut sink, &records(&["d"])).unwrap();
+    assert_eq!(written.texts(), ["a", "c", "d"]);
+    assert_eq!(queue.stats().dropped(), 1);
+    assert!(!path.exists());
+}
+
+#[test]
+fn test_spill_leftovers_written_on_start() {
+    use crate::rotation_logger::logger::spill::SpillQueue;
+    use crate::{OutputChannel, Settings};
+
+    let dir = TempDir::new("spill_leftovers");
+    let queue = Arc::new(Queue::new());
+    let spill = |name: &str, texts: &[&str]| {
+        let mut spill = SpillQueue::new(dir.join(name), 1, queue.clone());
+        let time = chrono::Local::now();
+        let records = texts
+            .iter()
+            .map(|text| Record::new(Message::new(&[], text), text.to_string(), time))
+            .collect::<Vec<_>>();
+        let mut sink = FlakySink(1, CaptureSink::default());
+        assert!(spill.write(&mut sink, &records).is_err());
+        spill
+    };
+    // Crashed run left records of file, the one held in memory is lost with it.
+    drop(spill("1-1-0.spill", &["lost", "a", "b"]));
+    // File of running process is left alone.
+    let running = spill("2-1-0.spill", &["held", "c"]);
+
+    let lines = Arc::new(Mutex::new(Vec::<String>::new()));
+    let written = lines.clone();
+    let settings = Settings::new(true, 1, OutputChannel::Console, text_formatter())
+        .with_console_writer(move |line: &str| written.lock().unwrap().push(line.into()))
+        .with_spill(dir.clone(), 1);
+    run_worker(settings, messages(["new"]));
+
+    assert_eq!(*lines.lock().unwrap(), ["a", "b", "new"]);
+    assert!(!dir.join("1-1-0.spill").exists());
+    assert!(dir.join("2-1-0.spill").exists());
+    drop(running);
+}
+
+#[test]
+fn test_spill_leftovers_keep_unwritten_records() {
+    use crate::rotation_logger::logger::spill::{self, SpillQueue};
+
+    let dir = TempDir::new("spill_keep");
+    let queue = Arc::new(Queue::new());
+    let time = chrono::Local::now();
+    let records =
+        ["held", "a", "b", "c"].map(|text| Record::new(Message::new(&[], text), text.into(), time));
+    let mut left = SpillQueue::new(dir.join("1-1-0.spill"), 1, queue.clone());
+    assert!(
+        left.write(&mut FlakySink(1, CaptureSink::default()), &records)
+            .is_err()
+    );
+    drop(left);
+
+    // Sink fails after the first batch, the rest is written on the next start.
+    let written = CaptureSink::default();
+    let mut sink = FailingAfter(1, written.clone());
+    assert!(spill::replay_leftovers(&dir, 1, &mut sink, &queue).is_err());
+    assert_eq!(written.texts(), ["a"]);
+    assert_eq!(
+        spill::replay_leftovers(&dir, 2, &mut sink.1, &queue).unwrap(),
+        2
+    );
+    assert_eq!(written.texts(), ["a", "b", "c"]);
+    assert_eq!(written.batches().last().unwrap().len(), 2);
+    assert!(std::fs::read_dir(&*dir).unwrap().next().is_none());
+}
+
+#[test]
+fn test_dead_letter_keeps_failed_records() {
+    use crate::{OutputChannel, Settings};
+
+    let dir = TempDir::new("dead");
+    let path = dir.join("dead.log");
+    let written = CaptureSink::default();
+    let formatter = text_formatter();
+    let settings = Settings::new(true, 1, OutputChannel::Console, formatter)
+        .with_console_writer(|_: &str| {})
+        .with_sink(FlakySink(3, written.clone()))
+        .with_dead_letter(path.clone(), 2);
+
+    let queue = run_worker(settings, messages(["a", "b", "c", "d"]));
+
+    // Two failures bury `a` and `b`, `c` is written with `d` after the third.
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "a\nb\n");
+    assert_eq!(written.texts(), vec!["c", "d"]);
+    assert_eq!(queue.stats().dead_letters(), 2);
+}
+#[test]
+fn test_dead_letter_caps_held_records() {
+    use std::time::SystemTime;
+
+    use crate::rotation_logger::logger::dead_letter::{DeadLetter, MAX_HELD};
+
+    let dir = TempDir::new("dead_cap");
+    let queue = Arc::new(Queue::new());
+    let record = |text: &str| Record::new(Message::new(&[], text), text.into(), SystemTime::now());
+    let batch: Vec<Record> = (0..MAX_HELD / 2).map(|_| record("x")).collect();
+
+    // Directory in place of file, records can't be buried.
+    let mut dead_letter = DeadLetter::new(dir.to_path_buf(), 100, queue.clone());
+    let mut sink = FlakySink(usize::MAX, CaptureSink::default());
+    assert_eq!(
+        dead_letter
+            .write(&mut sink, &batch)
+            .unwrap_err()
+            .to_string(),
+        "offline"
+    );
+    let err = dead_letter.write(&mut sink, &batch).unwrap_err();
+    assert!(
+        err.to_string()
+            .starts_with("offline, dead letters not written: ")
+    );
+    dead_letter.write(&mut sink, &[record("last")]).unwrap_err();
+    assert_eq!(queue.stats().dropped(), 1);
+
+    // Writable file takes held records before `max_attempts`.
+    let path = dir.join("dead.log");
+    let mut dead_letter = DeadLetter::new(path.clone(), 100, queue.clone());
+    dead_letter.write(&mut sink, &batch).unwrap_err();
+    dead_letter.write(&mut sink, &batch).unwrap_err();
+    assert_eq!(queue.stats().dead_letters(), MAX_HELD as u64);
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap().lines().count(),
+        MAX_HELD
+    );
+}
+
+#[test]
+fn test_dead_letter_replayed_on_start() {
+    use std::sync::{Arc, Mutex};
+
+    use crate::{OutputChannel, Settings};
+
+    let dir = TempDir::new("replay");
+    let path = dir.join("dead.log");
+    std::fs::write(&path, "old 1\nold 2\n").unwrap();
+
+    let lines = Arc::new(Mutex::new(vec![]));
+    let console = lines.clone();
+    let formatter = text_formatter();
+    let settings = Settings::new(true, 1, OutputChannel::Console, formatter)
+        .with_console_writer(move |line: &str| console.lock().unwrap().push(line.to_string()))
+        .with_dead_letter(path.clone(), 2);
+
+    run_worker(settings, [Message::new(&[], "new")]);
+
+    assert_eq!(*lines.lock().unwrap(), vec!["old 1", "old 2", "new"]);
+    assert_eq!(std::fs::read_dir(&*dir).unwrap().count(), 0);
+}
+
+#[test]
+fn test_dead_letter_replay_keeps_unwritten_lines() {
+    use crate::rotation_logger::logger::dead_letter::{self, REPLAY_BATCH};
+
+    let dir = TempDir::new("replay_keep");
+    let path = dir.join("dead.log");
+    let old: Vec<String> = (0..REPLAY_BATCH * 2 + 1)
+        .map(|i| format!("old {i}"))
+        .collect();
+    std::fs::write(&path, old.join("\n") + "\n").unwrap();
+
+    // Sink fails on second batch, lines of first one are not written again.
+    let written = CaptureSink::default();
+    assert!(dead_letter::replay(&path, &mut FailingAfter(1, written.clone())).is_err());
+    assert_eq!(written.texts(), old[..REPLAY_BATCH]);
+    assert!(!path.exists());
+
+    // Records buried meanwhile go after kept lines.
+    std::fs::write(&path, "new\n").unwrap();
+    let written = CaptureSink::default();
+    let replayed = dead_letter::replay(&path, &mut written.clone()).unwrap();
+    assert_eq!(replayed, REPLAY_BATCH + 2);
+    let mut expected = old[REPLAY_BATCH..].to_vec();
+    expected.push("new".into());
+    assert_eq!(written.texts(), expected);
+    assert_eq!(written.batches().len(), 3);
+    assert_eq!(std::fs::read_dir(&*dir).unwrap().count(), 0);
+}
+
+#[test]
+fn test_message_pool_resets_recycled_message() {
+    use crate::{Level, MessagePool};
+
+    let pool = MessagePool::new(1);
+    let message = pool
+        .message(&["a".into(), "b".into()], "first")
+        .with_level(Level::Error)
+        .with_field("k", "v");
+    pool.recycle(message);
+    pool.recycle(Message::new(&[], "over capacity"));
+    assert_eq!(pool.len(), 1);
+
+    let message = pool.message(&["c".into()], "second");
+    assert!(pool.is_empty());
+    assert_eq!(message.modules(), &vec!["c".to_string()]);
+    assert_eq!(message.text(), "second");
+    assert_eq!(message.level(), Level::Info);
+    assert!(message.fields().is_empty());
+}
+
+#[test]
+fn test_file_output_writes_lines() {
+    use crate::{FileSettings, FileSize, OutputChannel, Settings};
+
+    let dir = TempDir::new("file");
+    let output = OutputChannel::File(FileSettings::new(
+        dir.clone(),
+        2,
+        FileSize::from_megabytes(1),
+        "test".into(),
+        "log".into(),
+    ));
+    let formatter = text_formatter();
+    let settings = Settings::new(true, 2, output, formatter);
+
+    run_worker(settings, messages(["a", "b", "c", "d"]));
+
+    let content = std::fs::read_to_string(dir.join("test.log")).unwrap();
+    assert_eq!(content, "a\nb\nc\nd\n");
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_preallocation_reserves_file_size() {
+    use std::os::unix::fs::MetadataExt;
+
+    use crate::{FileSettings, FileSize, OutputChannel, Settings};
+
+    let dir = TempDir::new("preallocation");
+    let file = |name: &str, preallocation: bool| {
+        let file = FileSettings::new(
+            dir.clone(),
+            2,
+            FileSize::from_megabytes(1),
+            name.into(),
+            "log".into(),
+        );
+        OutputChannel::File(file.with_preallocation(preallocation))
+    };
+    let formatter = text_formatter();
+    let settings = Settings::new(true, 1, file("reserved", true), formatter)
+        .with_tee_output(file("plain", false));
+    run_worker(settings, [Message::new(&[], "started")]);
+
+    let reserved = dir.join("reserved.log").metadata().unwrap();
+    let plain = dir.join("plain.log").metadata().unwrap();
+    // Space is reserved, while length is left as is.
+    assert_eq!(reserved.len(), "started\n".len() as u64);
+    assert_eq!(reserved.len(), plain.len());
+    assert!(reserved.blocks() * 512 >= 1_000_000);
+    assert!(plain.blocks() * 512 < 1_000_000);
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn test_mmap_writer_cuts_preallocated_region() {
+    use crate::{FileSettings, FileSize, FileWriter, MsyncPolicy, OutputChannel, Settings};
+
+    let dir = TempDir::new("mmap");
+    let writer = FileWriter::Mmap {
+        region_size: 4,
+        sync: MsyncPolicy::EveryBatch,
+    };
+    let file_settings = FileSettings::new(
+        dir.clone(),
+        2,
+        FileSize::from_megabytes(1),
+        "test".into(),
+        "log".into(),
+    )
+    .with_writer(writer);
+    let formatter = text_formatter();
+
+    // Second run appends after data of the first one, not after its zeroed region.
+    for texts in [["a", "bb"], ["ccc", "d"]] {
+        let settings = Settings::new(
+            true,
+            1,
+            OutputChannel::File(file_settings.clone()),
+            formatter.clone(),
+        );
+        run_worker(settings, messages(texts));
+    }
+
+    let content = std::fs::read_to_string(dir.join("test.log")).unwrap();
+    assert_eq!(content, "a\nbb\nccc\nd\n");
+}
+
+#[test]
+fn test_file_lock_policies() {
+    use crate::rotation_logger::sink::FileSink;
+    use crate::{FileSettings, FileSize, LockPolicy, Record, Sink};
+
+    let dir = TempDir::new("lock");
+    let file_settings = |policy| {
+        FileSettings::new(
+            dir.clone(),
+            2,
+            FileSize::from_megabytes(1),
+            "test".into(),
+            "log".into(),
+        )
+        .with_lock_policy(policy)
+    };
+    let records = [Record::new(
+        Message::new(&[], "a"),
+        "a".into(),
+        std::time::SystemTime::now(),
+    )];
+
+    let mut owner = FileSink::new(file_settings(LockPolicy::FailFast));
+    let mut locked_out = FileSink::new(file_settings(LockPolicy::FailFast));
+    let mut suffixed = FileSink::new(file_settings(LockPolicy::PidSuffix));
+
+    assert!(owner.write_batch(&records).is_ok());
+    assert!(locked_out.write_batch(&records).is_err());
+    assert!(suffixed.write_batch(&records).is_ok());
+    let suffixed_file = dir.join(format!("test-{}.log", std::process::id()));
+    let suffixed_content = std::fs::read_to_string(suffixed_file).unwrap();
+
+    drop((owner, locked_out, suffixed));
+    assert_eq!(suffixed_content, "a\n");
+
+    let instance = file_settings(LockPolicy::None).with_instance_id("2");
+    assert_eq!(instance.filename(), "test-2");
+}
+
+#[test]
+fn test_module_routes() {
+    use crate::{FileSettings, FileSize, OutputChannel, Settings};
+
+    let dir = TempDir::new("routes");
+    let file_settings = |name: &str| {
+        FileSettings::new(
+            dir.clone(),
+            2,
+            FileSize::from_megabytes(1),
+            name.into(),
+            "log".into(),
+        )
+    };
+    let formatter = text_formatter();
+    let settings = Settings::new(
+        true,
+        2,
+        OutputChannel::File(file_settings("app")),
+        formatter,
+    )
+    .with_route(&["DB".into()], file_settings("db"));
+
+    let sent = [("DB", "a"), ("HTTP", "b"), ("DB", "c"), ("HTTP", "d")];
+    run_worker(
+        settings,
+        sent.map(|(module, text)| Message::new(&[module.into(), "POOL".into()], text)),
+    );
+
+    let read = |name: &str| std::fs::read_to_string(dir.join(name)).unwrap();
+    let (db, app) = (read("db.log"), read("app.log"));
+    assert_eq!(db, "a\nc\n");
+    assert_eq!(app, "b\nd\n");
+}
+
+#[test]
+fn test_module_logger_levels() {
+    use crate::{Level, Logger, Settings};
+
+    let logger = Logger::new(
+        Settings::default()
+            .with_min_level(Level::Info)
+            .with_module_level("app::db", Level::Warn)
+            .with_module_level("app::db::pool", Level::Trace),
+    );
+    let app = logger.get("app");
+    let db = app.get("db");
+    assert_eq!(db.modules(), ["app".to_string(), "db".to_string()]);
+    assert_eq!(app.level(), Some(Level::Info));
+    assert_eq!(db.level(), Some(Level::Warn));
+    assert_eq!(db.get("pool").level(), Some(Level::Trace));
+    assert_eq!(db.get("other").level(), Some(Level::Warn));
+
+    let quiet = app.with_level(Level::Error);
+    assert_eq!(quiet.get("db::pool").level(), Some(Level::Error));
+    assert_eq!(Logger::Disabled.get("app").level(), None);
+}
+
+#[test]
+fn test_child_logger_modules() {
+    use crate::{Logger, Settings};
+
+    let logger = Logger::new(Settings::default());
+    let worker = logger.child(["THREAD1", "WORKER"]);
+    assert_eq!(
+        worker.modules(),
+        ["THREAD1".to_string(), "WORKER".to_string()]
+    );
+    assert_eq!(
+        worker.child(vec![String::from("JOB")]).modules(),
+        ["THREAD1", "WORKER", "JOB"].map(String::from)
+    );
+}
+
+#[test]
+fn test_scoped_logger_nests() {
+    use crate::rotation_logger::logger::scoped_modules;
+    use crate::{Logger, Settings};
+
+    let logger = Logger::new(Settings::default());
+    let modules = |own: &[&str]| scoped_modules(own);
+    {
+        let http = logger.scoped("HTTP");
+        {
+            let _request = http.scoped("REQUEST");
+            assert_eq!(modules(&["PARSE"]), ["HTTP", "REQUEST", "PARSE"]);
+        }
+        assert_eq!(modules(&["PARSE"]), ["HTTP", "PARSE"]);
+    }
+    assert_eq!(modules(&["PARSE"]), ["PARSE"]);
+
+    // Outer scope dropped first closes inner one, later drop of inner one keeps new scopes.
+    let outer = logger.scoped("A");
+    let inner = logger.scoped("B");
+    drop(outer);
+    assert_eq!(modules(&["PARSE"]), ["PARSE"]);
+    let _next = logger.scoped("C");
+    drop(inner);
+    assert_eq!(modules(&["PARSE"]), ["C", "PARSE"]);
+}
+
+#[test]
+fn test_kill_switch_drops_messages() {
+    use crate::{Level, Logger, OutputChannel, Settings, error};
+
+    let _running = running_logger();
+    let written = CaptureSink::default();
+    let formatter = text_formatter();
+    let output = OutputChannel::custom(written.clone());
+    let settings = Settings::new(true, 1, output, formatter);
+    let logger = Logger::new(settings);
+    let worker = logger.run_async().unwrap();
+
+    logger.log("before");
+    Logger::set_kill_switch(true);
+    assert!(Logger::kill_switch());
+    assert!(!logger.is_enabled(Level::Error, [""; 0]));
+    logger.log("dropped");
+    error!("dropped by macro");
+    assert!(logger.log_sync([""; 0], "dropped sync").is_ok());
+    Logger::set_kill_switch(false);
+    logger.log("after");
+    logger.shutdown().unwrap();
+    worker.join().unwrap();
+
+    let expected = match Level::Info.is_statically_enabled() {
+        true => vec!["before", "after"],
+        false => vec![],
+    };
+    assert_eq!(written.texts(), expected);
+}
+
+#[test]
+fn test_is_enabled() {
+    use crate::{Level, Logger, Settings};
+
+    let _running = running_logger();
+    assert!(!Logger::Disabled.is_enabled(Level::Error, [""; 0]));
+
+    let logger = Logger::new(
+        Settings::default()
+            .with_min_level(Level::Info)
+            .with_module_level("DB", Level::Debug),
+    );
+    // Levels compiled out by `max_level_*` features are never enabled.
+    let info = Level::Info.is_statically_enabled();
+    let debug = Level::Debug.is_statically_enabled();
+    assert_eq!(logger.is_enabled(Level::Info, [""; 0]), info);
+    assert!(!logger.is_enabled(Level::Debug, [""; 0]));
+    assert_eq!(logger.is_enabled(Level::Debug, ["DB"]), debug);
+    let owned: Vec<String> = vec!["DB".into(), "POOL".into()];
+    assert_eq!(logger.is_enabled(Level::Debug, &owned), debug);
+    {
+        let _db = logger.scoped("DB");
+        assert_eq!(logger.is_enabled(Level::Debug, [""; 0]), debug);
+    }
+}
+
+#[test]
+fn test_lazy_log_text() {
+    use crate::{LogText, log};
+
+    // No logger runs, so message is never built.
+    let _running = running_logger();
+    log!(["MOD"], || -> String { panic!("closure called") });
+    log!("text");
+
+    assert_eq!(LogText::into_text("text"), "text");
+    assert_eq!(LogText::into_text(|| format!("{}", 1)), "1");
+}
+
+#[test]
+fn test_level_macros() {
+    use crate::{debug, error, info, trace, warn};
+
+    // Every form of `log!` is accepted, nothing runs to write messages.
+    let _running = running_logger();
+    error!(["DB"], "connection lost");
+    warn!(target: "wire", "retry {} of {}", 1, 3);
+    warn!(target: "wire", ["HTTP"], "sent {} bytes", 42);
+    info!("started");
+    debug!((CACHE, MISS), "key");
+    trace!(|| String::from("expensive"));
+}
+
+#[test]
+fn test_borrowed_module_arguments() {
+    use crate::{Level, Logger, OutputChannel, Settings, warn};
+
+    let _running = running_logger();
+    let lines = Arc::new(Mutex::new(vec![]));
+    let console = lines.clone();
+    let formatter = MessageFormatter::new("::", "{modules:0:0}{splitter}{message:0:0}", "");
+    let settings = Settings::new(true, 1, OutputChannel::Console, formatter)
+        .with_console_writer(move |line: &str| console.lock().unwrap().push(line.to_string()));
+    let logger = Logger::new(settings);
+    let worker = logger.run_async().unwrap();
+
+    let owned: Vec<String> = vec!["DB".into(), "POOL".into()];
+    logger.log_with_level(Level::Warn, ["DB", "POOL"], "array");
+    logger.log_with_level(Level::Warn, &owned, "vec");
+    logger.log_with_level(Level::Warn, "DB::POOL".split("::"), "iterator");
+    // Temporary modules of macros live until message is sent.
+    let id = 7;
+    warn!([format!("WORKER{id}"), String::from("POOL")], "temporary");
+    logger.shutdown().unwrap();
+    worker.join().unwrap();
+
+    let expected = match Level::Warn.is_statically_enabled() {
+        true => vec![
+            "DB::POOL::array",
+            "DB::POOL::vec",
+            "DB::POOL::iterator",
+            "WORKER7::POOL::temporary",
+        ],
+        false => vec![],
+    };
+    assert_eq!(*lines.lock().unwrap(), expected);
+}
+
+#[test]
+fn test_static_max_level_skips_arguments() {
+    use std::cell::RefCell;
+
+    use crate::{Level, STATIC_MAX_LEVEL, debug, error, info, trace, warn};
+
+    // Modules are evaluated before running logger is asked, only for compiled in levels.
+    let evaluated = RefCell::new(vec![]);
+    let module = |level: Level| {
+        evaluated.borrow_mut().push(level);
+        "APP"
+    };
+    let _running = running_logger();
+    error!([module(Level::Error)], "error");
+    warn!([module(Level::Warn)], "warn");
+    info!([module(Level::Info)], "info");
+    debug!([module(Level::Debug)], "debug");
+    trace!([module(Level::Trace)], "trace");
+
+    let levels = [
+        Level::Error,
+        Level::Warn,
+        Level::Info,
+        Level::Debug,
+        Level::Trace,
+    ];
+    let enabled = levels
+        .into_iter()
+        .filter(|level| level.is_statically_enabled());
+    assert_eq!(*evaluated.borrow(), enabled.collect::<Vec<_>>());
+    for level in levels {
+        let compiled_in = STATIC_MAX_LEVEL.is_some_and(|max| level <= max);
+        assert_eq!(level.is_statically_enabled(), compiled_in);
+    }
+    #[cfg(all(
+        debug_assertions,
+        not(any(
+            feature = "max_level_off",
+            feature = "max_level_error",
+            feature = "max_level_warn",
+            feature = "max_level_info",
+            feature = "max_level_debug"
+        ))
+    ))]
+    assert_eq!(STATIC_MAX_LEVEL, Some(Level::Trace));
+}
+
+#[test]
+fn test_macros_filter_by_running_levels() {
+    use crate::rotation_logger::logger::queue;
+    use crate::{FlushPolicy, Level, Logger, OutputChannel, Settings, debug, log_enabled, warn};
+
+    /// Module name which is not `Display`, macros only borrow it.
+    struct Module(&'static str);
+
+    impl AsRef<str> for Module {
+        fn as_ref(&self) -> &str {
+            self.0
+        }
+    }
+
+    let _running = running_logger();
+    assert!(!log_enabled!(Level::Error));
+
+    let written = CaptureSink::default();
+    let lines = Arc::new(Mutex::new(vec![]));
+    let console = lines.clone();
+    let formatter = MessageFormatter::new("::", "{modules:0:0}{splitter}{message:0:0}", "");
+    let settings = Settings::new(true, 100, OutputChannel::Console, formatter)
+        .with_console_writer(move |line: &str| console.lock().unwrap().push(line.to_string()))
+        .with_sink_policy(written.clone(), FlushPolicy::new(100))
+        .with_min_level(Level::Warn)
+        .with_module_level("DB", Level::Debug);
+    let logger = Logger::new(settings);
+    let worker = logger.run_async().unwrap();
+    let running = queue::running().unwrap();
+
+    // Levels compiled out by `max_level_*` features are never enabled.
+    let debug = Level::Debug.is_statically_enabled();
+    let warn = Level::Warn.is_statically_enabled();
+    assert_eq!(log_enabled!(Level::Debug, [Module("DB")]), debug);
+    assert!(!log_enabled!(Level::Debug, ["HTTP"]));
+    assert!(!log_enabled!(Level::Info));
+    debug!([Module("HTTP")], || -> String {
+        panic!("dropped message is built")
+    });
+    debug!([Module("DB")], "query");
+    warn!("slow");
+    // Macros and module loggers put modules of open scopes first, like `Logger`.
+    {
+        let _scope = logger.scoped("JOB");
+        warn!(["DB"], "retry");
+        logger.get("app").log_with_level(Level::Warn, "done");
+    }
+    logger.shutdown().unwrap();
+    let texts = [
+        ("query", debug),
+        ("slow", warn),
+        ("retry", warn),
+        ("done", warn),
+    ];
+    let texts = texts.iter().filter(|(_, enabled)| *enabled);
+    assert_eq!(
+        written.texts(),
+        texts.map(|(text, _)| *text).collect::<Vec<_>>()
+    );
+    let scoped = lines.lock().unwrap().clone();
+    let scoped = scoped.iter().filter(|line| line.starts_with("JOB::"));
+    let expected = match warn {
+        true => vec!["JOB::DB::retry", "JOB::app::done"],
+        false => vec![],
+    };
+    assert_eq!(scoped.collect::<Vec<_>>(), expected);
+
+    // Levels go with queue of stopped logger, nothing is kept for macros.
+    assert!(!log_enabled!(Level::Error));
+    worker.join().unwrap();
+    assert_eq!(Arc::strong_count(&running), 1);
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn test_text_filters_first_match_decides() {
+    use crate::TextFilter;
+
+    let filters = [
+        TextFilter::keep("critical").unwrap(),
+        TextFilter::drop(r"^connection \d+ reset").unwrap(),
+    ];
+    assert!(TextFilter::keeps(&filters, "started"));
+    assert!(!TextFilter::keeps(&filters, "connection 12 reset"));
+    assert!(TextFilter::keeps(&filters, "connection 12 reset, critical"));
+    assert!(TextFilter::drop("(").is_err());
+}
+
+#[test]
+fn test_allowed_and_denied_modules() {
+    use crate::{Level, Settings};
+
+    let settings = Settings::default()
+        .with_allowed_module("app")
+        .with_allowed_module("db")
+        .with_denied_module("app::metrics");
+    let enabled = |modules: &[&str]| {
+        let modules = modules
+            .iter()
+            .map(|module| module.to_string())
+            .collect::<Vec<_>>();
+        settings.is_enabled_for(Level::Info, &modules)
+    };
+    assert!(enabled(&["app", "http"]));
+    assert!(enabled(&["db"]));
+    assert!(!enabled(&["app", "metrics", "cpu"]));
+    assert!(!enabled(&["cache"]));
+    assert!(!enabled(&[]));
+}
+
+#[test]
+fn test_level_windows() {
+    use std::time::SystemTime;
+
+    use chrono::{Local, NaiveTime, TimeZone};
+
+    use crate::{Level, LevelWindow, Settings};
+
+    let at = |hour| NaiveTime::from_hms_opt(hour, 0, 0).unwrap();
+    let night = LevelWindow::new(at(22), at(6), Level::Warn);
+    assert!(night.contains(at(23)) && night.contains(at(2)));
+    assert!(!night.contains(at(6)) && !night.contains(at(12)));
+
+    let settings = Settings::default()
+        .with_min_level(Level::Info)
+        .with_level_window(night)
+        .with_level_window(LevelWindow::new(at(12), at(13), Level::Trace));
+    let time = |hour| -> SystemTime {
+        Local
+            .from_local_datetime(
+                &chrono::NaiveDate::from_ymd_opt(2024, 1, 10)
+                    .unwrap()
+                    .and_time(at(hour)),
+            )
+            .unwrap()
+            .into()
+    };
+    // Senders let through levels of every window.
+    assert!(settings.is_enabled_for(Level::Trace, &[]));
+    assert!(!settings.is_enabled_at(Level::Info, &[], time(23)));
+    assert!(settings.is_enabled_at(Level::Trace, &[], time(12)));
+    assert!(settings.is_enabled_at(Level::Info, &[], time(15)));
+    assert!(!settings.is_enabled_at(Level::Debug, &[], time(15)));
+}
+
+#[test]
+fn test_timestamp_format_presets() {
+    use crate::TimestampFormat;
+
+    let time = chrono::DateTime::from_timestamp(1_704_884_400, 123_000_000).unwrap();
+    let message = Message::new(&[], "");
+    let format = |timestamp: TimestampFormat| {
+        MessageFormatter::new("::", "{timestamp:0:0}", "")
+            .with_timestamp_format(timestamp)
+            .format_at(&message, &time)
+    };
+    assert_eq!(
+        format(TimestampFormat::Rfc3339),
+        "2024-01-10T11:00:00+00:00"
+    );
+    assert_eq!(
+        format(TimestampFormat::Rfc3339Millis),
+        "2024-01-10T11:00:00.123+00:00"
+    );
+    assert_eq!(format(TimestampFormat::EpochSeconds), "1704884400");
+    assert_eq!(format(TimestampFormat::EpochMillis), "1704884400123");
+    assert_eq!(format("%H:%M".into()), "11:00");
+}
+
+#[test]
+fn test_empty_modules() {
+    use crate::EmptyModules;
+
+    let message = Message::new(&[], "up");
+    let format = |template: &str, empty_modules: EmptyModules| {
+        MessageFormatter::new("::", template, "")
+            .with_empty_modules(empty_modules)
+            .format(&message)
+    };
+    let template = "{target:0:2} {splitter} {modules:0:4} {splitter} {message:0:0}";
+    assert_eq!(format(template, EmptyModules::Keep), "   ::      :: up");
+    assert_eq!(format(template, EmptyModules::Collapse), "   :: up");
+    assert_eq!(
+        format(template, EmptyModules::Placeholder("-".into())),
+        "   ::  -   :: up"
+    );
+    assert_eq!(
+        format("{message:0:0} {splitter} {modules}", EmptyModules::Collapse),
+        "up"
+    );
+}
+
+#[test]
+fn test_positional_splitters() {
+    let message = Message::new(&["db".into(), "pool".into()], "up");
+    let formatter =
+        MessageFormatter::new("::", "{modules:0:0} {splitter} {message:0:0}{splitter}", "")
+            .with_splitters(&["|"])
+            .with_modules_separator("/");
+    assert_eq!(formatter.format(&message), "db/pool | up::");
+
+    let simple = MessageFormatter::simple("::", "").with_splitters(&["-"]);
+    assert_eq!(simple.format(&message), "db::pool - up");
+}
+
+#[test]
+fn test_optional_masks() {
+    use crate::Template;
+
+    let formatter = MessageFormatter::new("|", "{message:0:0} {splitter} {fields:0:0:?}", "");
+    let message = Message::new(&[], "up");
+    assert_eq!(formatter.format(&message), "up");
+    let message = message.with_field("id", 7);
+    assert_eq!(formatter.format(&message), "up | id=7");
+
+    let template = Template::new()
+        .message()
+        .width(0)
+        .splitter()
+        .target()
+        .width(0)
+        .optional();
+    let formatter = MessageFormatter::from_template("|", template, "");
+    assert_eq!(formatter.format(&Message::new(&[], "up")), "up");
+}
+
+#[test]
+fn test_level_templates() {
+    use crate::Level;
+
+    let formatter = text_formatter().with_level_template(
+        Level::Error,
+        "{modules:0:0} {splitter} {message:0:0} {fields:0:0}",
+    );
+    let message = Message::new(&["db".into()], "lost").with_field("id", 7);
+    assert_eq!(formatter.format(&message), "lost");
+    assert_eq!(
+        formatter.format(&message.with_level(Level::Error)),
+        "db :: lost id=7"
+    );
+}
+
+#[test]
+fn test_timestamp_precision() {
+    use crate::{TimestampFormat, TimestampPrecision};
+
+    let time = chrono::DateTime::from_timestamp(1_704_884_400, 123_456_789).unwrap();
+    let message = Message::new(&[], "");
+    let format = |timestamp: TimestampFormat, precision: TimestampPrecision| {
+        MessageFormatter::new("::", "{timestamp:0:0}", "")
+            .with_timestamp_format(timestamp)
+            .with_timestamp_precision(precision)
+            .format_at(&message, &time)
+    };
+    let custom = || TimestampFormat::from("%H:%M:%S.%f");
+    assert_eq!(format(custom(), TimestampPrecision::Millis), "11:00:00.123");
+    assert_eq!(
+        format(custom(), TimestampPrecision::Micros),
+        "11:00:00.123456"
+    );
+    assert_eq!(format(custom(), TimestampPrecision::Seconds), "11:00:00");
+    assert_eq!(
+        format("%S%.3f %%f".into(), TimestampPrecision::Nanos),
+        "00.123456789 %f"
+    );
+    assert_eq!(
+        format(TimestampFormat::Rfc3339, TimestampPrecision::Millis),
+        "2024-01-10T11:00:00.123+00:00"
+    );
+}
+
+#[test]
+fn test_timestamp_ms_mask() {
+    let time = chrono::DateTime::from_timestamp(1_704_884_400, 123_000_000).unwrap();
+    let formatter = MessageFormatter::new("::", "{timestamp:0:0} {timestamp_ms:0:0}", "%H:%M");
+    assert_eq!(
+        formatter.format_at(&Message::new(&[], ""), &time),
+        "11:00 1704884400123"
+    );
+}
+
+#[test]
+fn test_delta_masks() {
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use crate::rotation_logger::logger::EnabledLogger;
+    use crate::{OutputChannel, Settings};
+
+    let lines = Arc::new(Mutex::new(Vec::<String>::new()));
+    let written = lines.clone();
+    let formatter = MessageFormatter::new("::", "{message:0:0} {delta:0:0} {delta_all:0:0}", "");
+    let settings = Settings::new(true, 1, OutputChannel::Console, formatter)
+        .with_console_writer(move |line: &str| written.lock().unwrap().push(line.into()));
+
+    let queue = Arc::new(Queue::new());
+    let logger = EnabledLogger::new(settings, queue.clone());
+    let worker = std::thread::spawn(move || logger.run());
+    for (module, text) in [("DB", "a"), ("HTTP", "b"), ("DB", "c")] {
+        assert!(queue.send(Message::new(&[module.into()], text)));
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    queue.close();
+    worker.join().unwrap();
+
+    let lines = lines.lock().unwrap();
+    let delta = |line: &str, index: usize| {
+        let value = line.split(' ').nth(index).unwrap();
+        value
+            .trim_start_matches('+')
+            .trim_end_matches('s')
+            .parse::<f64>()
+            .unwrap()
+    };
+    assert_eq!(lines[0].trim_end(), "a");
+    assert!(lines[1].starts_with("b  +"));
+    // `c` is the second `DB` record, `delta` counts from `a`, `delta_all` from `b`.
+    assert!(delta(&lines[2], 1) >= 0.04 && delta(&lines[2], 2) < delta(&lines[2], 1));
+}
+
+#[test]
+fn test_schema_header_and_reader() {
+    use crate::rotation_logger::sink::FileSink;
+    use crate::{
+        FileHeader, FileSettings, FileSize, Level, LogEntry, LogReader, Record, RecordFormat, Sink,
+    };
+
+    let dir = TempDir::new("schema");
+    let settings = FileSettings::new(
+        dir.clone(),
+        2,
+        FileSize::from_megabytes(1),
+        "app".into(),
+        "log".into(),
+    )
+    .with_schema_header(true);
+    let record = |text: &str| {
+        Record::new(
+            Message::new(&["db".into()], text)
+                .with_level(Level::Warn)
+                .with_field("id", 7),
+            text.into(),
+            std::time::SystemTime::now(),
+        )
+    };
+    {
+        let mut sink = FileSink::json(settings);
+        sink.write_batch(&[record("a")]).unwrap();
+        sink.write_batch(&[record("b \"quoted\"\n")]).unwrap();
+    }
+    let reader = LogReader::open(dir.join("app.log")).unwrap();
+    assert_eq!(reader.header(), Some(&FileHeader::new(RecordFormat::Json)));
+    let entries = reader.collect::<std::io::Result<Vec<LogEntry>>>().unwrap();
+
+    let texts = entries
+        .iter()
+        .map(|entry| match entry {
+            LogEntry::Record(record) => {
+                assert_eq!(record.message().level(), Level::Warn);
+                assert_eq!(
+                    record.message().fields(),
+                    &vec![("id".to_string(), FieldValue::I64(7))]
+                );
+                record.message().text().clone()
+            }
+            LogEntry::Line(line) => panic!("unexpected line {line}"),
+        })
+        .collect::<Vec<String>>();
+    assert_eq!(texts, ["a", "b \"quoted\"\n"]);
+
+    // Schema `0` had no `schema` key.
+    let old =
+        "{\"timestamp\":1.5,\"level\":\"INFO\",\"modules\":[],\"message\":\"old\",\"fields\":{}}";
+    assert!(crate::rotation_logger::reader::parse_record(old).is_some());
+    assert!(
+        crate::rotation_logger::reader::parse_record(
+            &old.replace("{\"time", "{\"schema\":9,\"time")
+        )
+        .is_none()
+    );
+}
+
+#[test]
+fn test_manifest_follows_rotation() {
+    use crate::rotation_logger::sink::FileSink;
+    use crate::{FileSettings, FileSize, Level, Record, Sink};
+
+    let dir = TempDir::new("manifest");
+    let settings = FileSettings::new(
+        dir.clone(),
+        3,
+        FileSize::from_bytes(1),
+        "app".into(),
+        "log".into(),
+    )
+    .with_manifest(true);
+    let record = |text: &str, millis: u64| {
+        Record::new(
+            Message::new(&[], text).with_level(Level::Info),
+            text.into(),
+            std::time::UNIX_EPOCH + std::time::Duration::from_millis(millis),
+        )
+    };
+    {
+        let mut sink = FileSink::new(settings).with_format("{message}".into());
+        sink.write_batch(&[record("first", 1000), record("second", 2000)])
+            .unwrap();
+        sink.write_batch(&[record("c", 3000)]).unwrap();
+    }
+    let manifest = std::fs::read_to_string(dir.join("app.manifest.json")).unwrap();
+
+    assert!(manifest.starts_with("{\"schema\":3,\"format\":\"{message}\","));
+    assert!(manifest.contains("\"capacity\":3"));
+    assert!(
+        manifest.contains(
+            "{\"name\":\"app.log1\",\"generation\":1,\"first_ms\":1000,\"last_ms\":2000}"
+        )
+    );
+    assert!(
+        manifest.contains(
+            "{\"name\":\"app.log0\",\"generation\":0,\"first_ms\":3000,\"last_ms\":3000}"
+        )
+    );
+}
+
+#[test]
+fn test_instance_files_rotate_apart() {
+    use crate::rotation_logger::sink::FileSink;
+    use crate::{FileSettings, FileSize, Level, Record, Sink};
+
+    let dir = TempDir::new("instances");
+    let settings = FileSettings::new(
+        dir.clone(),
+        3,
+        FileSize::from_bytes(1),
+        "worker".into(),
+        "log".into(),
+    );
+    let record = |text: &str| {
+        Record::new(
+            Message::new(&[], text).with_level(Level::Info),
+            text.into(),
+            std::time::SystemTime::now(),
+        )
+    };
+    assert_eq!(
+        *settings.clone().with_pid_suffix().filename(),
+        format!("worker-{}", std::process::id())
+    );
+    {
+        let mut first = FileSink::new(settings.clone().with_instance_id("1"));
+        let mut second = FileSink::new(settings.with_instance_id("2"));
+        second.write_batch(&[record("b1")]).unwrap();
+        for text in ["a1", "a2", "a3"] {
+            first.write_batch(&[record(text)]).unwrap();
+        }
+    }
+    let mut files = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect::<Vec<_>>();
+    files.sort();
+
+    // Capacity of first instance does not remove files of second one.
+    assert_eq!(files, ["worker-1.log0", "worker-1.log1", "worker-2.log0"]);
+    let read = |name: &str| std::fs::read_to_string(dir.join(name)).unwrap();
+    assert_eq!(read("worker-1.log1"), "a2\n");
+    assert_eq!(read("worker-1.log0"), "a3\n");
+    assert_eq!(read("worker-2.log0"), "b1\n");
+}
+
+#[test]
+fn test_rotation_records() {
+    use crate::rotation_logger::sink::FileSink;
+    use crate::{FileSettings, FileSize, Level, Record, Sink};
+
+    let dir = TempDir::new("rotation");
+    let settings = FileSettings::new(
+        dir.clone(),
+        3,
+        FileSize::from_bytes(1),
+        "app".into(),
+        "log".into(),
+    )
+    .with_rotation_records(true);
+    let record = |text: &str| {
+        Record::new(
+            Message::new(&[], text).with_level(Level::Info),
+            text.into(),
+            std::time::SystemTime::now(),
+        )
+    };
+    {
+        let mut sink = FileSink::new(settings);
+        sink.write_batch(&[record("first"), record("second")])
+            .unwrap();
+        sink.write_batch(&[record("third")]).unwrap();
+    }
+    let second = std::fs::read_to_string(dir.join("app.log0")).unwrap();
+
+    let lines = second.lines().collect::<Vec<&str>>();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("rotated from app.log0 at "));
+    assert!(lines[0].ends_with(", 13 bytes, seq range 0..1"));
+    assert_eq!(lines[1], "third");
+}
+
+#[test]
+fn test_crlf_line_ending() {
+    use crate::rotation_logger::sink::FileSink;
+    use crate::{FileSettings, FileSize, LineEnding, Record, Sink};
+
+    let dir = TempDir::new("crlf");
+    let settings = FileSettings::new(
+        dir.clone(),
+        3,
+        FileSize::from_bytes(1024),
+        "app".into(),
+        "log".into(),
+    )
+    .with_line_ending(LineEnding::CrLf);
+    let record = |text: &str| {
+        Record::new(
+            Message::new(&[], text),
+            text.into(),
+            std::time::SystemTime::now(),
+        )
+    };
+    {
+        let mut sink = FileSink::new(settings);
+        sink.write_batch(&[record("first"), record("second")])
+            .unwrap();
+    }
+    let written = std::fs::read_to_string(dir.join("app.log")).unwrap();
+
+    assert_eq!(written, "first\r\nsecond\r\n");
+}
+
+#[test]
+fn test_file_encoding() {
+    use crate::rotation_logger::sink::FileSink;
+    use crate::{Encoding, FileSettings, FileSize, LogEntry, LogReader, Record, Sink};
+
+    assert_eq!(
+        Encoding::Latin1.encode("café “ok” – 5€").as_ref(),
+        b"caf\xE9 \"ok\" - 5EUR"
+    );
+    assert_eq!(Encoding::Utf8.encode("café").as_ref(), "café".as_bytes());
+
+    let dir = TempDir::new("bom");
+    let settings = FileSettings::new(
+        dir.clone(),
+        3,
+        FileSize::from_bytes(1024),
+        "app".into(),
+        "log".into(),
+    )
+    .with_encoding(Encoding::Utf8Bom);
+    let record = Record::new(
+        Message::new(&[], "first"),
+        "first".into(),
+        std::time::SystemTime::now(),
+    );
+    FileSink::new(settings).write_batch(&[record]).unwrap();
+    let written = std::fs::read(dir.join("app.log")).unwrap();
+    let entries = LogReader::open(dir.join("app.log"))
+        .unwrap()
+        .collect::<Vec<_>>();
+
+    assert_eq!(written, b"\xEF\xBB\xBFfirst\n");
+    assert!(matches!(&entries[..], [Ok(LogEntry::Line(line))] if line == "first"));
+}
+
+#[test]
+fn test_banner_written_on_start() {
+    use std::sync::{Arc, Mutex};
+
+    use crate::{Banner, Level, OutputChannel, Settings};
+
+    let banner = Banner::new("app", "1.2.3")
+        .with_git_sha("4f2a9c1")
+        .with_env("ROTATION_LOGGER_BANNER_UNSET");
+    let fields = banner.message().fields().clone();
+    assert_eq!(
+        fields,
+        vec![
+            ("app".to_string(), "app".into()),
+            ("version".into(), "1.2.3".into()),
+            ("git_sha".into(), "4f2a9c1".into()),
+        ]
+    );
+
+    let lines = Arc::new(Mutex::new(Vec::<String>::new()));
+    let written = lines.clone();
+    let formatter = text_formatter();
+    let settings = Settings::new(true, 1, OutputChannel::Console, formatter)
+        .with_min_level(Level::Error)
+        .with_banner(banner)
+        .with_console_writer(move |line: &str| written.lock().unwrap().push(line.into()));
+
+    run_worker(
+        settings,
+        [Message::new(&[], "dropped").with_level(Level::Info)],
+    );
+
+    // Banner is written past levels.
+    assert_eq!(*lines.lock().unwrap(), vec!["started app 1.2.3"]);
+}
+
+#[test]
+fn test_target_mask() {
+    use crate::rotation_logger::reader::parse_record;
+    use crate::{Record, Template};
+
+    let formatter = MessageFormatter::from_template(
+        "::",
+        Template::parse("{target:0:0}|{modules:0:0}|{message:0:0}"),
+        "",
+    );
+    let message = Message::new(&["HTTP".into()], "sent 42 bytes").with_target("wire");
+    assert_eq!(formatter.format(&message), "wire|HTTP|sent 42 bytes");
+    assert_eq!(formatter.format(&Message::new(&[], "plain")), "||plain");
+
+    let json = Record::new(message, String::new(), std::time::SystemTime::now()).to_json();
+    assert!(json.contains("\"target\":\"wire\""));
+    assert_eq!(
+        parse_record(&json).unwrap().message().target(),
+        Some("wire")
+    );
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_field_value() {
+    use crate::Record;
+    use crate::rotation_logger::reader::parse_record;
+
+    let message = Message::new(&[], "text").with_serde_field("tags", &["a", "b"]);
+    assert_eq!(
+        message.field("tags"),
+        Some(&FieldValue::Json(serde_json::json!(["a", "b"])))
+    );
+    let json = Record::new(message, String::new(), std::time::SystemTime::now()).to_json();
+    assert!(json.ends_with("\"fields\":{\"tags\":[\"a\",\"b\"]}}"));
+    let read = parse_record(&json).unwrap();
+    assert_eq!(
+        read.message().field("tags"),
+        Some(&FieldValue::Json(serde_json::json!(["a", "b"])))
+    );
+}
+
+#[test]
+fn test_humanized_fields() {
+    use crate::Humanize;
+
+    let formatter = MessageFormatter::new("::", "{fields:0:0}", "")
+        .with_humanized_field("elapsed", Humanize::Nanos)
+        .with_humanized_field("size", Humanize::Bytes);
+    let message = Message::new(&[], "done")
+        .with_field("elapsed", 1_234_000_000u64)
+        .with_field("size", 1_468_006)
+        .with_field("rows", 12);
+    assert_eq!(
+        formatter.format(&message),
+        "elapsed=1.2s size=1.4 MiB rows=12"
+    );
+    assert_eq!(Humanize::Millis.format(&340.into()).unwrap(), "340ms");
+    assert_eq!(Humanize::Micros.format(&"15".into()).unwrap(), "15µs");
+    assert_eq!(Humanize::Bytes.format(&512.into()).unwrap(), "512 B");
+    assert_eq!(Humanize::Bytes.format(&"n/a".into()), None);
+}
+
+#[test]
+fn test_auto_widths_grow_to_cap() {
+    use std::sync::{Arc, Mutex};
+
+    use crate::{OutputChannel, Settings};
+
+    let lines = Arc::new(Mutex::new(Vec::<String>::new()));
+    let written = lines.clone();
+    let formatter = MessageFormatter::new("::", "{modules:0:20:left}|{message:0:0}", "");
+    let settings = Settings::new(true, 1, OutputChannel::Console, formatter)
+        .with_auto_widths(6)
+        .with_console_writer(move |line: &str| written.lock().unwrap().push(line.into()));
+
+    let modules = ["a", "abcd", "a", "abcdefghij", "a"];
+    run_worker(
+        settings,
+        modules.map(|module| Message::new(&[module.into()], "x")),
+    );
+
+    assert_eq!(
+        *lines.lock().unwrap(),
+        vec!["a|x", "abcd|x", "a   |x", "abcdef|x", "a     |x"]
+    );
+}
+
+#[test]
+fn test_console_grid_header() {
+    use std::sync::{Arc, Mutex};
+
+    use crate::{Grid, OutputChannel, Settings, Template};
+
+    let lines = Arc::new(Mutex::new(Vec::<String>::new()));
+    let written = lines.clone();
+    let grid = Grid::new(Template::parse("[{modules:0:6:left}] - {message:0:5:left}"));
+    let formatter = MessageFormatter::new("::", "{message}", "");
+    let settings = Settings::new(true, 1, OutputChannel::Console, formatter)
+        .with_console_grid(grid)
+        .with_console_writer(move |line: &str| written.lock().unwrap().push(line.into()));
+
+    run_worker(settings, [Message::new(&["db".into()], "ok")]);
+
+    assert_eq!(
+        *lines.lock().unwrap(),
+        vec!["MODULE | MESSA", "-------+------", "db     | ok   "]
+    );
+}
+
+#[test]
+fn test_custom_format() {
+    use std::fmt;
+    use std::sync::{Arc, Mutex};
+
+    use crate::{Format, OutputChannel, Settings};
+
+    struct Logfmt;
+
+    impl Format for Logfmt {
+        fn format(&self, message: &Message, out: &mut dyn fmt::Write) -> fmt::Result {
+            write!(out, "msg={:?}", message.text())?;
+            for (key, value) in message.fields() {
+                write!(out, " {key}={value}")?;
+            }
+            Ok(())
+        }
+    }
+
+    let lines = Arc::new(Mutex::new(Vec::<String>::new()));
+    let written = lines.clone();
+    let settings = Settings::new(true, 1, OutputChannel::Console, MessageFormatter::default())
+        .with_custom_format(Box::new(Logfmt))
+        .with_auto_widths(40)
+        .with_console_writer(move |line: &str| written.lock().unwrap().push(line.into()));
+
+    run_worker(
+        settings,
+        [Message::new(&["db".into()], "up").with_field("id", 7)],
+    );
+
+    assert_eq!(*lines.lock().unwrap(), vec!["msg=\"up\" id=7"]);
+}
+
+#[test]
+fn test_binary_message_schemas() {
+    use crate::Level;
+
+    let message = Message::new(&["db".into()], "text")
+        .with_level(Level::Warn)
+        .with_field("id", 7)
+        .with_field("ok", true)
+        .with_target("wire");
+    let bytes = message.to_bytes();
+    assert_eq!(bytes[0], 0x83);
+    let read = Message::from_bytes(&bytes).unwrap();
+    assert_eq!((read.level(), read.text().as_str()), (Level::Warn, "text"));
+    assert_eq!(
+        (read.fields(), read.target()),
+        (message.fields(), Some("wire"))
+    );
+
+    // Schema `1` has text field values and no target, schema `0` also starts with level.
+    let mut v1 = vec![0x81, Level::Warn as u8];
+    for part in [
+        &1u32.to_le_bytes()[..],
+        &2u32.to_le_bytes(),
+        b"db",
+        &4u32.to_le_bytes(),
+    ] {
+        v1.extend_from_slice(part);
+    }
+    v1.extend_from_slice(b"text");
+    for part in [
+        &1u32.to_le_bytes()[..],
+        &2u32.to_le_bytes(),
+        b"id",
+        &1u32.to_le_bytes(),
+        b"7",
+    ] {
+        v1.extend_from_slice(part);
+    }
+    let read = Message::from_bytes(&v1).unwrap();
+    assert_eq!(read.fields(), &vec![("id".to_string(), "7".into())]);
+    assert_eq!(read.target(), None);
+    let read = Message::from_bytes(&v1[1..]).unwrap();
+    assert_eq!(read.text(), "text");
+    assert!(Message::from_bytes(&[0x84, 1]).is_none());
+}
+
+#[test]
+fn test_flush_request_writes_buffered_records() {
+    use std::{thread, time::Duration};
+
+    use crate::rotation_logger::logger::{EnabledLogger, flush};
+    use crate::{FlushPolicy, OutputChannel, Settings};
+
+    let written = CaptureSink::default();
+    let formatter = text_formatter();
+    let settings = Settings::new(true, 100, OutputChannel::Console, formatter)
+        .with_console_writer(|_: &str| {})
+        .with_sink_policy(written.clone(), FlushPolicy::new(100));
+
+    let queue = Arc::new(Queue::new());
+    let logger = EnabledLogger::new(settings, queue.clone());
+    let worker = thread::spawn(move || logger.run());
+    assert!(queue.send(Message::new(&[], "a")));
+    assert!(queue.send(Message::new(&[], "b")));
+
+    // Idle worker is woken up by request, requests of other workers are not its own.
+    flush::request_on(&queue)
+        .wait(Duration::from_secs(5))
+        .unwrap();
+    assert_eq!(written.texts(), vec!["a", "b"]);
+    assert_eq!(queue.requests().pending(), None);
+
+    // Worker stopped by closed queue serves requests taken before.
+    assert!(queue.send(Message::new(&[], "c")));
+    let future = flush::request_on(&queue);
+    queue.close();
+    future.wait(Duration::from_secs(5)).unwrap();
+    worker.join().unwrap();
+    assert_eq!(written.texts(), vec!["a", "b", "c"]);
+}
+#[test]
+fn test_flush_requests_answered_when_worker_panics() {
+    use std::{thread, time::Duration};
+
+    use crate::rotation_logger::logger::{EnabledLogger, flush};
+    use crate::{FlushPolicy, OutputChannel, Settings};
+
+    struct PanicSink;
+
+    impl Sink for PanicSink {
+        fn write_batch(&mut self, _: &[Record]) -> std::io::Result<()> {
+            panic!("sink failed");
+        }
+    }
+
+    let formatter = text_formatter();
+    let settings = Settings::new(true, 100, OutputChannel::Console, formatter)
+        .with_console_writer(|_: &str| {})
+        .with_sink_policy(PanicSink, FlushPolicy::new(100));
+    let queue = Arc::new(Queue::new());
+    let logger = EnabledLogger::new(settings, queue.clone());
+    let worker = thread::spawn(move || logger.run());
+
+    assert!(queue.send(Message::new(&[], "a")));
+    let called = Arc::new(Mutex::new(false));
+    let flag = called.clone();
+    let future = flush::request_on(&queue);
+    queue.requests().push(Box::new(move |result| {
+        *flag.lock().unwrap() = result.is_err()
+    }));
+    assert!(worker.join().is_err());
+
+    // Waiting thread and callback don't wait for timeout of stopped worker.
+    let stopped = std::io::ErrorKind::BrokenPipe;
+    assert_eq!(future.wait(Duration::ZERO).unwrap_err().kind(), stopped);
+    assert!(*called.lock().unwrap());
+    assert!(!queue.send(Message::new(&[], "b")));
+    let late = flush::request_on(&queue).wait(Duration::ZERO);
+    assert_eq!(late.unwrap_err().kind(), stopped);
+}
+
+#[test]
+fn test_targets_are_never_commands() {
+    use crate::{OutputChannel, Settings};
+
+    // Worker is controlled by items of its own, any target is logged as it is.
+    let written = CaptureSink::default();
+    let settings = Settings::new(true, 1, OutputChannel::Console, text_formatter())
+        .with_console_writer(|_: &str| {})
+        .with_sink(written.clone());
+    let targets = ["\0rotation_logger::shutdown", "\0rotation_logger::flush"];
+    let sent = targets.map(|target| Message::new(&[], target).with_target(target));
+    run_worker(settings, sent.into_iter().chain(messages(["after"])));
+
+    assert_eq!(written.texts(), [targets[0], targets[1], "after"]);
+}
+
+#[test]
+fn test_queue_stats_high_water() {
+    use crate::rotation_logger::logger::queue::Control;
+
+    // Every item is counted by queue, control ones of worker too.
+    let queue = Queue::new();
+    for _ in 0..3 {
+        assert!(queue.send(Message::new(&[], "")));
+    }
+    queue.try_recv().unwrap();
+    queue.try_recv().unwrap();
+    assert!(queue.control(Control::Stop));
+
+    let stats = queue.stats();
+    assert_eq!(stats.sent(), 4);
+    assert_eq!(stats.queue_len(), 2);
+    assert_eq!(stats.high_water(), 3);
+
+    // Closed queue refuses messages, worker takes the rest.
+    queue.close();
+    assert!(!queue.send(Message::new(&[], "late")));
+    assert_eq!(queue.stats().sent(), 4);
+    assert!(queue.try_recv().is_some());
+    assert!(queue.try_recv().is_some());
+    assert!(queue.recv(None).is_err());
+}
+
+#[test]
+fn test_backlog_warning() {
+    use std::sync::{Arc, Mutex};
+
+    use crate::{BacklogWarning, OutputChannel, OverflowPolicy, Settings};
+
+    let lines = Arc::new(Mutex::new(vec![]));
+    let console = lines.clone();
+    let formatter = text_formatter();
+    let settings = Settings::new(true, 1, OutputChannel::Console, formatter)
+        .with_console_writer(move |line: &str| console.lock().unwrap().push(line.to_string()))
+        .with_queue_capacity(4, OverflowPolicy::Block)
+        .with_backlog_warning(BacklogWarning::new().with_thresholds(&[50]));
+
+    run_worker(settings, messages(["a", "b", "c"]));
+
+    assert_eq!(
+        *lines.lock().unwrap(),
+        vec!["logger backlog at 50% (2/4)", "a", "b", "c"]
+    );
+}
+
+#[test]
+fn test_backlog_warning_thresholds() {
+    use std::time::{Duration, SystemTime};
+
+    use crate::rotation_logger::logger::backlog::BacklogState;
+    use crate::{BacklogWarning, Level, OverflowPolicy, Settings};
+
+    let warning = BacklogWarning::new()
+        .with_thresholds(&[80, 50])
+        .with_interval(Duration::from_secs(10));
+    let mut state = BacklogState::default();
+    let mut check = |len, secs| {
+        let time = SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+        warning
+            .check(&mut state, len, 10, time)
+            .map(|message| message.text().clone())
+    };
+
+    assert_eq!(check(4, 0), None);
+    assert_eq!(check(5, 1).unwrap(), "logger backlog at 50% (5/10)");
+    // Once per threshold, and not more often than interval.
+    assert_eq!(check(6, 2), None);
+    assert_eq!(check(8, 3), None);
+    assert_eq!(check(8, 11).unwrap(), "logger backlog at 80% (8/10)");
+    // Queue which shrank warns again when it grows.
+    assert_eq!(check(1, 12), None);
+    assert_eq!(check(9, 30).unwrap(), "logger backlog at 80% (9/10)");
+
+    let message = warning.check(&mut BacklogState::default(), 5, 10, SystemTime::now());
+    assert_eq!(message.unwrap().level(), Level::Warn);
+
+    // Capacity of queue comes first, own one is for queue without it.
+    let settings = Settings::default().with_backlog_warning(warning.clone().with_capacity(100));
+    assert_eq!(settings.backlog_capacity(), Some(100));
+    let settings = settings.with_queue_capacity(10, OverflowPolicy::Block);
+    assert_eq!(settings.backlog_capacity(), Some(10));
+    let report = Settings::default().with_backlog_warning(warning).validate();
+    assert_eq!(
+        report.problems(),
+        ["backlog warning without queue capacity or its own"]
+    );
+}
+
+#[test]
+fn test_validate_and_dry_run() {
+    use crate::{FileSize, OutputChannel, Settings};
+
+    let dir = TempDir::new("validate");
+    let missing = dir.join("missing").join("logs");
+    let output = OutputChannel::file(
+        missing,
+        0,
+        FileSize::from_bytes(100),
+        "app".into(),
+        "log".into(),
+    );
+    let report = Settings::new(true, 0, output, MessageFormatter::default()).validate();
+    assert_eq!(report.problems().len(), 3);
+    assert!(report.problems()[0].contains("buffer_size"));
+    assert!(report.problems()[1].contains("capacity"));
+    assert!(report.problems()[2].contains("does not exist"));
+
+    let output = OutputChannel::file(
+        dir.join("logs"),
+        2,
+        FileSize::from_megabytes(5),
+        "app".into(),
+        "log".into(),
+    );
+    let settings = Settings::new(true, 10, output, MessageFormatter::default());
+    let report = settings.dry_run();
+    assert!(report.is_ok(), "{report}");
+    // Dry run leaves directory of output as it was.
+    assert!(!dir.join("logs").exists());
+}
+
+#[test]
+fn test_verify_log_set() {
+    use crate::{FileSettings, FileSize, verify_log_set};
+
+    let dir = TempDir::new("verify");
+    let settings = FileSettings::new(
+        dir.clone(),
+        5,
+        FileSize::from_bytes(100),
+        "app".into(),
+        "log".into(),
+    );
+    std::fs::write(dir.join("app.log3"), "a\n").unwrap();
+    std::fs::write(
+        dir.join("app.log2"),
+        "rotated from app.log2 at 1, 2 bytes, seq range 0..9\nb\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("app.log0"),
+        "rotated from app.log0 at 2, 2 bytes, seq range 12..20\nc\n",
+    )
+    .unwrap();
+    std::fs::write(
+        dir.join("app.log"),
+        "rotated from app.log0 at 3, 2 bytes, seq range 21..30\nd\n",
+    )
+    .unwrap();
+
+    let report = verify_log_set(&dir, &settings).unwrap();
+    assert_eq!(
+        report.problems(),
+        [
+            "generations 1..2 are missing",
+            "app.log0: sequence jumps from 9 to 12"
+        ]
+    );
+}
+
+#[test]
+fn test_set_files_oldest_first() {
+    use crate::{FileSettings, FileSize, LogReader};
+
+    let dir = TempDir::new("set");
+    for name in [
+        "app.log",
+        "app.log0",
+        "app.log10.gz",
+        "app.log2",
+        "app.log2.sha256",
+        "other.log",
+    ] {
+        std::fs::write(dir.join(name), "").unwrap();
+    }
+    let settings = FileSettings::new(
+        dir.clone(),
+        5,
+        FileSize::from_bytes(100),
+        "app".into(),
+        "log".into(),
+    );
+
+    let names: Vec<String> = LogReader::set_files(&settings)
+        .unwrap()
+        .iter()
+        .map(|path| path.file_name().unwrap().to_string_lossy().to_string())
+        .collect();
+    assert_eq!(names, ["app.log10.gz", "app.log2", "app.log0", "app.log"]);
+}
+
+#[test]
+fn test_search_rotated_logs() {
+    use crate::{FileSettings, FileSize, Level, LogEntry, LogReader, SearchOptions};
+
+    let dir = TempDir::new("search");
+    let record = |level: &str, module: &str, text: &str| {
+        format!(
+            r#"{{"schema":3,"timestamp":1.0,"level":"{level}","modules":["{module}"],"message":"{text}","fields":{{}}}}"#
+        )
+    };
+    let old = [
+        record("Warn", "db", "timeout"),
+        record("Info", "db", "timeout"),
+        record("Warn", "http", "timeout"),
+    ];
+    std::fs::write(dir.join("app.log0"), old.join("\n") + "\n").unwrap();
+    std::fs::write(dir.join("app.log"), "started\nplain timeout\n").unwrap();
+    let settings = FileSettings::new(
+        dir.clone(),
+        5,
+        FileSize::from_bytes(100),
+        "app".into(),
+        "log".into(),
+    );
+
+    let found: Vec<(String, u64)> =
+        LogReader::search(&settings, "timeout", SearchOptions::default())
+            .unwrap()
+            .map(|found| {
+                let found = found.unwrap();
+                let name = found
+                    .path()
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .to_string();
+                (name, found.offset())
+            })
+            .collect();
+    let line = old[0].len() as u64 + 1;
+    assert_eq!(
+        found,
+        [
+            ("app.log0".to_string(), 0),
+            ("app.log0".into(), line),
+            ("app.log0".into(), 2 * line),
+            ("app.log".into(), 8)
+        ]
+    );
+
+    let options = SearchOptions::default()
+        .with_module("db")
+        .with_level(Level::Warn);
+    let found: Vec<LogEntry> = LogReader::search(&settings, "timeout", options)
+        .unwrap()
+        .map(|found| found.unwrap().into_entry())
+        .collect();
+    assert_eq!(found.len(), 1);
+    assert!(
+        matches!(&found[0], LogEntry::Record(record) if record.message().level() == Level::Warn)
+    );
+}
+
+#[test]
+fn test_import_legacy_log() {
+    use crate::{FileSettings, FileSize, LogImport};
+
+    let dir = TempDir::new("import");
+    let source = dir.join("legacy.txt");
+    std::fs::write(&source, "line 1\nline 2\nline 3\nline 4\nline 5").unwrap();
+    std::fs::write(dir.join("app.log0"), "current\n").unwrap();
+    let settings = FileSettings::new(
+        dir.clone(),
+        10,
+        FileSize::from_bytes(14),
+        "app".into(),
+        "log".into(),
+    );
+
+    let files = LogImport::new(settings).run(&source).unwrap();
+    let names: Vec<String> = files
+        .iter()
+        .map(|path| path.file_name().unwrap().to_string_lossy().to_string())
+        .collect();
+    assert_eq!(names, ["app.log1", "app.log2", "app.log3"]);
+    let read = |name: &str| std::fs::read_to_string(dir.join(name)).unwrap();
+    assert_eq!(read("app.log3"), "line 1\nline 2\n");
+    assert_eq!(read("app.log2"), "line 3\nline 4\n");
+    assert_eq!(read("app.log1"), "line 5");
+    assert_eq!(read("app.log0"), "current\n");
+    assert!(source.exists());
+}
+
+#[test]
+fn test_log_sync_waits_for_worker() {
+    use crate::{FlushPolicy, Level, Logger, OutputChannel, Settings};
+
+    assert!(Logger::disabled().log_sync([""; 0], "audit").is_ok());
+
+    let _running = running_logger();
+    let written = CaptureSink::default();
+    let formatter = text_formatter();
+    let settings = Settings::new(true, 100, OutputChannel::Console, formatter)
+        .with_console_writer(|_: &str| {})
+        .with_sink_policy(FlakySink(1, written.clone()), FlushPolicy::new(100));
+    let logger = Logger::new(settings);
+    // Build with `max_level_*` features compiling `Info` out drops message at once.
+    if !Level::Info.is_statically_enabled() {
+        logger.run_async();
+        assert!(logger.log_sync(["AUDIT"], "dropped").is_ok());
+        logger.shutdown().unwrap();
+        assert!(written.texts().is_empty());
+        return;
+    }
+    let error = logger.log_sync(["AUDIT"], "not running").unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::NotConnected);
+    logger.run_async();
+
+    // Failure of sink is told to caller, not only printed by worker.
+    let error = logger.log_sync(["AUDIT"], "lost").unwrap_err();
+    assert_eq!(error.to_string(), "offline");
+    // Buffer of 100 records is written only by flush `log_sync` waits for.
+    logger.log_sync(["AUDIT"], "user deleted").unwrap();
+    assert_eq!(written.texts(), vec!["user deleted"]);
+
+    // Levels of running logger decide, not those of handle called.
+    let formatter = text_formatter();
+    let quiet =
+        Settings::new(true, 100, OutputChannel::Console, formatter).with_min_level(Level::Error);
+    Logger::new(quiet)
+        .log_sync(["AUDIT"], "role changed")
+        .unwrap();
+    assert_eq!(written.texts(), vec!["user deleted", "role changed"]);
+
+    logger.shutdown().unwrap();
+}
+
+#[test]
+fn test_flush_future_ready_after_write() {
+    use std::sync::{Arc, Mutex};
+    use std::task::{Context, Wake, Waker};
+    use std::{future::Future, thread, time::Duration};
+
+    use crate::rotation_logger::logger::{EnabledLogger, flush};
+    use crate::{FlushPolicy, OutputChannel, Settings};
+
+    struct ThreadWaker(thread::Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let written = CaptureSink::default();
+    let formatter = text_formatter();
+    let settings = Settings::new(true, 100, OutputChannel::Console, formatter)
+        .with_console_writer(|_: &str| {})
+        .with_sink_policy(written.clone(), FlushPolicy::new(100));
+    let queue = Arc::new(Queue::new());
+    let logger = EnabledLogger::new(settings, queue.clone());
+    let worker = thread::spawn(move || logger.run());
+
+    assert!(queue.send(Message::new(&[], "a")));
+    let mut future = Box::pin(flush::request_on(&queue));
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    while future.as_mut().poll(&mut cx).is_pending() {
+        thread::park_timeout(Duration::from_millis(100));
+    }
+    assert_eq!(written.texts(), vec!["a"]);
+
+    // Without running logger there is nothing to wait for.
+    let _running = running_logger();
+    assert!(Box::pin(flush::flush()).as_mut().poll(&mut cx).is_ready());
+    let called = Arc::new(Mutex::new(false));
+    let flag = called.clone();
+    crate::Logger::flush_with(move || *flag.lock().unwrap() = true);
+    assert!(*called.lock().unwrap());
+
+    queue.close();
+    worker.join().unwrap();
+}
+
+#[test]
+fn test_worker_thread_settings() {
+    use std::sync::mpsc::channel;
+
+    use crate::{Settings, WorkerThread};
+
+    let settings = Settings::default();
+    assert_eq!(settings.worker_thread().name(), "rotation-logger");
+    assert_eq!(settings.worker_thread().stack_size(), None);
+
+    let worker = WorkerThread::default()
+        .with_name("app-logger")
+        .with_stack_size(256 * 1024)
+        .with_low_priority(true);
+    let settings = settings.with_worker_thread(worker);
+    assert!(settings.worker_thread().low_priority());
+
+    let (tx, rx) = channel();
+    let handle = settings
+        .worker_thread()
+        .spawn(move || {
+            let name = std::thread::current().name().map(String::from);
+            tx.send(name).unwrap();
+        })
+        .unwrap();
+    handle.join().unwrap();
+    assert_eq!(rx.recv().unwrap().as_deref(), Some("app-logger"));
+}
+
+#[test]
+fn test_lazy_start_on_first_message() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::Settings;
+    use crate::rotation_logger::logger::queue::Control;
+
+    assert!(!Settings::default().lazy_start());
+    assert!(Settings::default().with_lazy_start(true).lazy_start());
+
+    let started = Arc::new(AtomicUsize::new(0));
+    let queue = Queue::new();
+    let counter = started.clone();
+    queue.lazy().defer(move || {
+        counter.fetch_add(1, Ordering::SeqCst);
+    });
+    // Requests to flush don't start worker, there is nothing to flush.
+    assert!(queue.control(Control::Stop));
+    assert!(queue.lazy().is_pending());
+    assert_eq!(started.load(Ordering::SeqCst), 0);
+
+    // Messages of aggregator go through the same `Queue::send`.
+    assert!(queue.send(Message::new(&[], "first")));
+    assert!(queue.send(Message::new(&[], "second")));
+    assert_eq!(started.load(Ordering::SeqCst), 1);
+    assert!(!queue.lazy().is_pending());
+
+    // Queue replaced by other logger drops worker not started yet.
+    let stale = Queue::new();
+    let counter = started.clone();
+    stale.lazy().defer(move || {
+        counter.fetch_add(1, Ordering::SeqCst);
+    });
+    stale.close();
+    assert!(!stale.lazy().is_pending());
+    assert!(!stale.send(Message::new(&[], "late")));
+    assert_eq!(started.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_try_new_reports_all_problems() {
+    use crate::{FileSettings, FileSize, OutputChannel, Settings, Template};
+
+    let problems = MessageFormatter::try_new(
+        "::",
+        "{timestmp} {message:x:5:up} {fields:0:0:left:1:2}",
+        "%Y-%m-%d",
+    )
+    .unwrap_err();
+    assert_eq!(
+        problems,
+        [
+            "unknown mask {timestmp}",
+            "mask {message:x:5:up}: length x is not a number",
+            "mask {message:x:5:up}: unknown align up",
+            "mask {fields:0:0:left:1:2} has more than 4 parts",
+        ]
+    );
+    assert!(Template::try_parse("no masks").is_err());
+    assert!(Template::try_parse("{message").is_err());
+    assert!(MessageFormatter::try_new("::", "{timestamp} {message:?}", "%H:%M").is_ok());
+
+    let report = FileSettings::try_new(
+        "./logs".into(),
+        0,
+        FileSize::from_bytes(0),
+        "".into(),
+        "log".into(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        report.problems(),
+        [
+            "file: capacity is 0",
+            "file: file size is 0",
+            "file: filename is empty",
+        ]
+    );
+
+    let report = Settings::try_new(true, 0, OutputChannel::Console, MessageFormatter::default())
+        .err()
+        .unwrap();
+    assert_eq!(report.problems(), ["buffer_size is 0"]);
+}
+
+#[test]
+fn test_log_into_message_and_display() {
+    use crate::{Logger, Settings};
+
+    let message = Message::from("started");
+    assert_eq!(message.text(), "started");
+    assert!(message.modules().is_empty());
+    assert_eq!(Message::from(String::from("owned")).text(), "owned");
+
+    // No worker runs in tests, calls only have to type check and not panic.
+    let logger = Logger::new(Settings::default());
+    logger.log("text");
+    logger.log(format!("job {}", 1));
+    logger.log(Message::new(&["DB".into()], "connected").with_field("pool", 8u64));
+    logger.log_display(["JOB"], &42);
+    logger.log_display(["IO"], &std::io::Error::other("disk full"));
+    Logger::disabled().log_display([""; 0], &1.5);
+}
+
+#[cfg(feature = "log")]
+#[test]
+fn test_log_facade_targets_become_modules() {
+    use std::sync::Mutex;
+
+    use log::Log;
+
+    use crate::{Level, LogFacade, Logger, Settings};
+
+    static MESSAGES: Mutex<Vec<Message>> = Mutex::new(vec![]);
+
+    let facade = LogFacade::new(&Logger::enabled(
+        Settings::default().with_min_level(Level::Info),
+    ))
+    .with_send(|message| MESSAGES.lock().unwrap().push(message));
+    let record = |level, target, module_path| {
+        let args = format_args!("pool exhausted");
+        let record = log::Record::builder()
+            .level(level)
+            .target(target)
+            .module_path(Some(module_path))
+            .args(args)
+            .build();
+        facade.log(&record);
+    };
+    record(log::Level::Warn, "db::pool", "app::db");
+    record(log::Level::Info, "app::http", "app::http");
+    record(log::Level::Debug, "app::http", "app::http");
+
+    let messages = MESSAGES.lock().unwrap();
+    // Levels compiled out by `max_level_*` features are dropped too.
+    let levels = [Level::Warn, Level::Info]
+        .into_iter()
+        .filter(|level| level.is_statically_enabled());
+    assert_eq!(
+        messages.iter().map(Message::level).collect::<Vec<_>>(),
+        levels.collect::<Vec<_>>()
+    );
+    if let Some(warn) = messages
+        .iter()
+        .find(|message| message.level() == Level::Warn)
+    {
+        assert_eq!(warn.modules(), &["db".to_string(), "pool".to_string()]);
+        assert_eq!(warn.target(), Some("db::pool"));
+        assert_eq!(warn.text(), "pool exhausted");
+    }
+    if let Some(info) = messages
+        .iter()
+        .find(|message| message.level() == Level::Info)
+    {
+        assert_eq!(info.target(), None);
+    }
+    assert!(!facade.enabled(&log::Metadata::builder().level(log::Level::Trace).build()));
+    let error = log::Metadata::builder().level(log::Level::Error).build();
+    assert!(!LogFacade::new(&Logger::disabled()).enabled(&error));
+
+    // Module level more verbose than `min_level` raises limit of `log` macros.
+    let facade = LogFacade::new(&Logger::enabled(
+        Settings::default()
+            .with_min_level(Level::Info)
+            .with_module_level("app::db", Level::Debug),
+    ));
+    assert_eq!(facade.max_level(), log::LevelFilter::Debug);
+    let debug = |target| {
+        log::Metadata::builder()
+            .level(log::Level::Debug)
+            .target(target)
+            .build()
+    };
+    assert_eq!(
+        facade.enabled(&debug("app::db::pool")),
+        Level::Debug.is_statically_enabled()
+    );
+    assert!(!facade.enabled(&debug("app::http")));
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn test_zstd_compressed_import_is_readable() {
+    use crate::{Compression, FileSettings, FileSize, LogEntry, LogImport, LogReader};
+
+    let dir = TempDir::new("zstd");
+    let source = dir.join("legacy.txt");
+    std::fs::write(&source, "line 1\nline 2\n").unwrap();
+    let settings = FileSettings::new(
+        dir.clone(),
+        10,
+        FileSize::from_megabytes(1),
+        "app".into(),
+        "log".into(),
+    );
+
+    let files = LogImport::new(settings)
+        .with_compression(Compression::Zstd { level: 19 })
+        .run(&source)
+        .unwrap();
+    assert_eq!(files, [dir.join("app.log0.zst")]);
+    let entries = LogReader::open(&files[0])
+        .unwrap()
+        .collect::<std::io::Result<Vec<LogEntry>>>()
+        .unwrap();
+    let lines: Vec<&str> = entries
+        .iter()
+        .map(|entry| match entry {
+            LogEntry::Line(line) => line.as_str(),
+            LogEntry::Record(_) => panic!("expected text line"),
+        })
+        .collect();
+    assert_eq!(lines, ["line 1", "line 2"]);
+}
+
+#[test]
+fn test_tee_outputs_get_every_line() {
+    use std::sync::{Arc, Mutex};
+
+    use crate::{FileSize, OutputChannel, Settings};
+
+    let dir = TempDir::new("tee");
+    let lines = Arc::new(Mutex::new(vec![]));
+    let written = lines.clone();
+    let formatter = text_formatter();
+    let settings = Settings::new(true, 1, OutputChannel::Console, formatter)
+        .with_tee_output(OutputChannel::file(
+            dir.clone(),
+            2,
+            FileSize::from_megabytes(1),
+            "app".into(),
+            "log".into(),
+        ))
+        .with_console_writer(move |line: &str| written.lock().unwrap().push(line.to_string()));
+    run_worker(settings, messages(["a", "b"]));
+
+    let file = std::fs::read_to_string(dir.join("app.log")).unwrap();
+    assert_eq!(*lines.lock().unwrap(), ["a", "b"]);
+    assert_eq!(file.lines().collect::<Vec<_>>(), ["a", "b"]);
+}
+
+#[test]
+fn test_custom_output_channel() {
+    use std::sync::{Arc, Mutex};
+
+    use crate::{FlushPolicy, OutputChannel, Record, Settings, Sink};
+
+    struct Lines(Arc<Mutex<Vec<Vec<String>>>>);
+
+    impl Sink for Lines {
+        fn write_batch(&mut self, records: &[Record]) -> std::io::Result<()> {
+            let lines = records.iter().map(|record| record.line().to_string());
+            self.0.lock().unwrap().push(lines.collect());
+            Ok(())
+        }
+    }
+
+    let batches = Arc::new(Mutex::new(vec![]));
+    let formatter = text_formatter();
+    let output = OutputChannel::custom(Lines(batches.clone()));
+    let settings =
+        Settings::new(true, 1, output, formatter).with_output_policy(FlushPolicy::new(2));
+    run_worker(settings, messages(["a", "b", "c", "d"]));
+
+    assert_eq!(
+        *batches.lock().unwrap(),
+        vec![
+            vec!["a".to_string(), "b".into()],
+            vec!["c".into(), "d".into()]
+        ]
+    );
+}
+
+#[test]
+fn test_max_buffer_bytes_writes_early() {
+    use crate::{OutputChannel, Settings};
+
+    let written = CaptureSink::default();
+    let formatter = text_formatter();
+    let output = OutputChannel::custom(written.clone());
+    let settings = Settings::new(true, 100, output, formatter).with_max_buffer_bytes(10);
+    run_worker(settings, messages(["aaaa", "bbbb", "cccc", "dd", "eeee"]));
+
+    // Third line takes buffer past 10 bytes, the rest waits for flush on exit.
+    assert_eq!(
+        written.batches(),
+        vec![
+            vec!["aaaa".to_string(), "bbbb".into(), "cccc".into()],
+            vec!["dd".into(), "eeee".into()]
+        ]
+    );
+}
+
+#[test]
+fn test_outputs_with_own_flush_policies() {
+    use std::sync::{Arc, Mutex};
+
+    use crate::{FlushPolicy, OutputChannel, Record, Settings, Sink};
+
+    struct Lines(Arc<Mutex<Vec<Vec<String>>>>);
+
+    impl Sink for Lines {
+        fn write_batch(&mut self, records: &[Record]) -> std::io::Result<()> {
+            let lines = records.iter().map(|record| record.line().to_string());
+            self.0.lock().unwrap().push(lines.collect());
+            Ok(())
+        }
+    }
+
+    let batched = Arc::new(Mutex::new(vec![]));
+    let unbuffered = Arc::new(Mutex::new(vec![]));
+    let formatter = text_formatter();
+    let output = OutputChannel::custom(Lines(batched.clone()));
+    let settings = Settings::new(true, 1, output, formatter)
+        .with_output_policy(FlushPolicy::new(2))
+        .with_tee_output_policy(
+            OutputChannel::custom(Lines(unbuffered.clone())),
+            FlushPolicy::unbuffered(),
+        );
+    run_worker(settings, messages(["a", "b", "c"]));
+
+    assert_eq!(
+        *batched.lock().unwrap(),
+        vec![vec!["a".to_string(), "b".into()], vec!["c".into()]]
+    );
+    assert_eq!(
+        *unbuffered.lock().unwrap(),
+        vec![vec!["a".to_string()], vec!["b".into()], vec!["c".into()]]
+    );
+}
+
+#[test]
+fn test_shutdown_writes_buffered_records() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use crate::{FlushPolicy, Level, Logger, OutputChannel, Settings};
+
+    /// Tells when worker dropped it.
+    struct DropSink(Arc<AtomicBool>);
+
+    impl Sink for DropSink {
+        fn write_batch(&mut self, _: &[Record]) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Drop for DropSink {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    assert!(Logger::disabled().shutdown().is_ok());
+
+    let _running = running_logger();
+    let written = CaptureSink::default();
+    let dropped = Arc::new(AtomicBool::new(false));
+    let formatter = text_formatter();
+    let settings = Settings::new(true, 100, OutputChannel::Console, formatter)
+        .with_console_writer(|_: &str| {})
+        .with_sink_policy(written.clone(), FlushPolicy::new(100))
+        .with_sink(DropSink(dropped.clone()));
+    let logger = Logger::new(settings);
+    let handle = logger.run_async().unwrap();
+    for text in ["a", "b"] {
+        logger.log_display(["MAIN"], &text);
+    }
+
+    // Returns once worker wrote buffered records and stopped, its sinks are dropped then.
+    logger.shutdown().unwrap();
+    // Build with `max_level_*` features compiling `Info` out drops them before worker.
+    let expected = match Level::Info.is_statically_enabled() {
+        true => vec!["a", "b"],
+        false => vec![],
+    };
+    assert_eq!(written.texts(), expected);
+    assert!(dropped.load(Ordering::SeqCst));
+    handle.join().unwrap();
+
+    // Later messages are dropped, nothing runs anymore.
+    Logger::send(Message::new(&[], "late"));
+    assert!(Logger::new(Settings::default()).shutdown().is_ok());
+    assert_eq!(written.texts(), expected);
+}
+
+#[test]
+fn test_flush_guard_writes_buffered_records_on_drop() {
+    use crate::{FileSettings, FileSize, Level, Logger, OutputChannel, Settings, flush_on_exit};
+
+    let _running = running_logger();
+    let dir = TempDir::new("flush_guard");
+    let output = OutputChannel::File(FileSettings::new(
+        dir.clone(),
+        2,
+        FileSize::from_megabytes(1),
+        "test".into(),
+        "log".into(),
+    ));
+    let logger = Logger::new(Settings::new(true, 100, output, text_formatter()));
+    let handle = logger.run_async().unwrap();
+    let guard = flush_on_exit();
+    logger.log_display(["MAIN"], &"buffered");
+
+    // Returns once worker wrote buffered record to file.
+    drop(guard);
+    // Build with `max_level_*` features compiling `Info` out drops it before worker.
+    let expected = match Level::Info.is_statically_enabled() {
+        true => "buffered\n",
+        false => "",
+    };
+    let content = std::fs::read_to_string(dir.join("test.log")).unwrap_or_default();
+    assert_eq!(content, expected);
+
+    logger.shutdown().unwrap();
+    handle.join().unwrap();
+}
+
+#[test]
+fn test_flush_interval_writes_quiet_buffer() {
+    use std::{thread, time::Duration};
+
+    use crate::rotation_logger::logger::EnabledLogger;
+    use crate::{OutputChannel, Settings};
+
+    let written = CaptureSink::default();
+    let formatter = text_formatter();
+    let settings = Settings::new(true, 100, OutputChannel::Console, formatter)
+        .with_console_writer(|_: &str| {})
+        .with_sink(written.clone())
+        .with_flush_interval(Duration::from_millis(20));
+    let queue = Arc::new(Queue::new());
+    let logger = EnabledLogger::new(settings, queue.clone());
+    let worker = thread::spawn(move || logger.run());
+
+    assert!(queue.send(Message::new(&[], "quiet")));
+    // Nothing else is logged, buffer is far from full.
+    for _ in 0..100 {
+        if !written.texts().is_empty() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(written.texts(), vec!["quiet"]);
+
+    queue.close();
+    worker.join().unwrap();
+}
+
+#[test]
+fn test_overflow_policies() {
+    use std::thread;
+
+    use crate::rotation_logger::logger::queue::{self, Control};
+    use crate::{OverflowPolicy, Settings};
+
+    let settings = Settings::default().with_queue_capacity(0, OverflowPolicy::DropNewest);
+    assert_eq!(
+        settings.queue_capacity(),
+        Some((1, OverflowPolicy::DropNewest))
+    );
+
+    let texts = |queue: &Queue| {
+        let mut texts = vec![];
+        while let Some(item) = queue.try_recv() {
+            texts.push(match item {
+                Item::Message(message) => message.text().clone(),
+                Item::Control(control) => format!("{control:?}"),
+            });
+        }
+        texts
+    };
+    let full = |policy| {
+        let queue = Arc::new(Queue::new().with_bound(Some((2, policy))));
+        for text in ["a", "b"] {
+            assert!(queue.send(Message::new(&[], text)));
+        }
+        queue
+    };
+
+    // New message is dropped, control ones go past bound.
+    let queue = full(OverflowPolicy::DropNewest);
+    assert!(!queue.send(Message::new(&[], "c")));
+    assert!(!queue.is_closed());
+    assert!(queue.control(Control::Stop));
+    assert_eq!(queue.stats().dropped(), 1);
+    assert_eq!(texts(&queue), ["a", "b", "Stop"]);
+
+    // The oldest user message makes room, not control one before it.
+    let queue = Arc::new(Queue::new().with_bound(Some((2, OverflowPolicy::DropOldest))));
+    assert!(queue.control(Control::Stop));
+    for text in ["a", "b"] {
+        assert!(queue.send(Message::new(&[], text)));
+    }
+    assert_eq!(queue.stats().dropped(), 1);
+    assert_eq!(texts(&queue), ["Stop", "b"]);
+
+    // Sender waits for room made by worker.
+    let queue = full(OverflowPolicy::Block);
+    let sender = thread::spawn({
+        let queue = queue.clone();
+        move || queue.send(Message::new(&[], "c"))
+    });
+    assert_eq!(message_of(queue.try_recv().unwrap()).text(), "a");
+    assert!(sender.join().unwrap());
+    assert_eq!(texts(&queue), ["b", "c"]);
+
+    // Sender stops waiting once worker is gone.
+    let queue = full(OverflowPolicy::Block);
+    let sender = thread::spawn({
+        let queue = queue.clone();
+        move || queue.send(Message::new(&[], "c"))
+    });
+    queue.close();
+    assert!(!sender.join().unwrap());
+    assert_eq!(queue.stats().sent(), 2);
+
+    // Worker never waits for itself.
+    let queue = full(OverflowPolicy::Block);
+    thread::spawn({
+        let queue = queue.clone();
+        move || {
+            queue::enter_worker();
+            queue.send(Message::new(&[], "c"))
+        }
+    })
+    .join()
+    .unwrap();
+    assert_eq!(texts(&queue), ["a", "b", "c"]);
+}
+
+#[test]
+fn test_settings_builder() {
+    use std::path::PathBuf;
+
+    use crate::{FileSettings, FileSize, MessageFormatter, OutputChannel, Settings};
+
+    let settings = Settings::builder().build();
+    assert!(settings.is_enabled());
+    assert_eq!(settings.buffer_size(), Settings::default().buffer_size());
+    assert!(matches!(settings.output(), OutputChannel::Console));
+
+    let settings = Settings::builder()
+        .enabled(false)
+        .buffer_size(512)
+        .file("./builder_logs")
+        .formatter(MessageFormatter::default())
+        .build();
+    assert!(!settings.is_enabled());
+    assert_eq!(settings.buffer_size(), 512);
+    let OutputChannel::File(file) = settings.output() else {
+        panic!("file output expected");
+    };
+    assert_eq!(file.path(), &PathBuf::from("./builder_logs"));
+    assert_eq!(file.capacity(), FileSettings::default().capacity());
+    assert_eq!(file.filename(), "logger");
+
+    let file = FileSettings::builder()
+        .capacity(3)
+        .file_size(FileSize::from_kilobytes(4))
+        .filename("app")
+        .file_extension("txt")
+        .build();
+    assert_eq!(file.path(), &PathBuf::from("./logs"));
+    assert_eq!(file.capacity(), 3);
+    assert!(FileSize::from_kilobytes(4) == file.file_size());
+    assert_eq!(file.filename(), "app");
+    assert_eq!(file.file_extension(), "txt");
+}
+
+#[cfg(feature = "ssh")]
+#[test]
+fn test_ssh_upload_verifies_host_key_by_default() {
+    use crate::{SshAuth, SshUploadStage};
+
+    let stage = |host: &str| SshUploadStage::new(host, "logs", SshAuth::Agent, "/logs".into());
+    let home = std::env::home_dir().map(|home| home.join(".ssh").join("known_hosts"));
+    assert_eq!(
+        stage("logs.example.org").known_hosts_file().ok().flatten(),
+        home
+    );
+    let custom = stage("logs.example.org").with_known_hosts("hosts".into());
+    assert_eq!(custom.known_hosts_file().unwrap(), Some("hosts".into()));
+    let insecure = custom.with_insecure_accept_any_host_key();
+    assert_eq!(insecure.known_hosts_file().unwrap(), None);
+
+    // Raw ed25519 host key blob, with same 32 bytes of key.
+    let key = |byte: u8| {
+        let mut key = vec![0, 0, 0, 11];
+        key.extend_from_slice(b"ssh-ed25519");
+        key.extend_from_slice(&[0, 0, 0, 32]);
+        key.extend_from_slice(&[byte; 32]);
+        key
+    };
+    let dir = TempDir::new("ssh_known_hosts");
+    let known_hosts = dir.join("known_hosts");
+    std::fs::write(
+        &known_hosts,
+        "logs.example.org ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIAEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEB\n",
+    )
+    .unwrap();
+    let session = ssh2::Session::new().unwrap();
+    let verify = |host: &str, key: &[u8]| stage(host).verify_host_key(&session, &known_hosts, key);
+
+    assert!(verify("logs.example.org", &key(1)).is_ok());
+    let changed = verify("logs.example.org", &key(2)).unwrap_err();
+    assert_eq!(changed.kind(), std::io::ErrorKind::PermissionDenied);
+    let unknown = verify("other.example.org", &key(1)).unwrap_err();
+    assert_eq!(unknown.kind(), std::io::ErrorKind::PermissionDenied);
+}
+
+#[cfg(feature = "smtp")]
+#[test]
+fn test_smtp_alert_window_and_hourly_limit() {
+    use std::time::{Duration, Instant};
+
+    use crate::rotation_logger::sink::smtp::AlertWindow;
+
+    let minute = Duration::from_secs(60);
+    let start = Instant::now();
+    let mut window = AlertWindow::new(minute, 2);
+    assert_eq!(window.deadline(), None);
+
+    // Records of one window go in one email.
+    window.push("a".into(), start);
+    window.push("b".into(), start + minute / 2);
+    assert_eq!(window.deadline(), Some(start + minute));
+    assert_eq!(window.take(start + minute / 2), None);
+    assert_eq!(
+        window.take(start + minute),
+        Some((vec!["a".into(), "b".into()], 0))
+    );
+    assert_eq!(window.deadline(), None);
+
+    window.push("c".into(), start + minute * 2);
+    assert_eq!(window.take(start + minute * 3), Some((vec!["c".into()], 0)));
+
+    // Third email in the hour waits until the first one leaves it.
+    window.push("d".into(), start + minute * 4);
+    assert_eq!(window.take(start + minute * 5), None);
+    assert_eq!(window.deadline(), Some(start + minute * 61));
+    assert_eq!(window.flush(start + minute * 5), None);
+    assert_eq!(window.pending(), 1);
+    assert_eq!(
+        window.take(start + minute * 61),
+        Some((vec!["d".into()], 0))
+    );
+    assert_eq!(window.pending(), 0);
+}